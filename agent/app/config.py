@@ -42,9 +42,10 @@ class Settings(BaseSettings):
 
     # --- Historial en el backend (HU 4.6) ---
     backend_url: str = "http://mediamtx-backend:8080"
-    # Token de administración para POST/GET /admin/failures. Sin él, el historial
-    # queda deshabilitado (Null) y el agente notifica en cada corrida como antes.
-    admin_api_token: str | None = None
+    # Clave de API del backend (alcances failures:read y failures:write) para
+    # POST/GET /admin/failures. Sin ella, el historial queda deshabilitado (Null)
+    # y el agente notifica en cada corrida como antes.
+    backend_api_key: str | None = None
 
 
 @lru_cache
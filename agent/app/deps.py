@@ -92,9 +92,9 @@ def get_notification_service() -> NotificationService:
 @lru_cache
 def get_failure_history() -> FailureHistory:
     settings = get_settings()
-    # Con token configurado, persiste en el backend; si no, historial deshabilitado.
-    if settings.admin_api_token:
-        return FailureHistoryApi(settings.backend_url, settings.admin_api_token)
+    # Con clave configurada, persiste en el backend; si no, historial deshabilitado.
+    if settings.backend_api_key:
+        return FailureHistoryApi(settings.backend_url, settings.backend_api_key)
     return NullFailureHistory()
 
 
@@ -21,7 +21,7 @@ _SEVERITY: dict[HealthStatus, str] = {
 
 
 class FailureHistoryApi:
-    """Cliente del historial en el backend (bearer ADMIN_API_TOKEN)."""
+    """Cliente del historial en el backend (bearer BACKEND_API_KEY)."""
 
     def __init__(self, base_url: str, token: str, timeout: float = 5.0) -> None:
         self._base_url = base_url.rstrip("/")
@@ -0,0 +1,21 @@
+-- 0002_refresh_tokens.sql — Refresh tokens opacos y rotatorios
+--
+-- El token en claro solo lo ve el consumidor; aquí se guarda su SHA-256 (es de
+-- alta entropía, no necesita Argon2). Cada uso rota el token dentro de su
+-- familia (family_id); presentar uno ya usado o revocado se trata como robo y
+-- revoca la familia completa.
+
+create table refresh_tokens (
+    id           uuid primary key,
+    family_id    uuid not null,
+    project_id   uuid not null references projects(id) on delete cascade,
+    token_hash   text not null unique,
+    -- Acciones pedidas al emitir (separadas por espacio); null = todas.
+    scope        text,
+    created_at   timestamptz not null default now(),
+    expires_at   timestamptz not null,
+    used_at      timestamptz,
+    revoked_at   timestamptz
+);
+create index refresh_tokens_family_idx on refresh_tokens (family_id);
+create index refresh_tokens_project_idx on refresh_tokens (project_id);
@@ -0,0 +1,24 @@
+-- 0003_token_revocations.sql — Denylist de JWT emitidos
+--
+-- Los JWT son autocontenidos: deshabilitar un proyecto no invalida los ya
+-- emitidos. Cada fila revoca, según las columnas presentes:
+--   - jti                        → un token concreto;
+--   - client_id + issued_before  → los tokens del proyecto emitidos antes;
+--   - solo issued_before         → todos los tokens emitidos antes (global).
+-- expires_at = cuando ya no puede quedar vivo ningún token afectado; a partir
+-- de ahí la fila es inocua y deja de cargarse.
+
+create table token_revocations (
+    id             uuid primary key,
+    jti            text,
+    client_id      text,
+    issued_before  timestamptz,
+    reason         text,
+    created_at     timestamptz not null default now(),
+    expires_at     timestamptz not null,
+    constraint token_revocations_target_chk check (
+        (jti is not null and client_id is null and issued_before is null)
+        or (jti is null and issued_before is not null)
+    )
+);
+create index token_revocations_expires_idx on token_revocations (expires_at);
@@ -0,0 +1,15 @@
+-- 0004_project_token_policy.sql — Política de tokens por proyecto
+--
+-- Vida de los JWT por proyecto (kioscos de pared: horas; portal público:
+-- minutos). Null = rige el global JWT_EXP_MINUTES. allowed_audiences lista los
+-- `aud` que el proyecto puede pedir al hacer login (vacío = ninguno).
+
+alter table projects
+    add column token_max_minutes      integer check (token_max_minutes > 0),
+    add column token_default_minutes  integer check (token_default_minutes > 0),
+    add column allowed_audiences      text[] not null default '{}',
+    add constraint projects_token_default_le_max check (
+        token_default_minutes is null
+        or token_max_minutes is null
+        or token_default_minutes <= token_max_minutes
+    );
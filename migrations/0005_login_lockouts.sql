@@ -0,0 +1,26 @@
+-- 0005_login_lockouts.sql — Protección contra fuerza bruta en el login
+--
+-- login_throttles lleva los fallos recientes por client_id ('client') y por IP
+-- de origen ('ip'). Un fallo posterior a la ventana reinicia el contador; al
+-- superar el umbral se fija locked_until (bloqueo temporal, creciente).
+-- auth_audit registra fallos, bloqueos y desbloqueos (sin secretos).
+
+create table login_throttles (
+    scope            text not null check (scope in ('client', 'ip')),
+    key              text not null,
+    failures         integer not null default 0,
+    last_failure_at  timestamptz not null default now(),
+    locked_until     timestamptz,
+    primary key (scope, key)
+);
+create index login_throttles_last_failure_idx on login_throttles (last_failure_at);
+
+create table auth_audit (
+    id          uuid primary key,
+    event       text not null,
+    client_id   text,
+    ip          text,
+    detail      text,
+    created_at  timestamptz not null default now()
+);
+create index auth_audit_created_idx on auth_audit (created_at desc);
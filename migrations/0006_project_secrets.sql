@@ -0,0 +1,24 @@
+-- 0006_project_secrets.sql — Varios secretos vigentes por proyecto
+--
+-- Rotación sin corte: se agrega el secreto nuevo, los consumidores migran y
+-- recién entonces se revoca el viejo. Un secreto vale si no está revocado ni
+-- expirado; el login prueba todos los vigentes del proyecto. Solo se guarda
+-- el hash (Argon2id); last_used_at dice cuáles siguen en uso antes de revocar.
+
+create table project_secrets (
+    id            uuid primary key,
+    project_id    uuid not null references projects(id) on delete cascade,
+    label         text not null,
+    secret_hash   text not null,
+    created_at    timestamptz not null default now(),
+    expires_at    timestamptz,
+    last_used_at  timestamptz,
+    revoked_at    timestamptz
+);
+create index project_secrets_project_idx on project_secrets (project_id);
+
+-- El secreto único que ya tenía cada proyecto pasa a ser su primer secreto.
+insert into project_secrets (id, project_id, label, secret_hash, created_at)
+select gen_random_uuid(), id, 'inicial', secret_hash, created_at from projects;
+
+alter table projects drop column secret_hash;
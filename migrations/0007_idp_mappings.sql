@@ -0,0 +1,16 @@
+-- 0007_idp_mappings.sql — Canje de tokens de un proveedor de identidad externo
+--
+-- Cada fila asocia un valor de un claim del JWT externo (p.ej. claim 'groups'
+-- con valor 'video-operadores', o 'email' con una dirección) a un proyecto.
+-- Un token externo que lleve ese valor puede canjearse por un token del
+-- proyecto en POST /oauth/token (RFC 8693).
+
+create table idp_mappings (
+    id          uuid primary key,
+    claim       text not null,
+    value       text not null,
+    project_id  uuid not null references projects(id) on delete cascade,
+    created_at  timestamptz not null default now(),
+    unique (claim, value, project_id)
+);
+create index idp_mappings_project_idx on idp_mappings (project_id);
@@ -0,0 +1,16 @@
+-- 0008_publish_grants.sql — Permisos de publicación y cámaras publisher
+--
+-- Body cams y unidades móviles EMPUJAN su stream a MediaMTX. Cada fila
+-- concede a un proyecto `publish` sobre un path exacto o, con prefix = true,
+-- sobre todo path que empiece por `path` (p.ej. 'bodycam/'). El JWT lleva
+-- una entrada `publish` por fila (las de prefijo, como regex `~^...`).
+
+create table project_publish_grants (
+    project_id  uuid not null references projects(id) on delete cascade,
+    path        text not null,
+    prefix      boolean not null default false,
+    primary key (project_id, path, prefix)
+);
+
+-- Cámara publisher: sin fuente RTSP (el dispositivo publica en su path).
+alter table cameras alter column rtsp_url_enc drop not null;
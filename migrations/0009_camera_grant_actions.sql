@@ -0,0 +1,9 @@
+-- 0009_camera_grant_actions.sql — Acciones por cámara asignada
+--
+-- Cada asignación proyecto↔cámara concede video en vivo (`read`), las
+-- grabaciones (`playback`) o ambas. Las filas existentes quedan en 'both',
+-- que era el comportamiento anterior.
+
+alter table project_cameras
+    add column actions text not null default 'both'
+        check (actions in ('read', 'playback', 'both'));
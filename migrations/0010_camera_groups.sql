@@ -0,0 +1,33 @@
+-- 0010_camera_groups.sql — Grupos de cámaras como unidad de acceso
+--
+-- Un grupo (p.ej. 'warehouse-north') agrupa cámaras; un proyecto puede recibir
+-- grupos enteros con las mismas acciones que una cámara suelta. La pertenencia
+-- se resuelve al calcular el acceso: una cámara nueva en el grupo la ven ya
+-- todos los proyectos que lo tienen.
+
+create table camera_groups (
+    id           uuid primary key,
+    name         text not null unique,
+    description  text,
+    created_at   timestamptz not null default now(),
+    updated_at   timestamptz not null default now()
+);
+create trigger camera_groups_set_updated_at
+    before update on camera_groups
+    for each row execute function set_updated_at();
+
+create table camera_group_members (
+    group_id   uuid not null references camera_groups(id) on delete cascade,
+    camera_id  uuid not null references cameras(id) on delete cascade,
+    primary key (group_id, camera_id)
+);
+create index camera_group_members_camera_idx on camera_group_members (camera_id);
+
+create table project_camera_groups (
+    project_id  uuid not null references projects(id) on delete cascade,
+    group_id    uuid not null references camera_groups(id) on delete cascade,
+    actions     text not null default 'both'
+        check (actions in ('read', 'playback', 'both')),
+    primary key (project_id, group_id)
+);
+create index project_camera_groups_group_idx on project_camera_groups (group_id);
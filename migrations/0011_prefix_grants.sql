@@ -0,0 +1,13 @@
+-- 0011_prefix_grants.sql — Acceso por prefijo de path
+--
+-- Un proyecto que ve muchas cámaras (p.ej. todas las de 'bodega-a/') recibe un
+-- solo permiso por prefijo en vez de dos por cámara: el JWT lleva una regex
+-- `~^bodega-a/` y no crece con la cantidad de cámaras.
+
+create table project_prefix_grants (
+    project_id  uuid not null references projects(id) on delete cascade,
+    prefix      text not null,
+    actions     text not null default 'both'
+        check (actions in ('read', 'playback', 'both')),
+    primary key (project_id, prefix)
+);
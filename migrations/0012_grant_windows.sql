@@ -0,0 +1,24 @@
+-- 0012_grant_windows.sql — Vigencia y horario de las asignaciones de cámaras
+--
+-- Auditores y contratistas temporales: la asignación vale desde `valid_from`
+-- hasta `valid_until` (nulos = sin límite) y, con horario, solo los días ISO
+-- de `schedule_days` (1 = lunes) entre `schedule_start` y `schedule_end`, en
+-- la hora local de SCHEDULE_TIMEZONE. Un horario no cruza la medianoche.
+
+alter table project_cameras
+    add column valid_from     timestamptz,
+    add column valid_until    timestamptz,
+    add column schedule_days  smallint[],
+    add column schedule_start time,
+    add column schedule_end   time,
+    add constraint project_cameras_validity
+        check (valid_from is null or valid_until is null or valid_from < valid_until),
+    add constraint project_cameras_schedule
+        check (
+            (schedule_days is null and schedule_start is null and schedule_end is null)
+            or (
+                cardinality(schedule_days) > 0
+                and schedule_days <@ array[1, 2, 3, 4, 5, 6, 7]::smallint[]
+                and schedule_start < schedule_end
+            )
+        );
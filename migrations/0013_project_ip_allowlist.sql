@@ -0,0 +1,8 @@
+-- 0013_project_ip_allowlist.sql — Redes de origen permitidas por proyecto
+--
+-- Un secreto filtrado no debe servir desde cualquier parte de internet: con
+-- `allowed_cidrs` no vacío, el proyecto solo se autentica desde esas redes
+-- (IP del cliente según TRUSTED_PROXIES). Vacío = desde cualquier IP.
+
+alter table projects
+    add column allowed_cidrs cidr[] not null default '{}';
@@ -0,0 +1,30 @@
+-- 0014_admin_accounts.sql — Cuentas de administración con roles
+--
+-- Cada persona que administra tiene su cuenta (contraseña Argon2id) y un rol:
+-- 'viewer' solo consulta, 'operator' opera cámaras, grupos, fallos, bloqueos
+-- y revocaciones, 'admin' además gestiona proyectos, secretos, IdP y cuentas.
+-- El login en /admin/login abre una sesión: un token opaco del que aquí solo
+-- vive el SHA-256. ADMIN_API_TOKEN queda como cuenta de emergencia.
+
+create table admin_accounts (
+    id             uuid primary key,
+    username       text not null unique,
+    password_hash  text not null,
+    role           text not null check (role in ('viewer', 'operator', 'admin')),
+    enabled        boolean not null default true,
+    last_login_at  timestamptz,
+    created_at     timestamptz not null default now(),
+    updated_at     timestamptz not null default now()
+);
+create trigger admin_accounts_set_updated_at
+    before update on admin_accounts
+    for each row execute function set_updated_at();
+
+create table admin_sessions (
+    token_hash  text primary key,
+    account_id  uuid not null references admin_accounts(id) on delete cascade,
+    created_at  timestamptz not null default now(),
+    expires_at  timestamptz not null
+);
+create index admin_sessions_account_idx on admin_sessions (account_id);
+create index admin_sessions_expires_idx on admin_sessions (expires_at);
@@ -0,0 +1,18 @@
+-- 0015_api_keys.sql — Claves de API de servicios con alcances
+--
+-- Para clientes máquina (p.ej. el agente de streams) en vez de ADMIN_API_TOKEN:
+-- cada clave tiene nombre, una lista explícita de alcances `<recurso>:read` o
+-- `<recurso>:write` sobre /admin/*, vencimiento opcional y último uso. Aquí
+-- solo vive el SHA-256 de la clave; `key_prefix` la identifica en listados.
+
+create table api_keys (
+    id            uuid primary key,
+    name          text not null unique,
+    key_hash      text not null unique,
+    key_prefix    text not null,
+    scopes        text[] not null,
+    expires_at    timestamptz,
+    last_used_at  timestamptz,
+    created_by    text not null,
+    created_at    timestamptz not null default now()
+);
@@ -4,7 +4,10 @@
 //! `Camera.rtsp_url` va EN CLARO aquí; el cifrado en reposo es responsabilidad
 //! del adaptador de datos, no del dominio.
 
-use chrono::{DateTime, Utc};
+use std::net::IpAddr;
+
+use chrono::{DateTime, NaiveTime, Utc, Weekday};
+use ipnet::IpNet;
 use uuid::Uuid;
 
 /// Severidad de un diagnóstico de cámara.
@@ -36,19 +39,67 @@ impl Severity {
     }
 }
 
-/// Proyecto consumidor (reemplaza clients.json). `secret_hash` es Argon2id.
+/// Proyecto consumidor (reemplaza clients.json). Sus secretos viven aparte
+/// (`ProjectSecret`), para poder tener varios vigentes durante una rotación.
 #[derive(Debug, Clone)]
 pub struct Project {
     pub id: Uuid,
     pub client_id: String,
-    pub secret_hash: String,
     pub all_cameras: bool,
     pub enabled: bool,
+    /// Vida máxima de sus JWT en minutos; `None` = la global.
+    pub token_max_minutes: Option<i32>,
+    /// Vida de sus JWT cuando no se pide otra; `None` = la global.
+    pub token_default_minutes: Option<i32>,
+    /// Valores de `aud` que puede pedir al hacer login.
+    pub allowed_audiences: Vec<String>,
+    /// Redes (CIDR) desde las que puede autenticarse; vacía = cualquiera.
+    pub allowed_cidrs: Vec<String>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
 
+impl Project {
+    /// Vida del JWT en minutos: la pedida o la por defecto del proyecto,
+    /// acotada a su máximo. Lo que el proyecto no define lo pone `global`.
+    pub fn token_lifetime(&self, requested: Option<i64>, global: i64) -> i64 {
+        let max = self.token_max_minutes.map_or(global, i64::from);
+        let default = self.token_default_minutes.map_or(global, i64::from);
+        requested.unwrap_or(default).min(max).max(1)
+    }
+
+    /// Claim `aud` del JWT: la audiencia pedida si está permitida, o todas las
+    /// permitidas si no se pide ninguna. `None` = audiencia no permitida.
+    pub fn token_audience(&self, requested: Option<&str>) -> Option<Vec<String>> {
+        match requested {
+            Some(aud) => self
+                .allowed_audiences
+                .iter()
+                .any(|a| a == aud)
+                .then(|| vec![aud.to_string()]),
+            None => Some(self.allowed_audiences.clone()),
+        }
+    }
+
+    /// ¿Puede autenticarse desde `ip`? Sin lista, desde cualquiera; con
+    /// lista, solo desde sus redes (IP desconocida → no, fail-closed).
+    pub fn allows_ip(&self, ip: Option<&str>) -> bool {
+        if self.allowed_cidrs.is_empty() {
+            return true;
+        }
+        let Some(ip) = ip.and_then(|ip| ip.parse::<IpAddr>().ok()) else {
+            return false;
+        };
+        let ip = ip.to_canonical(); // ::ffff:a.b.c.d de un socket dual
+        self.allowed_cidrs
+            .iter()
+            .filter_map(|cidr| cidr.parse::<IpNet>().ok())
+            .any(|net| net.contains(&ip))
+    }
+}
+
 /// Alta de un proyecto (el id y los timestamps los pone la capa de datos).
+/// `secret_hash` (Argon2id) queda como su primer secreto.
 #[derive(Debug, Clone)]
 pub struct NewProject {
     pub client_id: String,
@@ -57,12 +108,273 @@ pub struct NewProject {
     pub enabled: bool,
 }
 
+/// Acciones que concede la asignación de una cámara a un proyecto.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CameraActions {
+    /// Solo video en vivo.
+    Read,
+    /// Solo grabaciones.
+    Playback,
+    Both,
+}
+
+impl CameraActions {
+    /// Representación en texto tal como se guarda en `project_cameras.actions`.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            CameraActions::Read => "read",
+            CameraActions::Playback => "playback",
+            CameraActions::Both => "both",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "read" => Some(CameraActions::Read),
+            "playback" => Some(CameraActions::Playback),
+            "both" => Some(CameraActions::Both),
+            _ => None,
+        }
+    }
+
+    /// Lo que conceden `self` y `other` juntos.
+    pub fn union(self, other: Self) -> Self {
+        if self == other {
+            self
+        } else {
+            CameraActions::Both
+        }
+    }
+
+    /// Acciones de MediaMTX que concede.
+    pub fn mtx_actions(self) -> &'static [&'static str] {
+        match self {
+            CameraActions::Read => &["read"],
+            CameraActions::Playback => &["playback"],
+            CameraActions::Both => &["read", "playback"],
+        }
+    }
+}
+
+/// Asignación de una cámara a un proyecto, por ID, con su vigencia.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CameraGrant {
+    pub camera_id: Uuid,
+    pub actions: CameraActions,
+    pub window: GrantWindow,
+}
+
+/// Vigencia de una asignación: desde/hasta (`None` = sin límite) y, si hay
+/// horario, solo dentro de él. Sin nada, la asignación vale siempre.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct GrantWindow {
+    pub valid_from: Option<DateTime<Utc>>,
+    pub valid_until: Option<DateTime<Utc>>,
+    pub schedule: Option<WeeklySchedule>,
+}
+
+impl GrantWindow {
+    /// ¿Ya venció? (no vuelve a valer; el admin la muestra como expirada).
+    pub fn expired(&self, now: DateTime<Utc>) -> bool {
+        self.valid_until.is_some_and(|until| until <= now)
+    }
+}
+
+/// Horario semanal: los días indicados, de `start` a `end` en la hora local
+/// de SCHEDULE_TIMEZONE. No cruza la medianoche (`start < end`).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WeeklySchedule {
+    pub days: Vec<Weekday>,
+    pub start: NaiveTime,
+    pub end: NaiveTime,
+}
+
+/// Asignación de un grupo de cámaras entero a un proyecto.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GroupGrant {
+    pub group_id: Uuid,
+    pub actions: CameraActions,
+}
+
+/// Acceso por prefijo: todo path que empiece por `prefix` (p.ej. `bodega-a/`).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PrefixGrant {
+    pub prefix: String,
+    pub actions: CameraActions,
+}
+
+/// Path de una cámara asignada (o, con `prefix`, prefijo de paths) y lo que
+/// concede (para los permisos del JWT).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AllowedCamera {
+    pub path: String,
+    pub actions: CameraActions,
+    pub prefix: bool,
+    /// Fin de la ventana vigente de la asignación (`None` = sin fin).
+    pub until: Option<DateTime<Utc>>,
+}
+
+/// Permiso de publicación de un proyecto: sobre `path` exacto o, con
+/// `prefix`, sobre todo path que empiece por él (p.ej. `bodycam/`).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PublishGrant {
+    pub path: String,
+    pub prefix: bool,
+}
+
+/// Secreto de un proyecto. Un proyecto puede tener varios vigentes a la vez
+/// (rotación sin corte). `secret_hash` es Argon2id; nunca sale por la API.
+#[derive(Debug, Clone)]
+pub struct ProjectSecret {
+    pub id: Uuid,
+    pub project_id: Uuid,
+    /// Nombre para identificarlo al rotar (p.ej. `prod-2025-06`).
+    pub label: String,
+    pub secret_hash: String,
+    pub created_at: DateTime<Utc>,
+    /// Deja de valer en este instante; `None` = no expira.
+    pub expires_at: Option<DateTime<Utc>>,
+    /// Último login exitoso con este secreto.
+    pub last_used_at: Option<DateTime<Utc>>,
+    pub revoked_at: Option<DateTime<Utc>>,
+}
+
+impl ProjectSecret {
+    /// Vigente: ni revocado ni expirado en `now`.
+    pub fn is_active(&self, now: DateTime<Utc>) -> bool {
+        self.revoked_at.is_none() && self.expires_at.is_none_or(|e| e > now)
+    }
+}
+
+/// Alta de un secreto de proyecto (ya hasheado).
+#[derive(Debug, Clone)]
+pub struct NewProjectSecret {
+    pub project_id: Uuid,
+    pub label: String,
+    pub secret_hash: String,
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+/// Refresh token de un proyecto. Solo se conoce su hash (SHA-256); el valor en
+/// claro lo tiene únicamente el consumidor.
+#[derive(Debug, Clone)]
+pub struct RefreshToken {
+    pub id: Uuid,
+    /// Cadena de rotaciones: un reuso revoca la familia completa.
+    pub family_id: Uuid,
+    pub project_id: Uuid,
+    pub token_hash: String,
+    /// Acciones pedidas al emitir (separadas por espacio); `None` = todas.
+    pub scope: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+    /// Momento en que se canjeó (rotó). Un token usado no vuelve a valer.
+    pub used_at: Option<DateTime<Utc>>,
+    pub revoked_at: Option<DateTime<Utc>>,
+}
+
+/// Alta de un refresh token.
+#[derive(Debug, Clone)]
+pub struct NewRefreshToken {
+    pub family_id: Uuid,
+    pub project_id: Uuid,
+    pub token_hash: String,
+    pub scope: Option<String>,
+    pub expires_at: DateTime<Utc>,
+}
+
+/// Revocación de JWT (denylist). Según los campos presentes revoca un token
+/// (`jti`), los de un proyecto emitidos antes de `issued_before` (`client_id`)
+/// o, sin `client_id`, todos los emitidos antes de `issued_before`.
+#[derive(Debug, Clone)]
+pub struct Revocation {
+    pub id: Uuid,
+    pub jti: Option<String>,
+    pub client_id: Option<String>,
+    pub issued_before: Option<DateTime<Utc>>,
+    pub reason: Option<String>,
+    pub created_at: DateTime<Utc>,
+    /// A partir de aquí ningún token afectado sigue vivo: la entrada caduca.
+    pub expires_at: DateTime<Utc>,
+}
+
+/// Alta de una revocación.
+#[derive(Debug, Clone)]
+pub struct NewRevocation {
+    pub jti: Option<String>,
+    pub client_id: Option<String>,
+    pub issued_before: Option<DateTime<Utc>>,
+    pub reason: Option<String>,
+    pub expires_at: DateTime<Utc>,
+}
+
+/// Ámbito de un contador de fallos de login.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ThrottleScope {
+    /// Por `client_id` (aunque no exista el proyecto).
+    Client,
+    /// Por IP de origen.
+    Ip,
+}
+
+impl ThrottleScope {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ThrottleScope::Client => "client",
+            ThrottleScope::Ip => "ip",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "client" => Some(ThrottleScope::Client),
+            "ip" => Some(ThrottleScope::Ip),
+            _ => None,
+        }
+    }
+}
+
+/// Fallos recientes de login de un `client_id` o una IP, y su bloqueo.
+#[derive(Debug, Clone)]
+pub struct Throttle {
+    pub scope: ThrottleScope,
+    pub key: String,
+    /// Fallos dentro de la ventana actual.
+    pub failures: i32,
+    pub last_failure_at: DateTime<Utc>,
+    /// Bloqueado hasta este instante (si es futuro).
+    pub locked_until: Option<DateTime<Utc>>,
+}
+
+/// Entrada de auditoría de autenticación (nunca incluye secretos).
+#[derive(Debug, Clone)]
+pub struct AuditEntry {
+    pub id: Uuid,
+    /// p.ej. `login_failed`, `lockout`, `lockout_cleared`.
+    pub event: String,
+    pub client_id: Option<String>,
+    pub ip: Option<String>,
+    pub detail: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Alta de una entrada de auditoría.
+#[derive(Debug, Clone)]
+pub struct NewAuditEntry {
+    pub event: String,
+    pub client_id: Option<String>,
+    pub ip: Option<String>,
+    pub detail: Option<String>,
+}
+
 /// Cámara. `rtsp_url` en claro en el dominio; el adaptador la cifra/descifra.
+/// Sin `rtsp_url` es una cámara publisher: el dispositivo (body cam, unidad
+/// móvil) publica en su path y MediaMTX no tira de ninguna fuente.
 #[derive(Debug, Clone)]
 pub struct Camera {
     pub id: Uuid,
     pub path: String,
-    pub rtsp_url: String,
+    pub rtsp_url: Option<String>,
     pub record: bool,
     pub enabled: bool,
     pub description: Option<String>,
@@ -70,11 +382,36 @@ pub struct Camera {
     pub updated_at: DateTime<Utc>,
 }
 
-/// Alta de una cámara.
+impl Camera {
+    /// ¿La publica un dispositivo en vez de leerse de una fuente RTSP?
+    pub fn is_publisher(&self) -> bool {
+        self.rtsp_url.is_none()
+    }
+}
+
+/// Grupo de cámaras (p.ej. todas las de un almacén), unidad de asignación a
+/// proyectos.
+#[derive(Debug, Clone)]
+pub struct CameraGroup {
+    pub id: Uuid,
+    pub name: String,
+    pub description: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Alta de un grupo de cámaras.
+#[derive(Debug, Clone)]
+pub struct NewCameraGroup {
+    pub name: String,
+    pub description: Option<String>,
+}
+
+/// Alta de una cámara (`rtsp_url: None` = publisher).
 #[derive(Debug, Clone)]
 pub struct NewCamera {
     pub path: String,
-    pub rtsp_url: String,
+    pub rtsp_url: Option<String>,
     pub record: bool,
     pub enabled: bool,
     pub description: Option<String>,
@@ -102,3 +439,215 @@ pub struct NewFailure {
     pub diagnosis: Option<String>,
     pub raw: Option<serde_json::Value>,
 }
+
+/// Asociación de un valor de claim del IdP externo con un proyecto: un JWT
+/// externo cuyo `claim` contenga `value` se canjea por un token del proyecto.
+#[derive(Debug, Clone)]
+pub struct IdpMapping {
+    pub id: Uuid,
+    /// Nombre del claim; admite rutas con punto (`realm_access.roles`).
+    pub claim: String,
+    pub value: String,
+    pub project_id: Uuid,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Alta de una asociación claim → proyecto.
+#[derive(Debug, Clone)]
+pub struct NewIdpMapping {
+    pub claim: String,
+    pub value: String,
+    pub project_id: Uuid,
+}
+
+/// Rol de una cuenta de administración, de menor a mayor poder: cada rol
+/// puede todo lo del anterior.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum AdminRole {
+    /// Solo consulta.
+    Viewer,
+    /// Opera cámaras, grupos, fallos, bloqueos y revocaciones.
+    Operator,
+    /// Además gestiona proyectos, secretos, IdP externo y cuentas.
+    Admin,
+}
+
+impl AdminRole {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            AdminRole::Viewer => "viewer",
+            AdminRole::Operator => "operator",
+            AdminRole::Admin => "admin",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "viewer" => Some(AdminRole::Viewer),
+            "operator" => Some(AdminRole::Operator),
+            "admin" => Some(AdminRole::Admin),
+            _ => None,
+        }
+    }
+}
+
+/// Cuenta de administración. `password_hash` es Argon2id; nunca sale por la API.
+#[derive(Debug, Clone)]
+pub struct AdminAccount {
+    pub id: Uuid,
+    pub username: String,
+    pub password_hash: String,
+    pub role: AdminRole,
+    pub enabled: bool,
+    pub last_login_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Alta de una cuenta de administración (contraseña ya hasheada).
+#[derive(Debug, Clone)]
+pub struct NewAdminAccount {
+    pub username: String,
+    pub password_hash: String,
+    pub role: AdminRole,
+}
+
+/// Clave de API de un servicio: entra a /admin/* solo con sus alcances
+/// (`<recurso>:read` / `<recurso>:write`). De la clave solo se guarda el hash.
+#[derive(Debug, Clone)]
+pub struct ApiKey {
+    pub id: Uuid,
+    pub name: String,
+    /// Primeros caracteres de la clave, para reconocerla en los listados.
+    pub key_prefix: String,
+    pub scopes: Vec<String>,
+    pub expires_at: Option<DateTime<Utc>>,
+    pub last_used_at: Option<DateTime<Utc>>,
+    /// Principal que la creó.
+    pub created_by: String,
+    pub created_at: DateTime<Utc>,
+}
+
+impl ApiKey {
+    /// Alcance exacto: `cameras:write` no incluye `cameras:read`.
+    pub fn allows(&self, scope: &str) -> bool {
+        self.scopes.iter().any(|s| s == scope)
+    }
+
+    pub fn is_expired(&self, now: DateTime<Utc>) -> bool {
+        self.expires_at.is_some_and(|exp| exp <= now)
+    }
+}
+
+/// Alta de una clave de API (clave ya hasheada).
+#[derive(Debug, Clone)]
+pub struct NewApiKey {
+    pub name: String,
+    pub key_hash: String,
+    pub key_prefix: String,
+    pub scopes: Vec<String>,
+    pub expires_at: Option<DateTime<Utc>>,
+    pub created_by: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{AdminRole, ApiKey, Project};
+    use chrono::{Duration, Utc};
+    use uuid::Uuid;
+
+    fn project(max: Option<i32>, default: Option<i32>) -> Project {
+        Project {
+            id: Uuid::new_v4(),
+            client_id: "kiosco".into(),
+            all_cameras: true,
+            enabled: true,
+            token_max_minutes: max,
+            token_default_minutes: default,
+            allowed_audiences: vec![],
+            allowed_cidrs: vec![],
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn lifetime_without_policy_uses_global() {
+        let p = project(None, None);
+        assert_eq!(p.token_lifetime(None, 60), 60);
+        assert_eq!(p.token_lifetime(Some(720), 60), 60, "acotada al global");
+        assert_eq!(p.token_lifetime(Some(5), 60), 5);
+    }
+
+    #[test]
+    fn lifetime_is_clamped_to_project_policy() {
+        let kiosk = project(Some(720), Some(720));
+        assert_eq!(kiosk.token_lifetime(None, 60), 720);
+        assert_eq!(kiosk.token_lifetime(Some(10_000), 60), 720);
+
+        let portal = project(Some(5), None);
+        assert_eq!(portal.token_lifetime(None, 60), 5, "default global acotado al máximo");
+        assert_eq!(portal.token_lifetime(Some(0), 60), 1, "nunca menos de un minuto");
+    }
+
+    #[test]
+    fn audience_must_be_allowed() {
+        let mut p = project(None, None);
+        assert_eq!(p.token_audience(None), Some(vec![]));
+        assert_eq!(p.token_audience(Some("odin")), None);
+
+        p.allowed_audiences = vec!["odin".into(), "kiosk".into()];
+        assert_eq!(p.token_audience(Some("kiosk")), Some(vec!["kiosk".to_string()]));
+        assert_eq!(p.token_audience(None).unwrap().len(), 2);
+        assert_eq!(p.token_audience(Some("otro")), None);
+    }
+
+    #[test]
+    fn ip_allowlist_is_fail_closed() {
+        let mut p = project(None, None);
+        assert!(p.allows_ip(Some("203.0.113.9")), "sin lista, cualquiera");
+        assert!(p.allows_ip(None));
+
+        p.allowed_cidrs = vec!["10.20.0.0/16".into(), "2001:db8::/32".into()];
+        assert!(p.allows_ip(Some("10.20.3.4")));
+        assert!(p.allows_ip(Some("::ffff:10.20.3.4")), "IPv4 mapeada");
+        assert!(p.allows_ip(Some("2001:db8::1")));
+        assert!(!p.allows_ip(Some("10.21.0.1")));
+        assert!(!p.allows_ip(None), "IP desconocida");
+        assert!(!p.allows_ip(Some("no-es-ip")));
+    }
+
+    #[test]
+    fn admin_roles_are_ordered_by_power() {
+        assert!(AdminRole::Viewer < AdminRole::Operator);
+        assert!(AdminRole::Operator < AdminRole::Admin);
+        for role in [AdminRole::Viewer, AdminRole::Operator, AdminRole::Admin] {
+            assert_eq!(AdminRole::parse(role.as_str()), Some(role));
+        }
+        assert_eq!(AdminRole::parse("root"), None);
+    }
+
+    #[test]
+    fn api_key_scopes_are_exact_and_expiry_is_inclusive() {
+        let now = Utc::now();
+        let mut key = ApiKey {
+            id: Uuid::new_v4(),
+            name: "stream-agent".into(),
+            key_prefix: "mk_abcd".into(),
+            scopes: vec!["failures:write".into(), "cameras:read".into()],
+            expires_at: None,
+            last_used_at: None,
+            created_by: "ana".into(),
+            created_at: now,
+        };
+        assert!(key.allows("failures:write"));
+        assert!(key.allows("cameras:read"));
+        assert!(!key.allows("failures:read"), "write no incluye read");
+        assert!(!key.allows("cameras:write"));
+        assert!(!key.is_expired(now), "sin vencimiento");
+
+        key.expires_at = Some(now);
+        assert!(key.is_expired(now));
+        assert!(!key.is_expired(now - Duration::seconds(1)));
+    }
+}
@@ -5,9 +5,16 @@
 //! traits, nunca de la implementación concreta (Inversión de Dependencias).
 
 use async_trait::async_trait;
+use chrono::{DateTime, Utc};
 use uuid::Uuid;
 
-use super::models::{Camera, Failure, NewCamera, NewFailure, NewProject, Project};
+use super::models::{
+    AdminAccount, AllowedCamera, ApiKey, AuditEntry, Camera, CameraGrant, CameraGroup, Failure,
+    GroupGrant, IdpMapping, NewAdminAccount, NewApiKey, NewAuditEntry, NewCamera, NewCameraGroup,
+    NewFailure, NewIdpMapping, NewProject, NewProjectSecret, NewRefreshToken, NewRevocation,
+    PrefixGrant, Project, ProjectSecret, PublishGrant, RefreshToken, Revocation, Throttle,
+    ThrottleScope,
+};
 
 /// Error de almacenamiento del dominio. NO expone tipos de infraestructura
 /// (sqlx, etc.); el adaptador traduce sus errores a estas variantes.
@@ -33,15 +40,63 @@ pub trait ProjectRepo: Send + Sync {
     async fn update(&self, project: &Project) -> RepoResult<Project>;
     async fn delete(&self, id: Uuid) -> RepoResult<()>;
 
-    /// Reemplaza el conjunto de cámaras permitidas del proyecto (n-a-n).
-    async fn set_cameras(&self, project_id: Uuid, camera_ids: &[Uuid]) -> RepoResult<()>;
+    /// Reemplaza el conjunto de cámaras permitidas del proyecto (n-a-n), cada
+    /// una con sus acciones.
+    async fn set_cameras(&self, project_id: Uuid, grants: &[CameraGrant]) -> RepoResult<()>;
 
-    /// Paths de las cámaras con acceso EXPLÍCITO del proyecto. Ignora la bandera
-    /// `all_cameras`; eso lo resuelve el servicio de autorización (HU 4.4).
-    async fn allowed_camera_paths(&self, project_id: Uuid) -> RepoResult<Vec<String>>;
+    /// Paths de las cámaras con acceso EXPLÍCITO del proyecto y sus acciones,
+    /// solo las asignaciones vigentes ahora (vigencia y horario), con el fin de
+    /// su ventana. Ignora la bandera `all_cameras`; eso lo resuelve el servicio
+    /// de autorización (HU 4.4).
+    async fn allowed_camera_paths(&self, project_id: Uuid) -> RepoResult<Vec<AllowedCamera>>;
 
-    /// IDs de las cámaras asignadas explícitamente al proyecto (n-a-n).
-    async fn assigned_camera_ids(&self, project_id: Uuid) -> RepoResult<Vec<Uuid>>;
+    /// Cámaras asignadas explícitamente al proyecto (n-a-n), con sus acciones
+    /// y su vigencia (también las vencidas o fuera de horario).
+    async fn assigned_cameras(&self, project_id: Uuid) -> RepoResult<Vec<CameraGrant>>;
+
+    /// Reemplaza los grupos de cámaras asignados al proyecto.
+    async fn set_groups(&self, project_id: Uuid, grants: &[GroupGrant]) -> RepoResult<()>;
+
+    /// Grupos asignados al proyecto, con sus acciones.
+    async fn assigned_groups(&self, project_id: Uuid) -> RepoResult<Vec<GroupGrant>>;
+
+    /// Paths de las cámaras que el proyecto recibe por sus grupos, según la
+    /// pertenencia ACTUAL. Una cámara en varios grupos aparece una vez por
+    /// grupo; el servicio de autorización une las acciones.
+    async fn group_camera_paths(&self, project_id: Uuid) -> RepoResult<Vec<AllowedCamera>>;
+
+    /// Reemplaza los accesos por prefijo de path del proyecto.
+    async fn set_prefix_grants(&self, project_id: Uuid, grants: &[PrefixGrant]) -> RepoResult<()>;
+
+    /// Accesos por prefijo de path del proyecto, ordenados por prefijo.
+    async fn prefix_grants(&self, project_id: Uuid) -> RepoResult<Vec<PrefixGrant>>;
+
+    /// Reemplaza los permisos de publicación del proyecto.
+    async fn set_publish_grants(&self, project_id: Uuid, grants: &[PublishGrant]) -> RepoResult<()>;
+
+    /// Permisos de publicación del proyecto, ordenados por path.
+    async fn publish_grants(&self, project_id: Uuid) -> RepoResult<Vec<PublishGrant>>;
+}
+
+/// Secretos de proyecto (varios vigentes a la vez para rotar sin corte).
+#[async_trait]
+pub trait ProjectSecretRepo: Send + Sync {
+    async fn add(&self, new: NewProjectSecret) -> RepoResult<ProjectSecret>;
+    /// Todos los secretos del proyecto (también revocados), el más nuevo primero.
+    async fn list_by_project(&self, project_id: Uuid) -> RepoResult<Vec<ProjectSecret>>;
+    /// Solo los vigentes (ni revocados ni expirados).
+    async fn list_active(&self, project_id: Uuid) -> RepoResult<Vec<ProjectSecret>>;
+    /// Revoca un secreto vigente del proyecto. `NotFound` si no hay tal.
+    async fn revoke(&self, project_id: Uuid, id: Uuid) -> RepoResult<()>;
+    /// Revoca todos los secretos vigentes del proyecto salvo `keep`.
+    async fn revoke_others(&self, project_id: Uuid, keep: Uuid) -> RepoResult<()>;
+    /// Adelanta a `at` la expiración de los demás secretos vigentes (gracia de
+    /// una rotación). No extiende los que ya expiraban antes.
+    async fn expire_others(&self, project_id: Uuid, keep: Uuid, at: DateTime<Utc>) -> RepoResult<()>;
+    /// Registra un login exitoso con el secreto.
+    async fn touch(&self, id: Uuid) -> RepoResult<()>;
+    /// Reemplaza el hash de un secreto (mismo secreto, parámetros nuevos).
+    async fn update_hash(&self, id: Uuid, secret_hash: &str) -> RepoResult<()>;
 }
 
 /// Cámaras: fuente de verdad; el reconciler las lleva a MediaMTX (HU 4.2).
@@ -65,6 +120,140 @@ pub trait FailureRepo: Send + Sync {
     async fn latest_by_camera(&self, camera_path: &str) -> RepoResult<Option<Failure>>;
 }
 
+/// Refresh tokens de proyectos (guardados por hash, rotatorios por familia).
+#[async_trait]
+pub trait RefreshTokenRepo: Send + Sync {
+    async fn create(&self, new: NewRefreshToken) -> RepoResult<RefreshToken>;
+    async fn find_by_hash(&self, token_hash: &str) -> RepoResult<Option<RefreshToken>>;
+    /// Marca el token como usado de forma atómica. `false` si ya estaba usado o
+    /// revocado (dos canjes concurrentes: solo uno gana).
+    async fn mark_used(&self, id: Uuid) -> RepoResult<bool>;
+    /// Revoca todos los tokens vivos de la familia (detección de reuso).
+    async fn revoke_family(&self, family_id: Uuid) -> RepoResult<()>;
+    /// Revoca todos los tokens vivos del proyecto (revocación administrativa).
+    async fn revoke_project(&self, project_id: Uuid) -> RepoResult<()>;
+    /// Borra los tokens vencidos antes de `before` y los revocados. Los usados
+    /// aún no vencidos se quedan: sin ellos no se detecta el reuso.
+    async fn purge(&self, before: DateTime<Utc>) -> RepoResult<u64>;
+}
+
+/// Denylist de JWT emitidos (por jti, por proyecto o global).
+#[async_trait]
+pub trait RevocationRepo: Send + Sync {
+    async fn create(&self, new: NewRevocation) -> RepoResult<Revocation>;
+    /// Revocaciones que aún afectan a algún token vivo (`expires_at > now`).
+    async fn list_active(&self, now: DateTime<Utc>) -> RepoResult<Vec<Revocation>>;
+    async fn delete(&self, id: Uuid) -> RepoResult<()>;
+}
+
+/// Contadores de fallos de login y bloqueos temporales (fuerza bruta).
+#[async_trait]
+pub trait LoginThrottleRepo: Send + Sync {
+    async fn find(&self, scope: ThrottleScope, key: &str) -> RepoResult<Option<Throttle>>;
+    /// Suma un fallo de forma atómica. Si el último fallo y el fin del último
+    /// bloqueo son anteriores a `window_start`, el contador vuelve a empezar en 1.
+    async fn record_failure(
+        &self,
+        scope: ThrottleScope,
+        key: &str,
+        window_start: DateTime<Utc>,
+    ) -> RepoResult<Throttle>;
+    async fn lock(&self, scope: ThrottleScope, key: &str, until: DateTime<Utc>) -> RepoResult<()>;
+    /// Borra el contador (y su bloqueo). `NotFound` si no había.
+    async fn clear(&self, scope: ThrottleScope, key: &str) -> RepoResult<()>;
+    /// Contadores con un fallo o un fin de bloqueo desde `window_start`.
+    async fn list_active(&self, window_start: DateTime<Utc>) -> RepoResult<Vec<Throttle>>;
+    /// Borra los contadores que ya no están activos (criterio de `list_active`).
+    async fn purge(&self, window_start: DateTime<Utc>) -> RepoResult<u64>;
+}
+
+/// Auditoría de eventos de autenticación.
+#[async_trait]
+pub trait AuditRepo: Send + Sync {
+    async fn record(&self, new: NewAuditEntry) -> RepoResult<AuditEntry>;
+    /// Las `limit` entradas más recientes, de la más nueva a la más vieja.
+    async fn list_recent(&self, limit: i64) -> RepoResult<Vec<AuditEntry>>;
+}
+
+/// Grupos de cámaras y su pertenencia.
+#[async_trait]
+pub trait CameraGroupRepo: Send + Sync {
+    async fn list_all(&self) -> RepoResult<Vec<CameraGroup>>;
+    async fn find_by_id(&self, id: Uuid) -> RepoResult<Option<CameraGroup>>;
+    /// `Conflict` si el nombre ya existe.
+    async fn create(&self, new: NewCameraGroup) -> RepoResult<CameraGroup>;
+    /// `NotFound` si no existe; `Conflict` si el nombre ya existe.
+    async fn update(&self, group: &CameraGroup) -> RepoResult<CameraGroup>;
+    /// `NotFound` si no existe.
+    async fn delete(&self, id: Uuid) -> RepoResult<()>;
+
+    /// Reemplaza las cámaras del grupo.
+    async fn set_members(&self, group_id: Uuid, camera_ids: &[Uuid]) -> RepoResult<()>;
+    /// Agrega una cámara al grupo (idempotente).
+    async fn add_member(&self, group_id: Uuid, camera_id: Uuid) -> RepoResult<()>;
+    /// `NotFound` si la cámara no estaba en el grupo.
+    async fn remove_member(&self, group_id: Uuid, camera_id: Uuid) -> RepoResult<()>;
+    /// IDs de las cámaras del grupo.
+    async fn members(&self, group_id: Uuid) -> RepoResult<Vec<Uuid>>;
+}
+
+/// Asociaciones claim del IdP externo → proyecto (canje de tokens).
+#[async_trait]
+pub trait IdpMappingRepo: Send + Sync {
+    async fn list_all(&self) -> RepoResult<Vec<IdpMapping>>;
+    /// `Conflict` si ya existe la misma asociación.
+    async fn create(&self, new: NewIdpMapping) -> RepoResult<IdpMapping>;
+    /// `NotFound` si no existe.
+    async fn delete(&self, id: Uuid) -> RepoResult<()>;
+}
+
+/// Cuentas de administración y sus sesiones (guardadas por hash del token).
+#[async_trait]
+pub trait AdminAccountRepo: Send + Sync {
+    async fn list_all(&self) -> RepoResult<Vec<AdminAccount>>;
+    async fn find_by_id(&self, id: Uuid) -> RepoResult<Option<AdminAccount>>;
+    async fn find_by_username(&self, username: &str) -> RepoResult<Option<AdminAccount>>;
+    /// `Conflict` si el nombre de usuario ya existe.
+    async fn create(&self, new: NewAdminAccount) -> RepoResult<AdminAccount>;
+    /// Rol, habilitación y contraseña. `NotFound` si no existe.
+    async fn update(&self, account: &AdminAccount) -> RepoResult<AdminAccount>;
+    /// `NotFound` si no existe. Sus sesiones se borran con ella.
+    async fn delete(&self, id: Uuid) -> RepoResult<()>;
+
+    /// Abre una sesión y registra el login. Aprovecha para borrar las vencidas.
+    async fn create_session(
+        &self,
+        account_id: Uuid,
+        token_hash: &str,
+        expires_at: DateTime<Utc>,
+    ) -> RepoResult<()>;
+    /// Cuenta dueña de una sesión vigente (no vencida y con la cuenta habilitada).
+    async fn find_session(&self, token_hash: &str) -> RepoResult<Option<AdminAccount>>;
+    /// Cierra una sesión (idempotente).
+    async fn delete_session(&self, token_hash: &str) -> RepoResult<()>;
+    /// Cierra todas las sesiones de la cuenta.
+    async fn delete_sessions(&self, account_id: Uuid) -> RepoResult<()>;
+}
+
+/// Claves de API de servicios (solo el hash de la clave).
+#[async_trait]
+pub trait ApiKeyRepo: Send + Sync {
+    async fn list_all(&self) -> RepoResult<Vec<ApiKey>>;
+    /// `Conflict` si el nombre ya existe.
+    async fn create(&self, new: NewApiKey) -> RepoResult<ApiKey>;
+    /// `NotFound` si no existe.
+    async fn delete(&self, id: Uuid) -> RepoResult<()>;
+    /// Clave vigente (no vencida) con ese hash; registra el uso.
+    async fn use_key(&self, key_hash: &str) -> RepoResult<Option<ApiKey>>;
+}
+
+/// Claves públicas (JWKS) del proveedor de identidad externo, leídas de un
+/// archivo o de una URL. El `Err` describe por qué no se pudieron obtener.
+#[async_trait]
+pub trait ExternalKeySource: Send + Sync {
+    async fn fetch(&self) -> Result<jsonwebtoken::jwk::JwkSet, String>;
+}
+
 /// Error al aprovisionar rutas en el servidor de streaming (HU 4.2).
 /// No expone tipos de infraestructura (reqwest, etc.); el adaptador los traduce.
 #[derive(Debug, thiserror::Error)]
@@ -1,48 +1,227 @@
-//! Endpoints de administración (HU 4.5): CRUD de cámaras y proyectos.
+//! Endpoints de administración (HU 4.5): CRUD de cámaras y proyectos, secretos
+//! de proyecto, revocación de tokens emitidos, bloqueos de login por fuerza
+//! bruta, asociaciones de identidades externas a proyectos (canje de tokens),
+//! cuentas de administración y claves de API de servicios.
 //!
-//! Protegidos por `require_admin` (bearer ADMIN_API_TOKEN). Las respuestas NO
-//! exponen secretos (rtsp_url / secret_hash).
+//! Protegidos por `require_admin`: bearer de una sesión abierta en
+//! `POST /admin/login`, una clave de API o, como cuenta de emergencia,
+//! ADMIN_API_TOKEN. Cada ruta exige un rol mínimo (`viewer` consulta,
+//! `operator` opera, `admin` gestiona proyectos y cuentas); una clave de API
+//! solo entra con el alcance `<recurso>:read|write` de la ruta. Toda
+//! modificación queda en la auditoría con quién la hizo. Las respuestas NO
+//! exponen secretos (rtsp_url / secret_hash / claves).
 
+use std::net::{IpAddr, SocketAddr};
 use std::sync::Arc;
 
-use axum::extract::{Path, Query, Request, State};
-use axum::http::{header::AUTHORIZATION, StatusCode};
-use axum::middleware::Next;
+use axum::extract::{ConnectInfo, OriginalUri, Path, Query, Request, State};
+use axum::http::{header, header::AUTHORIZATION, HeaderMap, Method, StatusCode};
+use axum::middleware::{self, Next};
 use axum::response::{IntoResponse, Response};
-use axum::routing::get;
-use axum::{Json, Router};
-use chrono::{DateTime, Utc};
+use axum::routing::{delete, get, patch, post, MethodRouter};
+use axum::{Extension, Json, Router};
+use chrono::{DateTime, NaiveTime, Utc, Weekday};
+use ipnet::IpNet;
 use serde::{Deserialize, Serialize};
-use tracing::warn;
+use tracing::{info, warn};
 use utoipa::ToSchema;
 use uuid::Uuid;
 
-use crate::domain::models::{Camera, Failure, NewCamera, NewFailure, NewProject, Project, Severity};
+use crate::domain::models::{
+    AdminAccount, AdminRole, ApiKey, AuditEntry, Camera, CameraActions, CameraGrant, CameraGroup, Failure,
+    GrantWindow, GroupGrant, IdpMapping, NewAdminAccount, NewApiKey, NewCamera, NewCameraGroup, NewFailure,
+    NewIdpMapping, NewProject, NewProjectSecret, PrefixGrant, Project, ProjectSecret,
+    PublishGrant, Revocation, Severity, Throttle, ThrottleScope, WeeklySchedule,
+};
 use crate::domain::ports::RepoError;
+use crate::services::lockout::LoginError;
+use crate::services::refresh::{generate_token, hash_token};
+use crate::services::revocation::RevocationTarget;
 use crate::AppState;
 
-/// Middleware: exige `Authorization: Bearer <ADMIN_API_TOKEN>`. Fail-closed:
-/// si no hay token configurado, se rechaza todo el panel de administración.
-pub async fn require_admin(State(state): State<Arc<AppState>>, req: Request, next: Next) -> Response {
-    let configured = state.config.admin_api_token.as_str();
-    if configured.is_empty() {
-        return (
-            StatusCode::SERVICE_UNAVAILABLE,
-            "administración no configurada (ADMIN_API_TOKEN)",
-        )
-            .into_response();
+/// Nombre con el que se audita el uso de ADMIN_API_TOKEN (los usuarios van en
+/// minúsculas, así que ninguna cuenta puede llamarse igual).
+const BREAK_GLASS: &str = "ADMIN_API_TOKEN";
+
+/// Las claves de API empiezan así: se reconocen sin consultar las sesiones.
+const API_KEY_PREFIX: &str = "mk_";
+
+/// Recursos de /admin/* a los que una clave de API puede tener alcance
+/// (`<recurso>:read` para GET, `<recurso>:write` para lo demás). Cuentas y
+/// claves quedan fuera: solo las gestiona una persona.
+const API_KEY_RESOURCES: &[&str] = &[
+    "cameras",
+    "camera-groups",
+    "projects",
+    "failures",
+    "revocations",
+    "lockouts",
+    "audit",
+    "idp-mappings",
+];
+
+/// Quién hace el request de administración: una cuenta, el token de
+/// emergencia o una clave de API.
+#[derive(Debug, Clone)]
+pub struct AdminPrincipal {
+    pub name: String,
+    pub access: AdminAccess,
+}
+
+/// Qué puede hacer el principal.
+#[derive(Debug, Clone)]
+pub enum AdminAccess {
+    /// Cuenta o token de emergencia: rige el rol de cada ruta.
+    Role(AdminRole),
+    /// Clave de API: solo los alcances listados.
+    Scopes(Vec<String>),
+}
+
+impl AdminPrincipal {
+    fn from_key(key: ApiKey) -> Self {
+        Self {
+            // `:` no cabe en un usuario, así que no se confunde con una cuenta.
+            name: format!("key:{}", key.name),
+            access: AdminAccess::Scopes(key.scopes),
+        }
     }
 
-    let auth_header = req.headers().get(AUTHORIZATION).and_then(|v| v.to_str().ok());
-    if is_authorized(configured, auth_header) {
-        next.run(req).await
+    pub fn is_api_key(&self) -> bool {
+        matches!(self.access, AdminAccess::Scopes(_))
+    }
+}
+
+/// Alcance que exige `method` sobre `path` (con o sin `/admin`) a una clave de
+/// API; `None` si la ruta no es de un recurso de `API_KEY_RESOURCES`.
+fn required_scope(method: &Method, path: &str) -> Option<String> {
+    let path = path.strip_prefix("/admin").unwrap_or(path);
+    let resource = path.trim_start_matches('/').split('/').next()?;
+    if !API_KEY_RESOURCES.contains(&resource) {
+        return None;
+    }
+    let action = if method == Method::GET || method == Method::HEAD {
+        "read"
     } else {
-        (StatusCode::UNAUTHORIZED, "no autorizado").into_response()
+        "write"
+    };
+    Some(format!("{resource}:{action}"))
+}
+
+/// ¿Alcanzan los `scopes` de una clave para `method` sobre `path`? `GET /me`
+/// vale siempre (para que el servicio revise su clave); el `Err` dice qué falta.
+fn key_allows(scopes: &[String], method: &Method, path: &str) -> Result<(), String> {
+    match required_scope(method, path) {
+        Some(scope) if scopes.contains(&scope) => Ok(()),
+        Some(scope) => Err(format!("la clave no tiene el alcance requerido: {scope}")),
+        None if method == Method::GET && path.strip_prefix("/admin").unwrap_or(path) == "/me" => {
+            Ok(())
+        }
+        None => Err("ruta solo para cuentas de administración".to_string()),
+    }
+}
+
+/// Resuelve el bearer a su principal: el token de emergencia (rol `admin`),
+/// una clave de API vigente (registra su uso) o una sesión vigente de una
+/// cuenta habilitada. `None` = no autorizado.
+pub(crate) async fn principal(
+    state: &AppState,
+    auth_header: Option<&str>,
+) -> Result<Option<AdminPrincipal>, RepoError> {
+    if is_authorized(&state.config.admin_api_token, auth_header) {
+        warn!("uso del token de emergencia {}", BREAK_GLASS);
+        return Ok(Some(AdminPrincipal {
+            name: BREAK_GLASS.to_string(),
+            access: AdminAccess::Role(AdminRole::Admin),
+        }));
+    }
+    let Some(token) = auth_header.and_then(|v| v.strip_prefix("Bearer ")) else {
+        return Ok(None);
+    };
+    if token.starts_with(API_KEY_PREFIX) {
+        let key = state.api_key_repo.use_key(&hash_token(token)).await?;
+        return Ok(key.map(AdminPrincipal::from_key));
+    }
+    Ok(state.admin_auth.session(token).await?.map(|a| AdminPrincipal {
+        name: a.username,
+        access: AdminAccess::Role(a.role),
+    }))
+}
+
+/// Middleware: exige un principal de administración (ver `principal`) y lo
+/// deja en las extensiones del request para `require_role` y los handlers.
+/// A una clave de API le exige aquí el alcance de la ruta (403 si no lo
+/// tiene). Fail-closed: sin bearer válido (o ante error de BD) no pasa nada. Las
+/// modificaciones (todo lo que no es GET) quedan en la auditoría como
+/// `admin_action`, con el principal, la ruta y el status.
+pub async fn require_admin(
+    State(state): State<Arc<AppState>>,
+    connect: Option<ConnectInfo<SocketAddr>>,
+    mut req: Request,
+    next: Next,
+) -> Response {
+    let auth_header = req.headers().get(AUTHORIZATION).and_then(|v| v.to_str().ok());
+    let principal = match principal(&state, auth_header).await {
+        Ok(Some(principal)) => principal,
+        Ok(None) => return (StatusCode::UNAUTHORIZED, "no autorizado").into_response(),
+        Err(e) => {
+            warn!("error resolviendo la sesión de administración: {}", e);
+            return (StatusCode::INTERNAL_SERVER_ERROR, "error interno").into_response();
+        }
+    };
+
+    let method = req.method().clone();
+    let path = req
+        .extensions()
+        .get::<OriginalUri>()
+        .map_or_else(|| req.uri().path().to_string(), |u| u.path().to_string());
+    if let AdminAccess::Scopes(scopes) = &principal.access {
+        if let Err(msg) = key_allows(scopes, &method, &path) {
+            warn!("{} sin alcance para {} {}", principal.name, method, path);
+            return (StatusCode::FORBIDDEN, msg).into_response();
+        }
+    }
+    let ip = crate::http::client_ip::from_request(
+        connect,
+        req.headers(),
+        &state.config.trusted_proxies,
+    );
+    req.extensions_mut().insert(principal.clone());
+    let response = next.run(req).await;
+
+    if method != Method::GET && method != Method::HEAD {
+        let detail = format!("{} {} → {}", method, path, response.status().as_u16());
+        state
+            .admin_auth
+            .record("admin_action", &principal.name, ip.as_deref(), detail)
+            .await;
+    }
+    response
+}
+
+/// Middleware por ruta: exige al menos el rol `min` al principal que dejó
+/// `require_admin` (403 si no le alcanza). Una clave de API ya pasó por su
+/// alcance en `require_admin`.
+async fn require_role(State(min): State<AdminRole>, req: Request, next: Next) -> Response {
+    let Some(principal) = req.extensions().get::<AdminPrincipal>() else {
+        return (StatusCode::UNAUTHORIZED, "no autorizado").into_response();
+    };
+    match &principal.access {
+        AdminAccess::Role(role) if *role < min => (
+            StatusCode::FORBIDDEN,
+            format!("el rol '{}' no alcanza: requiere '{}'", role.as_str(), min.as_str()),
+        )
+            .into_response(),
+        _ => next.run(req).await,
     }
 }
 
-/// Lógica pura de autorización: token configurado no vacío y bearer coincidente.
-fn is_authorized(configured: &str, auth_header: Option<&str>) -> bool {
+/// Los métodos de `methods` exigen al menos el rol `min`.
+fn allow(min: AdminRole, methods: MethodRouter<Arc<AppState>>) -> MethodRouter<Arc<AppState>> {
+    methods.route_layer(middleware::from_fn_with_state(min, require_role))
+}
+
+/// Lógica pura del token de emergencia: token configurado no vacío y bearer coincidente.
+pub(crate) fn is_authorized(configured: &str, auth_header: Option<&str>) -> bool {
     if configured.is_empty() {
         return false;
     }
@@ -51,20 +230,307 @@ fn is_authorized(configured: &str, auth_header: Option<&str>) -> bool {
         .is_some_and(|token| token == configured)
 }
 
-/// Router de administración (se monta bajo /admin con el middleware require_admin).
+/// Router de administración (se monta bajo /admin con el middleware
+/// require_admin). Toda consulta (GET) admite `viewer`; cada modificación
+/// declara aquí su rol mínimo.
 pub fn router() -> Router<Arc<AppState>> {
+    use AdminRole::{Admin, Operator};
     Router::new()
-        .route("/cameras", get(list_cameras).post(create_camera))
+        .route(
+            "/cameras",
+            get(list_cameras).merge(allow(Operator, post(create_camera))),
+        )
         .route(
             "/cameras/:id",
-            get(get_camera).patch(update_camera).delete(delete_camera),
+            get(get_camera).merge(allow(Operator, patch(update_camera).delete(delete_camera))),
+        )
+        .route(
+            "/camera-groups",
+            get(list_camera_groups).merge(allow(Operator, post(create_camera_group))),
+        )
+        .route(
+            "/camera-groups/:id",
+            get(get_camera_group).merge(allow(
+                Operator,
+                patch(update_camera_group).delete(delete_camera_group),
+            )),
+        )
+        .route(
+            "/camera-groups/:id/cameras/:camera_id",
+            allow(Operator, post(add_group_camera).delete(remove_group_camera)),
+        )
+        .route(
+            "/projects",
+            get(list_projects).merge(allow(Admin, post(create_project))),
         )
-        .route("/projects", get(list_projects).post(create_project))
         .route(
             "/projects/:id",
-            get(get_project).patch(update_project).delete(delete_project),
+            get(get_project).merge(allow(Admin, patch(update_project).delete(delete_project))),
+        )
+        .route(
+            "/projects/:id/secrets",
+            get(list_project_secrets).merge(allow(Admin, post(add_project_secret))),
+        )
+        .route(
+            "/projects/:id/secrets/:secret_id",
+            allow(Admin, delete(revoke_project_secret)),
+        )
+        .route("/projects/:id/:action", allow(Admin, post(project_action)))
+        .route(
+            "/failures",
+            get(list_failures).merge(allow(Operator, post(record_failure))),
+        )
+        .route(
+            "/revocations",
+            get(list_revocations).merge(allow(Operator, post(create_revocation))),
+        )
+        .route("/revocations/:id", allow(Admin, delete(delete_revocation)))
+        .route("/lockouts", get(list_lockouts))
+        .route("/lockouts/:scope/:key", allow(Operator, delete(clear_lockout)))
+        .route("/audit", get(list_audit))
+        .route(
+            "/idp-mappings",
+            get(list_idp_mappings).merge(allow(Admin, post(create_idp_mapping))),
+        )
+        .route("/idp-mappings/:id", allow(Admin, delete(delete_idp_mapping)))
+        .route(
+            "/accounts",
+            allow(Admin, get(list_admin_accounts).post(create_admin_account)),
+        )
+        .route(
+            "/accounts/:id",
+            allow(Admin, patch(update_admin_account).delete(delete_admin_account)),
+        )
+        .route(
+            "/api-keys",
+            allow(Admin, get(list_api_keys).post(create_api_key)),
+        )
+        .route("/api-keys/:id", allow(Admin, delete(delete_api_key)))
+        .route("/me", get(admin_me))
+        .route("/logout", post(admin_logout))
+}
+
+/// Login de cuentas de administración: fuera de `require_admin` (se monta
+/// aparte, en `/admin/login`).
+pub fn login_router() -> Router<Arc<AppState>> {
+    Router::new().route("/admin/login", post(admin_login))
+}
+
+/// Política de tokens de un proyecto mientras se edita.
+#[derive(Default, PartialEq)]
+struct TokenPolicy {
+    max_minutes: Option<i32>,
+    default_minutes: Option<i32>,
+    audiences: Vec<String>,
+}
+
+impl TokenPolicy {
+    fn of(project: &Project) -> Self {
+        Self {
+            max_minutes: project.token_max_minutes,
+            default_minutes: project.token_default_minutes,
+            audiences: project.allowed_audiences.clone(),
+        }
+    }
+
+    /// Aplica los campos presentes; `0` quita el valor (rige el global).
+    fn apply(&mut self, max: Option<i32>, default: Option<i32>, audiences: Option<Vec<String>>) {
+        if let Some(m) = max {
+            self.max_minutes = (m != 0).then_some(m);
+        }
+        if let Some(d) = default {
+            self.default_minutes = (d != 0).then_some(d);
+        }
+        if let Some(mut a) = audiences {
+            a.sort();
+            a.dedup();
+            self.audiences = a;
+        }
+    }
+
+    fn validate(&self, ceiling: i64) -> Result<(), (StatusCode, String)> {
+        let bad = |msg: String| Err((StatusCode::BAD_REQUEST, msg));
+        for m in [self.max_minutes, self.default_minutes].into_iter().flatten() {
+            if m < 0 || i64::from(m) > ceiling {
+                return bad(format!("la vida del token debe estar entre 1 y {ceiling} minutos"));
+            }
+        }
+        if let (Some(max), Some(default)) = (self.max_minutes, self.default_minutes) {
+            if default > max {
+                return bad("token_default_minutes no puede superar token_max_minutes".into());
+            }
+        }
+        if self.audiences.iter().any(|a| a.trim().is_empty()) {
+            return bad("allowed_audiences no admite valores vacíos".into());
+        }
+        Ok(())
+    }
+
+    fn store(self, project: &mut Project) {
+        project.token_max_minutes = self.max_minutes;
+        project.token_default_minutes = self.default_minutes;
+        project.allowed_audiences = self.audiences;
+    }
+}
+
+/// Une `camera_ids` (ambas acciones, sin vencimiento) y `cameras` en las
+/// asignaciones a guardar; una cámara repetida, acciones desconocidas o una
+/// vigencia u horario inválidos → 400.
+fn camera_grants(
+    camera_ids: Vec<Uuid>,
+    cameras: Vec<CameraGrantDto>,
+) -> Result<Vec<CameraGrant>, (StatusCode, String)> {
+    let bad = |msg: String| (StatusCode::BAD_REQUEST, msg);
+    let mut grants: Vec<CameraGrant> = camera_ids
+        .into_iter()
+        .map(|camera_id| CameraGrant {
+            camera_id,
+            actions: CameraActions::Both,
+            window: GrantWindow::default(),
+        })
+        .collect();
+    for c in cameras {
+        grants.push(CameraGrant {
+            camera_id: c.camera_id,
+            actions: parse_actions(&c.actions)?,
+            window: grant_window(c.valid_from, c.valid_until, c.schedule)?,
+        });
+    }
+    for (i, g) in grants.iter().enumerate() {
+        if grants[..i].iter().any(|o| o.camera_id == g.camera_id) {
+            return Err(bad(format!("cámara repetida: {}", g.camera_id)));
+        }
+    }
+    Ok(grants)
+}
+
+/// Grupos a asignar; uno repetido o acciones desconocidas → 400.
+fn group_grants(groups: Vec<GroupGrantDto>) -> Result<Vec<GroupGrant>, (StatusCode, String)> {
+    let mut grants: Vec<GroupGrant> = Vec::with_capacity(groups.len());
+    for g in groups {
+        if grants.iter().any(|o| o.group_id == g.group_id) {
+            return Err((StatusCode::BAD_REQUEST, format!("grupo repetido: {}", g.group_id)));
+        }
+        grants.push(GroupGrant {
+            group_id: g.group_id,
+            actions: parse_actions(&g.actions)?,
+        });
+    }
+    Ok(grants)
+}
+
+/// Vigencia de una asignación: `valid_from < valid_until` y un horario con
+/// días conocidos y `start < end` (no cruza la medianoche).
+fn grant_window(
+    valid_from: Option<DateTime<Utc>>,
+    valid_until: Option<DateTime<Utc>>,
+    schedule: Option<ScheduleDto>,
+) -> Result<GrantWindow, (StatusCode, String)> {
+    let bad = |msg: String| (StatusCode::BAD_REQUEST, msg);
+    if let (Some(from), Some(until)) = (valid_from, valid_until) {
+        if from >= until {
+            return Err(bad("valid_from debe ser anterior a valid_until".to_string()));
+        }
+    }
+    let schedule = match schedule {
+        None => None,
+        Some(s) => {
+            let mut days: Vec<Weekday> = Vec::with_capacity(s.days.len());
+            for day in &s.days {
+                let day: Weekday = day
+                    .parse()
+                    .map_err(|_| bad(format!("día desconocido: '{day}' (mon..sun)")))?;
+                if !days.contains(&day) {
+                    days.push(day);
+                }
+            }
+            if days.is_empty() {
+                return Err(bad("el horario necesita al menos un día".to_string()));
+            }
+            let time = |t: &str| {
+                NaiveTime::parse_from_str(t, "%H:%M")
+                    .map_err(|_| bad(format!("hora inválida: '{t}' (HH:MM)")))
+            };
+            let (start, end) = (time(&s.start)?, time(&s.end)?);
+            if start >= end {
+                return Err(bad("el horario no puede cruzar la medianoche (start < end)".to_string()));
+            }
+            Some(WeeklySchedule { days, start, end })
+        }
+    };
+    Ok(GrantWindow {
+        valid_from,
+        valid_until,
+        schedule,
+    })
+}
+
+fn parse_actions(actions: &str) -> Result<CameraActions, (StatusCode, String)> {
+    CameraActions::parse(actions).ok_or_else(|| {
+        (
+            StatusCode::BAD_REQUEST,
+            format!("acciones desconocidas: '{actions}' (read, playback o both)"),
         )
-        .route("/failures", get(list_failures).post(record_failure))
+    })
+}
+
+/// Path (o prefijo) literal de un permiso: sin espacios ni regex crudas (el
+/// `~` lo reservan las regex que genera el servicio).
+fn literal_path<'a>(path: &'a str, what: &str) -> Result<&'a str, (StatusCode, String)> {
+    let trimmed = path.trim();
+    if trimmed.is_empty() || trimmed.starts_with('~') || trimmed.contains(char::is_whitespace) {
+        return Err((StatusCode::BAD_REQUEST, format!("{what} inválido: '{path}'")));
+    }
+    Ok(trimmed)
+}
+
+/// Valida los permisos de publicación pedidos (paths literales, sin duplicados).
+fn publish_grants(req: Vec<PublishGrantDto>) -> Result<Vec<PublishGrant>, (StatusCode, String)> {
+    let mut grants: Vec<PublishGrant> = Vec::with_capacity(req.len());
+    for g in req {
+        let path = literal_path(&g.path, "path de publicación")?;
+        let grant = PublishGrant { path: path.to_string(), prefix: g.prefix };
+        if !grants.contains(&grant) {
+            grants.push(grant);
+        }
+    }
+    Ok(grants)
+}
+
+/// Accesos por prefijo pedidos (literales); uno repetido o acciones
+/// desconocidas → 400.
+fn prefix_grants(req: Vec<PrefixGrantDto>) -> Result<Vec<PrefixGrant>, (StatusCode, String)> {
+    let mut grants: Vec<PrefixGrant> = Vec::with_capacity(req.len());
+    for g in req {
+        let prefix = literal_path(&g.prefix, "prefijo")?;
+        if grants.iter().any(|o| o.prefix == prefix) {
+            return Err((StatusCode::BAD_REQUEST, format!("prefijo repetido: '{prefix}'")));
+        }
+        grants.push(PrefixGrant {
+            prefix: prefix.to_string(),
+            actions: parse_actions(&g.actions)?,
+        });
+    }
+    Ok(grants)
+}
+
+/// Redes de origen permitidas: CIDR o IP suelta (= /32 o /128), normalizadas
+/// a su red (`10.1.2.3/16` → `10.1.0.0/16`) y sin duplicados; inválida → 400.
+fn allowed_cidrs(req: Vec<String>) -> Result<Vec<String>, (StatusCode, String)> {
+    let mut nets: Vec<String> = Vec::with_capacity(req.len());
+    for entry in req {
+        let trimmed = entry.trim();
+        let net = trimmed
+            .parse::<IpNet>()
+            .or_else(|_| trimmed.parse::<IpAddr>().map(IpNet::from))
+            .map_err(|_| (StatusCode::BAD_REQUEST, format!("red inválida: '{entry}'")))?
+            .trunc()
+            .to_string();
+        if !nets.contains(&net) {
+            nets.push(net);
+        }
+    }
+    Ok(nets)
 }
 
 /// Traduce un error de repositorio a una respuesta HTTP (sin filtrar detalles).
@@ -91,6 +557,8 @@ pub struct CameraResponse {
     pub record: bool,
     pub enabled: bool,
     pub description: Option<String>,
+    /// Sin fuente RTSP: el dispositivo publica en su path.
+    pub publisher: bool,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
@@ -99,6 +567,7 @@ impl From<Camera> for CameraResponse {
     fn from(c: Camera) -> Self {
         Self {
             id: c.id,
+            publisher: c.is_publisher(),
             path: c.path,
             record: c.record,
             enabled: c.enabled,
@@ -109,25 +578,61 @@ impl From<Camera> for CameraResponse {
     }
 }
 
-/// Alta de cámara.
+/// Alta de cámara: con `rtsp_url` (MediaMTX la consume) o `publisher: true`
+/// (body cam / unidad móvil que empuja su stream). Exactamente una de las dos.
 #[derive(Deserialize, ToSchema)]
 pub struct CreateCameraRequest {
     pub path: String,
-    pub rtsp_url: String,
+    pub rtsp_url: Option<String>,
+    #[serde(default)]
+    pub publisher: bool,
     pub record: Option<bool>,
     pub enabled: Option<bool>,
     pub description: Option<String>,
 }
 
 /// Edición parcial de cámara (solo los campos presentes se actualizan).
+/// `rtsp_url` convierte una publisher en cámara consumida; `publisher: true`
+/// descarta la fuente.
 #[derive(Deserialize, ToSchema)]
 pub struct UpdateCameraRequest {
     pub rtsp_url: Option<String>,
+    pub publisher: Option<bool>,
     pub record: Option<bool>,
     pub enabled: Option<bool>,
     pub description: Option<String>,
 }
 
+/// Grupo de cámaras con sus miembros.
+#[derive(Serialize, ToSchema)]
+pub struct CameraGroupResponse {
+    pub id: Uuid,
+    #[schema(example = "warehouse-north")]
+    pub name: String,
+    pub description: Option<String>,
+    pub camera_ids: Vec<Uuid>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Alta de grupo de cámaras.
+#[derive(Deserialize, ToSchema)]
+pub struct CreateCameraGroupRequest {
+    #[schema(example = "warehouse-north")]
+    pub name: String,
+    pub description: Option<String>,
+    #[serde(default)]
+    pub camera_ids: Vec<Uuid>,
+}
+
+/// Edición parcial de grupo; `camera_ids` reemplaza los miembros.
+#[derive(Deserialize, ToSchema)]
+pub struct UpdateCameraGroupRequest {
+    pub name: Option<String>,
+    pub description: Option<String>,
+    pub camera_ids: Option<Vec<Uuid>>,
+}
+
 /// Respuesta de proyecto SIN el `secret_hash` (incluye sus cámaras asignadas).
 #[derive(Serialize, ToSchema)]
 pub struct ProjectResponse {
@@ -135,29 +640,261 @@ pub struct ProjectResponse {
     pub client_id: String,
     pub all_cameras: bool,
     pub enabled: bool,
+    /// Todas las cámaras asignadas, con cualquier acción (también las vencidas).
     pub camera_ids: Vec<Uuid>,
+    /// Las mismas, con lo que concede cada una y su vigencia.
+    pub cameras: Vec<CameraGrantDto>,
+    /// Grupos asignados: sus cámaras actuales, con estas acciones.
+    pub groups: Vec<GroupGrantDto>,
+    /// Prefijos de path: toda cámara cuyo path empiece por ellos.
+    pub prefixes: Vec<PrefixGrantDto>,
+    /// Vida máxima de sus JWT (minutos); null = la global.
+    pub token_max_minutes: Option<i32>,
+    /// Vida por defecto de sus JWT (minutos); null = la global.
+    pub token_default_minutes: Option<i32>,
+    pub allowed_audiences: Vec<String>,
+    pub publish: Vec<PublishGrantDto>,
+    /// Redes desde las que puede autenticarse; vacío = cualquiera.
+    pub allowed_cidrs: Vec<String>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
 
-/// Alta de proyecto. El secreto llega en claro y se hashea (Argon2id).
+/// Cámara asignada a un proyecto: `read` (en vivo), `playback` (grabaciones)
+/// o `both` (por defecto), opcionalmente acotada en el tiempo.
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct CameraGrantDto {
+    pub camera_id: Uuid,
+    #[serde(default = "both_actions")]
+    #[schema(example = "read")]
+    pub actions: String,
+    /// Desde cuándo vale; omitido = ya.
+    #[serde(default)]
+    pub valid_from: Option<DateTime<Utc>>,
+    /// Hasta cuándo vale; omitido = sin vencimiento.
+    #[serde(default)]
+    pub valid_until: Option<DateTime<Utc>>,
+    /// Horario semanal; omitido = a toda hora.
+    #[serde(default)]
+    pub schedule: Option<ScheduleDto>,
+    /// Solo en respuestas: ya pasó `valid_until` (la asignación no vale más).
+    #[serde(default, skip_deserializing)]
+    #[schema(read_only)]
+    pub expired: bool,
+}
+
+/// Horario semanal de una asignación, en la hora local de SCHEDULE_TIMEZONE.
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct ScheduleDto {
+    /// Días de la semana: `mon`, `tue`, `wed`, `thu`, `fri`, `sat`, `sun`.
+    #[schema(example = json!(["mon", "tue", "wed", "thu", "fri"]))]
+    pub days: Vec<String>,
+    /// Inicio de la franja (`HH:MM`).
+    #[schema(example = "08:00")]
+    pub start: String,
+    /// Fin de la franja (`HH:MM`), posterior al inicio.
+    #[schema(example = "18:00")]
+    pub end: String,
+}
+
+impl From<WeeklySchedule> for ScheduleDto {
+    fn from(s: WeeklySchedule) -> Self {
+        Self {
+            days: s.days.iter().map(|d| d.to_string().to_lowercase()).collect(),
+            start: s.start.format("%H:%M").to_string(),
+            end: s.end.format("%H:%M").to_string(),
+        }
+    }
+}
+
+/// Grupo de cámaras asignado a un proyecto, con las mismas acciones que
+/// `CameraGrantDto`.
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct GroupGrantDto {
+    pub group_id: Uuid,
+    #[serde(default = "both_actions")]
+    #[schema(example = "read")]
+    pub actions: String,
+}
+
+/// Acceso por prefijo de path (p.ej. `bodega-a/`), con las mismas acciones
+/// que `CameraGrantDto`. Cubre también las cámaras creadas después.
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct PrefixGrantDto {
+    #[schema(example = "bodega-a/")]
+    pub prefix: String,
+    #[serde(default = "both_actions")]
+    #[schema(example = "read")]
+    pub actions: String,
+}
+
+impl From<PrefixGrant> for PrefixGrantDto {
+    fn from(g: PrefixGrant) -> Self {
+        Self {
+            prefix: g.prefix,
+            actions: g.actions.as_str().to_string(),
+        }
+    }
+}
+
+fn both_actions() -> String {
+    CameraActions::Both.as_str().to_string()
+}
+
+impl From<GroupGrant> for GroupGrantDto {
+    fn from(g: GroupGrant) -> Self {
+        Self {
+            group_id: g.group_id,
+            actions: g.actions.as_str().to_string(),
+        }
+    }
+}
+
+impl From<CameraGrant> for CameraGrantDto {
+    fn from(g: CameraGrant) -> Self {
+        Self {
+            camera_id: g.camera_id,
+            actions: g.actions.as_str().to_string(),
+            expired: g.window.expired(Utc::now()),
+            valid_from: g.window.valid_from,
+            valid_until: g.window.valid_until,
+            schedule: g.window.schedule.map(ScheduleDto::from),
+        }
+    }
+}
+
+/// Permiso de publicación: un path exacto o, con `prefix`, todo path que
+/// empiece por él (p.ej. `bodycam/`).
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct PublishGrantDto {
+    #[schema(example = "bodycam/")]
+    pub path: String,
+    #[serde(default)]
+    pub prefix: bool,
+}
+
+impl From<PublishGrant> for PublishGrantDto {
+    fn from(g: PublishGrant) -> Self {
+        Self { path: g.path, prefix: g.prefix }
+    }
+}
+
+/// Alta de proyecto. El secreto llega en claro y se hashea (Argon2id); si se
+/// omite, lo genera el servidor y lo devuelve una única vez.
 #[derive(Deserialize, ToSchema)]
 pub struct CreateProjectRequest {
     pub client_id: String,
-    pub secret: String,
+    /// Debe cumplir la política de fortaleza. Omitido = generado por el servidor.
+    pub secret: Option<String>,
     #[serde(default)]
     pub all_cameras: bool,
+    /// Cámaras con acceso completo (en vivo y grabaciones).
     #[serde(default)]
     pub camera_ids: Vec<Uuid>,
+    /// Cámaras con acciones específicas; se suman a `camera_ids`.
+    #[serde(default)]
+    pub cameras: Vec<CameraGrantDto>,
+    /// Grupos de cámaras (ver `/admin/camera-groups`).
+    #[serde(default)]
+    pub groups: Vec<GroupGrantDto>,
+    /// Prefijos de path (p.ej. `bodega-a/`): un solo permiso en el JWT.
+    #[serde(default)]
+    pub prefixes: Vec<PrefixGrantDto>,
+    pub token_max_minutes: Option<i32>,
+    pub token_default_minutes: Option<i32>,
+    #[serde(default)]
+    pub allowed_audiences: Vec<String>,
+    #[serde(default)]
+    pub publish: Vec<PublishGrantDto>,
+    /// Redes de origen permitidas (`10.0.0.0/8`, `203.0.113.7`); vacío = cualquiera.
+    #[serde(default)]
+    #[schema(example = json!(["203.0.113.0/24"]))]
+    pub allowed_cidrs: Vec<String>,
 }
 
 /// Edición parcial de proyecto (solo los campos presentes se actualizan).
+/// En la política de tokens, `0` vuelve a la vida global. `secret` reemplaza
+/// de golpe todos los secretos vigentes; para rotar sin corte, usar
+/// `POST /admin/projects/{id}/secrets`.
 #[derive(Deserialize, ToSchema)]
 pub struct UpdateProjectRequest {
-    pub secret: Option<String>,        // rotar el secreto
+    pub secret: Option<String>,        // reemplazar los secretos
     pub all_cameras: Option<bool>,
     pub enabled: Option<bool>,
-    pub camera_ids: Option<Vec<Uuid>>, // reasignar cámaras
+    pub camera_ids: Option<Vec<Uuid>>, // reasignar cámaras (en vivo y grabaciones)
+    pub cameras: Option<Vec<CameraGrantDto>>, // reasignar, con acciones por cámara
+    pub groups: Option<Vec<GroupGrantDto>>,   // reasignar grupos
+    pub prefixes: Option<Vec<PrefixGrantDto>>, // reemplazar los prefijos de path
+    pub token_max_minutes: Option<i32>,
+    pub token_default_minutes: Option<i32>,
+    pub allowed_audiences: Option<Vec<String>>,
+    pub publish: Option<Vec<PublishGrantDto>>, // reemplazar los permisos de publicación
+    pub allowed_cidrs: Option<Vec<String>>,    // reemplazar las redes permitidas ([] = cualquiera)
+}
+
+/// Proyecto recién creado. `client_secret` solo viene si lo generó el
+/// servidor, y no se puede volver a consultar.
+#[derive(Serialize, ToSchema)]
+pub struct CreatedProjectResponse {
+    #[serde(flatten)]
+    pub project: ProjectResponse,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub client_secret: Option<String>,
+}
+
+/// Rotación con secreto generado por el servidor (body opcional).
+#[derive(Deserialize, ToSchema, Default)]
+pub struct RotateSecretRequest {
+    /// Etiqueta del secreto nuevo; por defecto, `rotado-<fecha>`.
+    pub label: Option<String>,
+    /// Minutos que siguen valiendo los secretos anteriores (`0` = se revocan ya,
+    /// máximo una semana). Omitido = siguen vigentes hasta revocarlos a mano.
+    pub previous_grace_minutes: Option<i64>,
+}
+
+/// Secreto generado por el servidor: `client_secret` en claro, una única vez.
+#[derive(Serialize, ToSchema)]
+pub struct GeneratedSecretResponse {
+    #[serde(flatten)]
+    pub secret: ProjectSecretResponse,
+    pub client_secret: String,
+}
+
+/// Alta de un secreto adicional del proyecto (llega en claro y se hashea).
+#[derive(Deserialize, ToSchema)]
+pub struct AddSecretRequest {
+    pub secret: String,
+    #[schema(example = "prod-2025-06")]
+    pub label: String,
+    /// Deja de valer en este instante; por defecto, no expira.
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+/// Secreto de proyecto SIN su hash.
+#[derive(Serialize, ToSchema)]
+pub struct ProjectSecretResponse {
+    pub id: Uuid,
+    pub label: String,
+    pub created_at: DateTime<Utc>,
+    pub expires_at: Option<DateTime<Utc>>,
+    pub last_used_at: Option<DateTime<Utc>>,
+    pub revoked_at: Option<DateTime<Utc>>,
+    /// Vale para autenticar ahora (ni revocado ni expirado).
+    pub active: bool,
+}
+
+impl From<ProjectSecret> for ProjectSecretResponse {
+    fn from(s: ProjectSecret) -> Self {
+        Self {
+            active: s.is_active(Utc::now()),
+            id: s.id,
+            label: s.label,
+            created_at: s.created_at,
+            expires_at: s.expires_at,
+            last_used_at: s.last_used_at,
+            revoked_at: s.revoked_at,
+        }
+    }
 }
 
 /// Registro de un diagnóstico (lo envía el agente, ya redactado).
@@ -200,6 +937,43 @@ impl From<Failure> for FailureResponse {
     }
 }
 
+/// Revocación de tokens emitidos. Un solo alcance: `jti` (un token),
+/// `client_id` (los del proyecto emitidos hasta `issued_before`, por defecto
+/// ahora) o solo `issued_before` (todos los emitidos hasta ese momento).
+#[derive(Deserialize, ToSchema)]
+pub struct RevokeRequest {
+    pub jti: Option<String>,
+    pub client_id: Option<String>,
+    pub issued_before: Option<DateTime<Utc>>,
+    pub reason: Option<String>,
+}
+
+/// Revocación vigente (caduca cuando ya no queda vivo ningún token afectado).
+#[derive(Serialize, ToSchema)]
+pub struct RevocationResponse {
+    pub id: Uuid,
+    pub jti: Option<String>,
+    pub client_id: Option<String>,
+    pub issued_before: Option<DateTime<Utc>>,
+    pub reason: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+}
+
+impl From<Revocation> for RevocationResponse {
+    fn from(r: Revocation) -> Self {
+        Self {
+            id: r.id,
+            jti: r.jti,
+            client_id: r.client_id,
+            issued_before: r.issued_before,
+            reason: r.reason,
+            created_at: r.created_at,
+            expires_at: r.expires_at,
+        }
+    }
+}
+
 /// Filtros de consulta del historial.
 #[derive(Deserialize)]
 pub struct FailureQuery {
@@ -207,35 +981,284 @@ pub struct FailureQuery {
     pub limit: Option<i64>,
 }
 
-// ---------------------------------------------------------------------------
-// Handlers
-// ---------------------------------------------------------------------------
+/// Contador de fallos de login de un `client_id` o una IP.
+#[derive(Serialize, ToSchema)]
+pub struct LockoutResponse {
+    /// `client` o `ip`.
+    #[schema(example = "client")]
+    pub scope: &'static str,
+    #[schema(example = "sigac")]
+    pub key: String,
+    /// Fallos dentro de la ventana actual.
+    pub failures: i32,
+    pub last_failure_at: DateTime<Utc>,
+    /// Fin del último bloqueo (puede estar en el pasado).
+    pub locked_until: Option<DateTime<Utc>>,
+    /// Bloqueado ahora.
+    pub locked: bool,
+}
 
-#[utoipa::path(
-    get, path = "/admin/cameras", tag = "Administration",
-    security(("admin_token" = [])),
-    responses(
-        (status = 200, description = "Lista de cámaras", body = [CameraResponse]),
-        (status = 401, description = "No autorizado")
-    )
-)]
-pub async fn list_cameras(
-    State(state): State<Arc<AppState>>,
-) -> Result<Json<Vec<CameraResponse>>, StatusCode> {
-    let cameras = state
-        .camera_repo
-        .list_all()
-        .await
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
-    Ok(Json(cameras.into_iter().map(CameraResponse::from).collect()))
+impl From<Throttle> for LockoutResponse {
+    fn from(t: Throttle) -> Self {
+        Self {
+            scope: t.scope.as_str(),
+            locked: t.locked_until.is_some_and(|u| u > Utc::now()),
+            key: t.key,
+            failures: t.failures,
+            last_failure_at: t.last_failure_at,
+            locked_until: t.locked_until,
+        }
+    }
 }
 
-#[utoipa::path(
+/// Entrada de auditoría de autenticación.
+#[derive(Serialize, ToSchema)]
+pub struct AuditResponse {
+    pub id: Uuid,
+    #[schema(example = "lockout")]
+    pub event: String,
+    pub client_id: Option<String>,
+    pub ip: Option<String>,
+    pub detail: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl From<AuditEntry> for AuditResponse {
+    fn from(e: AuditEntry) -> Self {
+        Self {
+            id: e.id,
+            event: e.event,
+            client_id: e.client_id,
+            ip: e.ip,
+            detail: e.detail,
+            created_at: e.created_at,
+        }
+    }
+}
+
+/// Filtros de consulta de la auditoría.
+#[derive(Deserialize)]
+pub struct AuditQuery {
+    pub limit: Option<i64>,
+}
+
+/// Asociación de una identidad externa a un proyecto: un token del IdP cuyo
+/// claim `claim` vale (o, si es lista, contiene) `value` se canjea por uno del
+/// proyecto.
+#[derive(Deserialize, ToSchema)]
+pub struct CreateIdpMappingRequest {
+    /// Nombre del claim; `.` para anidar (`realm_access.roles`).
+    #[schema(example = "groups")]
+    pub claim: String,
+    #[schema(example = "video-operadores")]
+    pub value: String,
+    pub project_id: Uuid,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct IdpMappingResponse {
+    pub id: Uuid,
+    #[schema(example = "groups")]
+    pub claim: String,
+    #[schema(example = "video-operadores")]
+    pub value: String,
+    pub project_id: Uuid,
+    pub created_at: DateTime<Utc>,
+}
+
+impl From<IdpMapping> for IdpMappingResponse {
+    fn from(m: IdpMapping) -> Self {
+        Self {
+            id: m.id,
+            claim: m.claim,
+            value: m.value,
+            project_id: m.project_id,
+            created_at: m.created_at,
+        }
+    }
+}
+
+/// Login de una cuenta de administración.
+#[derive(Deserialize, ToSchema)]
+pub struct AdminLoginRequest {
+    #[schema(example = "ana")]
+    pub username: String,
+    pub password: String,
+}
+
+/// Sesión abierta: usar `token` como `Authorization: Bearer` en `/admin/*`.
+#[derive(Serialize, ToSchema)]
+pub struct AdminLoginResponse {
+    pub token: String,
+    #[schema(example = "Bearer")]
+    pub token_type: String,
+    /// Segundos de vida de la sesión.
+    pub expires_in: i64,
+    pub username: String,
+    #[schema(example = "operator")]
+    pub role: String,
+}
+
+/// Quién está autenticado (cuenta, `ADMIN_API_TOKEN` o clave de API) y con
+/// qué rol o alcances.
+#[derive(Serialize, ToSchema)]
+pub struct AdminMeResponse {
+    /// Usuario, `ADMIN_API_TOKEN` o `key:<nombre>`.
+    pub username: String,
+    /// Solo cuentas y token de emergencia.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schema(example = "viewer")]
+    pub role: Option<String>,
+    /// Solo claves de API.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub scopes: Option<Vec<String>>,
+}
+
+/// Cuenta de administración SIN el hash de su contraseña.
+#[derive(Serialize, ToSchema)]
+pub struct AdminAccountResponse {
+    pub id: Uuid,
+    pub username: String,
+    #[schema(example = "operator")]
+    pub role: String,
+    pub enabled: bool,
+    pub last_login_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl From<AdminAccount> for AdminAccountResponse {
+    fn from(a: AdminAccount) -> Self {
+        Self {
+            id: a.id,
+            username: a.username,
+            role: a.role.as_str().to_string(),
+            enabled: a.enabled,
+            last_login_at: a.last_login_at,
+            created_at: a.created_at,
+            updated_at: a.updated_at,
+        }
+    }
+}
+
+/// Alta de cuenta. Sin `password`, la genera el servidor y la devuelve una
+/// única vez.
+#[derive(Deserialize, ToSchema)]
+pub struct CreateAdminAccountRequest {
+    /// Minúsculas, dígitos y `.`, `_`, `-`, `@` (hasta 64).
+    #[schema(example = "ana")]
+    pub username: String,
+    /// Debe cumplir la política de fortaleza. Omitido = generada por el servidor.
+    pub password: Option<String>,
+    /// `viewer`, `operator` o `admin`.
+    #[schema(example = "operator")]
+    pub role: String,
+}
+
+/// Cuenta recién creada. `password` solo viene si la generó el servidor.
+#[derive(Serialize, ToSchema)]
+pub struct CreatedAdminAccountResponse {
+    #[serde(flatten)]
+    pub account: AdminAccountResponse,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub password: Option<String>,
+}
+
+/// Edición parcial de cuenta. Cambiar la contraseña o deshabilitarla cierra
+/// sus sesiones; un cambio de rol aplica desde el siguiente request.
+#[derive(Deserialize, ToSchema)]
+pub struct UpdateAdminAccountRequest {
+    pub password: Option<String>,
+    pub role: Option<String>,
+    pub enabled: Option<bool>,
+}
+
+/// Clave de API SIN la clave (solo su prefijo, para reconocerla).
+#[derive(Serialize, ToSchema)]
+pub struct ApiKeyResponse {
+    pub id: Uuid,
+    pub name: String,
+    #[schema(example = "mk_Q2xh")]
+    pub key_prefix: String,
+    #[schema(example = json!(["failures:read", "failures:write"]))]
+    pub scopes: Vec<String>,
+    pub expires_at: Option<DateTime<Utc>>,
+    /// `true` si ya venció (la clave deja de valer; se borra y se crea otra).
+    pub expired: bool,
+    pub last_used_at: Option<DateTime<Utc>>,
+    pub created_by: String,
+    pub created_at: DateTime<Utc>,
+}
+
+impl From<ApiKey> for ApiKeyResponse {
+    fn from(k: ApiKey) -> Self {
+        Self {
+            expired: k.is_expired(Utc::now()),
+            id: k.id,
+            name: k.name,
+            key_prefix: k.key_prefix,
+            scopes: k.scopes,
+            expires_at: k.expires_at,
+            last_used_at: k.last_used_at,
+            created_by: k.created_by,
+            created_at: k.created_at,
+        }
+    }
+}
+
+/// Alta de una clave de API para un servicio.
+#[derive(Deserialize, ToSchema)]
+pub struct CreateApiKeyRequest {
+    /// Minúsculas, dígitos y `.`, `_`, `-`, `@` (hasta 64).
+    #[schema(example = "stream-agent")]
+    pub name: String,
+    /// `<recurso>:read` (GET) o `<recurso>:write` (el resto) sobre `cameras`,
+    /// `camera-groups`, `projects`, `failures`, `revocations`, `lockouts`,
+    /// `audit` o `idp-mappings`. Al menos uno.
+    #[schema(example = json!(["failures:read", "failures:write"]))]
+    pub scopes: Vec<String>,
+    /// Omitido = no vence.
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+/// Clave recién creada: `key` se muestra una única vez.
+#[derive(Serialize, ToSchema)]
+pub struct CreatedApiKeyResponse {
+    #[serde(flatten)]
+    pub api_key: ApiKeyResponse,
+    pub key: String,
+}
+
+// ---------------------------------------------------------------------------
+// Handlers
+// ---------------------------------------------------------------------------
+
+#[utoipa::path(
+    get, path = "/admin/cameras", tag = "Administration",
+    security(("admin_token" = [])),
+    responses(
+        (status = 200, description = "Lista de cámaras", body = [CameraResponse]),
+        (status = 401, description = "No autorizado")
+    )
+)]
+pub async fn list_cameras(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<Vec<CameraResponse>>, StatusCode> {
+    let cameras = state
+        .camera_repo
+        .list_all()
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    Ok(Json(cameras.into_iter().map(CameraResponse::from).collect()))
+}
+
+#[utoipa::path(
     post, path = "/admin/cameras", tag = "Administration",
     security(("admin_token" = [])),
     request_body = CreateCameraRequest,
     responses(
         (status = 201, description = "Cámara creada", body = CameraResponse),
+        (status = 400, description = "Falta la fuente o sobra (rtsp_url vs publisher)"),
         (status = 401, description = "No autorizado"),
         (status = 409, description = "Path de cámara duplicado")
     )
@@ -244,6 +1267,12 @@ pub async fn create_camera(
     State(state): State<Arc<AppState>>,
     Json(req): Json<CreateCameraRequest>,
 ) -> Result<(StatusCode, Json<CameraResponse>), (StatusCode, String)> {
+    if req.publisher == req.rtsp_url.is_some() {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            "indicar rtsp_url o publisher: true (solo uno)".to_string(),
+        ));
+    }
     let camera = state
         .camera_repo
         .create(NewCamera {
@@ -293,6 +1322,7 @@ pub async fn get_camera(
     request_body = UpdateCameraRequest,
     responses(
         (status = 200, description = "Cámara actualizada", body = CameraResponse),
+        (status = 400, description = "rtsp_url y publisher contradictorios"),
         (status = 404, description = "No encontrada"),
         (status = 401, description = "No autorizado")
     )
@@ -309,8 +1339,22 @@ pub async fn update_camera(
         .map_err(repo_err)?
         .ok_or((StatusCode::NOT_FOUND, "cámara no encontrada".to_string()))?;
 
-    if let Some(url) = req.rtsp_url {
-        camera.rtsp_url = url;
+    match (req.publisher, req.rtsp_url) {
+        (Some(true), Some(_)) => {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                "una cámara publisher no lleva rtsp_url".to_string(),
+            ))
+        }
+        (Some(true), None) => camera.rtsp_url = None,
+        (_, Some(url)) => camera.rtsp_url = Some(url),
+        (Some(false), None) if camera.is_publisher() => {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                "para dejar de ser publisher hace falta rtsp_url".to_string(),
+            ))
+        }
+        _ => {}
     }
     if let Some(record) = req.record {
         camera.record = record;
@@ -366,252 +1410,1696 @@ pub async fn delete_camera(
     Ok(StatusCode::NO_CONTENT)
 }
 
-/// Construye la respuesta de proyecto (incluye sus cámaras asignadas).
-async fn to_project_response(
+/// Construye la respuesta de grupo (incluye sus cámaras).
+async fn to_group_response(
     state: &AppState,
-    project: Project,
-) -> Result<ProjectResponse, (StatusCode, String)> {
+    group: CameraGroup,
+) -> Result<CameraGroupResponse, (StatusCode, String)> {
     let camera_ids = state
-        .project_repo
-        .assigned_camera_ids(project.id)
+        .camera_group_repo
+        .members(group.id)
         .await
         .map_err(repo_err)?;
-    Ok(ProjectResponse {
-        id: project.id,
-        client_id: project.client_id,
-        all_cameras: project.all_cameras,
-        enabled: project.enabled,
+    Ok(CameraGroupResponse {
+        id: group.id,
+        name: group.name,
+        description: group.description,
         camera_ids,
-        created_at: project.created_at,
-        updated_at: project.updated_at,
+        created_at: group.created_at,
+        updated_at: group.updated_at,
     })
 }
 
+async fn find_group(state: &AppState, id: Uuid) -> Result<CameraGroup, (StatusCode, String)> {
+    state
+        .camera_group_repo
+        .find_by_id(id)
+        .await
+        .map_err(repo_err)?
+        .ok_or((StatusCode::NOT_FOUND, "grupo de cámaras no encontrado".to_string()))
+}
+
+/// 404 si algún grupo a asignar no existe.
+async fn check_groups(state: &AppState, groups: &[GroupGrant]) -> Result<(), (StatusCode, String)> {
+    for g in groups {
+        find_group(state, g.group_id).await?;
+    }
+    Ok(())
+}
+
+/// 404 si alguna cámara a agrupar no existe.
+async fn check_cameras(state: &AppState, camera_ids: &[Uuid]) -> Result<(), (StatusCode, String)> {
+    for id in camera_ids {
+        if state.camera_repo.find_by_id(*id).await.map_err(repo_err)?.is_none() {
+            return Err((StatusCode::NOT_FOUND, format!("cámara no encontrada: {id}")));
+        }
+    }
+    Ok(())
+}
+
+fn group_name(name: &str) -> Result<String, (StatusCode, String)> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err((StatusCode::BAD_REQUEST, "name es obligatorio".to_string()));
+    }
+    Ok(name.to_string())
+}
+
 #[utoipa::path(
-    get, path = "/admin/projects", tag = "Administration",
+    get, path = "/admin/camera-groups", tag = "Administration",
     security(("admin_token" = [])),
     responses(
-        (status = 200, description = "Lista de proyectos", body = [ProjectResponse]),
+        (status = 200, description = "Grupos de cámaras", body = [CameraGroupResponse]),
         (status = 401, description = "No autorizado")
     )
 )]
-pub async fn list_projects(
+pub async fn list_camera_groups(
     State(state): State<Arc<AppState>>,
-) -> Result<Json<Vec<ProjectResponse>>, (StatusCode, String)> {
-    let projects = state.project_repo.list_all().await.map_err(repo_err)?;
-    let mut out = Vec::with_capacity(projects.len());
-    for project in projects {
-        out.push(to_project_response(&state, project).await?);
+) -> Result<Json<Vec<CameraGroupResponse>>, (StatusCode, String)> {
+    let groups = state.camera_group_repo.list_all().await.map_err(repo_err)?;
+    let mut out = Vec::with_capacity(groups.len());
+    for group in groups {
+        out.push(to_group_response(&state, group).await?);
     }
     Ok(Json(out))
 }
 
 #[utoipa::path(
-    post, path = "/admin/projects", tag = "Administration",
+    post, path = "/admin/camera-groups", tag = "Administration",
     security(("admin_token" = [])),
-    request_body = CreateProjectRequest,
+    request_body = CreateCameraGroupRequest,
     responses(
-        (status = 201, description = "Proyecto creado", body = ProjectResponse),
-        (status = 401, description = "No autorizado"),
-        (status = 409, description = "client_id duplicado")
+        (status = 201, description = "Grupo creado", body = CameraGroupResponse),
+        (status = 400, description = "Nombre vacío"),
+        (status = 404, description = "Cámara inexistente"),
+        (status = 409, description = "Nombre duplicado"),
+        (status = 401, description = "No autorizado")
     )
 )]
-pub async fn create_project(
+pub async fn create_camera_group(
     State(state): State<Arc<AppState>>,
-    Json(req): Json<CreateProjectRequest>,
-) -> Result<(StatusCode, Json<ProjectResponse>), (StatusCode, String)> {
-    let secret_hash = crate::secret::hash_secret(&req.secret)
-        .map_err(|_| (StatusCode::INTERNAL_SERVER_ERROR, "error interno".to_string()))?;
-
-    let project = state
-        .project_repo
-        .create(NewProject {
-            client_id: req.client_id,
-            secret_hash,
-            all_cameras: req.all_cameras,
-            enabled: true,
+    Json(req): Json<CreateCameraGroupRequest>,
+) -> Result<(StatusCode, Json<CameraGroupResponse>), (StatusCode, String)> {
+    let name = group_name(&req.name)?;
+    check_cameras(&state, &req.camera_ids).await?;
+    let group = state
+        .camera_group_repo
+        .create(NewCameraGroup {
+            name,
+            description: req.description,
         })
         .await
         .map_err(repo_err)?;
-
     if !req.camera_ids.is_empty() {
         state
-            .project_repo
-            .set_cameras(project.id, &req.camera_ids)
+            .camera_group_repo
+            .set_members(group.id, &req.camera_ids)
             .await
             .map_err(repo_err)?;
     }
-
-    let resp = to_project_response(&state, project).await?;
-    Ok((StatusCode::CREATED, Json(resp)))
+    info!("grupo de cámaras creado: {}", group.name);
+    Ok((StatusCode::CREATED, Json(to_group_response(&state, group).await?)))
 }
 
 #[utoipa::path(
-    get, path = "/admin/projects/{id}", tag = "Administration",
+    get, path = "/admin/camera-groups/{id}", tag = "Administration",
     security(("admin_token" = [])),
-    params(("id" = Uuid, Path, description = "ID del proyecto")),
+    params(("id" = Uuid, Path, description = "ID del grupo")),
     responses(
-        (status = 200, description = "Proyecto", body = ProjectResponse),
+        (status = 200, description = "Grupo", body = CameraGroupResponse),
         (status = 404, description = "No encontrado"),
         (status = 401, description = "No autorizado")
     )
 )]
-pub async fn get_project(
+pub async fn get_camera_group(
     State(state): State<Arc<AppState>>,
     Path(id): Path<Uuid>,
-) -> Result<Json<ProjectResponse>, (StatusCode, String)> {
-    let project = state
-        .project_repo
-        .find_by_id(id)
-        .await
-        .map_err(repo_err)?
-        .ok_or((StatusCode::NOT_FOUND, "proyecto no encontrado".to_string()))?;
-    Ok(Json(to_project_response(&state, project).await?))
+) -> Result<Json<CameraGroupResponse>, (StatusCode, String)> {
+    let group = find_group(&state, id).await?;
+    Ok(Json(to_group_response(&state, group).await?))
 }
 
 #[utoipa::path(
-    patch, path = "/admin/projects/{id}", tag = "Administration",
+    patch, path = "/admin/camera-groups/{id}", tag = "Administration",
     security(("admin_token" = [])),
-    params(("id" = Uuid, Path, description = "ID del proyecto")),
-    request_body = UpdateProjectRequest,
+    params(("id" = Uuid, Path, description = "ID del grupo")),
+    request_body = UpdateCameraGroupRequest,
     responses(
-        (status = 200, description = "Proyecto actualizado", body = ProjectResponse),
-        (status = 404, description = "No encontrado"),
+        (status = 200, description = "Grupo actualizado", body = CameraGroupResponse),
+        (status = 400, description = "Nombre vacío"),
+        (status = 404, description = "Grupo o cámara inexistente"),
+        (status = 409, description = "Nombre duplicado"),
         (status = 401, description = "No autorizado")
     )
 )]
-pub async fn update_project(
+pub async fn update_camera_group(
     State(state): State<Arc<AppState>>,
     Path(id): Path<Uuid>,
-    Json(req): Json<UpdateProjectRequest>,
-) -> Result<Json<ProjectResponse>, (StatusCode, String)> {
-    let mut project = state
-        .project_repo
-        .find_by_id(id)
-        .await
-        .map_err(repo_err)?
-        .ok_or((StatusCode::NOT_FOUND, "proyecto no encontrado".to_string()))?;
-
-    if let Some(secret) = req.secret {
-        project.secret_hash = crate::secret::hash_secret(&secret)
-            .map_err(|_| (StatusCode::INTERNAL_SERVER_ERROR, "error interno".to_string()))?;
+    Json(req): Json<UpdateCameraGroupRequest>,
+) -> Result<Json<CameraGroupResponse>, (StatusCode, String)> {
+    let mut group = find_group(&state, id).await?;
+    if let Some(name) = req.name {
+        group.name = group_name(&name)?;
     }
-    if let Some(all_cameras) = req.all_cameras {
-        project.all_cameras = all_cameras;
+    if let Some(description) = req.description {
+        group.description = Some(description);
     }
-    if let Some(enabled) = req.enabled {
-        project.enabled = enabled;
+    if let Some(camera_ids) = &req.camera_ids {
+        check_cameras(&state, camera_ids).await?;
     }
-
-    let updated = state.project_repo.update(&project).await.map_err(repo_err)?;
-
+    let updated = state.camera_group_repo.update(&group).await.map_err(repo_err)?;
     if let Some(camera_ids) = req.camera_ids {
         state
-            .project_repo
-            .set_cameras(updated.id, &camera_ids)
+            .camera_group_repo
+            .set_members(updated.id, &camera_ids)
             .await
             .map_err(repo_err)?;
     }
-
-    Ok(Json(to_project_response(&state, updated).await?))
+    Ok(Json(to_group_response(&state, updated).await?))
 }
 
 #[utoipa::path(
-    delete, path = "/admin/projects/{id}", tag = "Administration",
+    delete, path = "/admin/camera-groups/{id}", tag = "Administration",
     security(("admin_token" = [])),
-    params(("id" = Uuid, Path, description = "ID del proyecto")),
+    params(("id" = Uuid, Path, description = "ID del grupo")),
     responses(
-        (status = 204, description = "Proyecto eliminado"),
+        (status = 204, description = "Grupo eliminado (los proyectos pierden sus cámaras)"),
         (status = 404, description = "No encontrado"),
         (status = 401, description = "No autorizado")
     )
 )]
-pub async fn delete_project(
+pub async fn delete_camera_group(
     State(state): State<Arc<AppState>>,
     Path(id): Path<Uuid>,
 ) -> Result<StatusCode, (StatusCode, String)> {
-    state.project_repo.delete(id).await.map_err(repo_err)?;
+    state.camera_group_repo.delete(id).await.map_err(repo_err)?;
+    info!("grupo de cámaras eliminado: {}", id);
     Ok(StatusCode::NO_CONTENT)
 }
 
+/// Agrega una cámara al grupo: la ven ya todos los proyectos con el grupo.
 #[utoipa::path(
-    post, path = "/admin/failures", tag = "Administration",
+    post, path = "/admin/camera-groups/{id}/cameras/{camera_id}", tag = "Administration",
     security(("admin_token" = [])),
-    request_body = RecordFailureRequest,
+    params(
+        ("id" = Uuid, Path, description = "ID del grupo"),
+        ("camera_id" = Uuid, Path, description = "ID de la cámara")
+    ),
     responses(
-        (status = 201, description = "Diagnóstico registrado", body = FailureResponse),
+        (status = 204, description = "Cámara en el grupo (idempotente)"),
+        (status = 404, description = "Grupo o cámara inexistente"),
         (status = 401, description = "No autorizado")
     )
 )]
-pub async fn record_failure(
+pub async fn add_group_camera(
     State(state): State<Arc<AppState>>,
-    Json(req): Json<RecordFailureRequest>,
-) -> Result<(StatusCode, Json<FailureResponse>), (StatusCode, String)> {
-    let failure = state
-        .failure_repo
-        .record(NewFailure {
-            camera_path: req.camera_path,
-            detected_at: req.detected_at.unwrap_or_else(Utc::now),
-            severity: Severity::from_db(&req.severity),
-            diagnosis: req.diagnosis,
-            raw: req.raw,
-        })
+    Path((id, camera_id)): Path<(Uuid, Uuid)>,
+) -> Result<StatusCode, (StatusCode, String)> {
+    let group = find_group(&state, id).await?;
+    check_cameras(&state, &[camera_id]).await?;
+    state
+        .camera_group_repo
+        .add_member(group.id, camera_id)
         .await
         .map_err(repo_err)?;
-    Ok((StatusCode::CREATED, Json(failure.into())))
+    Ok(StatusCode::NO_CONTENT)
 }
 
 #[utoipa::path(
-    get, path = "/admin/failures", tag = "Administration",
+    delete, path = "/admin/camera-groups/{id}/cameras/{camera_id}", tag = "Administration",
     security(("admin_token" = [])),
     params(
-        ("camera" = String, Query, description = "Path de la cámara"),
-        ("limit" = Option<i64>, Query, description = "Máximo de registros (default 50)")
+        ("id" = Uuid, Path, description = "ID del grupo"),
+        ("camera_id" = Uuid, Path, description = "ID de la cámara")
     ),
     responses(
-        (status = 200, description = "Historial de la cámara", body = [FailureResponse]),
+        (status = 204, description = "Cámara quitada del grupo"),
+        (status = 404, description = "La cámara no estaba en el grupo"),
         (status = 401, description = "No autorizado")
     )
 )]
-pub async fn list_failures(
+pub async fn remove_group_camera(
     State(state): State<Arc<AppState>>,
-    Query(q): Query<FailureQuery>,
-) -> Result<Json<Vec<FailureResponse>>, (StatusCode, String)> {
-    let limit = q.limit.unwrap_or(50).clamp(1, 500);
-    let failures = state
-        .failure_repo
-        .list_by_camera(&q.camera, limit)
+    Path((id, camera_id)): Path<(Uuid, Uuid)>,
+) -> Result<StatusCode, (StatusCode, String)> {
+    state
+        .camera_group_repo
+        .remove_member(id, camera_id)
         .await
         .map_err(repo_err)?;
-    Ok(Json(failures.into_iter().map(FailureResponse::from).collect()))
+    Ok(StatusCode::NO_CONTENT)
 }
 
-#[cfg(test)]
-mod tests {
-    use super::is_authorized;
-
-    #[test]
-    fn empty_config_denies_all() {
-        assert!(!is_authorized("", Some("Bearer x")));
-    }
-
-    #[test]
-    fn correct_bearer_allows() {
-        assert!(is_authorized("secret", Some("Bearer secret")));
-    }
-
-    #[test]
-    fn wrong_token_denies() {
-        assert!(!is_authorized("secret", Some("Bearer otro")));
-    }
-
-    #[test]
-    fn missing_header_denies() {
-        assert!(!is_authorized("secret", None));
-    }
+/// Construye la respuesta de proyecto (incluye cámaras, grupos y prefijos
+/// asignados y permisos de publicación).
+async fn to_project_response(
+    state: &AppState,
+    project: Project,
+) -> Result<ProjectResponse, (StatusCode, String)> {
+    let cameras = state
+        .project_repo
+        .assigned_cameras(project.id)
+        .await
+        .map_err(repo_err)?;
+    let groups = state
+        .project_repo
+        .assigned_groups(project.id)
+        .await
+        .map_err(repo_err)?;
+    let prefixes = state
+        .project_repo
+        .prefix_grants(project.id)
+        .await
+        .map_err(repo_err)?;
+    let publish = state
+        .project_repo
+        .publish_grants(project.id)
+        .await
+        .map_err(repo_err)?;
+    Ok(ProjectResponse {
+        id: project.id,
+        client_id: project.client_id,
+        all_cameras: project.all_cameras,
+        enabled: project.enabled,
+        camera_ids: cameras.iter().map(|c| c.camera_id).collect(),
+        cameras: cameras.into_iter().map(CameraGrantDto::from).collect(),
+        groups: groups.into_iter().map(GroupGrantDto::from).collect(),
+        prefixes: prefixes.into_iter().map(PrefixGrantDto::from).collect(),
+        token_max_minutes: project.token_max_minutes,
+        token_default_minutes: project.token_default_minutes,
+        allowed_audiences: project.allowed_audiences,
+        publish: publish.into_iter().map(PublishGrantDto::from).collect(),
+        allowed_cidrs: project.allowed_cidrs,
+        created_at: project.created_at,
+        updated_at: project.updated_at,
+    })
+}
+
+#[utoipa::path(
+    get, path = "/admin/projects", tag = "Administration",
+    security(("admin_token" = [])),
+    responses(
+        (status = 200, description = "Lista de proyectos", body = [ProjectResponse]),
+        (status = 401, description = "No autorizado")
+    )
+)]
+pub async fn list_projects(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<Vec<ProjectResponse>>, (StatusCode, String)> {
+    let projects = state.project_repo.list_all().await.map_err(repo_err)?;
+    let mut out = Vec::with_capacity(projects.len());
+    for project in projects {
+        out.push(to_project_response(&state, project).await?);
+    }
+    Ok(Json(out))
+}
+
+#[utoipa::path(
+    post, path = "/admin/projects", tag = "Administration",
+    security(("admin_token" = [])),
+    request_body = CreateProjectRequest,
+    responses(
+        (status = 201, description = "Proyecto creado (con `client_secret` si lo generó el servidor)", body = CreatedProjectResponse),
+        (status = 400, description = "Política de tokens inválida, secreto débil, path de publicación, prefijo o red inválidos"),
+        (status = 401, description = "No autorizado"),
+        (status = 404, description = "Grupo de cámaras inexistente"),
+        (status = 409, description = "client_id duplicado")
+    )
+)]
+pub async fn create_project(
+    State(state): State<Arc<AppState>>,
+    Json(req): Json<CreateProjectRequest>,
+) -> Result<impl IntoResponse, (StatusCode, String)> {
+    let (secret, generated) = match req.secret {
+        Some(secret) => {
+            check_strength(&secret, &req.client_id)?;
+            (secret, false)
+        }
+        None => (crate::secret::generate_secret(), true),
+    };
+    let secret_hash = crate::secret::hash_secret(&secret, &state.config.hash_policy)
+        .map_err(|_| (StatusCode::INTERNAL_SERVER_ERROR, "error interno".to_string()))?;
+
+    let mut policy = TokenPolicy::default();
+    policy.apply(req.token_max_minutes, req.token_default_minutes, Some(req.allowed_audiences));
+    policy.validate(state.config.jwt_max_exp_minutes)?;
+    let publish = publish_grants(req.publish)?;
+    let cameras = camera_grants(req.camera_ids, req.cameras)?;
+    let groups = group_grants(req.groups)?;
+    check_groups(&state, &groups).await?;
+    let prefixes = prefix_grants(req.prefixes)?;
+    let cidrs = allowed_cidrs(req.allowed_cidrs)?;
+
+    let mut project = state
+        .project_repo
+        .create(NewProject {
+            client_id: req.client_id,
+            secret_hash,
+            all_cameras: req.all_cameras,
+            enabled: true,
+        })
+        .await
+        .map_err(repo_err)?;
+    let id = project.id;
+
+    // Si falla algo de aquí en adelante, el proyecto se borra: a medio
+    // configurar y con un secreto generado que nadie recibió no sirve.
+    let configured = async {
+        if policy != TokenPolicy::default() || !cidrs.is_empty() {
+            policy.store(&mut project);
+            project.allowed_cidrs = cidrs;
+            project = state.project_repo.update(&project).await?;
+        }
+        if !cameras.is_empty() {
+            state.project_repo.set_cameras(id, &cameras).await?;
+        }
+        if !groups.is_empty() {
+            state.project_repo.set_groups(id, &groups).await?;
+        }
+        if !prefixes.is_empty() {
+            state.project_repo.set_prefix_grants(id, &prefixes).await?;
+        }
+        if !publish.is_empty() {
+            state.project_repo.set_publish_grants(id, &publish).await?;
+        }
+        Ok::<_, RepoError>(project)
+    }
+    .await
+    .map_err(repo_err);
+    let response = match configured {
+        Ok(project) => to_project_response(&state, project).await,
+        Err(e) => Err(e),
+    };
+    let project = match response {
+        Ok(project) => project,
+        Err(e) => {
+            warn!("alta del proyecto {} incompleta, se deshace: {}", id, e.1);
+            if let Err(e) = state.project_repo.delete(id).await {
+                warn!("no se pudo borrar el proyecto incompleto {}: {}", id, e);
+            }
+            return Err(e);
+        }
+    };
+
+    let resp = CreatedProjectResponse {
+        project,
+        client_secret: generated.then_some(secret),
+    };
+    Ok((StatusCode::CREATED, NO_STORE, Json(resp)))
+}
+
+#[utoipa::path(
+    get, path = "/admin/projects/{id}", tag = "Administration",
+    security(("admin_token" = [])),
+    params(("id" = Uuid, Path, description = "ID del proyecto")),
+    responses(
+        (status = 200, description = "Proyecto", body = ProjectResponse),
+        (status = 404, description = "No encontrado"),
+        (status = 401, description = "No autorizado")
+    )
+)]
+pub async fn get_project(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<ProjectResponse>, (StatusCode, String)> {
+    let project = state
+        .project_repo
+        .find_by_id(id)
+        .await
+        .map_err(repo_err)?
+        .ok_or((StatusCode::NOT_FOUND, "proyecto no encontrado".to_string()))?;
+    Ok(Json(to_project_response(&state, project).await?))
+}
+
+#[utoipa::path(
+    patch, path = "/admin/projects/{id}", tag = "Administration",
+    security(("admin_token" = [])),
+    params(("id" = Uuid, Path, description = "ID del proyecto")),
+    request_body = UpdateProjectRequest,
+    responses(
+        (status = 200, description = "Proyecto actualizado", body = ProjectResponse),
+        (status = 400, description = "Política de tokens inválida, secreto débil, path de publicación, prefijo o red inválidos"),
+        (status = 404, description = "Proyecto o grupo de cámaras inexistente"),
+        (status = 401, description = "No autorizado")
+    )
+)]
+pub async fn update_project(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<Uuid>,
+    Json(req): Json<UpdateProjectRequest>,
+) -> Result<Json<ProjectResponse>, (StatusCode, String)> {
+    let mut project = state
+        .project_repo
+        .find_by_id(id)
+        .await
+        .map_err(repo_err)?
+        .ok_or((StatusCode::NOT_FOUND, "proyecto no encontrado".to_string()))?;
+
+    if let Some(secret) = &req.secret {
+        check_strength(secret, &project.client_id)?;
+    }
+    if let Some(all_cameras) = req.all_cameras {
+        project.all_cameras = all_cameras;
+    }
+    let was_enabled = project.enabled;
+    if let Some(enabled) = req.enabled {
+        project.enabled = enabled;
+    }
+
+    let mut policy = TokenPolicy::of(&project);
+    policy.apply(req.token_max_minutes, req.token_default_minutes, req.allowed_audiences);
+    policy.validate(state.config.jwt_max_exp_minutes)?;
+    policy.store(&mut project);
+    let publish = req.publish.map(publish_grants).transpose()?;
+    let cameras = match (req.camera_ids, req.cameras) {
+        (None, None) => None,
+        (ids, cameras) => Some(camera_grants(
+            ids.unwrap_or_default(),
+            cameras.unwrap_or_default(),
+        )?),
+    };
+    let groups = req.groups.map(group_grants).transpose()?;
+    if let Some(groups) = &groups {
+        check_groups(&state, groups).await?;
+    }
+    let prefixes = req.prefixes.map(prefix_grants).transpose()?;
+    if let Some(cidrs) = req.allowed_cidrs {
+        project.allowed_cidrs = allowed_cidrs(cidrs)?;
+    }
+
+    // Los secretos se tocan solo con todo lo anterior validado: un 400 no
+    // debe dejar al proyecto sin sus secretos desplegados.
+    if let Some(secret) = req.secret {
+        // Reemplazo inmediato: el nuevo queda como único secreto vigente.
+        let added = store_secret(&state, project.id, &secret, "reemplazo", None).await?;
+        state
+            .secret_repo
+            .revoke_others(project.id, added.id)
+            .await
+            .map_err(repo_err)?;
+        info!("secretos de {} reemplazados", project.client_id);
+    }
+    let updated = state.project_repo.update(&project).await.map_err(repo_err)?;
+
+    // Deshabilitar corta también los tokens ya emitidos, no solo los logins.
+    if was_enabled && !updated.enabled {
+        revoke_project_tokens(&state, &updated, "proyecto deshabilitado").await?;
+    }
+
+    if let Some(cameras) = cameras {
+        state
+            .project_repo
+            .set_cameras(updated.id, &cameras)
+            .await
+            .map_err(repo_err)?;
+    }
+    if let Some(groups) = groups {
+        state
+            .project_repo
+            .set_groups(updated.id, &groups)
+            .await
+            .map_err(repo_err)?;
+    }
+    if let Some(prefixes) = prefixes {
+        state
+            .project_repo
+            .set_prefix_grants(updated.id, &prefixes)
+            .await
+            .map_err(repo_err)?;
+    }
+    if let Some(publish) = publish {
+        state
+            .project_repo
+            .set_publish_grants(updated.id, &publish)
+            .await
+            .map_err(repo_err)?;
+    }
+
+    Ok(Json(to_project_response(&state, updated).await?))
+}
+
+#[utoipa::path(
+    delete, path = "/admin/projects/{id}", tag = "Administration",
+    security(("admin_token" = [])),
+    params(("id" = Uuid, Path, description = "ID del proyecto")),
+    responses(
+        (status = 204, description = "Proyecto eliminado"),
+        (status = 404, description = "No encontrado"),
+        (status = 401, description = "No autorizado")
+    )
+)]
+pub async fn delete_project(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<Uuid>,
+) -> Result<StatusCode, (StatusCode, String)> {
+    let project = state
+        .project_repo
+        .find_by_id(id)
+        .await
+        .map_err(repo_err)?
+        .ok_or((StatusCode::NOT_FOUND, "proyecto no encontrado".to_string()))?;
+    state.project_repo.delete(id).await.map_err(repo_err)?;
+    revoke_project_tokens(&state, &project, "proyecto eliminado").await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Las respuestas con un secreto en claro no deben cachearse.
+const NO_STORE: [(header::HeaderName, &str); 1] = [(header::CACHE_CONTROL, "no-store")];
+
+/// Política de fortaleza para secretos elegidos por el admin (400 si no la cumple).
+fn check_strength(secret: &str, client_id: &str) -> Result<(), (StatusCode, String)> {
+    crate::secret::check_strength(secret, client_id).map_err(|msg| (StatusCode::BAD_REQUEST, msg))
+}
+
+/// Hashea y guarda un secreto nuevo del proyecto.
+async fn store_secret(
+    state: &AppState,
+    project_id: Uuid,
+    secret: &str,
+    label: &str,
+    expires_at: Option<DateTime<Utc>>,
+) -> Result<ProjectSecret, (StatusCode, String)> {
+    let secret_hash = crate::secret::hash_secret(secret, &state.config.hash_policy)
+        .map_err(|_| (StatusCode::INTERNAL_SERVER_ERROR, "error interno".to_string()))?;
+    state
+        .secret_repo
+        .add(NewProjectSecret {
+            project_id,
+            label: label.to_string(),
+            secret_hash,
+            expires_at,
+        })
+        .await
+        .map_err(repo_err)
+}
+
+async fn find_project(state: &AppState, id: Uuid) -> Result<Project, (StatusCode, String)> {
+    state
+        .project_repo
+        .find_by_id(id)
+        .await
+        .map_err(repo_err)?
+        .ok_or((StatusCode::NOT_FOUND, "proyecto no encontrado".to_string()))
+}
+
+/// Secretos del proyecto (vigentes y revocados), sin hashes.
+#[utoipa::path(
+    get, path = "/admin/projects/{id}/secrets", tag = "Administration",
+    security(("admin_token" = [])),
+    params(("id" = Uuid, Path, description = "ID del proyecto")),
+    responses(
+        (status = 200, description = "Secretos del proyecto", body = [ProjectSecretResponse]),
+        (status = 404, description = "Proyecto no encontrado"),
+        (status = 401, description = "No autorizado")
+    )
+)]
+pub async fn list_project_secrets(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<Vec<ProjectSecretResponse>>, (StatusCode, String)> {
+    let project = find_project(&state, id).await?;
+    let secrets = state
+        .secret_repo
+        .list_by_project(project.id)
+        .await
+        .map_err(repo_err)?;
+    Ok(Json(secrets.into_iter().map(ProjectSecretResponse::from).collect()))
+}
+
+/// Agrega un secreto al proyecto sin tocar los vigentes (rotación sin corte:
+/// agregar, migrar los consumidores, revocar el viejo).
+#[utoipa::path(
+    post, path = "/admin/projects/{id}/secrets", tag = "Administration",
+    security(("admin_token" = [])),
+    params(("id" = Uuid, Path, description = "ID del proyecto")),
+    request_body = AddSecretRequest,
+    responses(
+        (status = 201, description = "Secreto agregado", body = ProjectSecretResponse),
+        (status = 400, description = "Etiqueta vacía, expiración en el pasado o secreto débil"),
+        (status = 404, description = "Proyecto no encontrado"),
+        (status = 401, description = "No autorizado")
+    )
+)]
+pub async fn add_project_secret(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<Uuid>,
+    Json(req): Json<AddSecretRequest>,
+) -> Result<(StatusCode, Json<ProjectSecretResponse>), (StatusCode, String)> {
+    let bad = |msg: &str| (StatusCode::BAD_REQUEST, msg.to_string());
+    let label = req.label.trim();
+    if label.is_empty() {
+        return Err(bad("label no puede estar vacío"));
+    }
+    if req.expires_at.is_some_and(|e| e <= Utc::now()) {
+        return Err(bad("expires_at debe estar en el futuro"));
+    }
+    let project = find_project(&state, id).await?;
+    check_strength(&req.secret, &project.client_id)?;
+    let added = store_secret(&state, project.id, &req.secret, label, req.expires_at).await?;
+    info!("secreto '{}' agregado a {}", added.label, project.client_id);
+    Ok((StatusCode::CREATED, Json(added.into())))
+}
+
+/// Acciones sobre un proyecto con sintaxis `recurso:verbo` (hoy solo
+/// `secrets:rotate`); el router no admite `:` dentro de un segmento fijo.
+pub async fn project_action(
+    state: State<Arc<AppState>>,
+    Path((id, action)): Path<(Uuid, String)>,
+    body: Option<Json<RotateSecretRequest>>,
+) -> Result<Response, (StatusCode, String)> {
+    match action.as_str() {
+        "secrets:rotate" => Ok(rotate_project_secret(state, Path(id), body)
+            .await?
+            .into_response()),
+        _ => Err((StatusCode::NOT_FOUND, "acción desconocida".to_string())),
+    }
+}
+
+/// Gracia máxima de los secretos anteriores al rotar (una semana).
+const MAX_SECRET_GRACE_MINUTES: i64 = 7 * 24 * 60;
+
+/// Fin de la gracia de `minutes` desde `now`; fuera de `0..=MAX_SECRET_GRACE_MINUTES` → 400.
+fn grace_until(now: DateTime<Utc>, minutes: i64) -> Result<DateTime<Utc>, (StatusCode, String)> {
+    let bad = || {
+        (
+            StatusCode::BAD_REQUEST,
+            format!("previous_grace_minutes debe estar entre 0 y {MAX_SECRET_GRACE_MINUTES}"),
+        )
+    };
+    if !(0..=MAX_SECRET_GRACE_MINUTES).contains(&minutes) {
+        return Err(bad());
+    }
+    chrono::Duration::try_minutes(minutes)
+        .and_then(|d| now.checked_add_signed(d))
+        .ok_or_else(bad)
+}
+
+/// Genera un secreto nuevo (256 bits de `OsRng`) y lo devuelve en claro UNA
+/// sola vez; en la BD solo queda su hash. Los secretos anteriores siguen
+/// vigentes, caducan tras `previous_grace_minutes` o se revocan ya (`0`).
+#[utoipa::path(
+    post, path = "/admin/projects/{id}/secrets:rotate", tag = "Administration",
+    security(("admin_token" = [])),
+    params(("id" = Uuid, Path, description = "ID del proyecto")),
+    request_body(content = Option<RotateSecretRequest>, description = "Opcional"),
+    responses(
+        (status = 201, description = "Secreto generado (`client_secret` no se vuelve a mostrar)", body = GeneratedSecretResponse),
+        (status = 400, description = "Gracia negativa o de más de una semana"),
+        (status = 404, description = "Proyecto no encontrado"),
+        (status = 401, description = "No autorizado")
+    )
+)]
+pub async fn rotate_project_secret(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<Uuid>,
+    body: Option<Json<RotateSecretRequest>>,
+) -> Result<impl IntoResponse, (StatusCode, String)> {
+    let req = body.map(|Json(r)| r).unwrap_or_default();
+    // Se calcula antes de tocar nada: una gracia inválida no deja un secreto a medias.
+    let grace_until = req
+        .previous_grace_minutes
+        .map(|m| grace_until(Utc::now(), m))
+        .transpose()?;
+    let project = find_project(&state, id).await?;
+    let label = req
+        .label
+        .map(|l| l.trim().to_string())
+        .filter(|l| !l.is_empty())
+        .unwrap_or_else(|| format!("rotado-{}", Utc::now().format("%Y-%m-%d")));
+
+    let secret = crate::secret::generate_secret();
+    let added = store_secret(&state, project.id, &secret, &label, None).await?;
+    match (req.previous_grace_minutes, grace_until) {
+        (Some(0), _) => state.secret_repo.revoke_others(project.id, added.id).await,
+        (_, Some(at)) => state.secret_repo.expire_others(project.id, added.id, at).await,
+        _ => Ok(()),
+    }
+    .map_err(repo_err)?;
+
+    info!("secreto '{}' generado para {}", added.label, project.client_id);
+    let resp = GeneratedSecretResponse {
+        secret: added.into(),
+        client_secret: secret,
+    };
+    Ok((StatusCode::CREATED, NO_STORE, Json(resp)))
+}
+
+/// Revoca un secreto del proyecto. Los JWT ya emitidos siguen valiendo; para
+/// cortarlos, `POST /admin/revocations`.
+#[utoipa::path(
+    delete, path = "/admin/projects/{id}/secrets/{secret_id}", tag = "Administration",
+    security(("admin_token" = [])),
+    params(
+        ("id" = Uuid, Path, description = "ID del proyecto"),
+        ("secret_id" = Uuid, Path, description = "ID del secreto")
+    ),
+    responses(
+        (status = 204, description = "Secreto revocado"),
+        (status = 404, description = "Sin secreto vigente con ese ID en el proyecto"),
+        (status = 401, description = "No autorizado")
+    )
+)]
+pub async fn revoke_project_secret(
+    State(state): State<Arc<AppState>>,
+    Path((id, secret_id)): Path<(Uuid, Uuid)>,
+) -> Result<StatusCode, (StatusCode, String)> {
+    state
+        .secret_repo
+        .revoke(id, secret_id)
+        .await
+        .map_err(repo_err)?;
+    info!("secreto {} del proyecto {} revocado", secret_id, id);
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Revoca los JWT ya emitidos del proyecto y sus refresh tokens.
+async fn revoke_project_tokens(
+    state: &AppState,
+    project: &Project,
+    reason: &str,
+) -> Result<Revocation, (StatusCode, String)> {
+    let revocation = state
+        .revocations
+        .revoke(
+            RevocationTarget::Project {
+                client_id: project.client_id.clone(),
+                issued_before: Utc::now(),
+            },
+            Some(reason.to_string()),
+        )
+        .await
+        .map_err(repo_err)?;
+    state
+        .refresh
+        .revoke_project(project.id)
+        .await
+        .map_err(repo_err)?;
+    info!("tokens revocados para proyecto {}: {}", project.client_id, reason);
+    Ok(revocation)
+}
+
+#[utoipa::path(
+    post, path = "/admin/failures", tag = "Administration",
+    security(("admin_token" = [])),
+    request_body = RecordFailureRequest,
+    responses(
+        (status = 201, description = "Diagnóstico registrado", body = FailureResponse),
+        (status = 401, description = "No autorizado")
+    )
+)]
+pub async fn record_failure(
+    State(state): State<Arc<AppState>>,
+    Json(req): Json<RecordFailureRequest>,
+) -> Result<(StatusCode, Json<FailureResponse>), (StatusCode, String)> {
+    let failure = state
+        .failure_repo
+        .record(NewFailure {
+            camera_path: req.camera_path,
+            detected_at: req.detected_at.unwrap_or_else(Utc::now),
+            severity: Severity::from_db(&req.severity),
+            diagnosis: req.diagnosis,
+            raw: req.raw,
+        })
+        .await
+        .map_err(repo_err)?;
+    Ok((StatusCode::CREATED, Json(failure.into())))
+}
+
+#[utoipa::path(
+    get, path = "/admin/failures", tag = "Administration",
+    security(("admin_token" = [])),
+    params(
+        ("camera" = String, Query, description = "Path de la cámara"),
+        ("limit" = Option<i64>, Query, description = "Máximo de registros (default 50)")
+    ),
+    responses(
+        (status = 200, description = "Historial de la cámara", body = [FailureResponse]),
+        (status = 401, description = "No autorizado")
+    )
+)]
+pub async fn list_failures(
+    State(state): State<Arc<AppState>>,
+    Query(q): Query<FailureQuery>,
+) -> Result<Json<Vec<FailureResponse>>, (StatusCode, String)> {
+    let limit = q.limit.unwrap_or(50).clamp(1, 500);
+    let failures = state
+        .failure_repo
+        .list_by_camera(&q.camera, limit)
+        .await
+        .map_err(repo_err)?;
+    Ok(Json(failures.into_iter().map(FailureResponse::from).collect()))
+}
+
+#[utoipa::path(
+    get, path = "/admin/revocations", tag = "Administration",
+    security(("admin_token" = [])),
+    responses(
+        (status = 200, description = "Revocaciones vigentes", body = [RevocationResponse]),
+        (status = 401, description = "No autorizado")
+    )
+)]
+pub async fn list_revocations(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<Vec<RevocationResponse>>, (StatusCode, String)> {
+    let revocations = state.revocations.list_active().await.map_err(repo_err)?;
+    Ok(Json(
+        revocations.into_iter().map(RevocationResponse::from).collect(),
+    ))
+}
+
+/// Revoca tokens emitidos. Se aplica de inmediato en esta instancia y, en las
+/// demás, en la siguiente recarga de la denylist (`REVOCATION_REFRESH_SECS`).
+/// Revocar por `client_id` también revoca los refresh tokens del proyecto.
+#[utoipa::path(
+    post, path = "/admin/revocations", tag = "Administration",
+    security(("admin_token" = [])),
+    request_body = RevokeRequest,
+    responses(
+        (status = 201, description = "Revocación registrada", body = RevocationResponse),
+        (status = 400, description = "Alcance inválido"),
+        (status = 404, description = "Proyecto no encontrado"),
+        (status = 401, description = "No autorizado")
+    )
+)]
+pub async fn create_revocation(
+    State(state): State<Arc<AppState>>,
+    Json(req): Json<RevokeRequest>,
+) -> Result<(StatusCode, Json<RevocationResponse>), (StatusCode, String)> {
+    let bad = |msg: &str| (StatusCode::BAD_REQUEST, msg.to_string());
+    let now = Utc::now();
+    if req.issued_before.is_some_and(|t| t > now) {
+        return Err(bad("issued_before no puede estar en el futuro"));
+    }
+
+    let revocation = match (req.jti, req.client_id) {
+        (Some(_), Some(_)) => return Err(bad("indica jti o client_id, no ambos")),
+        (Some(_), None) if req.issued_before.is_some() => {
+            return Err(bad("issued_before no aplica a una revocación por jti"))
+        }
+        (Some(jti), None) => state
+            .revocations
+            .revoke(RevocationTarget::Token(jti), req.reason)
+            .await
+            .map_err(repo_err)?,
+        (None, Some(client_id)) => {
+            let project = state
+                .project_repo
+                .find_by_client_id(&client_id)
+                .await
+                .map_err(repo_err)?
+                .ok_or((StatusCode::NOT_FOUND, "proyecto no encontrado".to_string()))?;
+            let revocation = state
+                .revocations
+                .revoke(
+                    RevocationTarget::Project {
+                        client_id,
+                        issued_before: req.issued_before.unwrap_or(now),
+                    },
+                    req.reason,
+                )
+                .await
+                .map_err(repo_err)?;
+            state
+                .refresh
+                .revoke_project(project.id)
+                .await
+                .map_err(repo_err)?;
+            revocation
+        }
+        (None, None) => {
+            let issued_before = req
+                .issued_before
+                .ok_or_else(|| bad("indica jti, client_id o issued_before"))?;
+            state
+                .revocations
+                .revoke(RevocationTarget::All { issued_before }, req.reason)
+                .await
+                .map_err(repo_err)?
+        }
+    };
+    info!("revocación registrada: {}", revocation.id);
+    Ok((StatusCode::CREATED, Json(revocation.into())))
+}
+
+/// Levanta una revocación (los tokens afectados que no hayan expirado vuelven
+/// a valer).
+#[utoipa::path(
+    delete, path = "/admin/revocations/{id}", tag = "Administration",
+    security(("admin_token" = [])),
+    params(("id" = Uuid, Path, description = "ID de la revocación")),
+    responses(
+        (status = 204, description = "Revocación levantada"),
+        (status = 404, description = "No encontrada"),
+        (status = 401, description = "No autorizado")
+    )
+)]
+pub async fn delete_revocation(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<Uuid>,
+) -> Result<StatusCode, (StatusCode, String)> {
+    state.revocations.lift(id).await.map_err(repo_err)?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Contadores de fallos de login activos: con fallos recientes o bloqueados.
+#[utoipa::path(
+    get, path = "/admin/lockouts", tag = "Administration",
+    security(("admin_token" = [])),
+    responses(
+        (status = 200, description = "Contadores activos", body = [LockoutResponse]),
+        (status = 401, description = "No autorizado")
+    )
+)]
+pub async fn list_lockouts(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<Vec<LockoutResponse>>, (StatusCode, String)> {
+    let throttles = state.lockout.list().await.map_err(repo_err)?;
+    Ok(Json(throttles.into_iter().map(LockoutResponse::from).collect()))
+}
+
+/// Desbloquea un `client_id` o una IP y pone su contador a cero.
+#[utoipa::path(
+    delete, path = "/admin/lockouts/{scope}/{key}", tag = "Administration",
+    security(("admin_token" = [])),
+    params(
+        ("scope" = String, Path, description = "`client` o `ip`"),
+        ("key" = String, Path, description = "client_id o IP")
+    ),
+    responses(
+        (status = 204, description = "Contador eliminado"),
+        (status = 400, description = "Ámbito inválido"),
+        (status = 404, description = "Sin contador para esa clave"),
+        (status = 401, description = "No autorizado")
+    )
+)]
+pub async fn clear_lockout(
+    State(state): State<Arc<AppState>>,
+    Path((scope, key)): Path<(String, String)>,
+) -> Result<StatusCode, (StatusCode, String)> {
+    let scope = ThrottleScope::parse(&scope).ok_or((
+        StatusCode::BAD_REQUEST,
+        "scope debe ser 'client' o 'ip'".to_string(),
+    ))?;
+    state.lockout.clear(scope, &key).await.map_err(repo_err)?;
+    info!("bloqueo de login levantado: {} {}", scope.as_str(), key);
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Auditoría de autenticación (fallos, bloqueos y desbloqueos), la más reciente primero.
+#[utoipa::path(
+    get, path = "/admin/audit", tag = "Administration",
+    security(("admin_token" = [])),
+    params(("limit" = Option<i64>, Query, description = "Máximo de registros (default 100)")),
+    responses(
+        (status = 200, description = "Entradas de auditoría", body = [AuditResponse]),
+        (status = 401, description = "No autorizado")
+    )
+)]
+pub async fn list_audit(
+    State(state): State<Arc<AppState>>,
+    Query(q): Query<AuditQuery>,
+) -> Result<Json<Vec<AuditResponse>>, (StatusCode, String)> {
+    let limit = q.limit.unwrap_or(100).clamp(1, 1000);
+    let entries = state.lockout.audit_log(limit).await.map_err(repo_err)?;
+    Ok(Json(entries.into_iter().map(AuditResponse::from).collect()))
+}
+
+/// Asociaciones claim → proyecto usadas por el canje de tokens externos.
+#[utoipa::path(
+    get, path = "/admin/idp-mappings", tag = "Administration",
+    security(("admin_token" = [])),
+    responses(
+        (status = 200, description = "Asociaciones", body = [IdpMappingResponse]),
+        (status = 401, description = "No autorizado")
+    )
+)]
+pub async fn list_idp_mappings(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<Vec<IdpMappingResponse>>, (StatusCode, String)> {
+    let mappings = state.idp_mapping_repo.list_all().await.map_err(repo_err)?;
+    Ok(Json(mappings.into_iter().map(IdpMappingResponse::from).collect()))
+}
+
+/// Asocia un valor de claim del IdP externo a un proyecto.
+#[utoipa::path(
+    post, path = "/admin/idp-mappings", tag = "Administration",
+    security(("admin_token" = [])),
+    request_body = CreateIdpMappingRequest,
+    responses(
+        (status = 201, description = "Asociación creada", body = IdpMappingResponse),
+        (status = 400, description = "Claim o valor vacíos"),
+        (status = 404, description = "Proyecto no encontrado"),
+        (status = 409, description = "La asociación ya existe"),
+        (status = 401, description = "No autorizado")
+    )
+)]
+pub async fn create_idp_mapping(
+    State(state): State<Arc<AppState>>,
+    Json(req): Json<CreateIdpMappingRequest>,
+) -> Result<(StatusCode, Json<IdpMappingResponse>), (StatusCode, String)> {
+    let (claim, value) = (req.claim.trim(), req.value.trim());
+    if claim.is_empty() || value.is_empty() {
+        return Err((StatusCode::BAD_REQUEST, "claim y value son obligatorios".to_string()));
+    }
+    let project = find_project(&state, req.project_id).await?;
+    let mapping = state
+        .idp_mapping_repo
+        .create(NewIdpMapping {
+            claim: claim.to_string(),
+            value: value.to_string(),
+            project_id: project.id,
+        })
+        .await
+        .map_err(repo_err)?;
+    info!(
+        "asociación IdP creada: {}={} → {}",
+        mapping.claim, mapping.value, project.client_id
+    );
+    Ok((StatusCode::CREATED, Json(mapping.into())))
+}
+
+#[utoipa::path(
+    delete, path = "/admin/idp-mappings/{id}", tag = "Administration",
+    security(("admin_token" = [])),
+    params(("id" = Uuid, Path, description = "ID de la asociación")),
+    responses(
+        (status = 204, description = "Asociación eliminada"),
+        (status = 404, description = "No encontrada"),
+        (status = 401, description = "No autorizado")
+    )
+)]
+pub async fn delete_idp_mapping(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<Uuid>,
+) -> Result<StatusCode, (StatusCode, String)> {
+    state.idp_mapping_repo.delete(id).await.map_err(repo_err)?;
+    info!("asociación IdP eliminada: {}", id);
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Nombre de usuario válido: minúsculas, dígitos y `.`, `_`, `-`, `@`.
+fn username(name: &str) -> Result<String, (StatusCode, String)> {
+    let name = name.trim();
+    let valid = !name.is_empty()
+        && name.len() <= 64
+        && name
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || "._-@".contains(c));
+    if !valid {
+        return Err((StatusCode::BAD_REQUEST, format!("nombre de usuario inválido: '{name}'")));
+    }
+    Ok(name.to_string())
+}
+
+fn parse_role(role: &str) -> Result<AdminRole, (StatusCode, String)> {
+    AdminRole::parse(role).ok_or_else(|| {
+        (
+            StatusCode::BAD_REQUEST,
+            format!("rol desconocido: '{role}' (viewer, operator o admin)"),
+        )
+    })
+}
+
+/// Abre una sesión de administración. Comparte el bloqueo por fuerza bruta
+/// del login de proyectos (clave `admin:<usuario>` y la IP).
+#[utoipa::path(
+    post, path = "/admin/login", tag = "Administration",
+    request_body = AdminLoginRequest,
+    responses(
+        (status = 200, description = "Sesión abierta", body = AdminLoginResponse),
+        (status = 401, description = "Credenciales inválidas"),
+        (status = 429, description = "Demasiados intentos fallidos (ver `Retry-After`)")
+    )
+)]
+pub async fn admin_login(
+    State(state): State<Arc<AppState>>,
+    connect: Option<ConnectInfo<SocketAddr>>,
+    headers: HeaderMap,
+    Json(req): Json<AdminLoginRequest>,
+) -> Result<impl IntoResponse, Response> {
+    let ip = crate::http::client_ip::from_request(connect, &headers, &state.config.trusted_proxies);
+    let session = match state
+        .admin_auth
+        .login(req.username.trim(), &req.password, ip.as_deref())
+        .await
+    {
+        Ok(session) => session,
+        Err(LoginError::Locked { retry_after }) => {
+            warn!("login de administración bloqueado para {} (ip {:?})", req.username, ip);
+            return Err((
+                StatusCode::TOO_MANY_REQUESTS,
+                [(header::RETRY_AFTER, retry_after.to_string())],
+                "demasiados intentos fallidos; reintente más tarde",
+            )
+                .into_response());
+        }
+        Err(LoginError::InvalidCredentials | LoginError::IpNotAllowed) => {
+            warn!("credenciales de administración inválidas para {} (ip {:?})", req.username, ip);
+            return Err((StatusCode::UNAUTHORIZED, "credenciales inválidas").into_response());
+        }
+        Err(LoginError::Repo(e)) => {
+            warn!("error en el login de administración de {}: {}", req.username, e);
+            return Err((StatusCode::INTERNAL_SERVER_ERROR, "error interno").into_response());
+        }
+    };
+    let resp = AdminLoginResponse {
+        token: session.token,
+        token_type: "Bearer".to_string(),
+        expires_in: (session.expires_at - Utc::now()).num_seconds(),
+        username: session.account.username,
+        role: session.account.role.as_str().to_string(),
+    };
+    Ok((NO_STORE, Json(resp)))
+}
+
+/// Cierra la sesión del bearer presentado (con ADMIN_API_TOKEN no hace nada).
+#[utoipa::path(
+    post, path = "/admin/logout", tag = "Administration",
+    security(("admin_token" = [])),
+    responses(
+        (status = 204, description = "Sesión cerrada"),
+        (status = 401, description = "No autorizado")
+    )
+)]
+pub async fn admin_logout(
+    State(state): State<Arc<AppState>>,
+    Extension(principal): Extension<AdminPrincipal>,
+    headers: HeaderMap,
+) -> Result<StatusCode, (StatusCode, String)> {
+    let token = headers
+        .get(AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "));
+    if let (Some(token), false) = (token, principal.name == BREAK_GLASS) {
+        state.admin_auth.logout(token).await.map_err(repo_err)?;
+        info!("sesión de administración de {} cerrada", principal.name);
+    }
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(
+    get, path = "/admin/me", tag = "Administration",
+    security(("admin_token" = [])),
+    responses(
+        (status = 200, description = "Principal autenticado", body = AdminMeResponse),
+        (status = 401, description = "No autorizado")
+    )
+)]
+pub async fn admin_me(Extension(principal): Extension<AdminPrincipal>) -> Json<AdminMeResponse> {
+    let (role, scopes) = match principal.access {
+        AdminAccess::Role(role) => (Some(role.as_str().to_string()), None),
+        AdminAccess::Scopes(scopes) => (None, Some(scopes)),
+    };
+    Json(AdminMeResponse {
+        username: principal.name,
+        role,
+        scopes,
+    })
+}
+
+#[utoipa::path(
+    get, path = "/admin/accounts", tag = "Administration",
+    security(("admin_token" = [])),
+    responses(
+        (status = 200, description = "Cuentas de administración", body = [AdminAccountResponse]),
+        (status = 401, description = "No autorizado"),
+        (status = 403, description = "Requiere rol admin")
+    )
+)]
+pub async fn list_admin_accounts(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<Vec<AdminAccountResponse>>, (StatusCode, String)> {
+    let accounts = state.admin_account_repo.list_all().await.map_err(repo_err)?;
+    Ok(Json(accounts.into_iter().map(AdminAccountResponse::from).collect()))
+}
+
+#[utoipa::path(
+    post, path = "/admin/accounts", tag = "Administration",
+    security(("admin_token" = [])),
+    request_body = CreateAdminAccountRequest,
+    responses(
+        (status = 201, description = "Cuenta creada (con `password` si la generó el servidor)", body = CreatedAdminAccountResponse),
+        (status = 400, description = "Usuario, rol o contraseña inválidos"),
+        (status = 401, description = "No autorizado"),
+        (status = 403, description = "Requiere rol admin"),
+        (status = 409, description = "Usuario duplicado")
+    )
+)]
+pub async fn create_admin_account(
+    State(state): State<Arc<AppState>>,
+    Json(req): Json<CreateAdminAccountRequest>,
+) -> Result<impl IntoResponse, (StatusCode, String)> {
+    let username = username(&req.username)?;
+    let role = parse_role(&req.role)?;
+    let (password, generated) = match req.password {
+        Some(password) => {
+            check_strength(&password, &username)?;
+            (password, false)
+        }
+        None => (crate::secret::generate_secret(), true),
+    };
+    let password_hash = crate::secret::hash_secret(&password, &state.config.hash_policy)
+        .map_err(|_| (StatusCode::INTERNAL_SERVER_ERROR, "error interno".to_string()))?;
+    let account = state
+        .admin_account_repo
+        .create(NewAdminAccount {
+            username,
+            password_hash,
+            role,
+        })
+        .await
+        .map_err(repo_err)?;
+    info!("cuenta de administración creada: {} ({})", account.username, role.as_str());
+    let resp = CreatedAdminAccountResponse {
+        account: account.into(),
+        password: generated.then_some(password),
+    };
+    Ok((StatusCode::CREATED, NO_STORE, Json(resp)))
+}
+
+#[utoipa::path(
+    patch, path = "/admin/accounts/{id}", tag = "Administration",
+    security(("admin_token" = [])),
+    params(("id" = Uuid, Path, description = "ID de la cuenta")),
+    request_body = UpdateAdminAccountRequest,
+    responses(
+        (status = 200, description = "Cuenta actualizada", body = AdminAccountResponse),
+        (status = 400, description = "Rol o contraseña inválidos, o quitarse el rol admin a sí mismo"),
+        (status = 401, description = "No autorizado"),
+        (status = 403, description = "Requiere rol admin"),
+        (status = 404, description = "No encontrada")
+    )
+)]
+pub async fn update_admin_account(
+    State(state): State<Arc<AppState>>,
+    Extension(principal): Extension<AdminPrincipal>,
+    Path(id): Path<Uuid>,
+    Json(req): Json<UpdateAdminAccountRequest>,
+) -> Result<Json<AdminAccountResponse>, (StatusCode, String)> {
+    let mut account = find_admin_account(&state, id).await?;
+    if let Some(role) = req.role {
+        account.role = parse_role(&role)?;
+    }
+    if let Some(enabled) = req.enabled {
+        account.enabled = enabled;
+    }
+    // Evita quedarse sin acceso por error; ADMIN_API_TOKEN sigue como emergencia.
+    if account.username == principal.name && (account.role != AdminRole::Admin || !account.enabled)
+    {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            "no puede quitarse a sí mismo el rol admin ni deshabilitarse".to_string(),
+        ));
+    }
+    let close_sessions = req.password.is_some() || !account.enabled;
+    if let Some(password) = req.password {
+        check_strength(&password, &account.username)?;
+        account.password_hash = crate::secret::hash_secret(&password, &state.config.hash_policy)
+            .map_err(|_| (StatusCode::INTERNAL_SERVER_ERROR, "error interno".to_string()))?;
+    }
+
+    let updated = state.admin_account_repo.update(&account).await.map_err(repo_err)?;
+    if close_sessions {
+        state
+            .admin_account_repo
+            .delete_sessions(updated.id)
+            .await
+            .map_err(repo_err)?;
+    }
+    info!(
+        "cuenta de administración {} actualizada ({}, {})",
+        updated.username,
+        updated.role.as_str(),
+        if updated.enabled { "habilitada" } else { "deshabilitada" }
+    );
+    Ok(Json(updated.into()))
+}
+
+#[utoipa::path(
+    delete, path = "/admin/accounts/{id}", tag = "Administration",
+    security(("admin_token" = [])),
+    params(("id" = Uuid, Path, description = "ID de la cuenta")),
+    responses(
+        (status = 204, description = "Cuenta eliminada (y sus sesiones)"),
+        (status = 400, description = "Borrar la propia cuenta"),
+        (status = 401, description = "No autorizado"),
+        (status = 403, description = "Requiere rol admin"),
+        (status = 404, description = "No encontrada")
+    )
+)]
+pub async fn delete_admin_account(
+    State(state): State<Arc<AppState>>,
+    Extension(principal): Extension<AdminPrincipal>,
+    Path(id): Path<Uuid>,
+) -> Result<StatusCode, (StatusCode, String)> {
+    let account = find_admin_account(&state, id).await?;
+    if account.username == principal.name {
+        return Err((StatusCode::BAD_REQUEST, "no puede borrar su propia cuenta".to_string()));
+    }
+    state.admin_account_repo.delete(id).await.map_err(repo_err)?;
+    info!("cuenta de administración eliminada: {}", account.username);
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn find_admin_account(
+    state: &AppState,
+    id: Uuid,
+) -> Result<AdminAccount, (StatusCode, String)> {
+    state
+        .admin_account_repo
+        .find_by_id(id)
+        .await
+        .map_err(repo_err)?
+        .ok_or((StatusCode::NOT_FOUND, "cuenta no encontrada".to_string()))
+}
+
+/// Alcances de una clave: conocidos, sin repetir y al menos uno (400 si no).
+fn api_key_scopes(scopes: Vec<String>) -> Result<Vec<String>, (StatusCode, String)> {
+    let bad = |msg: String| (StatusCode::BAD_REQUEST, msg);
+    let mut valid = Vec::with_capacity(scopes.len());
+    for scope in scopes {
+        let known = scope.split_once(':').is_some_and(|(resource, action)| {
+            API_KEY_RESOURCES.contains(&resource) && matches!(action, "read" | "write")
+        });
+        if !known {
+            return Err(bad(format!(
+                "alcance desconocido: '{scope}' (<recurso>:read o <recurso>:write; recursos: {})",
+                API_KEY_RESOURCES.join(", ")
+            )));
+        }
+        valid.push(scope);
+    }
+    valid.sort();
+    valid.dedup();
+    if valid.is_empty() {
+        return Err(bad("la clave necesita al menos un alcance".into()));
+    }
+    Ok(valid)
+}
+
+#[utoipa::path(
+    get, path = "/admin/api-keys", tag = "Administration",
+    security(("admin_token" = [])),
+    responses(
+        (status = 200, description = "Claves de API (sin la clave)", body = [ApiKeyResponse]),
+        (status = 401, description = "No autorizado"),
+        (status = 403, description = "Requiere rol admin")
+    )
+)]
+pub async fn list_api_keys(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<Vec<ApiKeyResponse>>, (StatusCode, String)> {
+    let keys = state.api_key_repo.list_all().await.map_err(repo_err)?;
+    Ok(Json(keys.into_iter().map(ApiKeyResponse::from).collect()))
+}
+
+/// Crea una clave de API; el servidor la genera y la devuelve una única vez.
+#[utoipa::path(
+    post, path = "/admin/api-keys", tag = "Administration",
+    security(("admin_token" = [])),
+    request_body = CreateApiKeyRequest,
+    responses(
+        (status = 201, description = "Clave creada (`key` solo se muestra ahora)", body = CreatedApiKeyResponse),
+        (status = 400, description = "Nombre, alcances o vencimiento inválidos"),
+        (status = 401, description = "No autorizado"),
+        (status = 403, description = "Requiere rol admin"),
+        (status = 409, description = "Nombre duplicado")
+    )
+)]
+pub async fn create_api_key(
+    State(state): State<Arc<AppState>>,
+    Extension(principal): Extension<AdminPrincipal>,
+    Json(req): Json<CreateApiKeyRequest>,
+) -> Result<impl IntoResponse, (StatusCode, String)> {
+    let name = username(&req.name)?;
+    let scopes = api_key_scopes(req.scopes)?;
+    if req.expires_at.is_some_and(|exp| exp <= Utc::now()) {
+        return Err((StatusCode::BAD_REQUEST, "expires_at debe ser futuro".to_string()));
+    }
+    let key = format!("{API_KEY_PREFIX}{}", generate_token());
+    let api_key = state
+        .api_key_repo
+        .create(NewApiKey {
+            name,
+            key_hash: hash_token(&key),
+            key_prefix: key[..API_KEY_PREFIX.len() + 4].to_string(),
+            scopes,
+            expires_at: req.expires_at,
+            created_by: principal.name,
+        })
+        .await
+        .map_err(repo_err)?;
+    info!(
+        "clave de API creada: {} ({})",
+        api_key.name,
+        api_key.scopes.join(" ")
+    );
+    let resp = CreatedApiKeyResponse {
+        api_key: api_key.into(),
+        key,
+    };
+    Ok((StatusCode::CREATED, NO_STORE, Json(resp)))
+}
+
+#[utoipa::path(
+    delete, path = "/admin/api-keys/{id}", tag = "Administration",
+    security(("admin_token" = [])),
+    params(("id" = Uuid, Path, description = "ID de la clave")),
+    responses(
+        (status = 204, description = "Clave revocada (deja de valer al instante)"),
+        (status = 401, description = "No autorizado"),
+        (status = 403, description = "Requiere rol admin"),
+        (status = 404, description = "No encontrada")
+    )
+)]
+pub async fn delete_api_key(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<Uuid>,
+) -> Result<StatusCode, (StatusCode, String)> {
+    state.api_key_repo.delete(id).await.map_err(repo_err)?;
+    info!("clave de API {} revocada", id);
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        allowed_cidrs, api_key_scopes, camera_grants, grace_until, grant_window, group_grants,
+        is_authorized,
+        key_allows, prefix_grants, publish_grants, username, CameraGrantDto, GroupGrantDto,
+        PrefixGrantDto, PublishGrantDto, ScheduleDto, TokenPolicy, BREAK_GLASS,
+    };
+    use axum::http::Method;
+    use chrono::{Duration, Utc, Weekday};
+    use crate::domain::models::{CameraActions, PublishGrant};
+    use uuid::Uuid;
+
+    #[test]
+    fn empty_config_denies_all() {
+        assert!(!is_authorized("", Some("Bearer x")));
+    }
+
+    #[test]
+    fn correct_bearer_allows() {
+        assert!(is_authorized("secret", Some("Bearer secret")));
+    }
+
+    #[test]
+    fn wrong_token_denies() {
+        assert!(!is_authorized("secret", Some("Bearer otro")));
+    }
+
+    #[test]
+    fn missing_header_denies() {
+        assert!(!is_authorized("secret", None));
+    }
 
     #[test]
     fn missing_bearer_prefix_denies() {
         assert!(!is_authorized("secret", Some("secret")));
     }
+
+    #[test]
+    fn token_policy_zero_clears_and_audiences_dedup() {
+        let mut policy = TokenPolicy {
+            max_minutes: Some(720),
+            default_minutes: Some(60),
+            audiences: vec![],
+        };
+        policy.apply(Some(0), None, Some(vec!["b".into(), "a".into(), "b".into()]));
+        assert_eq!(policy.max_minutes, None);
+        assert_eq!(policy.default_minutes, Some(60));
+        assert_eq!(policy.audiences, vec!["a".to_string(), "b".to_string()]);
+    }
+
+    #[test]
+    fn token_policy_validation() {
+        let policy = |max, default| TokenPolicy {
+            max_minutes: max,
+            default_minutes: default,
+            audiences: vec![],
+        };
+        assert!(policy(Some(720), Some(5)).validate(1440).is_ok());
+        assert!(policy(Some(2000), None).validate(1440).is_err(), "supera el techo");
+        assert!(policy(Some(5), Some(60)).validate(1440).is_err(), "default > max");
+        assert!(policy(None, Some(-1)).validate(1440).is_err());
+    }
+
+    #[test]
+    fn publish_grants_are_trimmed_deduped_and_literal() {
+        let dto = |path: &str, prefix| PublishGrantDto { path: path.into(), prefix };
+        let grants = publish_grants(vec![
+            dto(" bodycam/ ", true),
+            dto("bodycam/", true),
+            dto("bodycam/", false),
+        ])
+        .unwrap();
+        assert_eq!(
+            grants,
+            vec![
+                PublishGrant { path: "bodycam/".into(), prefix: true },
+                PublishGrant { path: "bodycam/".into(), prefix: false },
+            ]
+        );
+        assert!(publish_grants(vec![dto("", false)]).is_err());
+        assert!(publish_grants(vec![dto("~^live/", false)]).is_err(), "regex cruda");
+        assert!(publish_grants(vec![dto("body cam", false)]).is_err());
+    }
+
+    #[test]
+    fn camera_grants_merge_ids_and_actions() {
+        let (a, b) = (Uuid::new_v4(), Uuid::new_v4());
+        let dto = |camera_id, actions: &str| CameraGrantDto {
+            camera_id,
+            actions: actions.into(),
+            valid_from: None,
+            valid_until: None,
+            schedule: None,
+            expired: false,
+        };
+        let grants = camera_grants(vec![a], vec![dto(b, "read")]).unwrap();
+        assert_eq!(grants[0].actions, CameraActions::Both);
+        assert_eq!(grants[1].actions, CameraActions::Read);
+
+        assert!(camera_grants(vec![a], vec![dto(a, "read")]).is_err(), "repetida");
+        assert!(camera_grants(vec![], vec![dto(b, "publish")]).is_err());
+    }
+
+    #[test]
+    fn group_grants_reject_repeats_and_unknown_actions() {
+        let g = Uuid::new_v4();
+        let dto = |group_id, actions: &str| GroupGrantDto {
+            group_id,
+            actions: actions.into(),
+        };
+        let grants = group_grants(vec![dto(g, "playback")]).unwrap();
+        assert_eq!(grants[0].actions, CameraActions::Playback);
+        assert!(group_grants(vec![dto(g, "read"), dto(g, "both")]).is_err());
+        assert!(group_grants(vec![dto(g, "todo")]).is_err());
+    }
+
+    #[test]
+    fn prefix_grants_are_literal_and_unique() {
+        let dto = |prefix: &str| PrefixGrantDto {
+            prefix: prefix.into(),
+            actions: "read".into(),
+        };
+        let grants = prefix_grants(vec![dto(" bodega-a/ ")]).unwrap();
+        assert_eq!(grants[0].prefix, "bodega-a/");
+        assert_eq!(grants[0].actions, CameraActions::Read);
+        assert!(prefix_grants(vec![dto("bodega-a/"), dto("bodega-a/ ")]).is_err());
+        assert!(prefix_grants(vec![dto("~^bodega")]).is_err(), "regex cruda");
+        assert!(prefix_grants(vec![dto("")]).is_err());
+    }
+
+    #[test]
+    fn usernames_are_lowercase_and_cannot_impersonate_break_glass() {
+        assert_eq!(username(" ana.lopez@carmi-1 ").unwrap(), "ana.lopez@carmi-1");
+        assert!(username(BREAK_GLASS).is_err());
+        assert!(username("Ana").is_err());
+        assert!(username("ana lopez").is_err());
+        assert!(username("").is_err());
+        assert!(username(&"a".repeat(65)).is_err());
+    }
+
+    #[test]
+    fn api_keys_only_reach_routes_in_their_scopes() {
+        let scopes = vec!["failures:read".to_string(), "failures:write".to_string()];
+        assert!(key_allows(&scopes, &Method::POST, "/admin/failures").is_ok());
+        assert!(key_allows(&scopes, &Method::GET, "/admin/failures").is_ok());
+        assert!(key_allows(&scopes, &Method::GET, "/admin/me").is_ok());
+
+        let err = key_allows(&scopes, &Method::DELETE, "/admin/cameras/abc").unwrap_err();
+        assert!(err.contains("cameras:write"), "{err}");
+        assert!(key_allows(&scopes, &Method::GET, "/admin/cameras").is_err());
+        // Cuentas, claves y cerrar sesión son solo para personas.
+        assert!(key_allows(&scopes, &Method::GET, "/admin/api-keys").is_err());
+        assert!(key_allows(&scopes, &Method::POST, "/admin/accounts").is_err());
+        assert!(key_allows(&scopes, &Method::POST, "/admin/logout").is_err());
+
+        let camera_reader = vec!["cameras:read".to_string()];
+        assert!(key_allows(&camera_reader, &Method::GET, "/admin/cameras/abc").is_ok());
+        assert!(key_allows(&camera_reader, &Method::GET, "/admin/camera-groups").is_err());
+    }
+
+    #[test]
+    fn api_key_scopes_must_be_known() {
+        let scopes = |v: &[&str]| api_key_scopes(v.iter().map(|s| s.to_string()).collect());
+        assert_eq!(
+            scopes(&["failures:write", "cameras:read", "failures:write"]).unwrap(),
+            vec!["cameras:read", "failures:write"]
+        );
+        assert!(scopes(&[]).is_err());
+        assert!(scopes(&["accounts:write"]).is_err());
+        assert!(scopes(&["cameras:delete"]).is_err());
+        assert!(scopes(&["cameras"]).is_err());
+    }
+
+    #[test]
+    fn secret_grace_is_bounded() {
+        let now = Utc::now();
+        assert_eq!(grace_until(now, 0).unwrap(), now);
+        assert_eq!(grace_until(now, 1440).unwrap(), now + Duration::days(1));
+        assert!(grace_until(now, 7 * 24 * 60).is_ok());
+        assert!(grace_until(now, 7 * 24 * 60 + 1).is_err());
+        assert!(grace_until(now, -1).is_err());
+        assert!(grace_until(now, i64::MAX).is_err(), "no entra en pánico");
+    }
+
+    #[test]
+    fn allowed_cidrs_are_normalized() {
+        let nets = |v: &[&str]| allowed_cidrs(v.iter().map(|s| s.to_string()).collect());
+        assert_eq!(
+            nets(&[" 10.1.2.3/16", "203.0.113.7", "10.1.0.0/16", "2001:db8::1/32"]).unwrap(),
+            vec!["10.1.0.0/16", "203.0.113.7/32", "2001:db8::/32"]
+        );
+        assert!(nets(&[]).unwrap().is_empty());
+        assert!(nets(&["10.0.0.0/33"]).is_err());
+        assert!(nets(&["oficina"]).is_err());
+    }
+
+    #[test]
+    fn grant_window_validates_range_and_schedule() {
+        let schedule = |days: &[&str], start: &str, end: &str| ScheduleDto {
+            days: days.iter().map(|d| d.to_string()).collect(),
+            start: start.into(),
+            end: end.into(),
+        };
+        let window =
+            grant_window(None, None, Some(schedule(&["mon", "Fri", "mon"], "08:00", "18:30")))
+                .unwrap();
+        let parsed = window.schedule.unwrap();
+        assert_eq!(parsed.days, vec![Weekday::Mon, Weekday::Fri]);
+        assert_eq!(parsed.end.format("%H:%M").to_string(), "18:30");
+
+        let now = Utc::now();
+        assert!(grant_window(Some(now), Some(now - Duration::hours(1)), None).is_err());
+        assert!(grant_window(None, None, Some(schedule(&[], "08:00", "18:00"))).is_err());
+        assert!(grant_window(None, None, Some(schedule(&["lun"], "08:00", "18:00"))).is_err());
+        assert!(
+            grant_window(None, None, Some(schedule(&["sat"], "22:00", "06:00"))).is_err(),
+            "cruza la medianoche"
+        );
+        assert!(grant_window(None, None, Some(schedule(&["sat"], "8h", "18:00"))).is_err());
+    }
 }
@@ -0,0 +1,103 @@
+//! IP de origen del cliente, para contar fallos de login por IP.
+//!
+//! Detrás de Caddy la conexión llega desde el proxy, así que se recorre
+//! `X-Forwarded-For` de derecha a izquierda saltando los proxies de confianza
+//! (`TRUSTED_PROXIES`): la primera IP no confiable es el cliente. Si la conexión
+//! no viene de un proxy de confianza, el header se ignora (lo puede falsificar
+//! cualquiera) y vale la IP del socket.
+
+use std::net::{IpAddr, SocketAddr};
+
+use axum::extract::ConnectInfo;
+use axum::http::HeaderMap;
+use ipnet::IpNet;
+
+/// IP del cliente según el socket y la cadena `X-Forwarded-For`.
+pub fn resolve(peer: Option<IpAddr>, headers: &HeaderMap, trusted: &[IpNet]) -> Option<IpAddr> {
+    let peer = peer?;
+    let is_trusted = |ip: &IpAddr| trusted.iter().any(|net| net.contains(ip));
+    if !is_trusted(&peer) {
+        return Some(peer);
+    }
+    let forwarded: Vec<IpAddr> = headers
+        .get_all("x-forwarded-for")
+        .iter()
+        .filter_map(|v| v.to_str().ok())
+        .flat_map(|v| v.split(','))
+        .filter_map(|ip| ip.trim().parse().ok())
+        .collect();
+    // Todos confiables (o sin header): el más lejano conocido.
+    Some(
+        forwarded
+            .iter()
+            .rev()
+            .find(|ip| !is_trusted(ip))
+            .or(forwarded.first())
+            .copied()
+            .unwrap_or(peer),
+    )
+}
+
+/// Atajo para handlers: IP del cliente como texto, si se conoce.
+pub fn from_request(
+    connect: Option<ConnectInfo<SocketAddr>>,
+    headers: &HeaderMap,
+    trusted: &[IpNet],
+) -> Option<String> {
+    resolve(connect.map(|c| c.0.ip()), headers, trusted).map(|ip| ip.to_string())
+}
+
+/// Lista de CIDR separada por comas; las entradas inválidas se descartan.
+/// Una IP sola equivale a /32 (o /128).
+pub fn parse_trusted(list: &str) -> Vec<IpNet> {
+    list.split(',')
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .filter_map(|s| {
+            s.parse::<IpNet>()
+                .or_else(|_| s.parse::<IpAddr>().map(IpNet::from))
+                .map_err(|_| tracing::warn!("TRUSTED_PROXIES: entrada inválida '{}'", s))
+                .ok()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_trusted, resolve};
+    use axum::http::{HeaderMap, HeaderValue};
+    use std::net::IpAddr;
+
+    fn headers(xff: &str) -> HeaderMap {
+        let mut h = HeaderMap::new();
+        h.insert("x-forwarded-for", HeaderValue::from_str(xff).unwrap());
+        h
+    }
+
+    fn ip(s: &str) -> Option<IpAddr> {
+        Some(s.parse().unwrap())
+    }
+
+    #[test]
+    fn untrusted_peer_ignores_forwarded_header() {
+        let trusted = parse_trusted("172.16.0.0/12");
+        let got = resolve(ip("203.0.113.7"), &headers("1.2.3.4"), &trusted);
+        assert_eq!(got, ip("203.0.113.7"));
+    }
+
+    #[test]
+    fn trusted_proxy_yields_rightmost_untrusted_hop() {
+        let trusted = parse_trusted("172.16.0.0/12, 10.0.0.1");
+        // El cliente intentó falsificar 9.9.9.9; lo agregó Caddy al final.
+        let got = resolve(ip("172.18.0.5"), &headers("9.9.9.9, 198.51.100.2, 10.0.0.1"), &trusted);
+        assert_eq!(got, ip("198.51.100.2"));
+        // Sin header: la IP del proxy.
+        assert_eq!(resolve(ip("172.18.0.5"), &HeaderMap::new(), &trusted), ip("172.18.0.5"));
+    }
+
+    #[test]
+    fn parse_skips_invalid_entries() {
+        assert_eq!(parse_trusted("10.0.0.0/8, basura, ::1").len(), 2);
+        assert!(parse_trusted("").is_empty());
+    }
+}
@@ -0,0 +1,284 @@
+//! Descubrimiento de claves y endpoints para verificadores de tokens.
+//!
+//! `/jwks` (y su alias estándar `/.well-known/jwks.json`) publica las claves
+//! del anillo de firma con `ETag` y `Cache-Control`: MediaMTX y demás
+//! verificadores lo consultan seguido, y con `If-None-Match` reciben un 304
+//! sin cuerpo mientras el anillo no cambie. `/.well-known/openid-configuration`
+//! describe emisor, endpoints y algoritmos para librerías que se configuran
+//! solas a partir del emisor.
+
+use std::net::{IpAddr, SocketAddr};
+use std::sync::Arc;
+
+use axum::extract::{ConnectInfo, State};
+use axum::http::{header, HeaderMap, HeaderValue, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::routing::get;
+use axum::{Json, Router};
+use ipnet::IpNet;
+use serde::Serialize;
+use utoipa::ToSchema;
+
+use crate::http::oauth::TOKEN_EXCHANGE_GRANT;
+use crate::{AppState, Config};
+
+pub fn router() -> Router<Arc<AppState>> {
+    Router::new()
+        .route("/jwks", get(get_jwks))
+        .route("/.well-known/jwks.json", get(well_known_jwks))
+        .route("/.well-known/openid-configuration", get(openid_configuration))
+}
+
+/// Metadatos del servidor de autorización (OpenID Connect Discovery 1.0 /
+/// RFC 8414). Solo se emiten tokens de acceso; los campos de OIDC que exigen
+/// las librerías cliente se completan con lo que aplica a esos tokens.
+#[derive(Serialize, ToSchema)]
+pub struct OpenIdConfiguration {
+    /// `iss` de los tokens (`JWT_ISSUER`).
+    #[schema(example = "https://media.example.com")]
+    pub issuer: String,
+    #[schema(example = "https://media.example.com/.well-known/jwks.json")]
+    pub jwks_uri: String,
+    #[schema(example = "https://media.example.com/oauth/token")]
+    pub token_endpoint: String,
+    #[schema(example = "https://media.example.com/oauth/introspect")]
+    pub introspection_endpoint: String,
+    #[schema(example = json!(["client_credentials", "refresh_token"]))]
+    pub grant_types_supported: Vec<&'static str>,
+    #[schema(example = json!(["client_secret_basic", "client_secret_post"]))]
+    pub token_endpoint_auth_methods_supported: Vec<&'static str>,
+    #[schema(example = json!(["client_secret_basic", "client_secret_post"]))]
+    pub introspection_endpoint_auth_methods_supported: Vec<&'static str>,
+    #[schema(example = json!(["read", "playback", "publish"]))]
+    pub scopes_supported: Vec<&'static str>,
+    #[schema(example = json!(["token"]))]
+    pub response_types_supported: Vec<&'static str>,
+    #[schema(example = json!(["public"]))]
+    pub subject_types_supported: Vec<&'static str>,
+    /// Algoritmos de las claves publicadas (la activa y las en retiro).
+    #[schema(example = json!(["ES256", "RS256"]))]
+    pub id_token_signing_alg_values_supported: Vec<String>,
+}
+
+/// Retrieve the JSON Web Key Set (JWKS) for token validation.
+///
+/// This endpoint exposes the public keys used to verify JWT signatures.
+/// MediaMTX fetches this endpoint (configured via `authJWTJWKS`) to validate
+/// incoming tokens. It lists every published key of the signing keyring: the
+/// active one plus any key being retired after a rotation, so tokens signed
+/// before `rotate-signing-key` keep validating until they expire.
+///
+/// The response carries an `ETag` derived from the keyring and
+/// `Cache-Control: max-age=JWKS_CACHE_SECS`; a request with a matching
+/// `If-None-Match` gets `304 Not Modified`.
+///
+/// ## Usage
+/// Configure MediaMTX with:
+/// ```yaml
+/// authJWTJWKS: http://mediamtx-backend:8080/jwks
+/// ```
+#[utoipa::path(
+    get,
+    path = "/jwks",
+    tag = "JWT & Token Management",
+    operation_id = "getJwks",
+    params(("If-None-Match" = Option<String>, Header, description = "ETag de una respuesta anterior")),
+    responses(
+        (status = 200, description = "JWKS containing public keys for signature verification", body = Jwks,
+            headers(
+                ("ETag" = String, description = "Huella del anillo de claves publicado"),
+                ("Cache-Control" = String, description = "public, max-age=JWKS_CACHE_SECS")
+            ),
+            example = json!({
+                "keys": [{
+                    "kty": "RSA",
+                    "use": "sig",
+                    "alg": "RS256",
+                    "kid": "NzbLsXh8uDCcd-6MNwXF4W_7noWXFZAfHkxZsRGC9Xs",
+                    "n": "0vx7agoebGcQ...",
+                    "e": "AQAB"
+                }]
+            })
+        ),
+        (status = 304, description = "El JWKS no cambió desde el ETag enviado")
+    )
+)]
+pub async fn get_jwks(State(state): State<Arc<AppState>>, headers: HeaderMap) -> Response {
+    let signing = state.signing_keys();
+    let mut resp = if etag_matches(&headers, &signing.jwks_etag) {
+        StatusCode::NOT_MODIFIED.into_response()
+    } else {
+        Json(signing.jwks.clone()).into_response()
+    };
+    let h = resp.headers_mut();
+    if let Ok(etag) = HeaderValue::from_str(&signing.jwks_etag) {
+        h.insert(header::ETAG, etag);
+    }
+    cache_for(h, &state.config);
+    resp
+}
+
+/// Alias estándar de `/jwks` (mismo contenido, `ETag` y caché).
+#[utoipa::path(
+    get,
+    path = "/.well-known/jwks.json",
+    tag = "JWT & Token Management",
+    operation_id = "getWellKnownJwks",
+    params(("If-None-Match" = Option<String>, Header, description = "ETag de una respuesta anterior")),
+    responses(
+        (status = 200, description = "JWKS (igual que `/jwks`)", body = Jwks),
+        (status = 304, description = "El JWKS no cambió desde el ETag enviado")
+    )
+)]
+pub async fn well_known_jwks(state: State<Arc<AppState>>, headers: HeaderMap) -> Response {
+    get_jwks(state, headers).await
+}
+
+/// Documento de descubrimiento: emisor, endpoints de token e introspección,
+/// `jwks_uri` y algoritmos de firma publicados.
+///
+/// Las URLs se arman sobre `PUBLIC_BASE_URL`; sin ella, sobre `JWT_ISSUER` si
+/// es una URL, o sobre el `Host` de la petición (`X-Forwarded-*` solo si viene
+/// de `TRUSTED_PROXIES`). En este último caso la respuesta no se cachea: un
+/// `Host` arbitrario no debe quedar en una caché compartida.
+#[utoipa::path(
+    get,
+    path = "/.well-known/openid-configuration",
+    tag = "JWT & Token Management",
+    operation_id = "getOpenIdConfiguration",
+    responses(
+        (status = 200, description = "Metadatos del servidor de autorización", body = OpenIdConfiguration)
+    )
+)]
+pub async fn openid_configuration(
+    State(state): State<Arc<AppState>>,
+    connect: Option<ConnectInfo<SocketAddr>>,
+    headers: HeaderMap,
+) -> Response {
+    let configured = configured_base(&state.config);
+    let base = configured.clone().unwrap_or_else(|| {
+        request_base(connect.map(|c| c.0.ip()), &headers, &state.config.trusted_proxies)
+    });
+    let mut algs: Vec<String> = Vec::new();
+    for key in state.signing_keys().keyring.published() {
+        let alg = key.alg.to_string();
+        if !algs.contains(&alg) {
+            algs.push(alg);
+        }
+    }
+    let mut grants = vec!["client_credentials", "refresh_token"];
+    if state.token_exchange.is_some() {
+        grants.push(TOKEN_EXCHANGE_GRANT);
+    }
+    let doc = OpenIdConfiguration {
+        issuer: state.config.jwt_issuer.clone(),
+        jwks_uri: format!("{base}/.well-known/jwks.json"),
+        token_endpoint: format!("{base}/oauth/token"),
+        introspection_endpoint: format!("{base}/oauth/introspect"),
+        grant_types_supported: grants,
+        token_endpoint_auth_methods_supported: vec!["client_secret_basic", "client_secret_post"],
+        introspection_endpoint_auth_methods_supported: vec![
+            "client_secret_basic",
+            "client_secret_post",
+        ],
+        scopes_supported: vec!["read", "playback", "publish"],
+        response_types_supported: vec!["token"],
+        subject_types_supported: vec!["public"],
+        id_token_signing_alg_values_supported: algs,
+    };
+    let mut resp = Json(doc).into_response();
+    if configured.is_some() {
+        cache_for(resp.headers_mut(), &state.config);
+    } else {
+        resp.headers_mut()
+            .insert(header::CACHE_CONTROL, HeaderValue::from_static("no-store"));
+    }
+    resp
+}
+
+/// `max-age` = `JWKS_CACHE_SECS`. No abre huecos al rotar: `rotate-signing-key`
+/// publica la clave nueva al menos este tiempo antes de que firme.
+fn cache_for(headers: &mut HeaderMap, config: &Config) {
+    if let Ok(v) = HeaderValue::from_str(&format!("public, max-age={}", config.jwks_cache_secs)) {
+        headers.insert(header::CACHE_CONTROL, v);
+    }
+}
+
+/// ¿`If-None-Match` incluye `etag` (o `*`)? Comparación débil (RFC 9110
+/// §13.1.2): se ignora el prefijo `W/`.
+fn etag_matches(headers: &HeaderMap, etag: &str) -> bool {
+    let strip = |t: &str| t.trim().trim_start_matches("W/").to_string();
+    let ours = strip(etag);
+    headers
+        .get_all(header::IF_NONE_MATCH)
+        .iter()
+        .filter_map(|v| v.to_str().ok())
+        .flat_map(|v| v.split(','))
+        .any(|t| t.trim() == "*" || strip(t) == ours)
+}
+
+/// Base pública configurada (`PUBLIC_BASE_URL` o un `JWT_ISSUER` que sea URL),
+/// sin `/` final.
+fn configured_base(config: &Config) -> Option<String> {
+    if let Some(base) = &config.public_base_url {
+        return Some(base.clone());
+    }
+    if config.jwt_issuer.starts_with("https://") || config.jwt_issuer.starts_with("http://") {
+        return Some(config.jwt_issuer.trim_end_matches('/').to_string());
+    }
+    None
+}
+
+/// Base sacada de la petición. `X-Forwarded-Host`/`-Proto` solo cuentan si la
+/// conexión viene de un proxy de confianza (como en `client_ip::resolve`); si
+/// no, cualquiera podría inyectar su propio endpoint de tokens.
+fn request_base(peer: Option<IpAddr>, headers: &HeaderMap, trusted: &[IpNet]) -> String {
+    let header = |name: &str| headers.get(name).and_then(|v| v.to_str().ok());
+    let from_proxy = peer.is_some_and(|ip| trusted.iter().any(|net| net.contains(&ip)));
+    let forwarded = |name: &str| if from_proxy { header(name) } else { None };
+    let proto = forwarded("x-forwarded-proto")
+        .filter(|p| matches!(*p, "http" | "https"))
+        .unwrap_or("http");
+    let host = forwarded("x-forwarded-host")
+        .or_else(|| header(header::HOST.as_str()))
+        .unwrap_or("localhost");
+    format!("{proto}://{host}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{etag_matches, request_base};
+    use axum::http::{header::IF_NONE_MATCH, HeaderMap, HeaderValue};
+
+    fn if_none_match(v: &str) -> HeaderMap {
+        let mut h = HeaderMap::new();
+        h.insert(IF_NONE_MATCH, HeaderValue::from_str(v).unwrap());
+        h
+    }
+
+    #[test]
+    fn etag_matching_is_weak_and_accepts_lists() {
+        let etag = r#""abc""#;
+        assert!(etag_matches(&if_none_match(r#""abc""#), etag));
+        assert!(etag_matches(&if_none_match(r#""old", W/"abc""#), etag));
+        assert!(etag_matches(&if_none_match("*"), etag));
+        assert!(!etag_matches(&if_none_match(r#""old""#), etag));
+        assert!(!etag_matches(&HeaderMap::new(), etag));
+    }
+
+    #[test]
+    fn forwarded_host_only_counts_from_trusted_proxies() {
+        let mut h = HeaderMap::new();
+        h.insert("host", HeaderValue::from_static("backend:8080"));
+        h.insert("x-forwarded-host", HeaderValue::from_static("evil.example"));
+        h.insert("x-forwarded-proto", HeaderValue::from_static("https"));
+        let trusted = vec!["172.18.0.0/16".parse().unwrap()];
+
+        let caddy = Some("172.18.0.5".parse().unwrap());
+        assert_eq!(request_base(caddy, &h, &trusted), "https://evil.example");
+        let outsider = Some("203.0.113.9".parse().unwrap());
+        assert_eq!(request_base(outsider, &h, &trusted), "http://backend:8080");
+        assert_eq!(request_base(None, &h, &trusted), "http://backend:8080");
+        assert_eq!(request_base(caddy, &HeaderMap::new(), &trusted), "http://localhost");
+    }
+}
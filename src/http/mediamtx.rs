@@ -0,0 +1,260 @@
+//! Callback de autenticación de MediaMTX (`authMethod: http`).
+//!
+//! Alternativa al modo JWKS puro: MediaMTX consulta `POST /mediamtx/auth` en
+//! cada acceso y el backend decide con el estado VIVO (proyecto habilitado,
+//! cámaras asignadas y permisos de publicación ahora, denylist), así que un
+//! cambio de permisos aplica sin emitir un token nuevo. Acepta nuestro JWT o
+//! credenciales de proyecto (user = client_id, password = secreto). Las
+//! decisiones se cachean unos segundos porque HLS dispara una consulta por
+//! segmento.
+//!
+//! Solo se monta con `MEDIAMTX_AUTH_MODE=http`. No se publica en Caddy: lo
+//! llama MediaMTX por la red interna.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use axum::extract::State;
+use axum::http::StatusCode;
+use axum::routing::post;
+use axum::{Json, Router};
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use tracing::{debug, info, warn};
+use utoipa::ToSchema;
+
+use crate::domain::ports::RepoResult;
+use crate::http::consumer::{query_param, validate_token};
+use crate::services::lockout::LoginError;
+use crate::{build_permissions, permits, AppState};
+
+/// Entradas máximas antes de purgar las caducadas.
+const CACHE_SWEEP_THRESHOLD: usize = 10_000;
+
+pub fn router() -> Router<Arc<AppState>> {
+    Router::new().route("/mediamtx/auth", post(authorize))
+}
+
+/// Petición de autenticación que envía MediaMTX (todos los campos opcionales).
+#[derive(Deserialize, ToSchema)]
+pub struct MtxAuthRequest {
+    #[serde(default)]
+    pub user: String,
+    #[serde(default)]
+    pub password: String,
+    /// Bearer token (header `Authorization`).
+    #[serde(default)]
+    pub token: String,
+    #[serde(default)]
+    pub ip: String,
+    /// `read`, `playback`, `publish`, `api`, `metrics`, `pprof`.
+    #[schema(example = "read")]
+    #[serde(default)]
+    pub action: String,
+    #[schema(example = "cam-entrada")]
+    #[serde(default)]
+    pub path: String,
+    /// `rtsp`, `rtmp`, `hls`, `webrtc`, `srt`.
+    #[schema(example = "hls")]
+    #[serde(default)]
+    pub protocol: String,
+    /// Query string de la petición original (puede traer `jwt=<token>`).
+    #[serde(default)]
+    pub query: String,
+}
+
+impl MtxAuthRequest {
+    /// El JWT, por orden: header Bearer, `?jwt=` (hls.js) o el password con
+    /// usuario vacío (clientes RTSP que solo permiten user/password).
+    fn bearer(&self) -> Option<&str> {
+        if !self.token.is_empty() {
+            return Some(&self.token);
+        }
+        if let Some(jwt) = query_param(&self.query, "jwt") {
+            return Some(jwt);
+        }
+        (self.user.is_empty() && !self.password.is_empty()).then_some(self.password.as_str())
+    }
+
+    /// Clave de caché: hash de credenciales + acción + path (no se guardan
+    /// secretos en claro en memoria).
+    fn cache_key(&self) -> String {
+        let mut h = Sha256::new();
+        for part in [&self.action, &self.path, &self.token, &self.query, &self.user, &self.password] {
+            h.update(part.as_bytes());
+            h.update([0u8]);
+        }
+        h.finalize().iter().map(|b| format!("{b:02x}")).collect()
+    }
+}
+
+/// Caché de decisiones allow/deny con TTL corto. TTL 0 = sin caché.
+pub struct DecisionCache {
+    ttl: Duration,
+    entries: Mutex<HashMap<String, (bool, Instant)>>,
+}
+
+impl DecisionCache {
+    pub fn new(ttl_secs: u64) -> Self {
+        Self {
+            ttl: Duration::from_secs(ttl_secs),
+            entries: Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn get(&self, key: &str) -> Option<bool> {
+        let entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        entries
+            .get(key)
+            .filter(|(_, at)| at.elapsed() < self.ttl)
+            .map(|(allow, _)| *allow)
+    }
+
+    fn put(&self, key: String, allow: bool) {
+        if self.ttl.is_zero() {
+            return;
+        }
+        let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        if entries.len() >= CACHE_SWEEP_THRESHOLD {
+            entries.retain(|_, (_, at)| at.elapsed() < self.ttl);
+        }
+        entries.insert(key, (allow, Instant::now()));
+    }
+}
+
+/// Decide si MediaMTX debe permitir el acceso.
+///
+/// 200 = permitir; 401 = denegar. Con JWT se exige que el token siga vigente
+/// (firma, exp, denylist), que lo permita y que el proyecto, hoy, también.
+#[utoipa::path(
+    post, path = "/mediamtx/auth", tag = "JWT & Token Management",
+    request_body = MtxAuthRequest,
+    responses(
+        (status = 200, description = "Acceso permitido"),
+        (status = 401, description = "Acceso denegado")
+    )
+)]
+pub async fn authorize(
+    State(state): State<Arc<AppState>>,
+    Json(req): Json<MtxAuthRequest>,
+) -> StatusCode {
+    let key = req.cache_key();
+    if let Some(allow) = state.mtx_auth_cache.get(&key) {
+        return verdict(allow);
+    }
+    match decide(&state, &req).await {
+        Ok(allow) => {
+            if !allow {
+                info!(
+                    "MediaMTX: acceso denegado ({} {} vía {}, ip {})",
+                    req.action, req.path, req.protocol, req.ip
+                );
+            }
+            state.mtx_auth_cache.put(key, allow);
+            verdict(allow)
+        }
+        // Fail-closed y sin cachear: el próximo intento vuelve a consultar.
+        Err(e) => {
+            warn!("MediaMTX: error decidiendo acceso a '{}': {}", req.path, e);
+            verdict(false)
+        }
+    }
+}
+
+fn verdict(allow: bool) -> StatusCode {
+    if allow {
+        StatusCode::OK
+    } else {
+        StatusCode::UNAUTHORIZED
+    }
+}
+
+async fn decide(state: &AppState, req: &MtxAuthRequest) -> RepoResult<bool> {
+    // api/metrics/pprof (y los publishers internos) van en authHTTPExclude.
+    let action = req.action.as_str();
+    if !matches!(action, "read" | "playback" | "publish") {
+        return Ok(false);
+    }
+
+    let project = if let Some(token) = req.bearer() {
+        let claims = match validate_token(state, token) {
+            Ok(c) => c,
+            Err(e) => {
+                debug!("MediaMTX: token rechazado para {}: {}", req.path, e);
+                return Ok(false);
+            }
+        };
+        if !permits(&claims.mediamtx_permissions, action, &req.path) {
+            return Ok(false);
+        }
+        match state.project_repo.find_by_client_id(&claims.sub).await? {
+            Some(p) if p.enabled => p,
+            _ => return Ok(false),
+        }
+    } else if !req.user.is_empty() {
+        // Misma protección de fuerza bruta que el login; la IP la informa MediaMTX.
+        let ip = Some(req.ip.as_str()).filter(|ip| !ip.is_empty());
+        match state
+            .lockout
+            .authenticate(&state.auth, &req.user, &req.password, ip)
+            .await
+        {
+            Ok(p) => p,
+            Err(LoginError::Repo(e)) => return Err(e),
+            Err(e) => {
+                debug!("MediaMTX: credenciales de {} rechazadas: {}", req.user, e);
+                return Ok(false);
+            }
+        }
+    } else {
+        return Ok(false);
+    };
+
+    let access = state.auth.camera_access(&project).await?;
+    let publish = state.auth.publish_grants(&project).await?;
+    Ok(permits(&build_permissions(&access, &publish), action, &req.path))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{DecisionCache, MtxAuthRequest};
+
+    fn request(json: &str) -> MtxAuthRequest {
+        serde_json::from_str(json).unwrap()
+    }
+
+    #[test]
+    fn bearer_prefers_token_then_query_then_password() {
+        let r = request(r#"{"token":"t","query":"jwt=q","password":"p"}"#);
+        assert_eq!(r.bearer(), Some("t"));
+        let r = request(r#"{"query":"a=1&jwt=q","password":"p"}"#);
+        assert_eq!(r.bearer(), Some("q"));
+        let r = request(r#"{"password":"p"}"#);
+        assert_eq!(r.bearer(), Some("p"));
+        let r = request(r#"{"user":"sigac","password":"p"}"#);
+        assert_eq!(r.bearer(), None, "user+password son credenciales de proyecto");
+    }
+
+    #[test]
+    fn cache_key_depends_on_path_and_credentials() {
+        let a = request(r#"{"action":"read","path":"cam-a","token":"t"}"#);
+        let b = request(r#"{"action":"read","path":"cam-b","token":"t"}"#);
+        let c = request(r#"{"action":"read","path":"cam-a","token":"u"}"#);
+        assert_ne!(a.cache_key(), b.cache_key());
+        assert_ne!(a.cache_key(), c.cache_key());
+        assert_eq!(a.cache_key(), request(r#"{"action":"read","path":"cam-a","token":"t"}"#).cache_key());
+    }
+
+    #[test]
+    fn cache_respects_ttl() {
+        let cache = DecisionCache::new(60);
+        cache.put("k".into(), true);
+        assert_eq!(cache.get("k"), Some(true));
+        assert_eq!(cache.get("otra"), None);
+
+        let off = DecisionCache::new(0);
+        off.put("k".into(), true);
+        assert_eq!(off.get("k"), None);
+    }
+}
@@ -2,4 +2,8 @@
 //! llamadas de servicios/repositorios y respuestas a DTOs (sin exponer secretos).
 
 pub mod admin;
+pub mod client_ip;
 pub mod consumer;
+pub mod discovery;
+pub mod mediamtx;
+pub mod oauth;
@@ -0,0 +1,923 @@
+//! Endpoint OAuth2 estándar (RFC 6749) para consumidores con librerías
+//! genéricas: `POST /oauth/token` con `grant_type=client_credentials` o
+//! `grant_type=refresh_token`.
+//!
+//! Reutiliza la misma autenticación (`AuthService::authenticate`) y los mismos
+//! permisos (`build_permissions`) que `/auth/login`; solo cambia el formato:
+//! entrada form-urlencoded con credenciales por HTTP Basic o en el body, y
+//! salida `access_token`/`token_type`/`expires_in`/`scope` con los códigos de
+//! error de RFC 6749 §5.2. El canje de refresh token vuelve a calcular el
+//! acceso del proyecto, así que los cambios de permisos aplican al renovar.
+//!
+//! Con un IdP externo configurado (`EXTERNAL_IDP_*`), `grant_type=
+//! urn:ietf:params:oauth:grant-type:token-exchange` (RFC 8693) canjea el JWT
+//! corporativo de un operador por nuestro token, con los permisos del proyecto
+//! que le asigna `idp_mappings` (ver `services::token_exchange`).
+//!
+//! `POST /oauth/introspect` (RFC 7662) responde a los servicios que reciben
+//! nuestros JWT si un token sigue activo, de quién es y qué cámaras cubre, sin
+//! que tengan que replicar la validación de `consumer::validate_token`.
+
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+use axum::extract::rejection::FormRejection;
+use axum::extract::{ConnectInfo, State};
+use axum::http::{header, HeaderMap, HeaderValue, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::routing::post;
+use axum::{Form, Json, Router};
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+use tracing::{info, warn};
+use utoipa::ToSchema;
+
+use crate::domain::models::{Camera, Project};
+use crate::http::consumer::{self, CameraRef};
+use crate::http::{admin, client_ip};
+use crate::services::lockout::LoginError;
+use crate::services::refresh::RefreshError;
+use crate::services::token_exchange::ExchangeError;
+use crate::{build_permissions, cap_to_window, permits, AppState, Claims, MtxPermission};
+
+/// Acciones que un cliente puede pedir en `scope` (RFC 6749 §3.3).
+const SUPPORTED_SCOPES: [&str; 3] = ["read", "playback", "publish"];
+
+/// `grant_type` del canje de tokens (RFC 8693 §2.1).
+pub const TOKEN_EXCHANGE_GRANT: &str = "urn:ietf:params:oauth:grant-type:token-exchange";
+
+/// Tipos de `subject_token` aceptados: JWT firmados por el IdP externo.
+const SUBJECT_TOKEN_TYPES: [&str; 3] = [
+    "urn:ietf:params:oauth:token-type:jwt",
+    "urn:ietf:params:oauth:token-type:id_token",
+    "urn:ietf:params:oauth:token-type:access_token",
+];
+
+/// `issued_token_type` de lo que emite el canje (RFC 8693 §2.2.1).
+const ACCESS_TOKEN_TYPE: &str = "urn:ietf:params:oauth:token-type:access_token";
+
+pub fn router() -> Router<Arc<AppState>> {
+    Router::new()
+        .route("/oauth/token", post(token))
+        .route("/oauth/introspect", post(introspect))
+}
+
+/// Petición de token (form-urlencoded). Las credenciales pueden ir aquí o en
+/// `Authorization: Basic`, pero no en ambos.
+#[derive(Deserialize, ToSchema)]
+pub struct TokenRequest {
+    /// `client_credentials`, `refresh_token` o
+    /// `urn:ietf:params:oauth:grant-type:token-exchange`.
+    #[schema(example = "client_credentials")]
+    pub grant_type: Option<String>,
+    /// En el canje de tokens, sin secreto: elige el proyecto cuando la
+    /// identidad externa está asociada a varios.
+    #[schema(example = "sigac")]
+    pub client_id: Option<String>,
+    #[schema(example = "s3cret")]
+    pub client_secret: Option<String>,
+    /// Acciones separadas por espacio (`read`, `playback`, `publish`); por
+    /// defecto, todas las que tenga el proyecto.
+    #[schema(example = "read playback")]
+    pub scope: Option<String>,
+    /// Refresh token a canjear (solo con `grant_type=refresh_token`).
+    pub refresh_token: Option<String>,
+    /// JWT del IdP externo (solo en el canje de tokens).
+    pub subject_token: Option<String>,
+    /// Tipo del `subject_token`: `urn:ietf:params:oauth:token-type:jwt`,
+    /// `...:id_token` o `...:access_token`.
+    #[schema(example = "urn:ietf:params:oauth:token-type:jwt")]
+    pub subject_token_type: Option<String>,
+}
+
+/// Respuesta de token (RFC 6749 §5.1).
+#[derive(Serialize, ToSchema)]
+pub struct TokenResponse {
+    /// JWT firmado, el mismo formato que entrega `/auth/login`.
+    pub access_token: String,
+    #[schema(example = "Bearer")]
+    pub token_type: &'static str,
+    /// Vida del token en segundos.
+    #[schema(example = 3600)]
+    pub expires_in: i64,
+    /// Acciones concedidas, separadas por espacio.
+    #[schema(example = "read playback")]
+    pub scope: String,
+    /// Refresh token opaco de un solo uso (ausente si están deshabilitados).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub refresh_token: Option<String>,
+    /// Solo en el canje de tokens: `urn:ietf:params:oauth:token-type:access_token`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schema(example = "urn:ietf:params:oauth:token-type:access_token")]
+    pub issued_token_type: Option<&'static str>,
+}
+
+/// Petición de introspección (RFC 7662 §2.1, form-urlencoded). Quien pregunta
+/// se autentica como proyecto (Basic o `client_id`/`client_secret`) o con el
+/// token de administración (`Authorization: Bearer`).
+#[derive(Deserialize, ToSchema)]
+pub struct IntrospectRequest {
+    /// JWT de acceso a inspeccionar.
+    pub token: Option<String>,
+    /// Pista del tipo de token; solo hay `access_token`, así que se ignora.
+    #[allow(dead_code)]
+    #[schema(example = "access_token")]
+    pub token_type_hint: Option<String>,
+    #[schema(example = "sigac")]
+    pub client_id: Option<String>,
+    #[schema(example = "s3cret")]
+    pub client_secret: Option<String>,
+}
+
+/// Cámara cubierta por un token, con las acciones que este concede sobre ella.
+#[derive(Serialize, ToSchema)]
+pub struct IntrospectedCamera {
+    #[serde(flatten)]
+    pub camera: CameraRef,
+    #[schema(example = json!(["read", "playback"]))]
+    pub actions: Vec<String>,
+}
+
+/// Respuesta de introspección (RFC 7662 §2.2). Un token inactivo (inválido,
+/// expirado, revocado, de un proyecto deshabilitado o ajeno a quien
+/// pregunta) responde solo `{"active": false}`.
+#[derive(Default, Serialize, ToSchema)]
+pub struct IntrospectResponse {
+    pub active: bool,
+    /// `client_id` del proyecto dueño del token.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schema(example = "sigac")]
+    pub sub: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schema(example = "sigac")]
+    pub client_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schema(example = "Bearer")]
+    pub token_type: Option<&'static str>,
+    /// Acciones presentes en el token, separadas por espacio.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schema(example = "read playback")]
+    pub scope: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schema(example = 1733817600)]
+    pub exp: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schema(example = 1733814000)]
+    pub iat: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub iss: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub aud: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub jti: Option<String>,
+    /// `true` si es un token de visor.
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub viewer: bool,
+    /// Cámaras habilitadas que cubren los `mediamtx_permissions` del token.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cameras: Option<Vec<IntrospectedCamera>>,
+}
+
+/// Error OAuth2 (RFC 6749 §5.2).
+#[derive(Serialize, ToSchema)]
+pub struct OAuthErrorBody {
+    #[schema(example = "invalid_client")]
+    pub error: &'static str,
+    pub error_description: Option<String>,
+}
+
+/// Error OAuth2 con su estado HTTP. `invalid_client` responde 401 con
+/// `WWW-Authenticate: Basic`; el resto, 400 (salvo `server_error` y el
+/// bloqueo por fuerza bruta, 429 con `Retry-After`).
+pub struct OAuthError {
+    status: StatusCode,
+    code: &'static str,
+    description: String,
+    retry_after: Option<i64>,
+}
+
+impl OAuthError {
+    fn new(code: &'static str, description: impl Into<String>) -> Self {
+        let status = match code {
+            "invalid_client" => StatusCode::UNAUTHORIZED,
+            "server_error" => StatusCode::INTERNAL_SERVER_ERROR,
+            _ => StatusCode::BAD_REQUEST,
+        };
+        Self {
+            status,
+            code,
+            description: description.into(),
+            retry_after: None,
+        }
+    }
+
+    /// Cliente o IP bloqueados por demasiados intentos fallidos.
+    fn locked(retry_after: i64) -> Self {
+        Self {
+            status: StatusCode::TOO_MANY_REQUESTS,
+            code: "temporarily_unavailable",
+            description: "demasiados intentos fallidos; reintente más tarde".into(),
+            retry_after: Some(retry_after),
+        }
+    }
+}
+
+impl IntoResponse for OAuthError {
+    fn into_response(self) -> Response {
+        let mut resp = (
+            self.status,
+            Json(OAuthErrorBody {
+                error: self.code,
+                error_description: Some(self.description),
+            }),
+        )
+            .into_response();
+        let headers = resp.headers_mut();
+        no_store(headers);
+        if self.status == StatusCode::UNAUTHORIZED {
+            headers.insert(
+                header::WWW_AUTHENTICATE,
+                HeaderValue::from_static(r#"Basic realm="oauth""#),
+            );
+        }
+        if let Some(secs) = self.retry_after {
+            headers.insert(header::RETRY_AFTER, HeaderValue::from(secs));
+        }
+        resp
+    }
+}
+
+/// Las respuestas con tokens no deben cachearse (RFC 6749 §5.1).
+fn no_store(headers: &mut HeaderMap) {
+    headers.insert(header::CACHE_CONTROL, HeaderValue::from_static("no-store"));
+    headers.insert(header::PRAGMA, HeaderValue::from_static("no-cache"));
+}
+
+/// Emite un token de acceso OAuth2 (client credentials, refresh token o
+/// canje de un token externo).
+///
+/// Alternativa estándar a `/auth/login` para librerías OAuth2: el token es el
+/// mismo JWT con los permisos del proyecto. Credenciales por
+/// `Authorization: Basic base64(client_id:client_secret)` o en el body.
+/// Con `grant_type=refresh_token` basta el refresh token (cliente público);
+/// si además se presentan credenciales, deben ser del mismo proyecto.
+///
+/// Con `grant_type=urn:ietf:params:oauth:grant-type:token-exchange` (RFC
+/// 8693) el `subject_token` es un JWT del IdP corporativo; el proyecto sale de
+/// `idp_mappings` (`client_id` desambigua si hay varios). Sin refresh token y
+/// sin sobrevivir al token externo.
+#[utoipa::path(
+    post, path = "/oauth/token", tag = "Authentication",
+    operation_id = "oauthToken",
+    request_body(content = TokenRequest, content_type = "application/x-www-form-urlencoded"),
+    responses(
+        (status = 200, description = "Token emitido", body = TokenResponse),
+        (status = 400, description = "invalid_request, invalid_grant, unsupported_grant_type o invalid_scope", body = OAuthErrorBody),
+        (status = 401, description = "invalid_client", body = OAuthErrorBody),
+        (status = 429, description = "temporarily_unavailable: demasiados intentos fallidos del client_id o la IP (ver `Retry-After`)", body = OAuthErrorBody)
+    )
+)]
+pub async fn token(
+    State(state): State<Arc<AppState>>,
+    connect: Option<ConnectInfo<SocketAddr>>,
+    headers: HeaderMap,
+    form: Result<Form<TokenRequest>, FormRejection>,
+) -> Result<Response, OAuthError> {
+    let Form(req) = form.map_err(|e| OAuthError::new("invalid_request", e.body_text()))?;
+    let ip = client_ip::from_request(connect, &headers, &state.config.trusted_proxies);
+    let ip = ip.as_deref();
+
+    match req.grant_type.as_deref() {
+        Some("client_credentials") => client_credentials_grant(&state, &headers, &req, ip).await,
+        Some("refresh_token") => refresh_token_grant(&state, &headers, &req, ip).await,
+        Some(TOKEN_EXCHANGE_GRANT) => token_exchange_grant(&state, &req, ip).await,
+        Some(other) => Err(OAuthError::new(
+            "unsupported_grant_type",
+            format!("grant_type no soportado: {other}"),
+        )),
+        None => Err(OAuthError::new("invalid_request", "falta grant_type")),
+    }
+}
+
+/// Introspección de un token de acceso (RFC 7662).
+///
+/// Para servicios que reciben nuestros JWT: indica si el token sigue activo
+/// (firma, claims registrados, denylist y proyecto habilitado), de quién es y
+/// qué cámaras cubre. Un proyecto solo puede inspeccionar sus propios tokens;
+/// con el token de administración se inspecciona cualquiera.
+#[utoipa::path(
+    post, path = "/oauth/introspect", tag = "Authentication",
+    operation_id = "oauthIntrospect",
+    request_body(content = IntrospectRequest, content_type = "application/x-www-form-urlencoded"),
+    responses(
+        (status = 200, description = "Estado del token (`active: false` si no es válido)", body = IntrospectResponse),
+        (status = 400, description = "invalid_request: falta `token`", body = OAuthErrorBody),
+        (status = 401, description = "invalid_client: credenciales del proyecto o token de administración inválidos", body = OAuthErrorBody),
+        (status = 429, description = "temporarily_unavailable: demasiados intentos fallidos del client_id o la IP (ver `Retry-After`)", body = OAuthErrorBody)
+    )
+)]
+pub async fn introspect(
+    State(state): State<Arc<AppState>>,
+    connect: Option<ConnectInfo<SocketAddr>>,
+    headers: HeaderMap,
+    form: Result<Form<IntrospectRequest>, FormRejection>,
+) -> Result<Response, OAuthError> {
+    let Form(req) = form.map_err(|e| OAuthError::new("invalid_request", e.body_text()))?;
+    let ip = client_ip::from_request(connect, &headers, &state.config.trusted_proxies);
+    let caller = introspection_caller(&state, &headers, &req, ip.as_deref()).await?;
+    let token = req
+        .token
+        .as_deref()
+        .filter(|t| !t.is_empty())
+        .ok_or_else(|| OAuthError::new("invalid_request", "falta token"))?;
+
+    let body = match active_claims(&state, &caller, token).await? {
+        Some(claims) => {
+            let cameras = state.camera_repo.list_enabled().await.map_err(|e| {
+                warn!("Error listando cámaras para introspección: {}", e);
+                OAuthError::new("server_error", "error interno")
+            })?;
+            describe(claims, cameras)
+        }
+        None => IntrospectResponse::default(),
+    };
+    let mut resp = Json(body).into_response();
+    no_store(resp.headers_mut());
+    Ok(resp)
+}
+
+/// Quién pide la introspección.
+enum Caller {
+    Admin,
+    Project(Project),
+}
+
+/// Un Bearer debe ser de administración (sesión de una cuenta o token de
+/// emergencia; las claves de API no introspectan); si no, credenciales de
+/// proyecto (con el mismo bloqueo por fuerza bruta que el login).
+async fn introspection_caller(
+    state: &AppState,
+    headers: &HeaderMap,
+    req: &IntrospectRequest,
+    ip: Option<&str>,
+) -> Result<Caller, OAuthError> {
+    let auth = headers
+        .get(header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok());
+    if auth.is_some_and(|v| v.starts_with("Bearer ")) {
+        match admin::principal(state, auth).await {
+            Ok(Some(p)) if !p.is_api_key() => return Ok(Caller::Admin),
+            Ok(_) => {}
+            Err(e) => {
+                warn!("Error resolviendo la sesión de administración: {}", e);
+                return Err(OAuthError::new("server_error", "error interno"));
+            }
+        }
+        warn!("Introspección con token de administración inválido (ip {:?})", ip);
+        return Err(OAuthError::new("invalid_client", "token de administración inválido"));
+    }
+    let (client_id, client_secret) =
+        client_credentials(headers, req.client_id.as_deref(), req.client_secret.as_deref())?;
+    Ok(Caller::Project(
+        authenticate(state, &client_id, &client_secret, ip).await?,
+    ))
+}
+
+/// Claims del token si está activo para `caller`: válido y no revocado, del
+/// propio proyecto (salvo admin) y con el proyecto aún habilitado.
+async fn active_claims(
+    state: &AppState,
+    caller: &Caller,
+    token: &str,
+) -> Result<Option<Claims>, OAuthError> {
+    let Ok(claims) = consumer::validate_token(state, token) else {
+        return Ok(None);
+    };
+    if let Caller::Project(p) = caller {
+        if p.client_id != claims.sub {
+            warn!("Proyecto {} intentó inspeccionar un token ajeno", p.client_id);
+            return Ok(None);
+        }
+    }
+    let project = state
+        .project_repo
+        .find_by_client_id(&claims.sub)
+        .await
+        .map_err(|e| {
+            warn!("Error buscando el proyecto {}: {}", claims.sub, e);
+            OAuthError::new("server_error", "error interno")
+        })?;
+    Ok(project.is_some_and(|p| p.enabled).then_some(claims))
+}
+
+/// Arma la respuesta de un token activo con sus cámaras expandidas.
+fn describe(claims: Claims, cameras: Vec<Camera>) -> IntrospectResponse {
+    let cameras = covered_cameras(cameras, &claims.mediamtx_permissions);
+    let mut actions: Vec<&str> = Vec::new();
+    for p in &claims.mediamtx_permissions {
+        if !actions.contains(&p.action.as_str()) {
+            actions.push(&p.action);
+        }
+    }
+    IntrospectResponse {
+        active: true,
+        client_id: Some(claims.sub.clone()),
+        token_type: Some("Bearer"),
+        scope: Some(actions.join(" ")),
+        exp: Some(claims.exp),
+        iat: Some(claims.iat),
+        iss: Some(claims.iss),
+        aud: claims.aud,
+        jti: Some(claims.jti),
+        viewer: claims.viewer,
+        cameras: Some(cameras),
+        sub: Some(claims.sub),
+    }
+}
+
+/// Cámaras sobre las que algún permiso concede alguna acción, con esas
+/// acciones (path vacío = todas las cámaras).
+fn covered_cameras(cameras: Vec<Camera>, permissions: &[MtxPermission]) -> Vec<IntrospectedCamera> {
+    cameras
+        .into_iter()
+        .filter_map(|c| {
+            let mut actions: Vec<String> = Vec::new();
+            for p in permissions {
+                if !actions.contains(&p.action) && permits(permissions, &p.action, &c.path) {
+                    actions.push(p.action.clone());
+                }
+            }
+            (!actions.is_empty()).then(|| IntrospectedCamera {
+                camera: CameraRef::from(c),
+                actions,
+            })
+        })
+        .collect()
+}
+
+async fn client_credentials_grant(
+    state: &AppState,
+    headers: &HeaderMap,
+    req: &TokenRequest,
+    ip: Option<&str>,
+) -> Result<Response, OAuthError> {
+    let (client_id, client_secret) = body_credentials(headers, req)?;
+    info!("Solicitud OAuth2 client_credentials para proyecto: {}", client_id);
+    let project = authenticate(state, &client_id, &client_secret, ip).await?;
+
+    let lifetime = project.token_lifetime(None, state.config.jwt_exp_minutes);
+    let (permissions, scope, lifetime) =
+        project_permissions(state, &project, req.scope.as_deref(), lifetime).await?;
+    // Firmar primero: si falla no queda en la BD un refresh token sin entregar.
+    let access_token = sign_access_token(state, &project, permissions, lifetime)?;
+    let refresh_token = state
+        .refresh
+        .issue(project.id, req.scope.as_deref())
+        .await
+        .map_err(|e| {
+            warn!("Error emitiendo refresh token para {}: {}", project.client_id, e);
+            OAuthError::new("server_error", "error interno")
+        })?;
+    Ok(token_response(&project, access_token, scope, lifetime, refresh_token, None))
+}
+
+async fn refresh_token_grant(
+    state: &AppState,
+    headers: &HeaderMap,
+    req: &TokenRequest,
+    ip: Option<&str>,
+) -> Result<Response, OAuthError> {
+    let presented = req
+        .refresh_token
+        .as_deref()
+        .ok_or_else(|| OAuthError::new("invalid_request", "falta refresh_token"))?;
+
+    // Cliente confidencial opcional: si se autentica, debe ser el dueño.
+    let client = match body_credentials(headers, req) {
+        Ok((id, secret)) => Some(authenticate(state, &id, &secret, ip).await?),
+        Err(_) => None,
+    };
+
+    let refresh_err = |e: RefreshError| match e {
+        RefreshError::Repo(e) => {
+            warn!("Error canjeando refresh token: {}", e);
+            OAuthError::new("server_error", "error interno")
+        }
+        other => OAuthError::new("invalid_grant", other.to_string()),
+    };
+    // Las comprobaciones van antes de canjear: un rechazo no consume el token
+    // (si no, el dueño dispararía luego la detección de reuso).
+    let presented = state.refresh.present(presented).await.map_err(refresh_err)?;
+    if client.is_some_and(|c| c.id != presented.project.id) {
+        warn!("Refresh token presentado por otro proyecto");
+        return Err(OAuthError::new("invalid_grant", "refresh token de otro cliente"));
+    }
+    // Un refresh token filtrado tampoco sirve fuera de las redes del proyecto.
+    if state.lockout.check_source(&presented.project, ip).await.is_err() {
+        return Err(OAuthError::new("invalid_grant", "refresh token inválido"));
+    }
+    // Firmar antes de canjear: un fallo al firmar no debe consumir el token.
+    let lifetime = presented
+        .project
+        .token_lifetime(None, state.config.jwt_exp_minutes);
+    let (permissions, scope, lifetime) =
+        project_permissions(state, &presented.project, presented.scope(), lifetime).await?;
+    let access_token = sign_access_token(state, &presented.project, permissions, lifetime)?;
+
+    let rotated = state.refresh.rotate(presented).await.map_err(refresh_err)?;
+    info!("Refresh token canjeado para proyecto: {}", rotated.project.client_id);
+    Ok(token_response(
+        &rotated.project,
+        access_token,
+        scope,
+        lifetime,
+        Some(rotated.refresh_token),
+        None,
+    ))
+}
+
+async fn token_exchange_grant(
+    state: &AppState,
+    req: &TokenRequest,
+    ip: Option<&str>,
+) -> Result<Response, OAuthError> {
+    let Some(exchange) = state.token_exchange.as_ref() else {
+        return Err(OAuthError::new("unsupported_grant_type", "canje de tokens deshabilitado"));
+    };
+    let subject_token = req
+        .subject_token
+        .as_deref()
+        .filter(|t| !t.is_empty())
+        .ok_or_else(|| OAuthError::new("invalid_request", "falta subject_token"))?;
+    match req.subject_token_type.as_deref() {
+        Some(t) if SUBJECT_TOKEN_TYPES.contains(&t) => {}
+        Some(t) => {
+            return Err(OAuthError::new(
+                "invalid_request",
+                format!("subject_token_type no soportado: {t}"),
+            ))
+        }
+        None => return Err(OAuthError::new("invalid_request", "falta subject_token_type")),
+    }
+
+    let exchanged = exchange
+        .exchange(subject_token, req.client_id.as_deref())
+        .await
+        .map_err(|e| match e {
+            ExchangeError::InvalidToken(_) => {
+                warn!("Canje de token rechazado (ip {:?}): {}", ip, e);
+                OAuthError::new("invalid_grant", e.to_string())
+            }
+            ExchangeError::KeysUnavailable(_) | ExchangeError::Repo(_) => {
+                warn!("Error en el canje de token: {}", e);
+                OAuthError::new("server_error", "error interno")
+            }
+            other => {
+                warn!("Canje de token sin proyecto (ip {:?}): {}", ip, other);
+                OAuthError::new("invalid_target", other.to_string())
+            }
+        })?;
+    let project = exchanged.project;
+    if state.lockout.check_source(&project, ip).await.is_err() {
+        return Err(OAuthError::new("invalid_grant", "token externo no aceptado desde esta IP"));
+    }
+    info!(
+        "Token externo de '{}' canjeado para proyecto: {}",
+        exchanged.subject, project.client_id
+    );
+
+    // Nuestro token no sobrevive al externo: minutos enteros que le quedan.
+    let remaining = (exchanged.expires_at - chrono::Utc::now().timestamp()) / 60;
+    if remaining < 1 {
+        return Err(OAuthError::new("invalid_grant", "el token externo está por expirar"));
+    }
+    let lifetime = project
+        .token_lifetime(None, state.config.jwt_exp_minutes)
+        .min(remaining);
+
+    let (permissions, scope, lifetime) =
+        project_permissions(state, &project, req.scope.as_deref(), lifetime).await?;
+    let access_token = sign_access_token(state, &project, permissions, lifetime)?;
+    Ok(token_response(
+        &project,
+        access_token,
+        scope,
+        lifetime,
+        None,
+        Some(ACCESS_TOKEN_TYPE),
+    ))
+}
+
+async fn authenticate(
+    state: &AppState,
+    client_id: &str,
+    secret: &str,
+    ip: Option<&str>,
+) -> Result<Project, OAuthError> {
+    state
+        .lockout
+        .authenticate(&state.auth, client_id, secret, ip)
+        .await
+        .map_err(|e| match e {
+            LoginError::Locked { retry_after } => {
+                warn!("Login OAuth2 bloqueado para proyecto {} (ip {:?})", client_id, ip);
+                OAuthError::locked(retry_after)
+            }
+            LoginError::InvalidCredentials => {
+                warn!("Credenciales inválidas (OAuth2) para proyecto: {}", client_id);
+                OAuthError::new("invalid_client", "credenciales inválidas")
+            }
+            LoginError::IpNotAllowed => {
+                warn!("Login OAuth2 de {} desde IP no permitida: {:?}", client_id, ip);
+                OAuthError::new("invalid_client", "credenciales inválidas")
+            }
+            LoginError::Repo(e) => {
+                warn!("Error verificando el bloqueo de {}: {}", client_id, e);
+                OAuthError::new("server_error", "error interno")
+            }
+        })
+}
+
+/// Permisos actuales del proyecto, restringidos al `scope` pedido, y la vida
+/// del token acotada al fin de ventana de sus cámaras.
+async fn project_permissions(
+    state: &AppState,
+    project: &Project,
+    scope: Option<&str>,
+    lifetime: i64,
+) -> Result<(Vec<MtxPermission>, String, i64), OAuthError> {
+    let internal = |e| {
+        warn!("Error calculando el acceso del proyecto {}: {}", project.client_id, e);
+        OAuthError::new("server_error", "error interno")
+    };
+    let now = chrono::Utc::now();
+    let access = state
+        .auth
+        .camera_access(project)
+        .await
+        .map_err(internal)?
+        .for_token(now);
+    let publish = state.auth.publish_grants(project).await.map_err(internal)?;
+    let (permissions, scope) = narrow_scope(build_permissions(&access, &publish), scope)?;
+    Ok((permissions, scope, cap_to_window(lifetime, &access, now)))
+}
+
+fn sign_access_token(
+    state: &AppState,
+    project: &Project,
+    permissions: Vec<MtxPermission>,
+    lifetime: i64,
+) -> Result<String, OAuthError> {
+    let aud = project.token_audience(None).unwrap_or_default();
+    state
+        .generate_jwt(&project.client_id, permissions, lifetime, aud, false)
+        .map_err(|e| {
+            warn!("Error generando JWT: {}", e);
+            OAuthError::new("server_error", "error generando token")
+        })
+}
+
+fn token_response(
+    project: &Project,
+    access_token: String,
+    scope: String,
+    lifetime: i64,
+    refresh_token: Option<String>,
+    issued_token_type: Option<&'static str>,
+) -> Response {
+    info!("Token OAuth2 emitido para proyecto: {}", project.client_id);
+
+    let mut resp = Json(TokenResponse {
+        access_token,
+        token_type: "Bearer",
+        expires_in: lifetime * 60,
+        scope,
+        refresh_token,
+        issued_token_type,
+    })
+    .into_response();
+    no_store(resp.headers_mut());
+    resp
+}
+
+/// Credenciales del cliente de una petición de token.
+fn body_credentials(headers: &HeaderMap, req: &TokenRequest) -> Result<(String, String), OAuthError> {
+    client_credentials(headers, req.client_id.as_deref(), req.client_secret.as_deref())
+}
+
+/// Extrae `(client_id, client_secret)` de `Authorization: Basic` o del body
+/// (RFC 6749 §2.3.1). Usar ambos métodos a la vez es `invalid_request`.
+fn client_credentials(
+    headers: &HeaderMap,
+    body_id: Option<&str>,
+    body_secret: Option<&str>,
+) -> Result<(String, String), OAuthError> {
+    let basic = headers
+        .get(header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Basic "));
+
+    match (basic, body_id, body_secret) {
+        (Some(_), _, Some(_)) => Err(OAuthError::new(
+            "invalid_request",
+            "credenciales por más de un método",
+        )),
+        (Some(encoded), body_id, None) => {
+            let (id, secret) = parse_basic(encoded)
+                .ok_or_else(|| OAuthError::new("invalid_client", "cabecera Basic inválida"))?;
+            if body_id.is_some_and(|b| b != id) {
+                return Err(OAuthError::new("invalid_request", "client_id inconsistente"));
+            }
+            Ok((id, secret))
+        }
+        (None, Some(id), Some(secret)) => Ok((id.to_string(), secret.to_string())),
+        (None, _, _) => Err(OAuthError::new(
+            "invalid_client",
+            "faltan las credenciales del cliente",
+        )),
+    }
+}
+
+/// Decodifica `base64(client_id:client_secret)`; cada parte va form-urlencoded.
+fn parse_basic(encoded: &str) -> Option<(String, String)> {
+    let raw = String::from_utf8(STANDARD.decode(encoded.trim()).ok()?).ok()?;
+    let (id, secret) = raw.split_once(':')?;
+    Some((form_decode(id)?, form_decode(secret)?))
+}
+
+/// Decodificación `application/x-www-form-urlencoded` (`+` → espacio, `%XX`).
+fn form_decode(s: &str) -> Option<String> {
+    let bytes = s.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'+' => out.push(b' '),
+            b'%' => {
+                let hex = s.get(i + 1..i + 3)?;
+                out.push(u8::from_str_radix(hex, 16).ok()?);
+                i += 2;
+            }
+            b => out.push(b),
+        }
+        i += 1;
+    }
+    String::from_utf8(out).ok()
+}
+
+/// Restringe los permisos a las acciones pedidas en `scope` (todas si no se
+/// pide ninguna) y devuelve el `scope` concedido. Una acción desconocida es
+/// `invalid_scope`.
+fn narrow_scope(
+    permissions: Vec<MtxPermission>,
+    scope: Option<&str>,
+) -> Result<(Vec<MtxPermission>, String), OAuthError> {
+    let requested: Vec<&str> = match scope.map(str::split_whitespace) {
+        Some(parts) => parts.collect(),
+        None => Vec::new(),
+    };
+    if let Some(unknown) = requested.iter().find(|s| !SUPPORTED_SCOPES.contains(s)) {
+        return Err(OAuthError::new(
+            "invalid_scope",
+            format!("scope no soportado: {unknown}"),
+        ));
+    }
+
+    let permissions: Vec<MtxPermission> = permissions
+        .into_iter()
+        .filter(|p| requested.is_empty() || requested.contains(&p.action.as_str()))
+        .collect();
+    let granted: Vec<&str> = SUPPORTED_SCOPES
+        .into_iter()
+        .filter(|s| permissions.iter().any(|p| p.action == *s))
+        .collect();
+    let scope = granted.join(" ");
+    Ok((permissions, scope))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        body_credentials, covered_cameras, form_decode, narrow_scope, parse_basic,
+        IntrospectResponse, TokenRequest,
+    };
+    use crate::domain::models::Camera;
+    use crate::MtxPermission;
+    use chrono::Utc;
+    use uuid::Uuid;
+    use axum::http::{header::AUTHORIZATION, HeaderMap, HeaderValue};
+    use base64::engine::general_purpose::STANDARD;
+    use base64::Engine;
+
+    fn req(id: Option<&str>, secret: Option<&str>) -> TokenRequest {
+        TokenRequest {
+            grant_type: Some("client_credentials".into()),
+            client_id: id.map(Into::into),
+            client_secret: secret.map(Into::into),
+            scope: None,
+            refresh_token: None,
+            subject_token: None,
+            subject_token_type: None,
+        }
+    }
+
+    fn basic(id: &str, secret: &str) -> HeaderMap {
+        let mut h = HeaderMap::new();
+        let value = format!("Basic {}", STANDARD.encode(format!("{id}:{secret}")));
+        h.insert(AUTHORIZATION, HeaderValue::from_str(&value).unwrap());
+        h
+    }
+
+    fn perm(action: &str, path: &str) -> MtxPermission {
+        MtxPermission {
+            action: action.into(),
+            path: path.into(),
+        }
+    }
+
+    #[test]
+    fn basic_credentials_are_form_decoded() {
+        let encoded = STANDARD.encode("sigac:s3c%3Aret+x");
+        assert_eq!(
+            parse_basic(&encoded),
+            Some(("sigac".to_string(), "s3c:ret x".to_string()))
+        );
+        assert_eq!(form_decode("%zz"), None);
+    }
+
+    #[test]
+    fn credentials_from_basic_or_body() {
+        let (id, secret) = body_credentials(&basic("sigac", "s3cret"), &req(None, None))
+            .ok()
+            .unwrap();
+        assert_eq!((id.as_str(), secret.as_str()), ("sigac", "s3cret"));
+
+        let (id, _) = body_credentials(&HeaderMap::new(), &req(Some("odin"), Some("x")))
+            .ok()
+            .unwrap();
+        assert_eq!(id, "odin");
+    }
+
+    #[test]
+    fn both_methods_is_invalid_request() {
+        let err = body_credentials(&basic("sigac", "a"), &req(None, Some("b")))
+            .err()
+            .unwrap();
+        assert_eq!(err.code, "invalid_request");
+    }
+
+    #[test]
+    fn missing_credentials_is_invalid_client() {
+        let err = body_credentials(&HeaderMap::new(), &req(Some("sigac"), None))
+            .err()
+            .unwrap();
+        assert_eq!(err.code, "invalid_client");
+    }
+
+    #[test]
+    fn scope_narrows_actions() {
+        let perms = vec![perm("read", "a"), perm("playback", "a")];
+        let (narrowed, scope) = narrow_scope(perms.clone(), Some("read")).ok().unwrap();
+        assert_eq!(narrowed.len(), 1);
+        assert_eq!(scope, "read");
+
+        let (all, scope) = narrow_scope(perms.clone(), None).ok().unwrap();
+        assert_eq!(all.len(), 2);
+        assert_eq!(scope, "read playback");
+
+        let err = narrow_scope(perms, Some("api")).err().unwrap();
+        assert_eq!(err.code, "invalid_scope");
+    }
+
+    fn cam(path: &str) -> Camera {
+        Camera {
+            id: Uuid::new_v4(),
+            path: path.into(),
+            rtsp_url: Some("rtsp://x".into()),
+            record: true,
+            enabled: true,
+            description: None,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn introspection_expands_cameras_with_their_actions() {
+        let perms = vec![perm("read", "a"), perm("playback", ""), perm("read", "a")];
+        let covered = covered_cameras(vec![cam("a"), cam("b")], &perms);
+        assert_eq!(covered.len(), 2);
+        assert_eq!(covered[0].actions, vec!["read", "playback"]);
+        assert_eq!(covered[1].actions, vec!["playback"], "path vacío = todas");
+
+        let none = covered_cameras(vec![cam("a")], &[perm("read", "b")]);
+        assert!(none.is_empty());
+    }
+
+    #[test]
+    fn inactive_token_only_reports_active_false() {
+        let body = serde_json::to_value(IntrospectResponse::default()).unwrap();
+        assert_eq!(body, serde_json::json!({ "active": false }));
+    }
+}
@@ -58,3 +58,14 @@ pub async fn run_migrations(pool: &PgPool) -> Result<(), sqlx::migrate::MigrateE
     info!("Migraciones de esquema al día.");
     Ok(())
 }
+
+/// Comprueba que Postgres conoce la zona horaria `tz` (nombre IANA, p.ej.
+/// `America/Santiago`) con que se evalúan los horarios de las asignaciones.
+pub async fn check_timezone(pool: &PgPool, tz: &str) -> Result<(), sqlx::Error> {
+    sqlx::query("SELECT now() AT TIME ZONE $1")
+        .bind(tz)
+        .execute(pool)
+        .await?;
+    info!("Horarios de asignaciones en la zona {}", tz);
+    Ok(())
+}
@@ -0,0 +1,65 @@
+//! Adaptador de `ExternalKeySource`: el JWKS del proveedor de identidad
+//! externo (`EXTERNAL_IDP_JWKS`), desde un archivo local o una URL HTTPS.
+//!
+//! El archivo sirve para IdPs sin red hacia el backend o para fijar claves a
+//! mano; la URL es lo habitual (`jwks_uri` del discovery del IdP).
+
+use std::path::PathBuf;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use jsonwebtoken::jwk::JwkSet;
+use reqwest::Client;
+
+use crate::domain::ports::ExternalKeySource;
+
+enum Location {
+    File(PathBuf),
+    Url(String),
+}
+
+pub struct ExternalJwks {
+    location: Location,
+    client: Client,
+}
+
+impl ExternalJwks {
+    /// `http(s)://...` se descarga; cualquier otra cosa es una ruta de archivo.
+    pub fn new(location: &str) -> Self {
+        let location = if location.starts_with("https://") || location.starts_with("http://") {
+            Location::Url(location.to_string())
+        } else {
+            Location::File(PathBuf::from(location))
+        };
+        let client = Client::builder()
+            .timeout(Duration::from_secs(10))
+            .build()
+            .expect("no se pudo construir el cliente HTTP");
+        Self { location, client }
+    }
+}
+
+#[async_trait]
+impl ExternalKeySource for ExternalJwks {
+    async fn fetch(&self) -> Result<JwkSet, String> {
+        let body = match &self.location {
+            Location::File(path) => std::fs::read_to_string(path)
+                .map_err(|e| format!("no se pudo leer {}: {}", path.display(), e))?,
+            Location::Url(url) => {
+                let resp = self
+                    .client
+                    .get(url)
+                    .send()
+                    .await
+                    .map_err(|e| format!("no se pudo descargar el JWKS: {e}"))?;
+                if !resp.status().is_success() {
+                    return Err(format!("el IdP respondió {} al pedir el JWKS", resp.status()));
+                }
+                resp.text()
+                    .await
+                    .map_err(|e| format!("no se pudo leer el JWKS: {e}"))?
+            }
+        };
+        serde_json::from_str(&body).map_err(|e| format!("JWKS inválido: {e}"))
+    }
+}
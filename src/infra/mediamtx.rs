@@ -34,9 +34,10 @@ impl MediaMtxProvisioner {
     }
 
     /// Config de ruta para MediaMTX. El resto lo cubre `pathDefaults` del YAML.
+    /// Una cámara publisher usa `source: publisher` (el dispositivo empuja).
     fn path_config(camera: &Camera) -> serde_json::Value {
         json!({
-            "source": camera.rtsp_url,
+            "source": camera.rtsp_url.as_deref().unwrap_or("publisher"),
             "record": camera.record,
         })
     }
@@ -122,6 +123,8 @@ impl CameraProvisioner for MediaMtxProvisioner {
     async fn list_paths(&self) -> ProvisionResult<Vec<String>> {
         // Solo rutas de cámara gestionables (pull RTSP, no regex): así el
         // reconciler nunca elimina publishers (p.ej. "mosaic") ni patrones "~...".
+        // Las cámaras publisher de la BD tampoco aparecen: se quitan solo al
+        // borrarlas o deshabilitarlas desde la administración.
         let paths = self.list_source_paths().await?;
         Ok(paths
             .into_iter()
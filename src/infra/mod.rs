@@ -10,3 +10,5 @@ pub mod db;
 pub mod postgres;
 // Adaptador de la Control API de MediaMTX (lo consume el reconciler).
 pub mod mediamtx;
+// JWKS del proveedor de identidad externo (canje de tokens, RFC 8693).
+pub mod idp_jwks;
@@ -0,0 +1,260 @@
+//! Adaptador Postgres de `AdminAccountRepo`.
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use super::map_sqlx_err;
+use crate::domain::models::{AdminAccount, AdminRole, NewAdminAccount};
+use crate::domain::ports::{AdminAccountRepo, RepoError, RepoResult};
+
+#[derive(sqlx::FromRow)]
+struct AdminAccountRow {
+    id: Uuid,
+    username: String,
+    password_hash: String,
+    role: String,
+    enabled: bool,
+    last_login_at: Option<DateTime<Utc>>,
+    created_at: DateTime<Utc>,
+    updated_at: DateTime<Utc>,
+}
+
+impl TryFrom<AdminAccountRow> for AdminAccount {
+    type Error = RepoError;
+
+    fn try_from(r: AdminAccountRow) -> Result<Self, Self::Error> {
+        let role = AdminRole::parse(&r.role)
+            .ok_or_else(|| RepoError::Backend(format!("rol desconocido: {}", r.role)))?;
+        Ok(AdminAccount {
+            id: r.id,
+            username: r.username,
+            password_hash: r.password_hash,
+            role,
+            enabled: r.enabled,
+            last_login_at: r.last_login_at,
+            created_at: r.created_at,
+            updated_at: r.updated_at,
+        })
+    }
+}
+
+pub struct PgAdminAccountRepo {
+    pool: PgPool,
+}
+
+impl PgAdminAccountRepo {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait]
+impl AdminAccountRepo for PgAdminAccountRepo {
+    async fn list_all(&self) -> RepoResult<Vec<AdminAccount>> {
+        let rows = sqlx::query_as::<_, AdminAccountRow>(
+            "SELECT id, username, password_hash, role, enabled, last_login_at, created_at, updated_at
+             FROM admin_accounts ORDER BY username",
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(map_sqlx_err)?;
+        rows.into_iter().map(TryInto::try_into).collect()
+    }
+
+    async fn find_by_id(&self, id: Uuid) -> RepoResult<Option<AdminAccount>> {
+        let row = sqlx::query_as::<_, AdminAccountRow>(
+            "SELECT id, username, password_hash, role, enabled, last_login_at, created_at, updated_at
+             FROM admin_accounts WHERE id = $1",
+        )
+        .bind(id)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(map_sqlx_err)?;
+        row.map(TryInto::try_into).transpose()
+    }
+
+    async fn find_by_username(&self, username: &str) -> RepoResult<Option<AdminAccount>> {
+        let row = sqlx::query_as::<_, AdminAccountRow>(
+            "SELECT id, username, password_hash, role, enabled, last_login_at, created_at, updated_at
+             FROM admin_accounts WHERE username = $1",
+        )
+        .bind(username)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(map_sqlx_err)?;
+        row.map(TryInto::try_into).transpose()
+    }
+
+    async fn create(&self, new: NewAdminAccount) -> RepoResult<AdminAccount> {
+        let row = sqlx::query_as::<_, AdminAccountRow>(
+            "INSERT INTO admin_accounts (id, username, password_hash, role)
+             VALUES ($1, $2, $3, $4)
+             RETURNING id, username, password_hash, role, enabled, last_login_at, created_at,
+                       updated_at",
+        )
+        .bind(Uuid::new_v4())
+        .bind(new.username)
+        .bind(new.password_hash)
+        .bind(new.role.as_str())
+        .fetch_one(&self.pool)
+        .await
+        .map_err(map_sqlx_err)?;
+        row.try_into()
+    }
+
+    async fn update(&self, account: &AdminAccount) -> RepoResult<AdminAccount> {
+        let row = sqlx::query_as::<_, AdminAccountRow>(
+            "UPDATE admin_accounts SET password_hash = $2, role = $3, enabled = $4
+             WHERE id = $1
+             RETURNING id, username, password_hash, role, enabled, last_login_at, created_at,
+                       updated_at",
+        )
+        .bind(account.id)
+        .bind(account.password_hash.as_str())
+        .bind(account.role.as_str())
+        .bind(account.enabled)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(map_sqlx_err)?;
+        row.ok_or(RepoError::NotFound)?.try_into()
+    }
+
+    async fn delete(&self, id: Uuid) -> RepoResult<()> {
+        let result = sqlx::query("DELETE FROM admin_accounts WHERE id = $1")
+            .bind(id)
+            .execute(&self.pool)
+            .await
+            .map_err(map_sqlx_err)?;
+        if result.rows_affected() == 0 {
+            return Err(RepoError::NotFound);
+        }
+        Ok(())
+    }
+
+    async fn create_session(
+        &self,
+        account_id: Uuid,
+        token_hash: &str,
+        expires_at: DateTime<Utc>,
+    ) -> RepoResult<()> {
+        let mut tx = self.pool.begin().await.map_err(map_sqlx_err)?;
+        sqlx::query("DELETE FROM admin_sessions WHERE expires_at <= now()")
+            .execute(&mut *tx)
+            .await
+            .map_err(map_sqlx_err)?;
+        sqlx::query(
+            "INSERT INTO admin_sessions (token_hash, account_id, expires_at) VALUES ($1, $2, $3)",
+        )
+        .bind(token_hash)
+        .bind(account_id)
+        .bind(expires_at)
+        .execute(&mut *tx)
+        .await
+        .map_err(map_sqlx_err)?;
+        sqlx::query("UPDATE admin_accounts SET last_login_at = now() WHERE id = $1")
+            .bind(account_id)
+            .execute(&mut *tx)
+            .await
+            .map_err(map_sqlx_err)?;
+        tx.commit().await.map_err(map_sqlx_err)?;
+        Ok(())
+    }
+
+    async fn find_session(&self, token_hash: &str) -> RepoResult<Option<AdminAccount>> {
+        let row = sqlx::query_as::<_, AdminAccountRow>(
+            "SELECT a.id, a.username, a.password_hash, a.role, a.enabled, a.last_login_at,
+                    a.created_at, a.updated_at
+             FROM admin_sessions s JOIN admin_accounts a ON a.id = s.account_id
+             WHERE s.token_hash = $1 AND s.expires_at > now() AND a.enabled",
+        )
+        .bind(token_hash)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(map_sqlx_err)?;
+        row.map(TryInto::try_into).transpose()
+    }
+
+    async fn delete_session(&self, token_hash: &str) -> RepoResult<()> {
+        sqlx::query("DELETE FROM admin_sessions WHERE token_hash = $1")
+            .bind(token_hash)
+            .execute(&self.pool)
+            .await
+            .map_err(map_sqlx_err)?;
+        Ok(())
+    }
+
+    async fn delete_sessions(&self, account_id: Uuid) -> RepoResult<()> {
+        sqlx::query("DELETE FROM admin_sessions WHERE account_id = $1")
+            .bind(account_id)
+            .execute(&self.pool)
+            .await
+            .map_err(map_sqlx_err)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::PgAdminAccountRepo;
+    use crate::domain::models::{AdminRole, NewAdminAccount};
+    use crate::domain::ports::{AdminAccountRepo, RepoError};
+    use chrono::{Duration, Utc};
+    use sqlx::PgPool;
+
+    fn account(username: &str, role: AdminRole) -> NewAdminAccount {
+        NewAdminAccount {
+            username: username.into(),
+            password_hash: "$argon2id$x".into(),
+            role,
+        }
+    }
+
+    #[sqlx::test]
+    async fn crud_and_unique_username(pool: PgPool) {
+        let repo = PgAdminAccountRepo::new(pool);
+        let mut ana = repo.create(account("ana", AdminRole::Viewer)).await.unwrap();
+        assert!(matches!(
+            repo.create(account("ana", AdminRole::Admin)).await.unwrap_err(),
+            RepoError::Conflict(_)
+        ));
+
+        ana.role = AdminRole::Operator;
+        ana.enabled = false;
+        let ana = repo.update(&ana).await.unwrap();
+        let found = repo.find_by_username("ana").await.unwrap().unwrap();
+        assert_eq!(found.role, AdminRole::Operator);
+        assert!(!found.enabled);
+        assert_eq!(repo.list_all().await.unwrap().len(), 1);
+
+        repo.delete(ana.id).await.unwrap();
+        assert!(matches!(repo.delete(ana.id).await.unwrap_err(), RepoError::NotFound));
+        assert!(matches!(repo.update(&ana).await.unwrap_err(), RepoError::NotFound));
+    }
+
+    #[sqlx::test]
+    async fn sessions_require_validity_and_an_enabled_account(pool: PgPool) {
+        let repo = PgAdminAccountRepo::new(pool);
+        let mut ana = repo.create(account("ana", AdminRole::Admin)).await.unwrap();
+        let later = Utc::now() + Duration::hours(1);
+        repo.create_session(ana.id, "h1", later).await.unwrap();
+        repo.create_session(ana.id, "h2", later).await.unwrap();
+        repo.create_session(ana.id, "old", Utc::now() - Duration::seconds(1)).await.unwrap();
+
+        let found = repo.find_session("h1").await.unwrap().unwrap();
+        assert_eq!(found.username, "ana");
+        assert!(found.last_login_at.is_some());
+        assert!(repo.find_session("old").await.unwrap().is_none(), "vencida");
+        assert!(repo.find_session("otro").await.unwrap().is_none());
+
+        repo.delete_session("h1").await.unwrap();
+        assert!(repo.find_session("h1").await.unwrap().is_none());
+
+        // Deshabilitar la cuenta invalida sus sesiones aunque sigan en la tabla.
+        ana.enabled = false;
+        repo.update(&ana).await.unwrap();
+        assert!(repo.find_session("h2").await.unwrap().is_none());
+        repo.delete_sessions(ana.id).await.unwrap();
+    }
+}
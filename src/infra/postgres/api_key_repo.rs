@@ -0,0 +1,151 @@
+//! Adaptador Postgres de `ApiKeyRepo`.
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use super::map_sqlx_err;
+use crate::domain::models::{ApiKey, NewApiKey};
+use crate::domain::ports::{ApiKeyRepo, RepoError, RepoResult};
+
+#[derive(sqlx::FromRow)]
+struct ApiKeyRow {
+    id: Uuid,
+    name: String,
+    key_prefix: String,
+    scopes: Vec<String>,
+    expires_at: Option<DateTime<Utc>>,
+    last_used_at: Option<DateTime<Utc>>,
+    created_by: String,
+    created_at: DateTime<Utc>,
+}
+
+impl From<ApiKeyRow> for ApiKey {
+    fn from(r: ApiKeyRow) -> Self {
+        ApiKey {
+            id: r.id,
+            name: r.name,
+            key_prefix: r.key_prefix,
+            scopes: r.scopes,
+            expires_at: r.expires_at,
+            last_used_at: r.last_used_at,
+            created_by: r.created_by,
+            created_at: r.created_at,
+        }
+    }
+}
+
+pub struct PgApiKeyRepo {
+    pool: PgPool,
+}
+
+impl PgApiKeyRepo {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait]
+impl ApiKeyRepo for PgApiKeyRepo {
+    async fn list_all(&self) -> RepoResult<Vec<ApiKey>> {
+        let rows = sqlx::query_as::<_, ApiKeyRow>(
+            "SELECT id, name, key_prefix, scopes, expires_at, last_used_at, created_by, created_at
+             FROM api_keys ORDER BY name",
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(map_sqlx_err)?;
+        Ok(rows.into_iter().map(Into::into).collect())
+    }
+
+    async fn create(&self, new: NewApiKey) -> RepoResult<ApiKey> {
+        let row = sqlx::query_as::<_, ApiKeyRow>(
+            "INSERT INTO api_keys (id, name, key_hash, key_prefix, scopes, expires_at, created_by)
+             VALUES ($1, $2, $3, $4, $5, $6, $7)
+             RETURNING id, name, key_prefix, scopes, expires_at, last_used_at, created_by,
+                       created_at",
+        )
+        .bind(Uuid::new_v4())
+        .bind(new.name)
+        .bind(new.key_hash)
+        .bind(new.key_prefix)
+        .bind(new.scopes)
+        .bind(new.expires_at)
+        .bind(new.created_by)
+        .fetch_one(&self.pool)
+        .await
+        .map_err(map_sqlx_err)?;
+        Ok(row.into())
+    }
+
+    async fn delete(&self, id: Uuid) -> RepoResult<()> {
+        let result = sqlx::query("DELETE FROM api_keys WHERE id = $1")
+            .bind(id)
+            .execute(&self.pool)
+            .await
+            .map_err(map_sqlx_err)?;
+        if result.rows_affected() == 0 {
+            return Err(RepoError::NotFound);
+        }
+        Ok(())
+    }
+
+    async fn use_key(&self, key_hash: &str) -> RepoResult<Option<ApiKey>> {
+        let row = sqlx::query_as::<_, ApiKeyRow>(
+            "UPDATE api_keys SET last_used_at = now()
+             WHERE key_hash = $1 AND (expires_at IS NULL OR expires_at > now())
+             RETURNING id, name, key_prefix, scopes, expires_at, last_used_at, created_by,
+                       created_at",
+        )
+        .bind(key_hash)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(map_sqlx_err)?;
+        Ok(row.map(Into::into))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::PgApiKeyRepo;
+    use crate::domain::models::NewApiKey;
+    use crate::domain::ports::{ApiKeyRepo, RepoError};
+    use chrono::{DateTime, Duration, Utc};
+    use sqlx::PgPool;
+
+    fn key(name: &str, hash: &str, expires_at: Option<DateTime<Utc>>) -> NewApiKey {
+        NewApiKey {
+            name: name.into(),
+            key_hash: hash.into(),
+            key_prefix: "mk_abcd".into(),
+            scopes: vec!["failures:read".into(), "failures:write".into()],
+            expires_at,
+            created_by: "ana".into(),
+        }
+    }
+
+    #[sqlx::test]
+    async fn use_key_requires_validity_and_records_last_use(pool: PgPool) {
+        let repo = PgApiKeyRepo::new(pool);
+        let agent = repo.create(key("stream-agent", "h1", None)).await.unwrap();
+        assert!(agent.last_used_at.is_none());
+        assert!(matches!(
+            repo.create(key("stream-agent", "h2", None)).await.unwrap_err(),
+            RepoError::Conflict(_)
+        ));
+        let past = Some(Utc::now() - Duration::seconds(1));
+        repo.create(key("viejo", "h3", past)).await.unwrap();
+
+        let used = repo.use_key("h1").await.unwrap().unwrap();
+        assert_eq!(used.scopes, vec!["failures:read", "failures:write"]);
+        assert!(used.last_used_at.is_some());
+        assert!(repo.use_key("h3").await.unwrap().is_none(), "vencida");
+        assert!(repo.use_key("otro").await.unwrap().is_none());
+        assert_eq!(repo.list_all().await.unwrap().len(), 2);
+
+        repo.delete(agent.id).await.unwrap();
+        assert!(repo.use_key("h1").await.unwrap().is_none());
+        assert!(matches!(repo.delete(agent.id).await.unwrap_err(), RepoError::NotFound));
+    }
+}
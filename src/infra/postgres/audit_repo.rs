@@ -0,0 +1,105 @@
+//! Adaptador Postgres de `AuditRepo`.
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use super::map_sqlx_err;
+use crate::domain::models::{AuditEntry, NewAuditEntry};
+use crate::domain::ports::{AuditRepo, RepoResult};
+
+#[derive(sqlx::FromRow)]
+struct AuditRow {
+    id: Uuid,
+    event: String,
+    client_id: Option<String>,
+    ip: Option<String>,
+    detail: Option<String>,
+    created_at: DateTime<Utc>,
+}
+
+impl From<AuditRow> for AuditEntry {
+    fn from(r: AuditRow) -> Self {
+        AuditEntry {
+            id: r.id,
+            event: r.event,
+            client_id: r.client_id,
+            ip: r.ip,
+            detail: r.detail,
+            created_at: r.created_at,
+        }
+    }
+}
+
+pub struct PgAuditRepo {
+    pool: PgPool,
+}
+
+impl PgAuditRepo {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait]
+impl AuditRepo for PgAuditRepo {
+    async fn record(&self, new: NewAuditEntry) -> RepoResult<AuditEntry> {
+        let row = sqlx::query_as::<_, AuditRow>(
+            "INSERT INTO auth_audit (id, event, client_id, ip, detail)
+             VALUES ($1, $2, $3, $4, $5)
+             RETURNING id, event, client_id, ip, detail, created_at",
+        )
+        .bind(Uuid::new_v4())
+        .bind(new.event)
+        .bind(new.client_id)
+        .bind(new.ip)
+        .bind(new.detail)
+        .fetch_one(&self.pool)
+        .await
+        .map_err(map_sqlx_err)?;
+        Ok(row.into())
+    }
+
+    async fn list_recent(&self, limit: i64) -> RepoResult<Vec<AuditEntry>> {
+        let rows = sqlx::query_as::<_, AuditRow>(
+            "SELECT id, event, client_id, ip, detail, created_at
+             FROM auth_audit ORDER BY created_at DESC LIMIT $1",
+        )
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(map_sqlx_err)?;
+        Ok(rows.into_iter().map(Into::into).collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::PgAuditRepo;
+    use crate::domain::models::NewAuditEntry;
+    use crate::domain::ports::AuditRepo;
+    use sqlx::PgPool;
+
+    fn entry(event: &str) -> NewAuditEntry {
+        NewAuditEntry {
+            event: event.into(),
+            client_id: Some("sigac".into()),
+            ip: Some("10.0.0.1".into()),
+            detail: None,
+        }
+    }
+
+    #[sqlx::test]
+    async fn list_recent_newest_first_with_limit(pool: PgPool) {
+        let repo = PgAuditRepo::new(pool);
+        repo.record(entry("login_failed")).await.unwrap();
+        repo.record(entry("lockout")).await.unwrap();
+        repo.record(entry("lockout_cleared")).await.unwrap();
+
+        let recent = repo.list_recent(2).await.unwrap();
+        let events: Vec<&str> = recent.iter().map(|e| e.event.as_str()).collect();
+        assert_eq!(events, vec!["lockout_cleared", "lockout"]);
+        assert_eq!(recent[0].ip.as_deref(), Some("10.0.0.1"));
+    }
+}
@@ -0,0 +1,278 @@
+//! Adaptador Postgres de `CameraGroupRepo`.
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use super::map_sqlx_err;
+use crate::domain::models::{CameraGroup, NewCameraGroup};
+use crate::domain::ports::{CameraGroupRepo, RepoError, RepoResult};
+
+#[derive(sqlx::FromRow)]
+struct CameraGroupRow {
+    id: Uuid,
+    name: String,
+    description: Option<String>,
+    created_at: DateTime<Utc>,
+    updated_at: DateTime<Utc>,
+}
+
+impl From<CameraGroupRow> for CameraGroup {
+    fn from(r: CameraGroupRow) -> Self {
+        CameraGroup {
+            id: r.id,
+            name: r.name,
+            description: r.description,
+            created_at: r.created_at,
+            updated_at: r.updated_at,
+        }
+    }
+}
+
+pub struct PgCameraGroupRepo {
+    pool: PgPool,
+}
+
+impl PgCameraGroupRepo {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait]
+impl CameraGroupRepo for PgCameraGroupRepo {
+    async fn list_all(&self) -> RepoResult<Vec<CameraGroup>> {
+        let rows = sqlx::query_as::<_, CameraGroupRow>(
+            "SELECT id, name, description, created_at, updated_at
+             FROM camera_groups ORDER BY name",
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(map_sqlx_err)?;
+        Ok(rows.into_iter().map(Into::into).collect())
+    }
+
+    async fn find_by_id(&self, id: Uuid) -> RepoResult<Option<CameraGroup>> {
+        let row = sqlx::query_as::<_, CameraGroupRow>(
+            "SELECT id, name, description, created_at, updated_at
+             FROM camera_groups WHERE id = $1",
+        )
+        .bind(id)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(map_sqlx_err)?;
+        Ok(row.map(Into::into))
+    }
+
+    async fn create(&self, new: NewCameraGroup) -> RepoResult<CameraGroup> {
+        let row = sqlx::query_as::<_, CameraGroupRow>(
+            "INSERT INTO camera_groups (id, name, description)
+             VALUES ($1, $2, $3)
+             RETURNING id, name, description, created_at, updated_at",
+        )
+        .bind(Uuid::new_v4())
+        .bind(new.name)
+        .bind(new.description)
+        .fetch_one(&self.pool)
+        .await
+        .map_err(map_sqlx_err)?;
+        Ok(row.into())
+    }
+
+    async fn update(&self, group: &CameraGroup) -> RepoResult<CameraGroup> {
+        let row = sqlx::query_as::<_, CameraGroupRow>(
+            "UPDATE camera_groups SET name = $2, description = $3
+             WHERE id = $1
+             RETURNING id, name, description, created_at, updated_at",
+        )
+        .bind(group.id)
+        .bind(group.name.as_str())
+        .bind(group.description.as_deref())
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(map_sqlx_err)?;
+        row.map(Into::into).ok_or(RepoError::NotFound)
+    }
+
+    async fn delete(&self, id: Uuid) -> RepoResult<()> {
+        let result = sqlx::query("DELETE FROM camera_groups WHERE id = $1")
+            .bind(id)
+            .execute(&self.pool)
+            .await
+            .map_err(map_sqlx_err)?;
+        if result.rows_affected() == 0 {
+            return Err(RepoError::NotFound);
+        }
+        Ok(())
+    }
+
+    async fn set_members(&self, group_id: Uuid, camera_ids: &[Uuid]) -> RepoResult<()> {
+        let mut tx = self.pool.begin().await.map_err(map_sqlx_err)?;
+        sqlx::query("DELETE FROM camera_group_members WHERE group_id = $1")
+            .bind(group_id)
+            .execute(&mut *tx)
+            .await
+            .map_err(map_sqlx_err)?;
+        for camera_id in camera_ids {
+            sqlx::query("INSERT INTO camera_group_members (group_id, camera_id) VALUES ($1, $2)")
+                .bind(group_id)
+                .bind(camera_id)
+                .execute(&mut *tx)
+                .await
+                .map_err(map_sqlx_err)?;
+        }
+        tx.commit().await.map_err(map_sqlx_err)?;
+        Ok(())
+    }
+
+    async fn add_member(&self, group_id: Uuid, camera_id: Uuid) -> RepoResult<()> {
+        sqlx::query(
+            "INSERT INTO camera_group_members (group_id, camera_id) VALUES ($1, $2)
+             ON CONFLICT DO NOTHING",
+        )
+        .bind(group_id)
+        .bind(camera_id)
+        .execute(&self.pool)
+        .await
+        .map_err(map_sqlx_err)?;
+        Ok(())
+    }
+
+    async fn remove_member(&self, group_id: Uuid, camera_id: Uuid) -> RepoResult<()> {
+        let result =
+            sqlx::query("DELETE FROM camera_group_members WHERE group_id = $1 AND camera_id = $2")
+                .bind(group_id)
+                .bind(camera_id)
+                .execute(&self.pool)
+                .await
+                .map_err(map_sqlx_err)?;
+        if result.rows_affected() == 0 {
+            return Err(RepoError::NotFound);
+        }
+        Ok(())
+    }
+
+    async fn members(&self, group_id: Uuid) -> RepoResult<Vec<Uuid>> {
+        let rows: Vec<(Uuid,)> = sqlx::query_as(
+            "SELECT camera_id FROM camera_group_members WHERE group_id = $1 ORDER BY camera_id",
+        )
+        .bind(group_id)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(map_sqlx_err)?;
+        Ok(rows.into_iter().map(|(id,)| id).collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::PgCameraGroupRepo;
+    use crate::domain::models::{
+        AllowedCamera, CameraActions, GroupGrant, NewCameraGroup, NewProject,
+    };
+    use crate::domain::ports::{CameraGroupRepo, ProjectRepo, RepoError};
+    use crate::infra::postgres::PgProjectRepo;
+    use sqlx::PgPool;
+    use uuid::Uuid;
+
+    fn group(name: &str) -> NewCameraGroup {
+        NewCameraGroup {
+            name: name.into(),
+            description: None,
+        }
+    }
+
+    /// Cámara insertada directamente (aquí no interesa el cifrado).
+    async fn camera(pool: &PgPool, path: &str) -> Uuid {
+        let id = Uuid::new_v4();
+        sqlx::query("INSERT INTO cameras (id, path, rtsp_url_enc) VALUES ($1, $2, $3)")
+            .bind(id)
+            .bind(path)
+            .bind(vec![0u8, 1, 2, 3])
+            .execute(pool)
+            .await
+            .unwrap();
+        id
+    }
+
+    #[sqlx::test]
+    async fn crud_and_unique_name(pool: PgPool) {
+        let repo = PgCameraGroupRepo::new(pool);
+        let mut g = repo.create(group("warehouse-north")).await.unwrap();
+        assert!(matches!(
+            repo.create(group("warehouse-north")).await.unwrap_err(),
+            RepoError::Conflict(_)
+        ));
+
+        g.description = Some("Almacén norte".into());
+        let g = repo.update(&g).await.unwrap();
+        assert_eq!(
+            repo.find_by_id(g.id).await.unwrap().unwrap().description.as_deref(),
+            Some("Almacén norte")
+        );
+        assert_eq!(repo.list_all().await.unwrap().len(), 1);
+
+        repo.delete(g.id).await.unwrap();
+        assert!(matches!(repo.delete(g.id).await.unwrap_err(), RepoError::NotFound));
+    }
+
+    #[sqlx::test]
+    async fn membership_is_resolved_when_computing_access(pool: PgPool) {
+        let repo = PgCameraGroupRepo::new(pool.clone());
+        let projects = PgProjectRepo::new(pool.clone());
+        let project = projects
+            .create(NewProject {
+                client_id: "contratista".into(),
+                secret_hash: "$argon2id$x".into(),
+                all_cameras: false,
+                enabled: true,
+            })
+            .await
+            .unwrap();
+        let north = repo.create(group("warehouse-north")).await.unwrap();
+        let a = camera(&pool, "north-a").await;
+        repo.set_members(north.id, &[a]).await.unwrap();
+
+        let grant = GroupGrant {
+            group_id: north.id,
+            actions: CameraActions::Read,
+        };
+        projects.set_groups(project.id, &[grant]).await.unwrap();
+        assert_eq!(projects.assigned_groups(project.id).await.unwrap(), vec![grant]);
+
+        // Una cámara nueva en el grupo la recibe el proyecto sin reasignar nada.
+        let b = camera(&pool, "north-b").await;
+        repo.add_member(north.id, b).await.unwrap();
+        repo.add_member(north.id, b).await.unwrap(); // idempotente
+        let paths = projects.group_camera_paths(project.id).await.unwrap();
+        assert_eq!(
+            paths,
+            vec![
+                AllowedCamera {
+                    path: "north-a".into(),
+                    actions: CameraActions::Read,
+                    prefix: false,
+                    until: None,
+                },
+                AllowedCamera {
+                    path: "north-b".into(),
+                    actions: CameraActions::Read,
+                    prefix: false,
+                    until: None,
+                },
+            ]
+        );
+
+        repo.remove_member(north.id, a).await.unwrap();
+        assert!(matches!(
+            repo.remove_member(north.id, a).await.unwrap_err(),
+            RepoError::NotFound
+        ));
+        assert_eq!(repo.members(north.id).await.unwrap(), vec![b]);
+
+        // Borrar el grupo le quita el acceso al proyecto.
+        repo.delete(north.id).await.unwrap();
+        assert!(projects.group_camera_paths(project.id).await.unwrap().is_empty());
+    }
+}
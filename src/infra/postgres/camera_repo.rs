@@ -1,7 +1,8 @@
 //! Adaptador Postgres de `CameraRepo` (HU 4.1).
 //!
 //! Cifra/descifra `rtsp_url` con el `Cipher` al escribir/leer: en la BD vive
-//! `rtsp_url_enc` (bytea), en el dominio vive `rtsp_url` en claro.
+//! `rtsp_url_enc` (bytea), en el dominio vive `rtsp_url` en claro. Las cámaras
+//! publisher no tienen URL: la columna queda NULL.
 
 use async_trait::async_trait;
 use chrono::{DateTime, Utc};
@@ -17,7 +18,7 @@ use crate::domain::ports::{CameraRepo, RepoError, RepoResult};
 struct CameraRow {
     id: Uuid,
     path: String,
-    rtsp_url_enc: Vec<u8>,
+    rtsp_url_enc: Option<Vec<u8>>,
     record: bool,
     enabled: bool,
     description: Option<String>,
@@ -37,9 +38,10 @@ impl PgCameraRepo {
 
     /// Mapea una fila a la entidad de dominio descifrando la URL.
     fn to_camera(&self, r: CameraRow) -> RepoResult<Camera> {
-        let rtsp_url = self
-            .cipher
-            .decrypt(&r.rtsp_url_enc)
+        let rtsp_url = r
+            .rtsp_url_enc
+            .map(|enc| self.cipher.decrypt(&enc))
+            .transpose()
             .map_err(|e| RepoError::Backend(format!("descifrado de rtsp_url: {e}")))?;
         Ok(Camera {
             id: r.id,
@@ -56,6 +58,13 @@ impl PgCameraRepo {
     fn to_cameras(&self, rows: Vec<CameraRow>) -> RepoResult<Vec<Camera>> {
         rows.into_iter().map(|r| self.to_camera(r)).collect()
     }
+
+    fn encrypt(&self, rtsp_url: Option<&str>) -> RepoResult<Option<Vec<u8>>> {
+        rtsp_url
+            .map(|url| self.cipher.encrypt(url))
+            .transpose()
+            .map_err(|e| RepoError::Backend(format!("cifrado de rtsp_url: {e}")))
+    }
 }
 
 #[async_trait]
@@ -107,10 +116,7 @@ impl CameraRepo for PgCameraRepo {
     }
 
     async fn create(&self, new: NewCamera) -> RepoResult<Camera> {
-        let enc = self
-            .cipher
-            .encrypt(&new.rtsp_url)
-            .map_err(|e| RepoError::Backend(format!("cifrado de rtsp_url: {e}")))?;
+        let enc = self.encrypt(new.rtsp_url.as_deref())?;
         let row = sqlx::query_as::<_, CameraRow>(
             "INSERT INTO cameras (id, path, rtsp_url_enc, record, enabled, description)
              VALUES ($1, $2, $3, $4, $5, $6)
@@ -129,10 +135,7 @@ impl CameraRepo for PgCameraRepo {
     }
 
     async fn update(&self, camera: &Camera) -> RepoResult<Camera> {
-        let enc = self
-            .cipher
-            .encrypt(&camera.rtsp_url)
-            .map_err(|e| RepoError::Backend(format!("cifrado de rtsp_url: {e}")))?;
+        let enc = self.encrypt(camera.rtsp_url.as_deref())?;
         let row = sqlx::query_as::<_, CameraRow>(
             "UPDATE cameras
              SET path = $2, rtsp_url_enc = $3, record = $4, enabled = $5, description = $6
@@ -185,7 +188,7 @@ mod tests {
     fn sample(path: &str) -> NewCamera {
         NewCamera {
             path: path.into(),
-            rtsp_url: "rtsp://u:p@10.0.0.9/stream".into(),
+            rtsp_url: Some("rtsp://u:p@10.0.0.9/stream".into()),
             record: true,
             enabled: true,
             description: Some("cam de prueba".into()),
@@ -196,11 +199,11 @@ mod tests {
     async fn create_read_roundtrip_decrypts(pool: PgPool) {
         let repo = PgCameraRepo::new(pool, cipher());
         let created = repo.create(sample("cam-x")).await.unwrap();
-        assert_eq!(created.rtsp_url, "rtsp://u:p@10.0.0.9/stream");
+        assert_eq!(created.rtsp_url.as_deref(), Some("rtsp://u:p@10.0.0.9/stream"));
 
         let found = repo.find_by_path("cam-x").await.unwrap().unwrap();
         assert_eq!(found.id, created.id);
-        assert_eq!(found.rtsp_url, "rtsp://u:p@10.0.0.9/stream");
+        assert_eq!(found.rtsp_url.as_deref(), Some("rtsp://u:p@10.0.0.9/stream"));
     }
 
     #[sqlx::test]
@@ -209,12 +212,13 @@ mod tests {
         repo.create(sample("cam-enc")).await.unwrap();
 
         // Leer el bytea crudo: NO debe contener el texto plano de la URL.
-        let (enc,): (Vec<u8>,) =
+        let (enc,): (Option<Vec<u8>>,) =
             sqlx::query_as("SELECT rtsp_url_enc FROM cameras WHERE path = $1")
                 .bind("cam-enc")
                 .fetch_one(&pool)
                 .await
                 .unwrap();
+        let enc = enc.expect("la cámara RTSP guarda su URL");
         let as_text = String::from_utf8_lossy(&enc);
         assert!(!as_text.contains("10.0.0.9"), "la URL no debe estar en claro");
         assert!(!as_text.contains("rtsp://"), "la URL no debe estar en claro");
@@ -238,14 +242,26 @@ mod tests {
     async fn update_reencrypts(pool: PgPool) {
         let repo = PgCameraRepo::new(pool, cipher());
         let mut cam = repo.create(sample("cam-u")).await.unwrap();
-        cam.rtsp_url = "rtsp://new:pw@10.0.0.10/z".into();
+        cam.rtsp_url = Some("rtsp://new:pw@10.0.0.10/z".into());
         cam.enabled = false;
         let updated = repo.update(&cam).await.unwrap();
-        assert_eq!(updated.rtsp_url, "rtsp://new:pw@10.0.0.10/z");
+        assert_eq!(updated.rtsp_url.as_deref(), Some("rtsp://new:pw@10.0.0.10/z"));
         assert!(!updated.enabled);
 
         let found = repo.find_by_id(cam.id).await.unwrap().unwrap();
-        assert_eq!(found.rtsp_url, "rtsp://new:pw@10.0.0.10/z");
+        assert_eq!(found.rtsp_url.as_deref(), Some("rtsp://new:pw@10.0.0.10/z"));
+    }
+
+    #[sqlx::test]
+    async fn publisher_camera_has_no_source(pool: PgPool) {
+        let repo = PgCameraRepo::new(pool, cipher());
+        let mut new = sample("bodycam/ana");
+        new.rtsp_url = None;
+        let created = repo.create(new).await.unwrap();
+        assert!(created.is_publisher());
+
+        let found = repo.find_by_path("bodycam/ana").await.unwrap().unwrap();
+        assert!(found.rtsp_url.is_none());
     }
 
     #[sqlx::test]
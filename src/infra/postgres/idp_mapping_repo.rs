@@ -0,0 +1,144 @@
+//! Adaptador Postgres de `IdpMappingRepo`.
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use super::map_sqlx_err;
+use crate::domain::models::{IdpMapping, NewIdpMapping};
+use crate::domain::ports::{IdpMappingRepo, RepoError, RepoResult};
+
+#[derive(sqlx::FromRow)]
+struct IdpMappingRow {
+    id: Uuid,
+    claim: String,
+    value: String,
+    project_id: Uuid,
+    created_at: DateTime<Utc>,
+}
+
+impl From<IdpMappingRow> for IdpMapping {
+    fn from(r: IdpMappingRow) -> Self {
+        IdpMapping {
+            id: r.id,
+            claim: r.claim,
+            value: r.value,
+            project_id: r.project_id,
+            created_at: r.created_at,
+        }
+    }
+}
+
+pub struct PgIdpMappingRepo {
+    pool: PgPool,
+}
+
+impl PgIdpMappingRepo {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait]
+impl IdpMappingRepo for PgIdpMappingRepo {
+    async fn list_all(&self) -> RepoResult<Vec<IdpMapping>> {
+        let rows = sqlx::query_as::<_, IdpMappingRow>(
+            "SELECT id, claim, value, project_id, created_at
+             FROM idp_mappings ORDER BY claim, value",
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(map_sqlx_err)?;
+        Ok(rows.into_iter().map(Into::into).collect())
+    }
+
+    async fn create(&self, new: NewIdpMapping) -> RepoResult<IdpMapping> {
+        let row = sqlx::query_as::<_, IdpMappingRow>(
+            "INSERT INTO idp_mappings (id, claim, value, project_id)
+             VALUES ($1, $2, $3, $4)
+             RETURNING id, claim, value, project_id, created_at",
+        )
+        .bind(Uuid::new_v4())
+        .bind(new.claim)
+        .bind(new.value)
+        .bind(new.project_id)
+        .fetch_one(&self.pool)
+        .await
+        .map_err(map_sqlx_err)?;
+        Ok(row.into())
+    }
+
+    async fn delete(&self, id: Uuid) -> RepoResult<()> {
+        let result = sqlx::query("DELETE FROM idp_mappings WHERE id = $1")
+            .bind(id)
+            .execute(&self.pool)
+            .await
+            .map_err(map_sqlx_err)?;
+        if result.rows_affected() == 0 {
+            return Err(RepoError::NotFound);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::PgIdpMappingRepo;
+    use crate::domain::models::{NewIdpMapping, NewProject};
+    use crate::domain::ports::{IdpMappingRepo, ProjectRepo, RepoError};
+    use crate::infra::postgres::PgProjectRepo;
+    use sqlx::PgPool;
+    use uuid::Uuid;
+
+    async fn project_id(pool: &PgPool) -> Uuid {
+        PgProjectRepo::new(pool.clone())
+            .create(NewProject {
+                client_id: "sigac".into(),
+                secret_hash: "$argon2id$x".into(),
+                all_cameras: true,
+                enabled: true,
+            })
+            .await
+            .unwrap()
+            .id
+    }
+
+    fn mapping(claim: &str, value: &str, project_id: Uuid) -> NewIdpMapping {
+        NewIdpMapping {
+            claim: claim.into(),
+            value: value.into(),
+            project_id,
+        }
+    }
+
+    #[sqlx::test]
+    async fn create_list_and_reject_duplicates(pool: PgPool) {
+        let pid = project_id(&pool).await;
+        let repo = PgIdpMappingRepo::new(pool);
+        repo.create(mapping("groups", "video-operadores", pid)).await.unwrap();
+        repo.create(mapping("email", "ana@example.com", pid)).await.unwrap();
+
+        let all = repo.list_all().await.unwrap();
+        let claims: Vec<&str> = all.iter().map(|m| m.claim.as_str()).collect();
+        assert_eq!(claims, vec!["email", "groups"]);
+
+        let dup = repo.create(mapping("groups", "video-operadores", pid)).await;
+        assert!(matches!(dup, Err(RepoError::Conflict(_))));
+    }
+
+    #[sqlx::test]
+    async fn delete_and_cascade_with_project(pool: PgPool) {
+        let pid = project_id(&pool).await;
+        let repo = PgIdpMappingRepo::new(pool.clone());
+        let m = repo.create(mapping("groups", "a", pid)).await.unwrap();
+        repo.create(mapping("groups", "b", pid)).await.unwrap();
+
+        repo.delete(m.id).await.unwrap();
+        assert!(matches!(repo.delete(m.id).await, Err(RepoError::NotFound)));
+        assert_eq!(repo.list_all().await.unwrap().len(), 1);
+
+        PgProjectRepo::new(pool).delete(pid).await.unwrap();
+        assert!(repo.list_all().await.unwrap().is_empty());
+    }
+}
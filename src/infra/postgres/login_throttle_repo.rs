@@ -0,0 +1,200 @@
+//! Adaptador Postgres de `LoginThrottleRepo`.
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use sqlx::PgPool;
+
+use super::map_sqlx_err;
+use crate::domain::models::{Throttle, ThrottleScope};
+use crate::domain::ports::{LoginThrottleRepo, RepoError, RepoResult};
+
+#[derive(sqlx::FromRow)]
+struct ThrottleRow {
+    scope: String,
+    key: String,
+    failures: i32,
+    last_failure_at: DateTime<Utc>,
+    locked_until: Option<DateTime<Utc>>,
+}
+
+impl TryFrom<ThrottleRow> for Throttle {
+    type Error = RepoError;
+
+    fn try_from(r: ThrottleRow) -> Result<Self, Self::Error> {
+        let scope = ThrottleScope::parse(&r.scope)
+            .ok_or_else(|| RepoError::Backend(format!("scope desconocido: {}", r.scope)))?;
+        Ok(Throttle {
+            scope,
+            key: r.key,
+            failures: r.failures,
+            last_failure_at: r.last_failure_at,
+            locked_until: r.locked_until,
+        })
+    }
+}
+
+pub struct PgLoginThrottleRepo {
+    pool: PgPool,
+}
+
+impl PgLoginThrottleRepo {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait]
+impl LoginThrottleRepo for PgLoginThrottleRepo {
+    async fn find(&self, scope: ThrottleScope, key: &str) -> RepoResult<Option<Throttle>> {
+        let row = sqlx::query_as::<_, ThrottleRow>(
+            "SELECT scope, key, failures, last_failure_at, locked_until
+             FROM login_throttles WHERE scope = $1 AND key = $2",
+        )
+        .bind(scope.as_str())
+        .bind(key)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(map_sqlx_err)?;
+        row.map(Throttle::try_from).transpose()
+    }
+
+    async fn record_failure(
+        &self,
+        scope: ThrottleScope,
+        key: &str,
+        window_start: DateTime<Utc>,
+    ) -> RepoResult<Throttle> {
+        let row = sqlx::query_as::<_, ThrottleRow>(
+            "INSERT INTO login_throttles (scope, key, failures, last_failure_at)
+             VALUES ($1, $2, 1, now())
+             ON CONFLICT (scope, key) DO UPDATE SET
+                 failures = CASE WHEN GREATEST(login_throttles.last_failure_at,
+                                               login_throttles.locked_until) < $3
+                                 THEN 1 ELSE login_throttles.failures + 1 END,
+                 last_failure_at = now()
+             RETURNING scope, key, failures, last_failure_at, locked_until",
+        )
+        .bind(scope.as_str())
+        .bind(key)
+        .bind(window_start)
+        .fetch_one(&self.pool)
+        .await
+        .map_err(map_sqlx_err)?;
+        row.try_into()
+    }
+
+    async fn lock(&self, scope: ThrottleScope, key: &str, until: DateTime<Utc>) -> RepoResult<()> {
+        sqlx::query("UPDATE login_throttles SET locked_until = $3 WHERE scope = $1 AND key = $2")
+            .bind(scope.as_str())
+            .bind(key)
+            .bind(until)
+            .execute(&self.pool)
+            .await
+            .map_err(map_sqlx_err)?;
+        Ok(())
+    }
+
+    async fn clear(&self, scope: ThrottleScope, key: &str) -> RepoResult<()> {
+        let res = sqlx::query("DELETE FROM login_throttles WHERE scope = $1 AND key = $2")
+            .bind(scope.as_str())
+            .bind(key)
+            .execute(&self.pool)
+            .await
+            .map_err(map_sqlx_err)?;
+        if res.rows_affected() == 0 {
+            return Err(RepoError::NotFound);
+        }
+        Ok(())
+    }
+
+    async fn list_active(&self, window_start: DateTime<Utc>) -> RepoResult<Vec<Throttle>> {
+        let rows = sqlx::query_as::<_, ThrottleRow>(
+            "SELECT scope, key, failures, last_failure_at, locked_until
+             FROM login_throttles
+             WHERE GREATEST(last_failure_at, locked_until) >= $1
+             ORDER BY last_failure_at DESC",
+        )
+        .bind(window_start)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(map_sqlx_err)?;
+        rows.into_iter().map(Throttle::try_from).collect()
+    }
+
+    async fn purge(&self, window_start: DateTime<Utc>) -> RepoResult<u64> {
+        let res = sqlx::query(
+            "DELETE FROM login_throttles WHERE GREATEST(last_failure_at, locked_until) < $1",
+        )
+        .bind(window_start)
+        .execute(&self.pool)
+        .await
+        .map_err(map_sqlx_err)?;
+        Ok(res.rows_affected())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::PgLoginThrottleRepo;
+    use crate::domain::models::ThrottleScope;
+    use crate::domain::ports::{LoginThrottleRepo, RepoError};
+    use chrono::{Duration, Utc};
+    use sqlx::PgPool;
+
+    #[sqlx::test]
+    async fn failures_accumulate_and_reset_after_window(pool: PgPool) {
+        let repo = PgLoginThrottleRepo::new(pool);
+        let window_start = Utc::now() - Duration::minutes(15);
+        repo.record_failure(ThrottleScope::Client, "sigac", window_start).await.unwrap();
+        let t = repo.record_failure(ThrottleScope::Client, "sigac", window_start).await.unwrap();
+        assert_eq!(t.failures, 2);
+
+        // El mismo key en otro ámbito es otro contador.
+        let ip = repo.record_failure(ThrottleScope::Ip, "sigac", window_start).await.unwrap();
+        assert_eq!(ip.failures, 1);
+
+        // Ventana que empieza después del último fallo: reinicia.
+        let t = repo
+            .record_failure(ThrottleScope::Client, "sigac", Utc::now() + Duration::seconds(1))
+            .await
+            .unwrap();
+        assert_eq!(t.failures, 1);
+    }
+
+    #[sqlx::test]
+    async fn lock_list_and_clear(pool: PgPool) {
+        let repo = PgLoginThrottleRepo::new(pool);
+        let now = Utc::now();
+        let window_start = now - Duration::minutes(15);
+        repo.record_failure(ThrottleScope::Ip, "10.0.0.1", window_start).await.unwrap();
+        repo.lock(ThrottleScope::Ip, "10.0.0.1", now + Duration::minutes(5)).await.unwrap();
+
+        let found = repo.find(ThrottleScope::Ip, "10.0.0.1").await.unwrap().unwrap();
+        assert!(found.locked_until.unwrap() > now);
+        assert_eq!(repo.list_active(window_start).await.unwrap().len(), 1);
+        // Fuera de la ventana pero aún bloqueado: sigue activo y no se purga.
+        let later = now + Duration::minutes(1);
+        assert_eq!(repo.list_active(later).await.unwrap().len(), 1);
+        assert_eq!(repo.purge(later).await.unwrap(), 0);
+
+        // Un fallo tras el bloqueo sigue sumando (backoff progresivo).
+        let t = repo.record_failure(ThrottleScope::Ip, "10.0.0.1", later).await.unwrap();
+        assert_eq!(t.failures, 2);
+
+        repo.clear(ThrottleScope::Ip, "10.0.0.1").await.unwrap();
+        assert!(repo.find(ThrottleScope::Ip, "10.0.0.1").await.unwrap().is_none());
+        assert!(matches!(
+            repo.clear(ThrottleScope::Ip, "10.0.0.1").await,
+            Err(RepoError::NotFound)
+        ));
+    }
+
+    #[sqlx::test]
+    async fn purge_drops_stale_counters(pool: PgPool) {
+        let repo = PgLoginThrottleRepo::new(pool);
+        let now = Utc::now();
+        repo.record_failure(ThrottleScope::Client, "viejo", now).await.unwrap();
+        assert_eq!(repo.purge(now + Duration::minutes(1)).await.unwrap(), 1);
+        assert!(repo.list_active(now - Duration::minutes(15)).await.unwrap().is_empty());
+    }
+}
@@ -7,13 +7,31 @@
 
 use crate::domain::ports::RepoError;
 
+pub mod admin_account_repo;
+pub mod api_key_repo;
+pub mod audit_repo;
+pub mod camera_group_repo;
 pub mod camera_repo;
 pub mod failure_repo;
+pub mod idp_mapping_repo;
+pub mod login_throttle_repo;
 pub mod project_repo;
+pub mod project_secret_repo;
+pub mod refresh_token_repo;
+pub mod revocation_repo;
 
+pub use admin_account_repo::PgAdminAccountRepo;
+pub use api_key_repo::PgApiKeyRepo;
+pub use audit_repo::PgAuditRepo;
+pub use camera_group_repo::PgCameraGroupRepo;
 pub use camera_repo::PgCameraRepo;
 pub use failure_repo::PgFailureRepo;
+pub use idp_mapping_repo::PgIdpMappingRepo;
+pub use login_throttle_repo::PgLoginThrottleRepo;
 pub use project_repo::PgProjectRepo;
+pub use project_secret_repo::PgProjectSecretRepo;
+pub use refresh_token_repo::PgRefreshTokenRepo;
+pub use revocation_repo::PgRevocationRepo;
 
 /// Traduce errores de sqlx a errores de dominio.
 pub(crate) fn map_sqlx_err(e: sqlx::Error) -> RepoError {
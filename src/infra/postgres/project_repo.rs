@@ -1,21 +1,27 @@
 //! Adaptador Postgres de `ProjectRepo` (HU 4.1).
 
 use async_trait::async_trait;
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, NaiveTime, Utc, Weekday};
 use sqlx::PgPool;
 use uuid::Uuid;
 
 use super::map_sqlx_err;
-use crate::domain::models::{NewProject, Project};
+use crate::domain::models::{
+    AllowedCamera, CameraActions, CameraGrant, GrantWindow, GroupGrant, NewProject, PrefixGrant,
+    Project, PublishGrant, WeeklySchedule,
+};
 use crate::domain::ports::{ProjectRepo, RepoError, RepoResult};
 
 #[derive(sqlx::FromRow)]
 struct ProjectRow {
     id: Uuid,
     client_id: String,
-    secret_hash: String,
     all_cameras: bool,
     enabled: bool,
+    token_max_minutes: Option<i32>,
+    token_default_minutes: Option<i32>,
+    allowed_audiences: Vec<String>,
+    allowed_cidrs: Vec<String>,
     created_at: DateTime<Utc>,
     updated_at: DateTime<Utc>,
 }
@@ -25,22 +31,112 @@ impl From<ProjectRow> for Project {
         Project {
             id: r.id,
             client_id: r.client_id,
-            secret_hash: r.secret_hash,
             all_cameras: r.all_cameras,
             enabled: r.enabled,
+            token_max_minutes: r.token_max_minutes,
+            token_default_minutes: r.token_default_minutes,
+            allowed_audiences: r.allowed_audiences,
+            allowed_cidrs: r.allowed_cidrs,
             created_at: r.created_at,
             updated_at: r.updated_at,
         }
     }
 }
 
+#[derive(sqlx::FromRow)]
+struct CameraGrantRow {
+    camera_id: Uuid,
+    actions: String,
+    valid_from: Option<DateTime<Utc>>,
+    valid_until: Option<DateTime<Utc>>,
+    schedule_days: Option<Vec<i16>>,
+    schedule_start: Option<NaiveTime>,
+    schedule_end: Option<NaiveTime>,
+}
+
+impl TryFrom<CameraGrantRow> for CameraGrant {
+    type Error = RepoError;
+
+    fn try_from(r: CameraGrantRow) -> RepoResult<Self> {
+        let schedule = match (r.schedule_days, r.schedule_start, r.schedule_end) {
+            (Some(days), Some(start), Some(end)) => Some(WeeklySchedule {
+                days: days.into_iter().map(weekday).collect::<RepoResult<_>>()?,
+                start,
+                end,
+            }),
+            _ => None,
+        };
+        Ok(CameraGrant {
+            camera_id: r.camera_id,
+            actions: parse_actions(&r.actions)?,
+            window: GrantWindow {
+                valid_from: r.valid_from,
+                valid_until: r.valid_until,
+                schedule,
+            },
+        })
+    }
+}
+
 pub struct PgProjectRepo {
     pool: PgPool,
+    /// Zona horaria (IANA) en que se evalúan los horarios semanales.
+    schedule_tz: String,
 }
 
 impl PgProjectRepo {
     pub fn new(pool: PgPool) -> Self {
-        Self { pool }
+        Self {
+            pool,
+            schedule_tz: "UTC".to_string(),
+        }
+    }
+
+    /// Evalúa los horarios semanales en `tz` (por defecto, UTC).
+    pub fn with_schedule_timezone(mut self, tz: &str) -> Self {
+        self.schedule_tz = tz.to_string();
+        self
+    }
+
+    /// Asignaciones directas vigentes en `now`: dentro de `valid_from` /
+    /// `valid_until` y, si hay horario, en uno de sus días y franja. `until`
+    /// es lo primero que termine: la vigencia o la franja de hoy.
+    async fn allowed_camera_paths_at(
+        &self,
+        project_id: Uuid,
+        now: DateTime<Utc>,
+    ) -> RepoResult<Vec<AllowedCamera>> {
+        let rows: Vec<(String, String, Option<DateTime<Utc>>)> = sqlx::query_as(
+            "SELECT c.path, pc.actions,
+                    LEAST(pc.valid_until,
+                          (($2 AT TIME ZONE $3)::date + pc.schedule_end) AT TIME ZONE $3)
+             FROM project_cameras pc
+             JOIN cameras c ON c.id = pc.camera_id
+             WHERE pc.project_id = $1
+               AND (pc.valid_from IS NULL OR pc.valid_from <= $2)
+               AND (pc.valid_until IS NULL OR pc.valid_until > $2)
+               AND (pc.schedule_days IS NULL OR (
+                    extract(isodow FROM $2 AT TIME ZONE $3)::smallint = ANY (pc.schedule_days)
+                    AND ($2 AT TIME ZONE $3)::time >= pc.schedule_start
+                    AND ($2 AT TIME ZONE $3)::time < pc.schedule_end))
+             ORDER BY c.path",
+        )
+        .bind(project_id)
+        .bind(now)
+        .bind(self.schedule_tz.as_str())
+        .fetch_all(&self.pool)
+        .await
+        .map_err(map_sqlx_err)?;
+        rows.into_iter()
+            .map(|(path, actions, until)| {
+                Ok(AllowedCamera {
+                    path,
+                    actions: parse_actions(&actions)?,
+                    prefix: false,
+                    until,
+                })
+            })
+            .collect()
     }
 }
 
@@ -48,7 +144,9 @@ impl PgProjectRepo {
 impl ProjectRepo for PgProjectRepo {
     async fn find_by_client_id(&self, client_id: &str) -> RepoResult<Option<Project>> {
         let row = sqlx::query_as::<_, ProjectRow>(
-            "SELECT id, client_id, secret_hash, all_cameras, enabled, created_at, updated_at
+            "SELECT id, client_id, all_cameras, enabled,
+                    token_max_minutes, token_default_minutes, allowed_audiences,
+                    allowed_cidrs::text[] AS allowed_cidrs, created_at, updated_at
              FROM projects WHERE client_id = $1",
         )
         .bind(client_id)
@@ -60,7 +158,9 @@ impl ProjectRepo for PgProjectRepo {
 
     async fn find_by_id(&self, id: Uuid) -> RepoResult<Option<Project>> {
         let row = sqlx::query_as::<_, ProjectRow>(
-            "SELECT id, client_id, secret_hash, all_cameras, enabled, created_at, updated_at
+            "SELECT id, client_id, all_cameras, enabled,
+                    token_max_minutes, token_default_minutes, allowed_audiences,
+                    allowed_cidrs::text[] AS allowed_cidrs, created_at, updated_at
              FROM projects WHERE id = $1",
         )
         .bind(id)
@@ -72,7 +172,9 @@ impl ProjectRepo for PgProjectRepo {
 
     async fn list_all(&self) -> RepoResult<Vec<Project>> {
         let rows = sqlx::query_as::<_, ProjectRow>(
-            "SELECT id, client_id, secret_hash, all_cameras, enabled, created_at, updated_at
+            "SELECT id, client_id, all_cameras, enabled,
+                    token_max_minutes, token_default_minutes, allowed_audiences,
+                    allowed_cidrs::text[] AS allowed_cidrs, created_at, updated_at
              FROM projects ORDER BY client_id",
         )
         .fetch_all(&self.pool)
@@ -82,34 +184,55 @@ impl ProjectRepo for PgProjectRepo {
     }
 
     async fn create(&self, new: NewProject) -> RepoResult<Project> {
+        // El proyecto y su primer secreto, juntos o nada.
+        let mut tx = self.pool.begin().await.map_err(map_sqlx_err)?;
         let row = sqlx::query_as::<_, ProjectRow>(
-            "INSERT INTO projects (id, client_id, secret_hash, all_cameras, enabled)
-             VALUES ($1, $2, $3, $4, $5)
-             RETURNING id, client_id, secret_hash, all_cameras, enabled, created_at, updated_at",
+            "INSERT INTO projects (id, client_id, all_cameras, enabled)
+             VALUES ($1, $2, $3, $4)
+             RETURNING id, client_id, all_cameras, enabled,
+                       token_max_minutes, token_default_minutes, allowed_audiences,
+                       allowed_cidrs::text[] AS allowed_cidrs, created_at, updated_at",
         )
         .bind(Uuid::new_v4())
         .bind(new.client_id)
-        .bind(new.secret_hash)
         .bind(new.all_cameras)
         .bind(new.enabled)
-        .fetch_one(&self.pool)
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(map_sqlx_err)?;
+        sqlx::query(
+            "INSERT INTO project_secrets (id, project_id, label, secret_hash)
+             VALUES ($1, $2, 'inicial', $3)",
+        )
+        .bind(Uuid::new_v4())
+        .bind(row.id)
+        .bind(new.secret_hash)
+        .execute(&mut *tx)
         .await
         .map_err(map_sqlx_err)?;
+        tx.commit().await.map_err(map_sqlx_err)?;
         Ok(row.into())
     }
 
     async fn update(&self, project: &Project) -> RepoResult<Project> {
         let row = sqlx::query_as::<_, ProjectRow>(
             "UPDATE projects
-             SET client_id = $2, secret_hash = $3, all_cameras = $4, enabled = $5
+             SET client_id = $2, all_cameras = $3, enabled = $4,
+                 token_max_minutes = $5, token_default_minutes = $6, allowed_audiences = $7,
+                 allowed_cidrs = $8::cidr[]
              WHERE id = $1
-             RETURNING id, client_id, secret_hash, all_cameras, enabled, created_at, updated_at",
+             RETURNING id, client_id, all_cameras, enabled,
+                       token_max_minutes, token_default_minutes, allowed_audiences,
+                       allowed_cidrs::text[] AS allowed_cidrs, created_at, updated_at",
         )
         .bind(project.id)
         .bind(project.client_id.as_str())
-        .bind(project.secret_hash.as_str())
         .bind(project.all_cameras)
         .bind(project.enabled)
+        .bind(project.token_max_minutes)
+        .bind(project.token_default_minutes)
+        .bind(&project.allowed_audiences)
+        .bind(&project.allowed_cidrs)
         .fetch_optional(&self.pool)
         .await
         .map_err(map_sqlx_err)?;
@@ -128,56 +251,230 @@ impl ProjectRepo for PgProjectRepo {
         Ok(())
     }
 
-    async fn set_cameras(&self, project_id: Uuid, camera_ids: &[Uuid]) -> RepoResult<()> {
+    async fn set_cameras(&self, project_id: Uuid, grants: &[CameraGrant]) -> RepoResult<()> {
         let mut tx = self.pool.begin().await.map_err(map_sqlx_err)?;
         sqlx::query("DELETE FROM project_cameras WHERE project_id = $1")
             .bind(project_id)
             .execute(&mut *tx)
             .await
             .map_err(map_sqlx_err)?;
-        for camera_id in camera_ids {
-            sqlx::query("INSERT INTO project_cameras (project_id, camera_id) VALUES ($1, $2)")
-                .bind(project_id)
-                .bind(camera_id)
-                .execute(&mut *tx)
-                .await
-                .map_err(map_sqlx_err)?;
+        for grant in grants {
+            let schedule = grant.window.schedule.as_ref();
+            sqlx::query(
+                "INSERT INTO project_cameras
+                    (project_id, camera_id, actions, valid_from, valid_until,
+                     schedule_days, schedule_start, schedule_end)
+                 VALUES ($1, $2, $3, $4, $5, $6, $7, $8)",
+            )
+            .bind(project_id)
+            .bind(grant.camera_id)
+            .bind(grant.actions.as_str())
+            .bind(grant.window.valid_from)
+            .bind(grant.window.valid_until)
+            .bind(schedule.map(|s| {
+                s.days
+                    .iter()
+                    .map(|d| d.number_from_monday() as i16)
+                    .collect::<Vec<_>>()
+            }))
+            .bind(schedule.map(|s| s.start))
+            .bind(schedule.map(|s| s.end))
+            .execute(&mut *tx)
+            .await
+            .map_err(map_sqlx_err)?;
         }
         tx.commit().await.map_err(map_sqlx_err)?;
         Ok(())
     }
 
-    async fn allowed_camera_paths(&self, project_id: Uuid) -> RepoResult<Vec<String>> {
-        let rows: Vec<(String,)> = sqlx::query_as(
-            "SELECT c.path
-             FROM project_cameras pc
-             JOIN cameras c ON c.id = pc.camera_id
-             WHERE pc.project_id = $1
+    async fn allowed_camera_paths(&self, project_id: Uuid) -> RepoResult<Vec<AllowedCamera>> {
+        self.allowed_camera_paths_at(project_id, Utc::now()).await
+    }
+
+    async fn assigned_cameras(&self, project_id: Uuid) -> RepoResult<Vec<CameraGrant>> {
+        let rows = sqlx::query_as::<_, CameraGrantRow>(
+            "SELECT camera_id, actions, valid_from, valid_until,
+                    schedule_days, schedule_start, schedule_end
+             FROM project_cameras
+             WHERE project_id = $1 ORDER BY camera_id",
+        )
+        .bind(project_id)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(map_sqlx_err)?;
+        rows.into_iter().map(CameraGrant::try_from).collect()
+    }
+
+    async fn set_groups(&self, project_id: Uuid, grants: &[GroupGrant]) -> RepoResult<()> {
+        let mut tx = self.pool.begin().await.map_err(map_sqlx_err)?;
+        sqlx::query("DELETE FROM project_camera_groups WHERE project_id = $1")
+            .bind(project_id)
+            .execute(&mut *tx)
+            .await
+            .map_err(map_sqlx_err)?;
+        for grant in grants {
+            sqlx::query(
+                "INSERT INTO project_camera_groups (project_id, group_id, actions)
+                 VALUES ($1, $2, $3)",
+            )
+            .bind(project_id)
+            .bind(grant.group_id)
+            .bind(grant.actions.as_str())
+            .execute(&mut *tx)
+            .await
+            .map_err(map_sqlx_err)?;
+        }
+        tx.commit().await.map_err(map_sqlx_err)?;
+        Ok(())
+    }
+
+    async fn assigned_groups(&self, project_id: Uuid) -> RepoResult<Vec<GroupGrant>> {
+        let rows: Vec<(Uuid, String)> = sqlx::query_as(
+            "SELECT pg.group_id, pg.actions
+             FROM project_camera_groups pg
+             JOIN camera_groups g ON g.id = pg.group_id
+             WHERE pg.project_id = $1
+             ORDER BY g.name",
+        )
+        .bind(project_id)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(map_sqlx_err)?;
+        rows.into_iter()
+            .map(|(group_id, actions)| {
+                Ok(GroupGrant {
+                    group_id,
+                    actions: parse_actions(&actions)?,
+                })
+            })
+            .collect()
+    }
+
+    async fn group_camera_paths(&self, project_id: Uuid) -> RepoResult<Vec<AllowedCamera>> {
+        let rows: Vec<(String, String)> = sqlx::query_as(
+            "SELECT c.path, pg.actions
+             FROM project_camera_groups pg
+             JOIN camera_group_members m ON m.group_id = pg.group_id
+             JOIN cameras c ON c.id = m.camera_id
+             WHERE pg.project_id = $1
              ORDER BY c.path",
         )
         .bind(project_id)
         .fetch_all(&self.pool)
         .await
         .map_err(map_sqlx_err)?;
-        Ok(rows.into_iter().map(|(path,)| path).collect())
+        rows.into_iter()
+            .map(|(path, actions)| {
+                Ok(AllowedCamera {
+                    path,
+                    actions: parse_actions(&actions)?,
+                    prefix: false,
+                    until: None,
+                })
+            })
+            .collect()
     }
 
-    async fn assigned_camera_ids(&self, project_id: Uuid) -> RepoResult<Vec<Uuid>> {
-        let rows: Vec<(Uuid,)> = sqlx::query_as(
-            "SELECT camera_id FROM project_cameras WHERE project_id = $1 ORDER BY camera_id",
+    async fn set_prefix_grants(&self, project_id: Uuid, grants: &[PrefixGrant]) -> RepoResult<()> {
+        let mut tx = self.pool.begin().await.map_err(map_sqlx_err)?;
+        sqlx::query("DELETE FROM project_prefix_grants WHERE project_id = $1")
+            .bind(project_id)
+            .execute(&mut *tx)
+            .await
+            .map_err(map_sqlx_err)?;
+        for grant in grants {
+            sqlx::query(
+                "INSERT INTO project_prefix_grants (project_id, prefix, actions) VALUES ($1, $2, $3)",
+            )
+            .bind(project_id)
+            .bind(grant.prefix.as_str())
+            .bind(grant.actions.as_str())
+            .execute(&mut *tx)
+            .await
+            .map_err(map_sqlx_err)?;
+        }
+        tx.commit().await.map_err(map_sqlx_err)?;
+        Ok(())
+    }
+
+    async fn prefix_grants(&self, project_id: Uuid) -> RepoResult<Vec<PrefixGrant>> {
+        let rows: Vec<(String, String)> = sqlx::query_as(
+            "SELECT prefix, actions FROM project_prefix_grants
+             WHERE project_id = $1 ORDER BY prefix",
         )
         .bind(project_id)
         .fetch_all(&self.pool)
         .await
         .map_err(map_sqlx_err)?;
-        Ok(rows.into_iter().map(|(id,)| id).collect())
+        rows.into_iter()
+            .map(|(prefix, actions)| {
+                Ok(PrefixGrant {
+                    prefix,
+                    actions: parse_actions(&actions)?,
+                })
+            })
+            .collect()
     }
+
+    async fn set_publish_grants(&self, project_id: Uuid, grants: &[PublishGrant]) -> RepoResult<()> {
+        let mut tx = self.pool.begin().await.map_err(map_sqlx_err)?;
+        sqlx::query("DELETE FROM project_publish_grants WHERE project_id = $1")
+            .bind(project_id)
+            .execute(&mut *tx)
+            .await
+            .map_err(map_sqlx_err)?;
+        for grant in grants {
+            sqlx::query(
+                "INSERT INTO project_publish_grants (project_id, path, prefix) VALUES ($1, $2, $3)",
+            )
+            .bind(project_id)
+            .bind(grant.path.as_str())
+            .bind(grant.prefix)
+            .execute(&mut *tx)
+            .await
+            .map_err(map_sqlx_err)?;
+        }
+        tx.commit().await.map_err(map_sqlx_err)?;
+        Ok(())
+    }
+
+    async fn publish_grants(&self, project_id: Uuid) -> RepoResult<Vec<PublishGrant>> {
+        let rows: Vec<(String, bool)> = sqlx::query_as(
+            "SELECT path, prefix FROM project_publish_grants
+             WHERE project_id = $1 ORDER BY path, prefix",
+        )
+        .bind(project_id)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(map_sqlx_err)?;
+        Ok(rows
+            .into_iter()
+            .map(|(path, prefix)| PublishGrant { path, prefix })
+            .collect())
+    }
+}
+
+/// La columna tiene un CHECK; un valor desconocido es un error, no un permiso.
+fn parse_actions(s: &str) -> RepoResult<CameraActions> {
+    CameraActions::parse(s).ok_or_else(|| RepoError::Backend(format!("acciones desconocidas: {s}")))
+}
+
+/// Día ISO (1 = lunes) de `schedule_days`; también acotado por un CHECK.
+fn weekday(n: i16) -> RepoResult<Weekday> {
+    u8::try_from(n - 1)
+        .ok()
+        .and_then(|d| Weekday::try_from(d).ok())
+        .ok_or_else(|| RepoError::Backend(format!("día de horario inválido: {n}")))
 }
 
 #[cfg(test)]
 mod tests {
     use super::PgProjectRepo;
-    use crate::domain::models::NewProject;
+    use crate::domain::models::{
+        AllowedCamera, CameraActions, CameraGrant, GrantWindow, NewProject, PrefixGrant,
+        PublishGrant, WeeklySchedule,
+    };
+    use chrono::{DateTime, NaiveTime, Utc, Weekday};
     use crate::domain::ports::{ProjectRepo, RepoError};
     use sqlx::PgPool;
     use uuid::Uuid;
@@ -214,11 +511,24 @@ mod tests {
     async fn update_and_delete(pool: PgPool) {
         let repo = PgProjectRepo::new(pool);
         let mut p = repo.create(sample("odin")).await.unwrap();
+        assert!(p.token_max_minutes.is_none() && p.allowed_audiences.is_empty());
         p.all_cameras = true;
         p.enabled = false;
+        p.token_max_minutes = Some(720);
+        p.token_default_minutes = Some(60);
+        p.allowed_audiences = vec!["kiosk".into()];
+        p.allowed_cidrs = vec!["10.20.0.0/16".into(), "2001:db8::/32".into()];
         let updated = repo.update(&p).await.unwrap();
         assert!(updated.all_cameras);
         assert!(!updated.enabled);
+        assert_eq!(updated.token_max_minutes, Some(720));
+        assert_eq!(updated.token_default_minutes, Some(60));
+        assert_eq!(updated.allowed_audiences, vec!["kiosk".to_string()]);
+        assert_eq!(updated.allowed_cidrs, p.allowed_cidrs);
+
+        // La BD rechaza un default mayor que el máximo.
+        p.token_default_minutes = Some(800);
+        assert!(repo.update(&p).await.is_err());
 
         repo.delete(p.id).await.unwrap();
         assert!(repo.find_by_id(p.id).await.unwrap().is_none());
@@ -246,16 +556,181 @@ mod tests {
             .unwrap();
         }
 
-        repo.set_cameras(project.id, &[cam1, cam2]).await.unwrap();
-        let mut paths = repo.allowed_camera_paths(project.id).await.unwrap();
-        paths.sort();
-        assert_eq!(paths, vec!["cam-a".to_string(), "cam-b".to_string()]);
+        let grant = |camera_id, actions| CameraGrant {
+            camera_id,
+            actions,
+            window: GrantWindow::default(),
+        };
+        let allowed = |path: &str, actions| AllowedCamera {
+            path: path.into(),
+            actions,
+            prefix: false,
+            until: None,
+        };
+
+        repo.set_cameras(
+            project.id,
+            &[grant(cam1, CameraActions::Both), grant(cam2, CameraActions::Read)],
+        )
+        .await
+        .unwrap();
+        assert_eq!(
+            repo.allowed_camera_paths(project.id).await.unwrap(),
+            vec![allowed("cam-a", CameraActions::Both), allowed("cam-b", CameraActions::Read)]
+        );
 
-        // Reemplaza el conjunto por una sola cámara.
-        repo.set_cameras(project.id, &[cam1]).await.unwrap();
+        // Reemplaza el conjunto por una sola cámara, ahora solo grabaciones.
+        repo.set_cameras(project.id, &[grant(cam1, CameraActions::Playback)])
+            .await
+            .unwrap();
         assert_eq!(
             repo.allowed_camera_paths(project.id).await.unwrap(),
-            vec!["cam-a".to_string()]
+            vec![allowed("cam-a", CameraActions::Playback)]
+        );
+        assert_eq!(
+            repo.assigned_cameras(project.id).await.unwrap(),
+            vec![grant(cam1, CameraActions::Playback)]
+        );
+    }
+
+    #[sqlx::test]
+    async fn publish_grants_replace_and_list(pool: PgPool) {
+        let repo = PgProjectRepo::new(pool);
+        let project = repo.create(sample("moviles")).await.unwrap();
+        let grant = |path: &str, prefix| PublishGrant {
+            path: path.into(),
+            prefix,
+        };
+
+        repo.set_publish_grants(project.id, &[grant("bodycam/", true), grant("unidad-7", false)])
+            .await
+            .unwrap();
+        assert_eq!(
+            repo.publish_grants(project.id).await.unwrap(),
+            vec![grant("bodycam/", true), grant("unidad-7", false)]
         );
+
+        // Duplicado exacto: conflicto y no se toca el conjunto anterior.
+        let err = repo
+            .set_publish_grants(project.id, &[grant("a", false), grant("a", false)])
+            .await
+            .unwrap_err();
+        assert!(matches!(err, RepoError::Conflict(_)));
+        assert_eq!(repo.publish_grants(project.id).await.unwrap().len(), 2);
+
+        repo.set_publish_grants(project.id, &[]).await.unwrap();
+        assert!(repo.publish_grants(project.id).await.unwrap().is_empty());
+    }
+
+    #[sqlx::test]
+    async fn prefix_grants_replace_and_list(pool: PgPool) {
+        let repo = PgProjectRepo::new(pool);
+        let project = repo.create(sample("bodegas")).await.unwrap();
+        let grant = |prefix: &str, actions| PrefixGrant {
+            prefix: prefix.into(),
+            actions,
+        };
+
+        repo.set_prefix_grants(
+            project.id,
+            &[grant("bodega-b/", CameraActions::Both), grant("bodega-a/", CameraActions::Read)],
+        )
+        .await
+        .unwrap();
+        assert_eq!(
+            repo.prefix_grants(project.id).await.unwrap(),
+            vec![grant("bodega-a/", CameraActions::Read), grant("bodega-b/", CameraActions::Both)]
+        );
+
+        repo.set_prefix_grants(project.id, &[]).await.unwrap();
+        assert!(repo.prefix_grants(project.id).await.unwrap().is_empty());
+    }
+
+    #[sqlx::test]
+    async fn grant_windows_filter_by_time_and_schedule(pool: PgPool) {
+        let repo = PgProjectRepo::new(pool.clone()).with_schedule_timezone("America/Santiago");
+        let project = repo.create(sample("auditoria")).await.unwrap();
+        let (temporal, oficina, futura) = (Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4());
+        for (id, path) in [(temporal, "temporal"), (oficina, "oficina"), (futura, "futura")] {
+            sqlx::query("INSERT INTO cameras (id, path, rtsp_url_enc) VALUES ($1, $2, $3)")
+                .bind(id)
+                .bind(path)
+                .bind(vec![0u8, 1, 2, 3])
+                .execute(&pool)
+                .await
+                .unwrap();
+        }
+        let at = |s: &str| s.parse::<DateTime<Utc>>().unwrap();
+        let hhmm = |s: &str| NaiveTime::parse_from_str(s, "%H:%M").unwrap();
+        let grant = |camera_id, window| CameraGrant {
+            camera_id,
+            actions: CameraActions::Read,
+            window,
+        };
+        let grants = [
+            grant(
+                temporal,
+                GrantWindow {
+                    valid_until: Some(at("2026-03-10T00:00:00Z")),
+                    ..Default::default()
+                },
+            ),
+            // Lunes a viernes de 08:00 a 18:00 en Santiago (UTC-3 en marzo).
+            grant(
+                oficina,
+                GrantWindow {
+                    schedule: Some(WeeklySchedule {
+                        days: vec![Weekday::Mon, Weekday::Tue, Weekday::Wed, Weekday::Thu, Weekday::Fri],
+                        start: hhmm("08:00"),
+                        end: hhmm("18:00"),
+                    }),
+                    ..Default::default()
+                },
+            ),
+            grant(
+                futura,
+                GrantWindow {
+                    valid_from: Some(at("2026-04-01T00:00:00Z")),
+                    ..Default::default()
+                },
+            ),
+        ];
+        repo.set_cameras(project.id, &grants).await.unwrap();
+        assert_eq!(repo.assigned_cameras(project.id).await.unwrap().len(), 3);
+
+        let active = |now: &str| {
+            let repo = &repo;
+            let now = at(now);
+            async move {
+                repo.allowed_camera_paths_at(project.id, now)
+                    .await
+                    .unwrap()
+                    .into_iter()
+                    .map(|c| (c.path, c.until))
+                    .collect::<Vec<_>>()
+            }
+        };
+        // Miércoles 12:00 en Santiago: ambas, la de oficina hasta las 18:00.
+        assert_eq!(
+            active("2026-03-04T15:00:00Z").await,
+            vec![
+                ("oficina".to_string(), Some(at("2026-03-04T21:00:00Z"))),
+                ("temporal".to_string(), Some(at("2026-03-10T00:00:00Z"))),
+            ]
+        );
+        // Miércoles 20:00 y sábado al mediodía: fuera de horario.
+        let paths = |v: Vec<(String, Option<DateTime<Utc>>)>| {
+            v.into_iter().map(|(p, _)| p).collect::<Vec<_>>()
+        };
+        assert_eq!(paths(active("2026-03-04T23:00:00Z").await), vec!["temporal"]);
+        assert_eq!(paths(active("2026-03-07T15:00:00Z").await), vec!["temporal"]);
+        // Vencida la temporal; la futura ya vale.
+        assert!(active("2026-03-11T00:00:00Z").await.is_empty());
+        assert_eq!(paths(active("2026-04-01T00:00:00Z").await), vec!["futura"]);
+
+        // El horario se lee tal como se guardó.
+        let stored = repo.assigned_cameras(project.id).await.unwrap();
+        let oficina = stored.iter().find(|g| g.camera_id == oficina).unwrap();
+        assert_eq!(oficina.window, grants[1].window);
     }
 }
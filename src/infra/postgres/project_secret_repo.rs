@@ -0,0 +1,280 @@
+//! Adaptador Postgres de `ProjectSecretRepo`.
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use super::map_sqlx_err;
+use crate::domain::models::{NewProjectSecret, ProjectSecret};
+use crate::domain::ports::{ProjectSecretRepo, RepoError, RepoResult};
+
+#[derive(sqlx::FromRow)]
+struct ProjectSecretRow {
+    id: Uuid,
+    project_id: Uuid,
+    label: String,
+    secret_hash: String,
+    created_at: DateTime<Utc>,
+    expires_at: Option<DateTime<Utc>>,
+    last_used_at: Option<DateTime<Utc>>,
+    revoked_at: Option<DateTime<Utc>>,
+}
+
+impl From<ProjectSecretRow> for ProjectSecret {
+    fn from(r: ProjectSecretRow) -> Self {
+        ProjectSecret {
+            id: r.id,
+            project_id: r.project_id,
+            label: r.label,
+            secret_hash: r.secret_hash,
+            created_at: r.created_at,
+            expires_at: r.expires_at,
+            last_used_at: r.last_used_at,
+            revoked_at: r.revoked_at,
+        }
+    }
+}
+
+pub struct PgProjectSecretRepo {
+    pool: PgPool,
+}
+
+impl PgProjectSecretRepo {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait]
+impl ProjectSecretRepo for PgProjectSecretRepo {
+    async fn add(&self, new: NewProjectSecret) -> RepoResult<ProjectSecret> {
+        let row = sqlx::query_as::<_, ProjectSecretRow>(
+            "INSERT INTO project_secrets (id, project_id, label, secret_hash, expires_at)
+             VALUES ($1, $2, $3, $4, $5)
+             RETURNING id, project_id, label, secret_hash, created_at, expires_at,
+                       last_used_at, revoked_at",
+        )
+        .bind(Uuid::new_v4())
+        .bind(new.project_id)
+        .bind(new.label)
+        .bind(new.secret_hash)
+        .bind(new.expires_at)
+        .fetch_one(&self.pool)
+        .await
+        .map_err(map_sqlx_err)?;
+        Ok(row.into())
+    }
+
+    async fn list_by_project(&self, project_id: Uuid) -> RepoResult<Vec<ProjectSecret>> {
+        let rows = sqlx::query_as::<_, ProjectSecretRow>(
+            "SELECT id, project_id, label, secret_hash, created_at, expires_at,
+                    last_used_at, revoked_at
+             FROM project_secrets WHERE project_id = $1
+             ORDER BY created_at DESC",
+        )
+        .bind(project_id)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(map_sqlx_err)?;
+        Ok(rows.into_iter().map(Into::into).collect())
+    }
+
+    async fn list_active(&self, project_id: Uuid) -> RepoResult<Vec<ProjectSecret>> {
+        let rows = sqlx::query_as::<_, ProjectSecretRow>(
+            "SELECT id, project_id, label, secret_hash, created_at, expires_at,
+                    last_used_at, revoked_at
+             FROM project_secrets
+             WHERE project_id = $1 AND revoked_at IS NULL
+               AND (expires_at IS NULL OR expires_at > now())
+             ORDER BY created_at DESC",
+        )
+        .bind(project_id)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(map_sqlx_err)?;
+        Ok(rows.into_iter().map(Into::into).collect())
+    }
+
+    async fn revoke(&self, project_id: Uuid, id: Uuid) -> RepoResult<()> {
+        let res = sqlx::query(
+            "UPDATE project_secrets SET revoked_at = now()
+             WHERE id = $1 AND project_id = $2 AND revoked_at IS NULL",
+        )
+        .bind(id)
+        .bind(project_id)
+        .execute(&self.pool)
+        .await
+        .map_err(map_sqlx_err)?;
+        if res.rows_affected() == 0 {
+            return Err(RepoError::NotFound);
+        }
+        Ok(())
+    }
+
+    async fn revoke_others(&self, project_id: Uuid, keep: Uuid) -> RepoResult<()> {
+        sqlx::query(
+            "UPDATE project_secrets SET revoked_at = now()
+             WHERE project_id = $1 AND id <> $2 AND revoked_at IS NULL",
+        )
+        .bind(project_id)
+        .bind(keep)
+        .execute(&self.pool)
+        .await
+        .map_err(map_sqlx_err)?;
+        Ok(())
+    }
+
+    async fn expire_others(&self, project_id: Uuid, keep: Uuid, at: DateTime<Utc>) -> RepoResult<()> {
+        sqlx::query(
+            "UPDATE project_secrets SET expires_at = LEAST(expires_at, $3)
+             WHERE project_id = $1 AND id <> $2 AND revoked_at IS NULL",
+        )
+        .bind(project_id)
+        .bind(keep)
+        .bind(at)
+        .execute(&self.pool)
+        .await
+        .map_err(map_sqlx_err)?;
+        Ok(())
+    }
+
+    async fn touch(&self, id: Uuid) -> RepoResult<()> {
+        sqlx::query("UPDATE project_secrets SET last_used_at = now() WHERE id = $1")
+            .bind(id)
+            .execute(&self.pool)
+            .await
+            .map_err(map_sqlx_err)?;
+        Ok(())
+    }
+
+    async fn update_hash(&self, id: Uuid, secret_hash: &str) -> RepoResult<()> {
+        let result = sqlx::query("UPDATE project_secrets SET secret_hash = $2 WHERE id = $1")
+            .bind(id)
+            .bind(secret_hash)
+            .execute(&self.pool)
+            .await
+            .map_err(map_sqlx_err)?;
+        if result.rows_affected() == 0 {
+            return Err(RepoError::NotFound);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::PgProjectSecretRepo;
+    use crate::domain::models::{NewProject, NewProjectSecret};
+    use crate::domain::ports::{ProjectRepo, ProjectSecretRepo, RepoError};
+    use crate::infra::postgres::PgProjectRepo;
+    use chrono::{Duration, Utc};
+    use sqlx::PgPool;
+    use uuid::Uuid;
+
+    async fn project_id(pool: &PgPool) -> Uuid {
+        PgProjectRepo::new(pool.clone())
+            .create(NewProject {
+                client_id: "sigac".into(),
+                secret_hash: "$argon2id$inicial".into(),
+                all_cameras: true,
+                enabled: true,
+            })
+            .await
+            .unwrap()
+            .id
+    }
+
+    fn sample(project_id: Uuid, label: &str) -> NewProjectSecret {
+        NewProjectSecret {
+            project_id,
+            label: label.into(),
+            secret_hash: format!("$argon2id${label}"),
+            expires_at: None,
+        }
+    }
+
+    #[sqlx::test]
+    async fn project_creation_stores_initial_secret(pool: PgPool) {
+        let pid = project_id(&pool).await;
+        let repo = PgProjectSecretRepo::new(pool);
+        let secrets = repo.list_active(pid).await.unwrap();
+        assert_eq!(secrets.len(), 1);
+        assert_eq!(secrets[0].label, "inicial");
+        assert_eq!(secrets[0].secret_hash, "$argon2id$inicial");
+    }
+
+    #[sqlx::test]
+    async fn active_excludes_revoked_and_expired(pool: PgPool) {
+        let pid = project_id(&pool).await;
+        let repo = PgProjectSecretRepo::new(pool);
+        let nuevo = repo.add(sample(pid, "nuevo")).await.unwrap();
+        let mut vencido = sample(pid, "vencido");
+        vencido.expires_at = Some(Utc::now() - Duration::minutes(1));
+        repo.add(vencido).await.unwrap();
+        assert_eq!(repo.list_active(pid).await.unwrap().len(), 2);
+
+        repo.revoke_others(pid, nuevo.id).await.unwrap();
+        let active = repo.list_active(pid).await.unwrap();
+        assert_eq!(active.len(), 1);
+        assert_eq!(active[0].id, nuevo.id);
+        // El historial conserva todos.
+        assert_eq!(repo.list_by_project(pid).await.unwrap().len(), 3);
+    }
+
+    #[sqlx::test]
+    async fn expire_others_sets_grace_without_extending(pool: PgPool) {
+        let pid = project_id(&pool).await;
+        let repo = PgProjectSecretRepo::new(pool);
+        let nuevo = repo.add(sample(pid, "nuevo")).await.unwrap();
+        let mut pronto = sample(pid, "pronto");
+        let soon = Utc::now() + Duration::minutes(5);
+        pronto.expires_at = Some(soon);
+        repo.add(pronto).await.unwrap();
+
+        let grace = Utc::now() + Duration::hours(1);
+        repo.expire_others(pid, nuevo.id, grace).await.unwrap();
+        for s in repo.list_by_project(pid).await.unwrap() {
+            match s.label.as_str() {
+                "nuevo" => assert!(s.expires_at.is_none()),
+                "pronto" => assert!(s.expires_at.unwrap() < grace),
+                _ => assert!(s.expires_at.is_some()),
+            }
+        }
+        assert_eq!(repo.list_active(pid).await.unwrap().len(), 3);
+    }
+
+    #[sqlx::test]
+    async fn revoke_and_touch(pool: PgPool) {
+        let pid = project_id(&pool).await;
+        let repo = PgProjectSecretRepo::new(pool);
+        let s = repo.add(sample(pid, "b")).await.unwrap();
+
+        repo.touch(s.id).await.unwrap();
+        let listed = repo.list_by_project(pid).await.unwrap();
+        assert!(listed.iter().find(|x| x.id == s.id).unwrap().last_used_at.is_some());
+
+        // Solo el dueño puede revocarlo, y una sola vez.
+        assert!(matches!(repo.revoke(Uuid::new_v4(), s.id).await, Err(RepoError::NotFound)));
+        repo.revoke(pid, s.id).await.unwrap();
+        assert!(matches!(repo.revoke(pid, s.id).await, Err(RepoError::NotFound)));
+        assert!(repo.list_active(pid).await.unwrap().iter().all(|x| x.id != s.id));
+    }
+
+    #[sqlx::test]
+    async fn update_hash_replaces_only_the_hash(pool: PgPool) {
+        let pid = project_id(&pool).await;
+        let repo = PgProjectSecretRepo::new(pool);
+        let s = repo.add(sample(pid, "b")).await.unwrap();
+
+        repo.update_hash(s.id, "$argon2id$nuevo").await.unwrap();
+        let listed = repo.list_by_project(pid).await.unwrap();
+        let updated = listed.iter().find(|x| x.id == s.id).unwrap();
+        assert_eq!(updated.secret_hash, "$argon2id$nuevo");
+        assert_eq!(updated.label, "b");
+        assert!(matches!(
+            repo.update_hash(Uuid::new_v4(), "x").await,
+            Err(RepoError::NotFound)
+        ));
+    }
+}
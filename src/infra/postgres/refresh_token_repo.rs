@@ -0,0 +1,226 @@
+//! Adaptador Postgres de `RefreshTokenRepo`.
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use super::map_sqlx_err;
+use crate::domain::models::{NewRefreshToken, RefreshToken};
+use crate::domain::ports::{RefreshTokenRepo, RepoResult};
+
+#[derive(sqlx::FromRow)]
+struct RefreshTokenRow {
+    id: Uuid,
+    family_id: Uuid,
+    project_id: Uuid,
+    token_hash: String,
+    scope: Option<String>,
+    created_at: DateTime<Utc>,
+    expires_at: DateTime<Utc>,
+    used_at: Option<DateTime<Utc>>,
+    revoked_at: Option<DateTime<Utc>>,
+}
+
+impl From<RefreshTokenRow> for RefreshToken {
+    fn from(r: RefreshTokenRow) -> Self {
+        RefreshToken {
+            id: r.id,
+            family_id: r.family_id,
+            project_id: r.project_id,
+            token_hash: r.token_hash,
+            scope: r.scope,
+            created_at: r.created_at,
+            expires_at: r.expires_at,
+            used_at: r.used_at,
+            revoked_at: r.revoked_at,
+        }
+    }
+}
+
+pub struct PgRefreshTokenRepo {
+    pool: PgPool,
+}
+
+impl PgRefreshTokenRepo {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait]
+impl RefreshTokenRepo for PgRefreshTokenRepo {
+    async fn create(&self, new: NewRefreshToken) -> RepoResult<RefreshToken> {
+        let row = sqlx::query_as::<_, RefreshTokenRow>(
+            "INSERT INTO refresh_tokens (id, family_id, project_id, token_hash, scope, expires_at)
+             VALUES ($1, $2, $3, $4, $5, $6)
+             RETURNING id, family_id, project_id, token_hash, scope, created_at, expires_at,
+                       used_at, revoked_at",
+        )
+        .bind(Uuid::new_v4())
+        .bind(new.family_id)
+        .bind(new.project_id)
+        .bind(new.token_hash)
+        .bind(new.scope)
+        .bind(new.expires_at)
+        .fetch_one(&self.pool)
+        .await
+        .map_err(map_sqlx_err)?;
+        Ok(row.into())
+    }
+
+    async fn find_by_hash(&self, token_hash: &str) -> RepoResult<Option<RefreshToken>> {
+        let row = sqlx::query_as::<_, RefreshTokenRow>(
+            "SELECT id, family_id, project_id, token_hash, scope, created_at, expires_at,
+                    used_at, revoked_at
+             FROM refresh_tokens WHERE token_hash = $1",
+        )
+        .bind(token_hash)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(map_sqlx_err)?;
+        Ok(row.map(Into::into))
+    }
+
+    async fn mark_used(&self, id: Uuid) -> RepoResult<bool> {
+        let res = sqlx::query(
+            "UPDATE refresh_tokens SET used_at = now()
+             WHERE id = $1 AND used_at IS NULL AND revoked_at IS NULL",
+        )
+        .bind(id)
+        .execute(&self.pool)
+        .await
+        .map_err(map_sqlx_err)?;
+        Ok(res.rows_affected() == 1)
+    }
+
+    async fn revoke_family(&self, family_id: Uuid) -> RepoResult<()> {
+        sqlx::query(
+            "UPDATE refresh_tokens SET revoked_at = now()
+             WHERE family_id = $1 AND revoked_at IS NULL",
+        )
+        .bind(family_id)
+        .execute(&self.pool)
+        .await
+        .map_err(map_sqlx_err)?;
+        Ok(())
+    }
+
+    async fn revoke_project(&self, project_id: Uuid) -> RepoResult<()> {
+        sqlx::query(
+            "UPDATE refresh_tokens SET revoked_at = now()
+             WHERE project_id = $1 AND revoked_at IS NULL",
+        )
+        .bind(project_id)
+        .execute(&self.pool)
+        .await
+        .map_err(map_sqlx_err)?;
+        Ok(())
+    }
+
+    async fn purge(&self, before: DateTime<Utc>) -> RepoResult<u64> {
+        let res = sqlx::query(
+            "DELETE FROM refresh_tokens WHERE expires_at < $1 OR revoked_at IS NOT NULL",
+        )
+        .bind(before)
+        .execute(&self.pool)
+        .await
+        .map_err(map_sqlx_err)?;
+        Ok(res.rows_affected())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::PgRefreshTokenRepo;
+    use crate::domain::models::{NewProject, NewRefreshToken};
+    use crate::domain::ports::{ProjectRepo, RefreshTokenRepo};
+    use crate::infra::postgres::PgProjectRepo;
+    use chrono::{Duration, Utc};
+    use sqlx::PgPool;
+    use uuid::Uuid;
+
+    async fn project_id(pool: &PgPool) -> Uuid {
+        PgProjectRepo::new(pool.clone())
+            .create(NewProject {
+                client_id: "sigac".into(),
+                secret_hash: "$argon2id$dummy-hash".into(),
+                all_cameras: true,
+                enabled: true,
+            })
+            .await
+            .unwrap()
+            .id
+    }
+
+    fn sample(project_id: Uuid, family_id: Uuid, hash: &str) -> NewRefreshToken {
+        NewRefreshToken {
+            family_id,
+            project_id,
+            token_hash: hash.into(),
+            scope: Some("read".into()),
+            expires_at: Utc::now() + Duration::days(1),
+        }
+    }
+
+    #[sqlx::test]
+    async fn create_and_find_by_hash(pool: PgPool) {
+        let pid = project_id(&pool).await;
+        let repo = PgRefreshTokenRepo::new(pool);
+        let created = repo.create(sample(pid, Uuid::new_v4(), "h1")).await.unwrap();
+
+        let found = repo.find_by_hash("h1").await.unwrap().unwrap();
+        assert_eq!(found.id, created.id);
+        assert_eq!(found.scope.as_deref(), Some("read"));
+        assert!(found.used_at.is_none());
+        assert!(repo.find_by_hash("otro").await.unwrap().is_none());
+    }
+
+    #[sqlx::test]
+    async fn mark_used_only_once(pool: PgPool) {
+        let pid = project_id(&pool).await;
+        let repo = PgRefreshTokenRepo::new(pool);
+        let t = repo.create(sample(pid, Uuid::new_v4(), "h1")).await.unwrap();
+
+        assert!(repo.mark_used(t.id).await.unwrap());
+        assert!(!repo.mark_used(t.id).await.unwrap(), "el segundo canje pierde");
+    }
+
+    #[sqlx::test]
+    async fn revoke_family_revokes_all_members(pool: PgPool) {
+        let pid = project_id(&pool).await;
+        let repo = PgRefreshTokenRepo::new(pool);
+        let family = Uuid::new_v4();
+        let a = repo.create(sample(pid, family, "a")).await.unwrap();
+        repo.create(sample(pid, family, "b")).await.unwrap();
+        let other = repo.create(sample(pid, Uuid::new_v4(), "c")).await.unwrap();
+
+        repo.revoke_family(family).await.unwrap();
+        assert!(repo.find_by_hash("a").await.unwrap().unwrap().revoked_at.is_some());
+        assert!(repo.find_by_hash("b").await.unwrap().unwrap().revoked_at.is_some());
+        assert!(repo.find_by_hash("c").await.unwrap().unwrap().revoked_at.is_none());
+        assert!(!repo.mark_used(a.id).await.unwrap(), "revocado no se canjea");
+        assert!(repo.mark_used(other.id).await.unwrap());
+    }
+
+    #[sqlx::test]
+    async fn purge_drops_expired_and_revoked_but_keeps_used(pool: PgPool) {
+        let pid = project_id(&pool).await;
+        let repo = PgRefreshTokenRepo::new(pool);
+        let mut expired = sample(pid, Uuid::new_v4(), "vencido");
+        expired.expires_at = Utc::now() - Duration::minutes(1);
+        repo.create(expired).await.unwrap();
+        let revoked_family = Uuid::new_v4();
+        repo.create(sample(pid, revoked_family, "revocado")).await.unwrap();
+        repo.revoke_family(revoked_family).await.unwrap();
+        let used = repo.create(sample(pid, Uuid::new_v4(), "usado")).await.unwrap();
+        repo.mark_used(used.id).await.unwrap();
+        repo.create(sample(pid, Uuid::new_v4(), "vivo")).await.unwrap();
+
+        assert_eq!(repo.purge(Utc::now()).await.unwrap(), 2);
+        assert!(repo.find_by_hash("vencido").await.unwrap().is_none());
+        assert!(repo.find_by_hash("revocado").await.unwrap().is_none());
+        assert!(repo.find_by_hash("usado").await.unwrap().is_some(), "para detectar reuso");
+        assert!(repo.find_by_hash("vivo").await.unwrap().is_some());
+    }
+}
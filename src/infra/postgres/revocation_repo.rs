@@ -0,0 +1,143 @@
+//! Adaptador Postgres de `RevocationRepo`.
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use super::map_sqlx_err;
+use crate::domain::models::{NewRevocation, Revocation};
+use crate::domain::ports::{RepoError, RepoResult, RevocationRepo};
+
+#[derive(sqlx::FromRow)]
+struct RevocationRow {
+    id: Uuid,
+    jti: Option<String>,
+    client_id: Option<String>,
+    issued_before: Option<DateTime<Utc>>,
+    reason: Option<String>,
+    created_at: DateTime<Utc>,
+    expires_at: DateTime<Utc>,
+}
+
+impl From<RevocationRow> for Revocation {
+    fn from(r: RevocationRow) -> Self {
+        Revocation {
+            id: r.id,
+            jti: r.jti,
+            client_id: r.client_id,
+            issued_before: r.issued_before,
+            reason: r.reason,
+            created_at: r.created_at,
+            expires_at: r.expires_at,
+        }
+    }
+}
+
+pub struct PgRevocationRepo {
+    pool: PgPool,
+}
+
+impl PgRevocationRepo {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait]
+impl RevocationRepo for PgRevocationRepo {
+    async fn create(&self, new: NewRevocation) -> RepoResult<Revocation> {
+        let row = sqlx::query_as::<_, RevocationRow>(
+            "INSERT INTO token_revocations (id, jti, client_id, issued_before, reason, expires_at)
+             VALUES ($1, $2, $3, $4, $5, $6)
+             RETURNING id, jti, client_id, issued_before, reason, created_at, expires_at",
+        )
+        .bind(Uuid::new_v4())
+        .bind(new.jti)
+        .bind(new.client_id)
+        .bind(new.issued_before)
+        .bind(new.reason)
+        .bind(new.expires_at)
+        .fetch_one(&self.pool)
+        .await
+        .map_err(map_sqlx_err)?;
+        Ok(row.into())
+    }
+
+    async fn list_active(&self, now: DateTime<Utc>) -> RepoResult<Vec<Revocation>> {
+        let rows = sqlx::query_as::<_, RevocationRow>(
+            "SELECT id, jti, client_id, issued_before, reason, created_at, expires_at
+             FROM token_revocations WHERE expires_at > $1 ORDER BY created_at",
+        )
+        .bind(now)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(map_sqlx_err)?;
+        Ok(rows.into_iter().map(Into::into).collect())
+    }
+
+    async fn delete(&self, id: Uuid) -> RepoResult<()> {
+        let res = sqlx::query("DELETE FROM token_revocations WHERE id = $1")
+            .bind(id)
+            .execute(&self.pool)
+            .await
+            .map_err(map_sqlx_err)?;
+        if res.rows_affected() == 0 {
+            return Err(RepoError::NotFound);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::PgRevocationRepo;
+    use crate::domain::models::NewRevocation;
+    use crate::domain::ports::{RepoError, RevocationRepo};
+    use chrono::{Duration, Utc};
+    use sqlx::PgPool;
+
+    fn by_jti(jti: &str, ttl: Duration) -> NewRevocation {
+        NewRevocation {
+            jti: Some(jti.into()),
+            client_id: None,
+            issued_before: None,
+            reason: Some("prueba".into()),
+            expires_at: Utc::now() + ttl,
+        }
+    }
+
+    #[sqlx::test]
+    async fn list_active_skips_expired(pool: PgPool) {
+        let repo = PgRevocationRepo::new(pool);
+        repo.create(by_jti("vivo", Duration::hours(1))).await.unwrap();
+        repo.create(by_jti("caducado", Duration::hours(-1))).await.unwrap();
+
+        let active = repo.list_active(Utc::now()).await.unwrap();
+        assert_eq!(active.len(), 1);
+        assert_eq!(active[0].jti.as_deref(), Some("vivo"));
+        assert_eq!(active[0].reason.as_deref(), Some("prueba"));
+    }
+
+    #[sqlx::test]
+    async fn project_revocation_requires_cutoff(pool: PgPool) {
+        let repo = PgRevocationRepo::new(pool);
+        let mut new = by_jti("x", Duration::hours(1));
+        new.jti = None;
+        new.client_id = Some("sigac".into());
+        assert!(repo.create(new.clone()).await.is_err(), "sin issued_before no vale");
+
+        new.issued_before = Some(Utc::now());
+        let created = repo.create(new).await.unwrap();
+        assert_eq!(created.client_id.as_deref(), Some("sigac"));
+    }
+
+    #[sqlx::test]
+    async fn delete_lifts_and_reports_missing(pool: PgPool) {
+        let repo = PgRevocationRepo::new(pool);
+        let r = repo.create(by_jti("a", Duration::hours(1))).await.unwrap();
+        repo.delete(r.id).await.unwrap();
+        assert!(repo.list_active(Utc::now()).await.unwrap().is_empty());
+        assert!(matches!(repo.delete(r.id).await, Err(RepoError::NotFound)));
+    }
+}
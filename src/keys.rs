@@ -1,101 +1,844 @@
-//! Ciclo de vida de la clave de firma RS256 (HU 2.2).
+//! Anillo de claves de firma JWT (HU 2.2 + rotación).
 //!
-//! Responsabilidad única: obtener el material de firma. Carga la clave privada
-//! RSA desde disco (volumen persistente) o, si no existe, la genera y la guarda
-//! UNA sola vez. Así la clave sobrevive a los reinicios del backend y los JWT ya
-//! emitidos siguen validando (antes se generaba efímera en cada arranque).
+//! Responsabilidad única: obtener el material de firma. Las claves privadas
+//! viven en disco (volumen persistente) junto a un manifiesto `keyring.json`
+//! que registra el estado de cada una:
+//!
+//! - `pending`: recién rotada; ya se publica en el JWKS pero aún no firma, para
+//!   que los verificadores que cachearon el JWKS la conozcan antes de ver un
+//!   token suyo. Pasa a `active` sola al vencer `activate_after`.
+//! - `active`: firma los JWT nuevos (exactamente una).
+//! - `retiring`: ya no firma, pero sigue publicada en el JWKS y validando hasta
+//!   `retire_after` (cuando expiran los últimos tokens que firmó).
+//! - `retired`: fuera del JWKS; su material ya no se carga.
+//!
+//! El `kid` es el thumbprint RFC 7638 de la clave pública: estable y sin
+//! depender del nombre del archivo. Sin manifiesto, la clave histórica de
+//! `JWT_PRIVATE_KEY_PATH` se importa como activa (o se genera UNA sola vez);
+//! la importada sigue respondiendo también al kid fijo `key1` de las versiones
+//! previas al anillo mientras viven los tokens firmados con él.
+//!
+//! Cada clave tiene su algoritmo (`JWT_ALGORITHM`: RS256, PS256, ES256 o
+//! EdDSA). Cambiarlo aplica en la siguiente rotación: las claves ya emitidas
+//! conservan el suyo y siguen validando sus tokens.
 
+use std::fmt;
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
 
-use jsonwebtoken::{DecodingKey, EncodingKey};
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use chrono::{DateTime, Utc};
+use jsonwebtoken::{Algorithm, DecodingKey, EncodingKey};
+use p256::elliptic_curve::sec1::ToEncodedPoint;
 use rand::rngs::OsRng;
-use rsa::pkcs8::{DecodePrivateKey, EncodePrivateKey, EncodePublicKey, LineEnding};
+use rsa::pkcs8::{DecodePrivateKey, EncodePrivateKey, LineEnding};
+use rsa::traits::PublicKeyParts;
 use rsa::{RsaPrivateKey, RsaPublicKey};
-use tracing::info;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tracing::{info, warn};
+
+/// Nombre del manifiesto del anillo, en el mismo directorio que las claves.
+const MANIFEST_FILE: &str = "keyring.json";
+
+/// `kid` con el que firmaban las versiones previas al anillo.
+pub const LEGACY_KID: &str = "key1";
+
+/// Estado de una clave dentro del anillo.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum KeyState {
+    Pending,
+    Active,
+    Retiring,
+    Retired,
+}
+
+/// Algoritmo de firma de una clave (y de los JWT que firma).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum KeyAlg {
+    /// RSA 2048 + PKCS#1 v1.5 (default histórico).
+    #[default]
+    #[serde(rename = "RS256")]
+    Rs256,
+    /// RSA 2048 + PSS.
+    #[serde(rename = "PS256")]
+    Ps256,
+    /// ECDSA P-256: tokens mucho más cortos que RSA.
+    #[serde(rename = "ES256")]
+    Es256,
+    /// Ed25519: la firma más corta (64 bytes).
+    #[serde(rename = "EdDSA")]
+    EdDsa,
+}
+
+impl KeyAlg {
+    /// Algoritmo de `jsonwebtoken` para firmar y validar.
+    pub fn algorithm(self) -> Algorithm {
+        match self {
+            KeyAlg::Rs256 => Algorithm::RS256,
+            KeyAlg::Ps256 => Algorithm::PS256,
+            KeyAlg::Es256 => Algorithm::ES256,
+            KeyAlg::EdDsa => Algorithm::EdDSA,
+        }
+    }
+
+    /// Nombre JOSE (`alg` del header y del JWK).
+    pub fn as_str(self) -> &'static str {
+        match self {
+            KeyAlg::Rs256 => "RS256",
+            KeyAlg::Ps256 => "PS256",
+            KeyAlg::Es256 => "ES256",
+            KeyAlg::EdDsa => "EdDSA",
+        }
+    }
+}
+
+impl fmt::Display for KeyAlg {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for KeyAlg {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        [KeyAlg::Rs256, KeyAlg::Ps256, KeyAlg::Es256, KeyAlg::EdDsa]
+            .into_iter()
+            .find(|a| a.as_str().eq_ignore_ascii_case(s))
+            .ok_or_else(|| format!("algoritmo JWT no soportado: {s}"))
+    }
+}
+
+/// Componentes públicos de una clave en formato JWK (Base64 URL-safe sin
+/// padding).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PublicJwk {
+    /// `kty: RSA` (RS256/PS256).
+    Rsa { n: String, e: String },
+    /// `kty: EC`, `crv: P-256` (ES256).
+    Ec { x: String, y: String },
+    /// `kty: OKP`, `crv: Ed25519` (EdDSA).
+    Okp { x: String },
+}
+
+impl PublicJwk {
+    pub fn kty(&self) -> &'static str {
+        match self {
+            PublicJwk::Rsa { .. } => "RSA",
+            PublicJwk::Ec { .. } => "EC",
+            PublicJwk::Okp { .. } => "OKP",
+        }
+    }
+
+    /// Curva (`crv`); `None` para RSA.
+    pub fn crv(&self) -> Option<&'static str> {
+        match self {
+            PublicJwk::Rsa { .. } => None,
+            PublicJwk::Ec { .. } => Some("P-256"),
+            PublicJwk::Okp { .. } => Some("Ed25519"),
+        }
+    }
+}
+
+/// Material privado de una clave, según su algoritmo.
+enum KeyMaterial {
+    Rsa(RsaPrivateKey),
+    P256(p256::SecretKey),
+    Ed25519(ed25519_dalek::SigningKey),
+}
+
+impl KeyMaterial {
+    fn generate(alg: KeyAlg) -> Result<Self, Box<dyn std::error::Error>> {
+        Ok(match alg {
+            KeyAlg::Rs256 | KeyAlg::Ps256 => KeyMaterial::Rsa(RsaPrivateKey::new(&mut OsRng, 2048)?),
+            KeyAlg::Es256 => KeyMaterial::P256(p256::SecretKey::random(&mut OsRng)),
+            KeyAlg::EdDsa => KeyMaterial::Ed25519(ed25519_dalek::SigningKey::generate(&mut OsRng)),
+        })
+    }
+
+    /// Lee un PEM PKCS#8 del tipo que corresponde a `alg`.
+    fn from_pem(pem: &str, alg: KeyAlg) -> Result<Self, Box<dyn std::error::Error>> {
+        Ok(match alg {
+            KeyAlg::Rs256 | KeyAlg::Ps256 => KeyMaterial::Rsa(RsaPrivateKey::from_pkcs8_pem(pem)?),
+            KeyAlg::Es256 => KeyMaterial::P256(p256::SecretKey::from_pkcs8_pem(pem)?),
+            KeyAlg::EdDsa => {
+                KeyMaterial::Ed25519(ed25519_dalek::SigningKey::from_pkcs8_pem(pem)?)
+            }
+        })
+    }
+
+    /// PEM PKCS#8 de la clave privada.
+    fn to_pem(&self) -> Result<String, Box<dyn std::error::Error>> {
+        let pem = match self {
+            KeyMaterial::Rsa(k) => k.to_pkcs8_pem(LineEnding::LF)?,
+            KeyMaterial::P256(k) => k.to_pkcs8_pem(LineEnding::LF)?,
+            KeyMaterial::Ed25519(k) => k.to_pkcs8_pem(LineEnding::LF)?,
+        };
+        Ok(pem.to_string())
+    }
+
+    fn public_jwk(&self) -> PublicJwk {
+        match self {
+            KeyMaterial::Rsa(k) => {
+                let public_key = RsaPublicKey::from(k);
+                PublicJwk::Rsa {
+                    n: URL_SAFE_NO_PAD.encode(public_key.n().to_bytes_be()),
+                    e: URL_SAFE_NO_PAD.encode(public_key.e().to_bytes_be()),
+                }
+            }
+            KeyMaterial::P256(k) => {
+                let point = k.public_key().to_encoded_point(false);
+                PublicJwk::Ec {
+                    x: URL_SAFE_NO_PAD.encode(point.x().expect("punto P-256 sin comprimir")),
+                    y: URL_SAFE_NO_PAD.encode(point.y().expect("punto P-256 sin comprimir")),
+                }
+            }
+            KeyMaterial::Ed25519(k) => PublicJwk::Okp {
+                x: URL_SAFE_NO_PAD.encode(k.verifying_key().as_bytes()),
+            },
+        }
+    }
+
+    fn encoding_key(&self) -> Result<EncodingKey, Box<dyn std::error::Error>> {
+        let pem = self.to_pem()?;
+        Ok(match self {
+            KeyMaterial::Rsa(_) => EncodingKey::from_rsa_pem(pem.as_bytes())?,
+            KeyMaterial::P256(_) => EncodingKey::from_ec_pem(pem.as_bytes())?,
+            KeyMaterial::Ed25519(_) => EncodingKey::from_ed_pem(pem.as_bytes())?,
+        })
+    }
+
+    fn decoding_key(&self) -> Result<DecodingKey, Box<dyn std::error::Error>> {
+        Ok(match self.public_jwk() {
+            PublicJwk::Rsa { n, e } => DecodingKey::from_rsa_components(&n, &e)?,
+            PublicJwk::Ec { x, y } => DecodingKey::from_ec_components(&x, &y)?,
+            PublicJwk::Okp { x } => DecodingKey::from_ed_components(&x)?,
+        })
+    }
+}
+
+/// Entrada del manifiesto (solo metadatos; la clave va en su propio PEM).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct ManifestEntry {
+    kid: String,
+    /// Manifiestos previos a los algoritmos configurables: RS256.
+    #[serde(default)]
+    alg: KeyAlg,
+    /// Nombre del archivo PEM, relativo al directorio del anillo.
+    file: String,
+    state: KeyState,
+    created_at: DateTime<Utc>,
+    /// Fin de la gracia de una clave `retiring` (deja de publicarse después).
+    /// La activa ya lo lleva desde que se rota: cuenta desde que la sucesora
+    /// empieza a firmar.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    retire_after: Option<DateTime<Utc>>,
+    /// Desde cuándo firma una clave `pending`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    activate_after: Option<DateTime<Utc>>,
+    /// Kid anterior al anillo (`key1`) de la clave importada, y hasta cuándo
+    /// se acepta: lo que vive un token firmado antes de la importación.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    legacy_kid: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    legacy_until: Option<DateTime<Utc>>,
+}
 
-/// Material de firma listo para usar.
-pub struct SigningMaterial {
-    /// Clave para firmar JWT (RS256).
+impl ManifestEntry {
+    /// Si la clave debe cargarse y publicarse en el JWKS en el instante `now`.
+    fn is_published(&self, now: DateTime<Utc>) -> bool {
+        match self.state {
+            KeyState::Pending | KeyState::Active => true,
+            KeyState::Retiring => self.retire_after.is_none_or(|t| now < t),
+            KeyState::Retired => false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct Manifest {
+    keys: Vec<ManifestEntry>,
+}
+
+impl Manifest {
+    /// Lleva los estados a `now`: una clave `pending` cuya espera venció pasa a
+    /// `active` y la que firmaba hasta entonces, a `retiring`; las claves en
+    /// retiro con la gracia vencida pasan a `retired`. Es determinista, así que
+    /// todas las instancias cambian de clave a la vez sin escribir el manifiesto.
+    fn settle(&mut self, now: DateTime<Utc>) {
+        let due = |e: &ManifestEntry| {
+            e.state == KeyState::Pending && e.activate_after.is_none_or(|t| t <= now)
+        };
+        if self.keys.iter().any(due) {
+            for entry in self.keys.iter_mut() {
+                if due(entry) {
+                    entry.state = KeyState::Active;
+                    entry.activate_after = None;
+                } else if entry.state == KeyState::Active {
+                    entry.state = KeyState::Retiring;
+                }
+            }
+        }
+        for entry in self.keys.iter_mut() {
+            if entry.state == KeyState::Retiring && !entry.is_published(now) {
+                entry.state = KeyState::Retired;
+            }
+        }
+    }
+}
+
+/// Clave de firma cargada y lista para usar.
+pub struct SigningKey {
+    /// Thumbprint RFC 7638 de la clave pública.
+    pub kid: String,
+    pub state: KeyState,
+    /// Algoritmo con el que firma y con el que se validan sus tokens.
+    pub alg: KeyAlg,
+    /// Clave para firmar JWT. Solo se usa la de la clave activa.
     pub encoding_key: EncodingKey,
-    /// Clave para validar JWT emitidos (RS256), p.ej. en GET /cameras (HU 4.7).
+    /// Clave para validar JWT emitidos, p.ej. en GET /cameras (HU 4.7).
     pub decoding_key: DecodingKey,
     /// Clave pública, para construir el JWKS.
-    pub public_key: RsaPublicKey,
+    pub jwk: PublicJwk,
+    /// Kid previo al anillo con el que también se publica y se encuentra,
+    /// mientras sigue vigente.
+    pub legacy_kid: Option<String>,
+}
+
+/// Claves publicadas (activa, pendiente y en retiro). Invariante: la primera
+/// es la activa.
+pub struct Keyring {
+    keys: Vec<SigningKey>,
 }
 
-/// Carga la clave privada desde `path`; si no existe, la genera y la persiste.
-pub fn load_or_create(path: &str) -> Result<SigningMaterial, Box<dyn std::error::Error>> {
-    let private_key = if Path::new(path).exists() {
-        info!("Cargando clave de firma existente desde {}", path);
+impl Keyring {
+    /// Clave con la que se firman los tokens nuevos.
+    pub fn active(&self) -> &SigningKey {
+        &self.keys[0]
+    }
+
+    /// Claves que se publican en el JWKS y con las que se valida.
+    pub fn published(&self) -> &[SigningKey] {
+        &self.keys
+    }
+
+    /// Busca una clave publicada por su `kid` (header del JWT) o por su kid
+    /// previo al anillo.
+    pub fn find(&self, kid: &str) -> Option<&SigningKey> {
+        self.keys
+            .iter()
+            .find(|k| k.kid == kid || k.legacy_kid.as_deref() == Some(kid))
+    }
+
+    /// Huella del conjunto publicado (SHA-256 de alg+kid de cada clave, en
+    /// orden, la activa primero): cambia si y solo si cambia el JWKS o la clave
+    /// que firma. Base del `ETag` de `/jwks`.
+    pub fn fingerprint(&self) -> String {
+        let mut hasher = Sha256::new();
+        for key in &self.keys {
+            hasher.update(format!("{}:{}\n", key.alg, key.kid).as_bytes());
+            if let Some(legacy) = &key.legacy_kid {
+                hasher.update(format!("{}:{}\n", key.alg, legacy).as_bytes());
+            }
+        }
+        URL_SAFE_NO_PAD.encode(hasher.finalize())
+    }
+}
+
+/// Carga el anillo del directorio de `path`; si no hay manifiesto, importa la
+/// clave de `path` como activa (o la genera con `alg` y la persiste). La
+/// importada acepta además `key1` durante `legacy_ttl` (vida máxima de un JWT).
+pub fn load_or_create(
+    path: &str,
+    alg: KeyAlg,
+    legacy_ttl: chrono::Duration,
+) -> Result<Keyring, Box<dyn std::error::Error>> {
+    let dir = key_dir(path);
+    let manifest = load_or_init_manifest(path, alg, legacy_ttl)?;
+    let keyring = load_keyring(&dir, &manifest, Utc::now())?;
+    info!(
+        "Anillo de claves cargado: {} clave(s) publicada(s), activa kid={} ({})",
+        keyring.keys.len(),
+        keyring.active().kid,
+        keyring.active().alg
+    );
+    if keyring.active().alg != alg {
+        warn!(
+            "La clave activa es {} pero JWT_ALGORITHM={}: aplica en la próxima rotación (rotate-signing-key)",
+            keyring.active().alg,
+            alg
+        );
+    }
+    Ok(keyring)
+}
+
+/// Relee el anillo del disco para un servidor en marcha: recoge las rotaciones
+/// hechas con `rotate-signing-key` y deja de publicar las claves cuya gracia
+/// venció, sin reiniciar. A diferencia de `load_or_create`, nunca genera.
+pub fn reload(path: &str) -> Result<Keyring, Box<dyn std::error::Error>> {
+    let dir = key_dir(path);
+    let manifest = read_manifest(&dir)?
+        .ok_or_else(|| format!("falta {} en {}", MANIFEST_FILE, dir.display()))?;
+    load_keyring(&dir, &manifest, Utc::now())
+}
+
+/// Rota la clave de firma en dos pasos: genera una nueva en `pending`, que se
+/// publica ya pero solo firma pasado `activate_in` (lo que un verificador puede
+/// tardar en ver el JWKS nuevo). Desde entonces la anterior queda en `retiring`
+/// durante `grace` (vida máxima de los tokens que firmó). La nueva clave es de
+/// `alg` (así se cambia de algoritmo). Devuelve el nuevo `kid` y desde cuándo
+/// firma. Falla si ya hay una rotación pendiente.
+pub fn rotate(
+    path: &str,
+    grace: chrono::Duration,
+    alg: KeyAlg,
+    activate_in: chrono::Duration,
+) -> Result<(String, DateTime<Utc>), Box<dyn std::error::Error>> {
+    let dir = key_dir(path);
+    let mut manifest = load_or_init_manifest(path, alg, grace)?;
+    let now = Utc::now();
+    manifest.settle(now);
+    if let Some(pending) = manifest.keys.iter().find(|e| e.state == KeyState::Pending) {
+        return Err(format!(
+            "la clave {} ya está pendiente de activarse ({}); rotar después",
+            pending.kid,
+            pending.activate_after.unwrap_or(now)
+        )
+        .into());
+    }
+
+    let activate_after = now + activate_in;
+    for entry in manifest.keys.iter_mut() {
+        if entry.state == KeyState::Active {
+            entry.retire_after = Some(activate_after + grace);
+        }
+    }
+
+    let key = KeyMaterial::generate(alg)?;
+    let kid = thumbprint(&key.public_jwk());
+    let file = format!("jwt_{kid}.pem");
+    persist(&key, &dir.join(&file))?;
+    manifest.keys.push(ManifestEntry {
+        kid: kid.clone(),
+        alg,
+        file,
+        state: KeyState::Pending,
+        created_at: now,
+        retire_after: None,
+        activate_after: Some(activate_after),
+        legacy_kid: None,
+        legacy_until: None,
+    });
+    save_manifest(&dir, &manifest)?;
+    info!("Nueva clave de firma publicada: kid={} ({}), firma desde {}", kid, alg, activate_after);
+    Ok((kid, activate_after))
+}
+
+/// Thumbprint RFC 7638: SHA-256 del JWK canónico (miembros requeridos en
+/// orden lexicográfico, sin espacios), en Base64 URL-safe sin padding.
+pub fn thumbprint(jwk: &PublicJwk) -> String {
+    let canonical = match jwk {
+        PublicJwk::Rsa { n, e } => format!(r#"{{"e":"{e}","kty":"RSA","n":"{n}"}}"#),
+        PublicJwk::Ec { x, y } => {
+            format!(r#"{{"crv":"P-256","kty":"EC","x":"{x}","y":"{y}"}}"#)
+        }
+        PublicJwk::Okp { x } => format!(r#"{{"crv":"Ed25519","kty":"OKP","x":"{x}"}}"#),
+    };
+    URL_SAFE_NO_PAD.encode(Sha256::digest(canonical.as_bytes()))
+}
+
+/// Directorio del anillo: el de la clave histórica (`JWT_PRIVATE_KEY_PATH`).
+fn key_dir(path: &str) -> PathBuf {
+    Path::new(path)
+        .parent()
+        .map(Path::to_path_buf)
+        .unwrap_or_default()
+}
+
+/// Lee el manifiesto; si no existe, lo inicializa con la clave de `path`.
+fn load_or_init_manifest(
+    path: &str,
+    alg: KeyAlg,
+    legacy_ttl: chrono::Duration,
+) -> Result<Manifest, Box<dyn std::error::Error>> {
+    let dir = key_dir(path);
+    if let Some(manifest) = read_manifest(&dir)? {
+        return Ok(manifest);
+    }
+
+    let now = Utc::now();
+    let (alg, key, imported) = if Path::new(path).exists() {
+        info!("Importando la clave de firma existente de {} al anillo", path);
         let pem = fs::read_to_string(path)?;
-        RsaPrivateKey::from_pkcs8_pem(&pem)?
+        // La clave histórica es RSA: si no es del tipo configurado, se importa
+        // como RS256 y el cambio de algoritmo queda para la próxima rotación.
+        match KeyMaterial::from_pem(&pem, alg) {
+            Ok(key) => (alg, key, true),
+            Err(_) => (KeyAlg::Rs256, KeyMaterial::from_pem(&pem, KeyAlg::Rs256)?, true),
+        }
     } else {
-        info!("No hay clave en {}; generando una nueva (RSA 2048)...", path);
-        let mut rng = OsRng;
-        let key = RsaPrivateKey::new(&mut rng, 2048)?;
-        persist(&key, path)?;
+        info!("No hay clave en {}; generando una nueva ({})...", path, alg);
+        let key = KeyMaterial::generate(alg)?;
+        persist(&key, Path::new(path))?;
         info!("Clave de firma generada y guardada en {}", path);
-        key
+        (alg, key, false)
     };
 
-    let public_key = RsaPublicKey::from(&private_key);
-    let private_pem = private_key.to_pkcs8_pem(LineEnding::LF)?;
-    let encoding_key = EncodingKey::from_rsa_pem(private_pem.as_bytes())?;
+    let file = Path::new(path)
+        .file_name()
+        .and_then(|f| f.to_str())
+        .ok_or_else(|| format!("ruta de clave inválida: {path}"))?
+        .to_string();
+    let manifest = Manifest {
+        keys: vec![ManifestEntry {
+            kid: thumbprint(&key.public_jwk()),
+            alg,
+            file,
+            state: KeyState::Active,
+            created_at: now,
+            retire_after: None,
+            activate_after: None,
+            // Los tokens vivos firmados antes del anillo llevan `kid=key1`.
+            legacy_kid: imported.then(|| LEGACY_KID.to_string()),
+            legacy_until: imported.then(|| now + legacy_ttl),
+        }],
+    };
+    save_manifest(&dir, &manifest)?;
+    Ok(manifest)
+}
+
+/// Lee el manifiesto de `dir`, si existe.
+fn read_manifest(dir: &Path) -> Result<Option<Manifest>, Box<dyn std::error::Error>> {
+    let manifest_path = dir.join(MANIFEST_FILE);
+    if !manifest_path.exists() {
+        return Ok(None);
+    }
+    let content = fs::read_to_string(&manifest_path)?;
+    Ok(Some(serde_json::from_str(&content).map_err(|e| {
+        format!("{} inválido: {e}", manifest_path.display())
+    })?))
+}
 
-    let public_pem = public_key.to_public_key_pem(LineEnding::LF)?;
-    let decoding_key = DecodingKey::from_rsa_pem(public_pem.as_bytes())?;
+/// Carga el material de las claves publicadas en `now` (con los estados ya
+/// llevados a `now`). Fail-closed: sin exactamente una clave activa, o con un
+/// PEM que no coincide con su `kid`, no se arranca.
+fn load_keyring(
+    dir: &Path,
+    manifest: &Manifest,
+    now: DateTime<Utc>,
+) -> Result<Keyring, Box<dyn std::error::Error>> {
+    let mut manifest = manifest.clone();
+    manifest.settle(now);
+    let active = manifest
+        .keys
+        .iter()
+        .filter(|e| e.state == KeyState::Active)
+        .count();
+    if active != 1 {
+        return Err(format!("el anillo debe tener exactamente 1 clave activa (tiene {active})").into());
+    }
 
-    Ok(SigningMaterial {
-        encoding_key,
-        decoding_key,
-        public_key,
-    })
+    let mut keys = Vec::new();
+    for entry in manifest.keys.iter().filter(|e| e.is_published(now)) {
+        let pem = fs::read_to_string(dir.join(&entry.file))?;
+        let key = KeyMaterial::from_pem(&pem, entry.alg)
+            .map_err(|e| format!("la clave {} no es {}: {e}", entry.file, entry.alg))?;
+        let jwk = key.public_jwk();
+        if thumbprint(&jwk) != entry.kid {
+            return Err(format!("la clave {} no corresponde a su kid {}", entry.file, entry.kid).into());
+        }
+
+        keys.push(SigningKey {
+            kid: entry.kid.clone(),
+            state: entry.state,
+            alg: entry.alg,
+            encoding_key: key.encoding_key()?,
+            decoding_key: key.decoding_key()?,
+            jwk,
+            legacy_kid: entry
+                .legacy_kid
+                .clone()
+                .filter(|_| entry.legacy_until.is_some_and(|t| now < t)),
+        });
+    }
+    // La activa primero (invariante de `Keyring::active`).
+    keys.sort_by_key(|k| k.state != KeyState::Active);
+    Ok(Keyring { keys })
 }
 
 /// Escribe la clave en disco (creando el directorio) con permisos restrictivos.
-fn persist(key: &RsaPrivateKey, path: &str) -> Result<(), Box<dyn std::error::Error>> {
-    if let Some(parent) = Path::new(path).parent() {
+fn persist(key: &KeyMaterial, path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+    if let Some(parent) = path.parent() {
         fs::create_dir_all(parent)?;
     }
-    let pem = key.to_pkcs8_pem(LineEnding::LF)?;
-    fs::write(path, pem.as_bytes())?;
+    fs::write(path, key.to_pem()?.as_bytes())?;
     set_restrictive_perms(path)?;
     Ok(())
 }
 
+/// Guarda el manifiesto de forma atómica (archivo temporal + rename).
+fn save_manifest(dir: &Path, manifest: &Manifest) -> Result<(), Box<dyn std::error::Error>> {
+    fs::create_dir_all(dir)?;
+    let tmp = dir.join(format!("{MANIFEST_FILE}.tmp"));
+    fs::write(&tmp, serde_json::to_string_pretty(manifest)?)?;
+    fs::rename(&tmp, dir.join(MANIFEST_FILE))?;
+    Ok(())
+}
+
 #[cfg(unix)]
-fn set_restrictive_perms(path: &str) -> std::io::Result<()> {
+fn set_restrictive_perms(path: &Path) -> std::io::Result<()> {
     use std::os::unix::fs::PermissionsExt;
     fs::set_permissions(path, fs::Permissions::from_mode(0o600))
 }
 
 #[cfg(not(unix))]
-fn set_restrictive_perms(_path: &str) -> std::io::Result<()> {
+fn set_restrictive_perms(_path: &Path) -> std::io::Result<()> {
     Ok(())
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use rsa::traits::PublicKeyParts;
+
+    fn temp_key_path(name: &str) -> (PathBuf, String) {
+        let dir = std::env::temp_dir().join(format!("mtx-keys-{}-{}", name, std::process::id()));
+        std::fs::remove_dir_all(&dir).ok();
+        let path = dir.join("jwt_private_key.pem");
+        let p = path.to_str().unwrap().to_string();
+        (dir, p)
+    }
+
+    /// Ventana del kid `key1` de la clave importada.
+    const LEGACY_TTL: chrono::Duration = chrono::Duration::hours(1);
+
+    /// Rota sin espera de activación: la clave nueva firma de inmediato.
+    fn rotate_now(path: &str, grace: chrono::Duration, alg: KeyAlg) -> String {
+        rotate(path, grace, alg, chrono::Duration::zero()).unwrap().0
+    }
 
     #[test]
     fn load_or_create_persists_and_reuses_key() {
-        let dir = std::env::temp_dir().join(format!("mtx-keys-test-{}", std::process::id()));
-        let path = dir.join("jwt_private_key.pem");
-        let p = path.to_str().unwrap();
+        let (dir, p) = temp_key_path("reuse");
 
         // Primera vez: genera y persiste.
-        let first = load_or_create(p).unwrap();
-        assert!(path.exists(), "la clave debe quedar guardada en disco");
+        let first = load_or_create(&p, KeyAlg::Rs256, LEGACY_TTL).unwrap();
+        assert!(Path::new(&p).exists(), "la clave debe quedar guardada en disco");
+        assert!(dir.join(MANIFEST_FILE).exists(), "debe crearse el manifiesto");
 
         // Segunda vez: carga la misma clave.
-        let second = load_or_create(p).unwrap();
+        let second = load_or_create(&p, KeyAlg::Rs256, LEGACY_TTL).unwrap();
 
-        // Misma clave => mismo módulo público (la firma sobrevive al reinicio).
-        assert_eq!(first.public_key.n(), second.public_key.n());
+        // Misma clave => misma clave pública (la firma sobrevive al reinicio).
+        assert_eq!(first.active().jwk, second.active().jwk);
+        assert_eq!(first.active().kid, second.active().kid);
 
         std::fs::remove_dir_all(&dir).ok();
     }
+
+    #[test]
+    fn fingerprint_follows_the_published_keys() {
+        let (dir, p) = temp_key_path("fingerprint");
+        let first = load_or_create(&p, KeyAlg::EdDsa, LEGACY_TTL).unwrap();
+        let again = load_or_create(&p, KeyAlg::EdDsa, LEGACY_TTL).unwrap();
+        assert_eq!(first.fingerprint(), again.fingerprint(), "mismo anillo, misma huella");
+
+        rotate_now(&p, chrono::Duration::minutes(5), KeyAlg::EdDsa);
+        let rotated = load_or_create(&p, KeyAlg::EdDsa, LEGACY_TTL).unwrap();
+        assert_eq!(rotated.published().len(), 2);
+        assert_ne!(first.fingerprint(), rotated.fingerprint());
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn thumbprint_matches_rfc7638_example() {
+        // Ejemplo de la sección 3.1 de RFC 7638.
+        let key = PublicJwk::Rsa {
+            n: "0vx7agoebGcQSuuPiLJXZptN9nndrQmbXEps2aiAFbWhM78LhWx4cbbfAAtVT86zwu1RK7aPFFxuhDR1L6tSoc_BJECPebWKRXjBZCiFV4n3oknjhMstn64tZ_2W-5JsGY4Hc5n9yBXArwl93lqt7_RN5w6Cf0h4QyQ5v-65YGjQR0_FDW2QvzqY368QQMicAtaSqzs8KJZgnYb9c7d0zgdAZHzu6qMQvRL5hajrn1n91CbOpbISD08qNLyrdkt-bFTWhAI4vMQFh6WeZu0fM4lFd2NcRwr3XPksINHaQ-G_xBniIqbw0Ls1jF44-csFCur-kEgU8awapJzKnqDKgw".into(),
+            e: "AQAB".into(),
+        };
+        assert_eq!(thumbprint(&key), "NzbLsXh8uDCcd-6MNwXF4W_7noWXFZAfHkxZsRGC9Xs");
+    }
+
+    #[test]
+    fn rotate_keeps_previous_key_published_during_grace() {
+        let (dir, p) = temp_key_path("rotate");
+        let old_kid = load_or_create(&p, KeyAlg::Rs256, LEGACY_TTL).unwrap().active().kid.clone();
+
+        let new_kid = rotate_now(&p, chrono::Duration::minutes(60), KeyAlg::Rs256);
+        assert_ne!(new_kid, old_kid);
+
+        let ring = load_or_create(&p, KeyAlg::Rs256, LEGACY_TTL).unwrap();
+        assert_eq!(ring.active().kid, new_kid, "la nueva clave firma");
+        let old = ring.find(&old_kid).expect("la anterior sigue publicada");
+        assert_eq!(old.state, KeyState::Retiring);
+        assert_eq!(ring.published().len(), 2);
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn imported_key_answers_to_legacy_kid_for_a_while() {
+        let (dir, p) = temp_key_path("legacy");
+        // Clave de una versión previa al anillo: un PEM suelto, sin manifiesto.
+        persist(&KeyMaterial::generate(KeyAlg::Rs256).unwrap(), Path::new(&p)).unwrap();
+
+        let ring = load_or_create(&p, KeyAlg::Rs256, LEGACY_TTL).unwrap();
+        let legacy = ring.find(LEGACY_KID).expect("key1 resuelve a la importada");
+        assert_eq!(legacy.kid, ring.active().kid);
+
+        // Pasada la vida máxima de un JWT, `key1` deja de aceptarse.
+        let manifest: Manifest =
+            serde_json::from_str(&std::fs::read_to_string(dir.join(MANIFEST_FILE)).unwrap()).unwrap();
+        let later = load_keyring(&dir, &manifest, Utc::now() + LEGACY_TTL).unwrap();
+        assert!(later.find(LEGACY_KID).is_none());
+        assert!(later.find(&ring.active().kid).is_some());
+        assert_ne!(later.fingerprint(), ring.fingerprint(), "el JWKS cambia");
+
+        // Una clave generada de cero no tiene kid heredado.
+        let (fresh_dir, fresh) = temp_key_path("legacy-fresh");
+        let ring = load_or_create(&fresh, KeyAlg::Rs256, LEGACY_TTL).unwrap();
+        assert!(ring.find(LEGACY_KID).is_none());
+
+        std::fs::remove_dir_all(&dir).ok();
+        std::fs::remove_dir_all(&fresh_dir).ok();
+    }
+
+    #[test]
+    fn rotated_key_is_published_before_it_signs() {
+        let (dir, p) = temp_key_path("pending");
+        let old_kid = load_or_create(&p, KeyAlg::EdDsa, LEGACY_TTL).unwrap().active().kid.clone();
+
+        let (new_kid, activate_after) = rotate(
+            &p,
+            chrono::Duration::minutes(60),
+            KeyAlg::EdDsa,
+            chrono::Duration::minutes(6),
+        )
+        .unwrap();
+        let ring = reload(&p).unwrap();
+        assert_eq!(ring.active().kid, old_kid, "la anterior sigue firmando");
+        assert_eq!(ring.find(&new_kid).unwrap().state, KeyState::Pending);
+        assert_eq!(ring.published().len(), 2);
+        assert!(
+            rotate(&p, chrono::Duration::minutes(60), KeyAlg::EdDsa, chrono::Duration::zero()).is_err(),
+            "una rotación a la vez"
+        );
+
+        // Vencida la espera, la nueva firma y la anterior entra en retiro.
+        let manifest: Manifest =
+            serde_json::from_str(&std::fs::read_to_string(dir.join(MANIFEST_FILE)).unwrap()).unwrap();
+        let later = load_keyring(&dir, &manifest, activate_after).unwrap();
+        assert_eq!(later.active().kid, new_kid);
+        assert_eq!(later.find(&old_kid).unwrap().state, KeyState::Retiring);
+        assert_ne!(later.fingerprint(), ring.fingerprint());
+        // Y deja de publicarse una gracia después de la activación.
+        let gone = load_keyring(&dir, &manifest, activate_after + chrono::Duration::minutes(60)).unwrap();
+        assert!(gone.find(&old_kid).is_none());
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn retiring_key_is_dropped_after_grace() {
+        let (dir, p) = temp_key_path("grace");
+        let old_kid = load_or_create(&p, KeyAlg::Rs256, LEGACY_TTL).unwrap().active().kid.clone();
+
+        // Gracia nula: la anterior deja de publicarse de inmediato.
+        rotate_now(&p, chrono::Duration::zero(), KeyAlg::Rs256);
+        let ring = load_or_create(&p, KeyAlg::Rs256, LEGACY_TTL).unwrap();
+        assert!(ring.find(&old_kid).is_none());
+        assert_eq!(ring.published().len(), 1);
+
+        // La siguiente rotación la marca como retirada en el manifiesto.
+        rotate_now(&p, chrono::Duration::minutes(60), KeyAlg::Rs256);
+        let manifest: Manifest =
+            serde_json::from_str(&std::fs::read_to_string(dir.join(MANIFEST_FILE)).unwrap()).unwrap();
+        let old = manifest.keys.iter().find(|e| e.kid == old_kid).unwrap();
+        assert_eq!(old.state, KeyState::Retired);
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn reload_picks_up_rotation_and_expired_grace() {
+        let (dir, p) = temp_key_path("reload");
+        assert!(reload(&p).is_err(), "reload no genera claves");
+        let old_kid = load_or_create(&p, KeyAlg::EdDsa, LEGACY_TTL).unwrap().active().kid.clone();
+
+        // Rotación hecha por otro proceso: el anillo recargado ya firma con la
+        // nueva y, con la gracia vencida, deja de publicar la anterior.
+        let new_kid = rotate_now(&p, chrono::Duration::zero(), KeyAlg::EdDsa);
+        let ring = reload(&p).unwrap();
+        assert_eq!(ring.active().kid, new_kid);
+        assert!(ring.find(&old_kid).is_none());
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    /// Firma y valida un token con la clave activa del anillo.
+    fn sign_and_verify(ring: &Keyring) {
+        let key = ring.active();
+        let mut header = jsonwebtoken::Header::new(key.alg.algorithm());
+        header.kid = Some(key.kid.clone());
+        let claims = serde_json::json!({"sub": "sigac", "exp": Utc::now().timestamp() + 60});
+        let token = jsonwebtoken::encode(&header, &claims, &key.encoding_key).unwrap();
+        let validation = jsonwebtoken::Validation::new(key.alg.algorithm());
+        jsonwebtoken::decode::<serde_json::Value>(&token, &key.decoding_key, &validation).unwrap();
+    }
+
+    #[test]
+    fn every_algorithm_persists_and_signs() {
+        for alg in [KeyAlg::Rs256, KeyAlg::Ps256, KeyAlg::Es256, KeyAlg::EdDsa] {
+            let (dir, p) = temp_key_path(alg.as_str());
+            let first = load_or_create(&p, alg, LEGACY_TTL).unwrap();
+            assert_eq!(first.active().alg, alg);
+            let second = load_or_create(&p, alg, LEGACY_TTL).unwrap();
+            assert_eq!(first.active().kid, second.active().kid, "{alg}: misma clave");
+            assert_eq!(thumbprint(&second.active().jwk), second.active().kid);
+            sign_and_verify(&second);
+            std::fs::remove_dir_all(&dir).ok();
+        }
+    }
+
+    #[test]
+    fn jwk_shape_matches_key_type() {
+        let (dir, p) = temp_key_path("shape");
+        let ring = load_or_create(&p, KeyAlg::Es256, LEGACY_TTL).unwrap();
+        assert!(matches!(&ring.active().jwk, PublicJwk::Ec { x, y } if x.len() == 43 && y.len() == 43));
+        assert_eq!(ring.active().jwk.crv(), Some("P-256"));
+
+        rotate_now(&p, chrono::Duration::minutes(60), KeyAlg::EdDsa);
+        let ring = load_or_create(&p, KeyAlg::EdDsa, LEGACY_TTL).unwrap();
+        assert!(matches!(&ring.active().jwk, PublicJwk::Okp { x } if x.len() == 43));
+        assert_eq!(ring.active().jwk.kty(), "OKP");
+        // La anterior (ES256) sigue publicada con su propio algoritmo.
+        assert_eq!(ring.published()[1].alg, KeyAlg::Es256);
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn legacy_rsa_key_is_imported_as_rs256() {
+        let (dir, p) = temp_key_path("legacy");
+        std::fs::create_dir_all(&dir).unwrap();
+        persist(&KeyMaterial::generate(KeyAlg::Rs256).unwrap(), Path::new(&p)).unwrap();
+
+        // Sin manifiesto y con JWT_ALGORITHM=ES256: la RSA histórica sigue activa.
+        let ring = load_or_create(&p, KeyAlg::Es256, LEGACY_TTL).unwrap();
+        assert_eq!(ring.active().alg, KeyAlg::Rs256);
+        let manifest = std::fs::read_to_string(dir.join(MANIFEST_FILE)).unwrap();
+        assert!(manifest.contains(r#""alg": "RS256""#));
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn key_alg_parses_jose_names() {
+        assert_eq!("ES256".parse::<KeyAlg>().unwrap(), KeyAlg::Es256);
+        assert_eq!("eddsa".parse::<KeyAlg>().unwrap(), KeyAlg::EdDsa);
+        assert!("HS256".parse::<KeyAlg>().is_err());
+    }
 }
@@ -1,20 +1,23 @@
 //! Backend de autenticación JWT para MediaMTX
 //!
-//! Este servicio genera tokens JWT firmados con RS256 y expone un JWKS
+//! Este servicio genera tokens JWT firmados (RS256 por defecto; PS256, ES256
+//! o EdDSA según `JWT_ALGORITHM`) y expone un JWKS
 //! para que MediaMTX pueda validar los tokens.
 
 use axum::{
-    extract::State,
-    http::StatusCode,
+    extract::{ConnectInfo, State},
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
     routing::{get, post},
     Json, Router,
 };
-use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
-use jsonwebtoken::{encode, Algorithm, DecodingKey, EncodingKey, Header};
-use rsa::RsaPublicKey;
+use jsonwebtoken::{encode, Header};
 use serde::{Deserialize, Serialize};
 use sqlx::PgPool;
-use std::{env, sync::Arc};
+use std::{
+    env,
+    sync::{Arc, RwLock},
+};
 use time::{Duration, OffsetDateTime};
 use tracing::{info, warn};
 use utoipa::openapi::security::{HttpAuthScheme, HttpBuilder, SecurityScheme};
@@ -31,12 +34,28 @@ mod infra;
 mod keys;
 mod secret;
 mod services;
+#[cfg(test)]
+mod test_support;
 
-use domain::ports::{CameraProvisioner, CameraRepo, FailureRepo, ProjectRepo};
+use domain::ports::{
+    AdminAccountRepo, ApiKeyRepo, AuditRepo, CameraGroupRepo, CameraProvisioner, CameraRepo,
+    FailureRepo, IdpMappingRepo, LoginThrottleRepo, ProjectRepo, ProjectSecretRepo, RefreshTokenRepo, RevocationRepo,
+};
+use infra::idp_jwks::ExternalJwks;
 use infra::mediamtx::MediaMtxProvisioner;
-use infra::postgres::{PgCameraRepo, PgFailureRepo, PgProjectRepo};
+use infra::postgres::{
+    PgAdminAccountRepo, PgApiKeyRepo, PgAuditRepo, PgCameraGroupRepo, PgCameraRepo,
+    PgFailureRepo, PgIdpMappingRepo, PgLoginThrottleRepo, PgProjectRepo, PgProjectSecretRepo, PgRefreshTokenRepo,
+    PgRevocationRepo,
+};
+use domain::models::{CameraActions, PublishGrant};
+use services::admin_auth::AdminAuthService;
 use services::auth::{AuthService, CameraAccess};
+use services::lockout::{LockoutPolicy, LockoutService, LoginError};
 use services::reconciler::ReconcilerService;
+use services::refresh::RefreshService;
+use services::revocation::RevocationService;
+use services::token_exchange::{ExternalIdpPolicy, TokenExchangeService};
 
 // ============================================================================
 // Configuración
@@ -47,10 +66,25 @@ use services::reconciler::ReconcilerService;
 struct Config {
     /// Puerto del servidor HTTP
     server_port: u16,
-    /// Minutos de expiración del JWT
+    /// Minutos de expiración del JWT (proyectos sin política propia)
     jwt_exp_minutes: i64,
+    /// Techo de vida de cualquier JWT, también con política por proyecto.
+    /// Acota la gracia de rotación de claves y la retención de revocaciones.
+    jwt_max_exp_minutes: i64,
+    /// Vida por defecto de los tokens de visor (`/auth/viewer-token`), en minutos
+    viewer_token_exp_minutes: i64,
+    /// Minutos de vida de cada refresh token (0 = refresh tokens deshabilitados)
+    refresh_token_exp_minutes: i64,
     /// Ruta del archivo de la clave privada RSA (volumen persistente)
     jwt_private_key_path: String,
+    /// Algoritmo de las claves de firma nuevas (se aplica al generar o rotar)
+    jwt_algorithm: keys::KeyAlg,
+    /// `iss` de los JWT emitidos y exigido al validarlos
+    jwt_issuer: String,
+    /// `aud` del despliegue: se agrega a todo JWT y se exige al validarlo
+    jwt_audience: Option<String>,
+    /// Tolerancia de reloj (exp/nbf/iat) al validar, en segundos
+    jwt_leeway_secs: u64,
     /// Ruta del archivo JSON con credenciales (solo para el subcomando
     /// `migrate-clients`; la autenticación en runtime ya usa la BD).
     #[allow(dead_code)]
@@ -63,8 +97,78 @@ struct Config {
     mediamtx_api_url: String,
     /// Intervalo del reconcile periódico, en segundos
     reconcile_interval_secs: u64,
-    /// Token bearer para los endpoints de administración (secreto → se redacta)
+    /// Token bearer de emergencia para la administración, con rol admin; el
+    /// uso diario va con cuentas (`/admin/login`). Secreto → se redacta
     admin_api_token: String,
+    /// Minutos de vida de una sesión de administración
+    admin_session_minutes: i64,
+    /// Cada cuántos segundos se recarga la denylist de tokens revocados
+    revocation_refresh_secs: u64,
+    /// `MEDIAMTX_AUTH_MODE=http`: MediaMTX consulta `/mediamtx/auth` en cada
+    /// acceso en lugar de validar el JWT solo contra `/jwks`
+    mediamtx_http_auth: bool,
+    /// Segundos que se cachea cada decisión de `/mediamtx/auth` (0 = sin caché)
+    mediamtx_auth_cache_secs: u64,
+    /// URLs públicas de MediaMTX para `/cameras/{id}/playback`
+    public_urls: PublicUrls,
+    /// Umbrales y duraciones del bloqueo por fuerza bruta en el login
+    lockout: LockoutPolicy,
+    /// Proxies cuyo `X-Forwarded-For` se acepta para la IP del cliente
+    trusted_proxies: Vec<ipnet::IpNet>,
+    /// Parámetros de Argon2id para hashear secretos (y rehashear los viejos)
+    hash_policy: secret::HashPolicy,
+    /// `max-age` de `/jwks` y del documento de descubrimiento, en segundos
+    jwks_cache_secs: u64,
+    /// URL pública del backend para el documento de descubrimiento
+    public_base_url: Option<String>,
+    /// IdP corporativo cuyos JWT se canjean en `/oauth/token` (RFC 8693);
+    /// `None` = canje deshabilitado
+    external_idp: Option<ExternalIdpConfig>,
+    /// Zona horaria (IANA) de los horarios semanales de las asignaciones
+    schedule_timezone: String,
+}
+
+/// IdP externo para el canje de tokens (`EXTERNAL_IDP_*`).
+#[derive(Debug, Clone)]
+struct ExternalIdpConfig {
+    /// URL (`http(s)://`) o ruta de archivo del JWKS del IdP
+    jwks: String,
+    /// Emisor, audiencia y recarga de claves exigidos a sus tokens
+    policy: ExternalIdpPolicy,
+}
+
+/// URLs base públicas de MediaMTX por protocolo (sin `/` final). Un protocolo
+/// sin URL configurada no se ofrece en `/cameras/{id}/playback`.
+#[derive(Debug, Clone, Default)]
+struct PublicUrls {
+    /// HLS (p.ej. `https://media.example.com`, detrás de Caddy)
+    hls: Option<String>,
+    /// LL-HLS: MediaMTX con `hlsVariant: lowLatency`
+    ll_hls: Option<String>,
+    /// WebRTC / WHEP (p.ej. `https://media.example.com:8889`)
+    webrtc: Option<String>,
+    /// RTSP (p.ej. `rtsp://media.example.com:8554`)
+    rtsp: Option<String>,
+    /// Servidor de playback de grabaciones de MediaMTX
+    playback: Option<String>,
+}
+
+impl PublicUrls {
+    fn from_env() -> Self {
+        let var = |name: &str| {
+            env::var(name)
+                .ok()
+                .map(|v| v.trim().trim_end_matches('/').to_string())
+                .filter(|v| !v.is_empty())
+        };
+        Self {
+            hls: var("PUBLIC_HLS_URL"),
+            ll_hls: var("PUBLIC_LLHLS_URL"),
+            webrtc: var("PUBLIC_WEBRTC_URL"),
+            rtsp: var("PUBLIC_RTSP_URL"),
+            playback: var("PUBLIC_PLAYBACK_URL"),
+        }
+    }
 }
 
 /// `Debug` manual: NUNCA imprime la cadena de conexión ni la clave de cifrado.
@@ -73,20 +177,40 @@ impl std::fmt::Debug for Config {
         f.debug_struct("Config")
             .field("server_port", &self.server_port)
             .field("jwt_exp_minutes", &self.jwt_exp_minutes)
+            .field("jwt_max_exp_minutes", &self.jwt_max_exp_minutes)
+            .field("viewer_token_exp_minutes", &self.viewer_token_exp_minutes)
+            .field("refresh_token_exp_minutes", &self.refresh_token_exp_minutes)
             .field("jwt_private_key_path", &self.jwt_private_key_path)
+            .field("jwt_algorithm", &self.jwt_algorithm)
+            .field("jwt_issuer", &self.jwt_issuer)
+            .field("jwt_audience", &self.jwt_audience)
+            .field("jwt_leeway_secs", &self.jwt_leeway_secs)
             .field("clients_path", &self.clients_path)
             .field("database_url", &"<redactado>")
             .field("db_encryption_key", &"<redactado>")
             .field("mediamtx_api_url", &self.mediamtx_api_url)
             .field("reconcile_interval_secs", &self.reconcile_interval_secs)
             .field("admin_api_token", &"<redactado>")
+            .field("admin_session_minutes", &self.admin_session_minutes)
+            .field("revocation_refresh_secs", &self.revocation_refresh_secs)
+            .field("mediamtx_http_auth", &self.mediamtx_http_auth)
+            .field("mediamtx_auth_cache_secs", &self.mediamtx_auth_cache_secs)
+            .field("public_urls", &self.public_urls)
+            .field("lockout", &self.lockout)
+            .field("trusted_proxies", &self.trusted_proxies)
+            .field("hash_policy", &self.hash_policy)
+            .field("jwks_cache_secs", &self.jwks_cache_secs)
+            .field("public_base_url", &self.public_base_url)
+            .field("external_idp", &self.external_idp)
+            .field("schedule_timezone", &self.schedule_timezone)
             .finish()
     }
 }
 
 impl Config {
-    /// Carga la configuración desde variables de entorno con valores por defecto
-    fn from_env() -> Self {
+    /// Carga la configuración desde variables de entorno con valores por defecto.
+    /// Falla si una variable que decide el material de firma es inválida.
+    fn from_env() -> Result<Self, String> {
         let server_port = env::var("SERVER_PORT")
             .ok()
             .and_then(|v| v.parse().ok())
@@ -97,9 +221,42 @@ impl Config {
             .and_then(|v| v.parse().ok())
             .unwrap_or(60);
 
+        let jwt_max_exp_minutes = env::var("JWT_MAX_EXP_MINUTES")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(1440)
+            .max(jwt_exp_minutes);
+
+        let viewer_token_exp_minutes = env::var("VIEWER_TOKEN_EXP_MINUTES")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(5);
+
+        let refresh_token_exp_minutes = env::var("REFRESH_TOKEN_EXP_MINUTES")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(43200);
+
         let jwt_private_key_path = env::var("JWT_PRIVATE_KEY_PATH")
             .unwrap_or_else(|_| "/keys/jwt_private_key.pem".to_string());
 
+        let jwt_algorithm = parse_jwt_algorithm(env::var("JWT_ALGORITHM").ok().as_deref())?;
+
+        // Emisor y audiencia del despliegue: un token de otro despliegue que
+        // comparta claves no valida aquí.
+        let jwt_issuer = env::var("JWT_ISSUER")
+            .ok()
+            .filter(|v| !v.is_empty())
+            .unwrap_or_else(|| "mediamtx-auth-backend".to_string());
+        let jwt_audience = match env::var("JWT_AUDIENCE") {
+            Ok(v) => Some(v).filter(|v| !v.is_empty()),
+            Err(_) => Some("mediamtx".to_string()),
+        };
+        let jwt_leeway_secs = env::var("JWT_LEEWAY_SECS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(30);
+
         let clients_path = env::var("CLIENTS_PATH")
             .unwrap_or_else(|_| "/config/clients.json".to_string());
 
@@ -117,21 +274,174 @@ impl Config {
             .unwrap_or(300);
 
         let admin_api_token = env::var("ADMIN_API_TOKEN").unwrap_or_default();
+        let admin_session_minutes = env::var("ADMIN_SESSION_MINUTES")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .filter(|&m: &i64| m > 0)
+            .unwrap_or(480);
 
-        Self {
+        let revocation_refresh_secs = env::var("REVOCATION_REFRESH_SECS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .filter(|&s: &u64| s > 0)
+            .unwrap_or(5);
+
+        let mediamtx_http_auth = match env::var("MEDIAMTX_AUTH_MODE").as_deref() {
+            Ok("http") => true,
+            Ok("jwt") | Err(_) => false,
+            Ok(other) => {
+                warn!("MEDIAMTX_AUTH_MODE desconocido '{}', uso 'jwt'", other);
+                false
+            }
+        };
+
+        let mediamtx_auth_cache_secs = env::var("MEDIAMTX_AUTH_CACHE_SECS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(5);
+
+        // Fuerza bruta en el login: umbral por client_id y por IP (0 = sin
+        // límite), ventana de olvido y bloqueo inicial/máximo.
+        let defaults = LockoutPolicy::default();
+        let secs = |name: &str, default: chrono::Duration| {
+            env::var(name)
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .map(chrono::Duration::seconds)
+                .unwrap_or(default)
+        };
+        let lockout = LockoutPolicy {
+            max_failures_client: env::var("LOGIN_MAX_FAILURES")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(defaults.max_failures_client),
+            max_failures_ip: env::var("LOGIN_MAX_FAILURES_PER_IP")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(defaults.max_failures_ip),
+            window: secs("LOGIN_FAILURE_WINDOW_SECS", defaults.window),
+            base_lock: secs("LOGIN_LOCKOUT_SECS", defaults.base_lock),
+            max_lock: secs("LOGIN_LOCKOUT_MAX_SECS", defaults.max_lock),
+        };
+
+        let trusted_proxies =
+            http::client_ip::parse_trusted(&env::var("TRUSTED_PROXIES").unwrap_or_default());
+
+        // Los verificadores pueden cachear el JWKS este tiempo: una clave rotada
+        // se publica al menos esto (más la recarga del anillo) antes de firmar.
+        let jwks_cache_secs = env::var("JWKS_CACHE_SECS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(300);
+        let public_base_url = env::var("PUBLIC_BASE_URL")
+            .ok()
+            .map(|v| v.trim().trim_end_matches('/').to_string())
+            .filter(|v| !v.is_empty());
+
+        // Hora local de los horarios de las asignaciones (p.ej. America/Santiago).
+        let schedule_timezone = env::var("SCHEDULE_TIMEZONE")
+            .ok()
+            .map(|v| v.trim().to_string())
+            .filter(|v| !v.is_empty())
+            .unwrap_or_else(|| "UTC".to_string());
+
+        Ok(Self {
             server_port,
             jwt_exp_minutes,
+            jwt_max_exp_minutes,
+            viewer_token_exp_minutes,
+            refresh_token_exp_minutes,
             jwt_private_key_path,
+            jwt_algorithm,
+            jwt_issuer,
+            jwt_audience,
+            jwt_leeway_secs,
             clients_path,
             database_url,
             db_encryption_key,
             mediamtx_api_url,
             reconcile_interval_secs,
             admin_api_token,
+            admin_session_minutes,
+            revocation_refresh_secs,
+            mediamtx_http_auth,
+            mediamtx_auth_cache_secs,
+            public_urls: PublicUrls::from_env(),
+            lockout,
+            trusted_proxies,
+            hash_policy: hash_policy_from_env(),
+            jwks_cache_secs,
+            public_base_url,
+            external_idp: external_idp_from_env(jwt_leeway_secs),
+            schedule_timezone,
+        })
+    }
+}
+
+/// Algoritmo de `JWT_ALGORITHM`; solo sin valor vale el default (RS256). Uno
+/// inválido es un error: si no, una rotación crearía una clave RS256 que nadie
+/// pidió.
+fn parse_jwt_algorithm(value: Option<&str>) -> Result<keys::KeyAlg, String> {
+    match value.map(str::trim).filter(|v| !v.is_empty()) {
+        Some(v) => v.parse().map_err(|e| format!("JWT_ALGORITHM: {e}")),
+        None => Ok(keys::KeyAlg::default()),
+    }
+}
+
+/// Parámetros de Argon2id (`ARGON2_MEMORY_KIB`, `ARGON2_ITERATIONS`,
+/// `ARGON2_PARALLELISM`). Una combinación inválida vuelve a los valores por
+/// defecto: mejor hashear con parámetros conocidos que no poder dar de alta.
+fn hash_policy_from_env() -> secret::HashPolicy {
+    let defaults = secret::HashPolicy::default();
+    let var = |name: &str, default: u32| {
+        env::var(name)
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(default)
+    };
+    let policy = secret::HashPolicy {
+        memory_kib: var("ARGON2_MEMORY_KIB", defaults.memory_kib),
+        iterations: var("ARGON2_ITERATIONS", defaults.iterations),
+        parallelism: var("ARGON2_PARALLELISM", defaults.parallelism),
+    };
+    match policy.params() {
+        Ok(_) => policy,
+        Err(e) => {
+            warn!("{}; se usan los valores por defecto", e);
+            defaults
         }
     }
 }
 
+/// IdP externo (`EXTERNAL_IDP_ISSUER`, `EXTERNAL_IDP_JWKS`,
+/// `EXTERNAL_IDP_AUDIENCE`, `EXTERNAL_IDP_JWKS_REFRESH_SECS`). Sin emisor no
+/// hay canje; con emisor pero sin JWKS tampoco (no habría cómo verificar).
+fn external_idp_from_env(leeway: u64) -> Option<ExternalIdpConfig> {
+    let var = |name: &str| {
+        env::var(name)
+            .ok()
+            .map(|v| v.trim().to_string())
+            .filter(|v| !v.is_empty())
+    };
+    let issuer = var("EXTERNAL_IDP_ISSUER")?;
+    let Some(jwks) = var("EXTERNAL_IDP_JWKS") else {
+        warn!("EXTERNAL_IDP_ISSUER sin EXTERNAL_IDP_JWKS: canje de tokens deshabilitado");
+        return None;
+    };
+    let refresh_secs = var("EXTERNAL_IDP_JWKS_REFRESH_SECS")
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(3600);
+    Some(ExternalIdpConfig {
+        jwks,
+        policy: ExternalIdpPolicy {
+            issuer,
+            audience: var("EXTERNAL_IDP_AUDIENCE"),
+            leeway,
+            jwks_refresh: std::time::Duration::from_secs(refresh_secs),
+        },
+    })
+}
+
 // ============================================================================
 // Modelos de Claims para JWT
 // ============================================================================
@@ -163,12 +473,45 @@ struct MtxPermission {
 #[derive(Debug, Serialize, Deserialize, ToSchema)]
 struct Claims {
     /// Subject identifier (the project / client id)
+    #[serde(default)]
     #[schema(example = "sigac")]
     sub: String,
     
+    /// Issuer: the deployment that minted the token (`JWT_ISSUER`).
+    #[serde(default)]
+    #[schema(example = "https://media.example.com")]
+    iss: String,
+
     /// Token expiration time as Unix timestamp (seconds since epoch)
     #[schema(example = 1733817600)]
     exp: i64,
+
+    /// Audiences: the deployment audience (`JWT_AUDIENCE`) plus the project's
+    /// `allowed_audiences`. Omitted when both are empty.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    #[schema(example = json!(["mediamtx", "odin-portal"]))]
+    aud: Vec<String>,
+
+    /// Issued-at time as Unix timestamp. Revocations "issued before" a cutoff
+    /// compare against it.
+    #[serde(default)]
+    #[schema(example = 1733814000)]
+    iat: i64,
+
+    /// Not-before time as Unix timestamp (equal to `iat`).
+    #[serde(default)]
+    #[schema(example = 1733814000)]
+    nbf: i64,
+
+    /// Unique token identifier (UUID v4), used to revoke a single token.
+    #[serde(default)]
+    #[schema(example = "6f1c2b9e-8a51-4c1e-9d5e-2f0b7a3c4d10")]
+    jti: String,
+
+    /// `true` for down-scoped viewer tokens (`/auth/viewer-token`). A viewer
+    /// token cannot mint further tokens. Omitted for project tokens.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    viewer: bool,
     
     /// Array of MediaMTX permissions granted to this token.
     /// MediaMTX reads this claim to determine stream access rights.
@@ -179,13 +522,15 @@ struct Claims {
 // JWKS (JSON Web Key Set)
 // ============================================================================
 
-/// JSON Web Key (JWK) representing a public RSA key.
+/// JSON Web Key (JWK) representing a public signing key.
 /// 
-/// This structure follows the JWK specification (RFC 7517) and contains
-/// the public key components needed to verify RS256 JWT signatures.
+/// This structure follows the JWK specification (RFC 7517/8037) and contains
+/// the public key components needed to verify JWT signatures: `n`/`e` for
+/// RSA keys (RS256/PS256), `crv`/`x`/`y` for EC keys (ES256) and `crv`/`x`
+/// for OKP keys (EdDSA).
 #[derive(Debug, Serialize, Clone, ToSchema)]
 struct Jwk {
-    /// Key type. Always "RSA" for this implementation.
+    /// Key type: "RSA", "EC" or "OKP".
     #[schema(example = "RSA")]
     kty: String,
     
@@ -198,17 +543,32 @@ struct Jwk {
     #[schema(example = "RS256")]
     alg: String,
     
-    /// Key ID. Used to match specific keys when multiple are present.
-    #[schema(example = "key1")]
+    /// Key ID: RFC 7638 thumbprint of the key. MediaMTX matches it against
+    /// the `kid` header of each JWT to pick the verification key.
+    #[schema(example = "NzbLsXh8uDCcd-6MNwXF4W_7noWXFZAfHkxZsRGC9Xs")]
     kid: String,
     
     /// RSA public key modulus (Base64 URL-encoded)
+    #[serde(skip_serializing_if = "Option::is_none")]
     #[schema(example = "0vx7agoebGcQSuuPiLJXZptN9nndrQmbXEps2aiAFbWhM78LhWx4cbbfAAtVT86zwu1RK7aPFFxuhDR1L6tSoc_BJECPebWKRXjBZCiFV4n3oknjhMstn64tZ_2W-5JsGY4Hc5n9yBXArwl93lqt7_RN5w6Cf0h4QyQ5v-65YGjQR0_FDW2QvzqY368QQMicAtaSqzs8KJZgnYb9c7d0zgdAZHzu6qMQvRL5hajrn1n91CbOpbISD08qNLyrdkt-bFTWhAI4vMQFh6WeZu0fM4lFd2NcRwr3XPksINHaQ-G_xBniIqbw0Ls1jF44-csFCur-kEgU8awapJzKnqDKgw")]
-    n: String,
+    n: Option<String>,
     
     /// RSA public key exponent (Base64 URL-encoded)
+    #[serde(skip_serializing_if = "Option::is_none")]
     #[schema(example = "AQAB")]
-    e: String,
+    e: Option<String>,
+
+    /// Curve of EC/OKP keys: "P-256" or "Ed25519".
+    #[serde(skip_serializing_if = "Option::is_none")]
+    crv: Option<String>,
+
+    /// EC x coordinate, or the OKP public key (Base64 URL-encoded)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    x: Option<String>,
+
+    /// EC y coordinate (Base64 URL-encoded)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    y: Option<String>,
 }
 
 /// JSON Web Key Set (JWKS) containing public keys for JWT verification.
@@ -226,37 +586,81 @@ struct Jwks {
 // Estado de la aplicación
 // ============================================================================
 
+/// Anillo de firma con su JWKS y `ETag` derivados. Se reemplaza entero al
+/// recargar, así una petición nunca mezcla el anillo viejo con el JWKS nuevo.
+struct SigningKeys {
+    /// Anillo de claves de firma: la activa firma; activa + en retiro validan
+    keyring: keys::Keyring,
+    /// JWKS preconstruido en memoria (todas las claves publicadas)
+    jwks: Jwks,
+    /// `ETag` del JWKS: huella del anillo (cambia al rotar o al vencer una gracia)
+    jwks_etag: String,
+}
+
+impl SigningKeys {
+    fn new(keyring: keys::Keyring) -> Self {
+        let jwks = AppState::build_jwks(&keyring);
+        let jwks_etag = format!("\"{}\"", keyring.fingerprint());
+        Self {
+            keyring,
+            jwks,
+            jwks_etag,
+        }
+    }
+}
+
 /// Estado global compartido entre handlers
 struct AppState {
-    /// Clave para firmar JWT (RS256)
-    encoding_key: EncodingKey,
-    /// Clave para validar JWT (RS256); se usa en GET /cameras (HU 4.7)
-    decoding_key: DecodingKey,
-    /// JWKS preconstruido en memoria
-    jwks: Jwks,
+    /// Claves de firma vigentes; `spawn_keyring_reload` las renueva
+    signing: RwLock<Arc<SigningKeys>>,
     /// Autenticación de proyectos contra la BD (HU 4.3)
     auth: Arc<AuthService>,
+    /// Refresh tokens rotatorios (renovación sin reenviar el secreto)
+    refresh: Arc<RefreshService>,
+    /// Denylist de JWT revocados (por jti, por proyecto o global)
+    revocations: Arc<RevocationService>,
+    /// Bloqueo por fuerza bruta en el login (por client_id y por IP)
+    lockout: Arc<LockoutService>,
+    /// Cuentas de administración: login, sesiones y auditoría de acciones
+    admin_auth: Arc<AdminAuthService>,
+    /// Canje de JWT del IdP externo (`None` si no está configurado)
+    token_exchange: Option<Arc<TokenExchangeService>>,
+    /// Caché de decisiones del callback de MediaMTX (`authMethod: http`)
+    mtx_auth_cache: http::mediamtx::DecisionCache,
     /// Configuración
     config: Config,
     /// Repositorios (puertos) respaldados por Postgres (HU 4.1).
     /// Aún sin consumir por los handlers; se usan desde HU 4.2+.
     project_repo: Arc<dyn ProjectRepo>,
+    secret_repo: Arc<dyn ProjectSecretRepo>,
     camera_repo: Arc<dyn CameraRepo>,
+    camera_group_repo: Arc<dyn CameraGroupRepo>,
     failure_repo: Arc<dyn FailureRepo>,
+    idp_mapping_repo: Arc<dyn IdpMappingRepo>,
+    admin_account_repo: Arc<dyn AdminAccountRepo>,
+    api_key_repo: Arc<dyn ApiKeyRepo>,
     /// Reconciler BD → MediaMTX (HU 4.2). Lo usa la tarea de arranque y, en
     /// HU 4.5, los endpoints de administración para sync puntual.
     reconciler: Arc<ReconcilerService>,
 }
 
 impl AppState {
-    /// Crea un nuevo AppState generando un par de claves RSA
+    /// Crea un nuevo AppState cargando el anillo de claves de firma
     fn new(config: Config, db: PgPool) -> Result<Self, Box<dyn std::error::Error>> {
-        // Carga la clave de firma persistente (o la genera y guarda la 1a vez).
-        let material = keys::load_or_create(&config.jwt_private_key_path)?;
-
-        // Construir JWKS desde la clave pública
-        let jwks = Self::build_jwks(&material.public_key)?;
-        info!("JWKS construido con kid='key1'");
+        // Carga el anillo persistente (o genera y guarda la 1a clave).
+        let keyring = keys::load_or_create(
+            &config.jwt_private_key_path,
+            config.jwt_algorithm,
+            chrono::Duration::minutes(config.jwt_max_exp_minutes),
+        )?;
+
+        // Construir JWKS desde las claves públicas publicadas
+        let signing = SigningKeys::new(keyring);
+        info!(
+            "JWKS construido con {} clave(s), ETag {}",
+            signing.jwks.keys.len(),
+            signing.jwks_etag
+        );
 
         // Cifrador de credenciales de cámara en reposo (fail-closed: sin clave
         // válida no arrancamos; no podríamos almacenar cámaras de forma segura).
@@ -264,12 +668,77 @@ impl AppState {
         info!("Cifrado en reposo inicializado (AES-256-GCM)");
 
         // Repositorios (adaptadores Postgres) detrás de los puertos del dominio.
-        let project_repo: Arc<dyn ProjectRepo> = Arc::new(PgProjectRepo::new(db.clone()));
+        let project_repo: Arc<dyn ProjectRepo> = Arc::new(
+            PgProjectRepo::new(db.clone()).with_schedule_timezone(&config.schedule_timezone),
+        );
+        let secret_repo: Arc<dyn ProjectSecretRepo> =
+            Arc::new(PgProjectSecretRepo::new(db.clone()));
         let camera_repo: Arc<dyn CameraRepo> = Arc::new(PgCameraRepo::new(db.clone(), cipher));
-        let failure_repo: Arc<dyn FailureRepo> = Arc::new(PgFailureRepo::new(db));
+        let camera_group_repo: Arc<dyn CameraGroupRepo> =
+            Arc::new(PgCameraGroupRepo::new(db.clone()));
+        let failure_repo: Arc<dyn FailureRepo> = Arc::new(PgFailureRepo::new(db.clone()));
+        let refresh_repo: Arc<dyn RefreshTokenRepo> =
+            Arc::new(PgRefreshTokenRepo::new(db.clone()));
+        let revocation_repo: Arc<dyn RevocationRepo> =
+            Arc::new(PgRevocationRepo::new(db.clone()));
+        let throttle_repo: Arc<dyn LoginThrottleRepo> =
+            Arc::new(PgLoginThrottleRepo::new(db.clone()));
+        let idp_mapping_repo: Arc<dyn IdpMappingRepo> =
+            Arc::new(PgIdpMappingRepo::new(db.clone()));
+        let admin_account_repo: Arc<dyn AdminAccountRepo> =
+            Arc::new(PgAdminAccountRepo::new(db.clone()));
+        let api_key_repo: Arc<dyn ApiKeyRepo> = Arc::new(PgApiKeyRepo::new(db.clone()));
+        let audit_repo: Arc<dyn AuditRepo> = Arc::new(PgAuditRepo::new(db));
 
         // Autenticación de proyectos contra la BD (HU 4.3).
-        let auth = Arc::new(AuthService::new(project_repo.clone()));
+        let auth = Arc::new(AuthService::new(
+            project_repo.clone(),
+            secret_repo.clone(),
+            config.hash_policy,
+        ));
+
+        // Refresh tokens (0 minutos = deshabilitados).
+        let refresh_ttl = (config.refresh_token_exp_minutes > 0)
+            .then(|| chrono::Duration::minutes(config.refresh_token_exp_minutes));
+        let refresh = Arc::new(RefreshService::new(
+            refresh_repo,
+            project_repo.clone(),
+            refresh_ttl,
+        ));
+
+        // Denylist: una revocación caduca cuando ya no queda vivo ningún JWT
+        // que pueda afectar (su vida máxima).
+        let revocations = Arc::new(RevocationService::new(
+            revocation_repo,
+            chrono::Duration::minutes(config.jwt_max_exp_minutes),
+        ));
+
+        // Contadores de fallos de login y auditoría.
+        let lockout = Arc::new(LockoutService::new(
+            throttle_repo,
+            audit_repo.clone(),
+            config.lockout.clone(),
+        ));
+
+        // Cuentas de administración (mismo bloqueo por fuerza bruta y auditoría).
+        let admin_auth = Arc::new(AdminAuthService::new(
+            admin_account_repo.clone(),
+            audit_repo,
+            lockout.clone(),
+            config.hash_policy,
+            chrono::Duration::minutes(config.admin_session_minutes),
+        ));
+
+        // Canje de tokens del IdP corporativo (RFC 8693), si está configurado.
+        let token_exchange = config.external_idp.as_ref().map(|idp| {
+            info!("Canje de tokens habilitado para el emisor {}", idp.policy.issuer);
+            Arc::new(TokenExchangeService::new(
+                Arc::new(ExternalJwks::new(&idp.jwks)),
+                idp_mapping_repo.clone(),
+                project_repo.clone(),
+                idp.policy.clone(),
+            ))
+        });
 
         // Reconciler BD → MediaMTX (HU 4.2).
         let provisioner: Arc<dyn CameraProvisioner> =
@@ -277,91 +746,241 @@ impl AppState {
         let reconciler = Arc::new(ReconcilerService::new(camera_repo.clone(), provisioner));
 
         Ok(Self {
-            encoding_key: material.encoding_key,
-            decoding_key: material.decoding_key,
-            jwks,
+            signing: RwLock::new(Arc::new(signing)),
             auth,
+            refresh,
+            revocations,
+            lockout,
+            admin_auth,
+            token_exchange,
+            mtx_auth_cache: http::mediamtx::DecisionCache::new(config.mediamtx_auth_cache_secs),
             config,
             project_repo,
+            secret_repo,
             camera_repo,
+            camera_group_repo,
             failure_repo,
+            idp_mapping_repo,
+            admin_account_repo,
+            api_key_repo,
             reconciler,
         })
     }
 
-    /// Construye el JWKS a partir de la clave pública RSA
-    fn build_jwks(public_key: &RsaPublicKey) -> Result<Jwks, Box<dyn std::error::Error>> {
-        // Obtener los componentes n y e de la clave pública
-        use rsa::traits::PublicKeyParts;
-        
-        let n_bytes = public_key.n().to_bytes_be();
-        let e_bytes = public_key.e().to_bytes_be();
-
-        // Codificar en Base64 URL-safe sin padding
-        let n_b64 = URL_SAFE_NO_PAD.encode(&n_bytes);
-        let e_b64 = URL_SAFE_NO_PAD.encode(&e_bytes);
-
-        let jwk = Jwk {
-            kty: "RSA".to_string(),
-            use_: "sig".to_string(),
-            alg: "RS256".to_string(),
-            kid: "key1".to_string(),
-            n: n_b64,
-            e: e_b64,
-        };
+    /// Claves de firma vigentes (instantánea: una recarga no la altera).
+    fn signing_keys(&self) -> Arc<SigningKeys> {
+        self.signing
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
 
-        Ok(Jwks { keys: vec![jwk] })
+    /// Relee el anillo del disco y, si cambió lo publicado (rotación o fin de
+    /// una gracia), reemplaza las claves y el JWKS. Si falla, se conservan las
+    /// anteriores.
+    fn reload_keyring(&self) -> Result<(), Box<dyn std::error::Error>> {
+        let keyring = keys::reload(&self.config.jwt_private_key_path)?;
+        if keyring.fingerprint() == self.signing_keys().keyring.fingerprint() {
+            return Ok(());
+        }
+        let signing = SigningKeys::new(keyring);
+        info!(
+            "Anillo de claves recargado: {} clave(s) publicada(s), activa kid={}, ETag {}",
+            signing.jwks.keys.len(),
+            signing.keyring.active().kid,
+            signing.jwks_etag
+        );
+        *self.signing.write().unwrap_or_else(|e| e.into_inner()) = Arc::new(signing);
+        Ok(())
     }
 
-    /// Genera un JWT firmado con RS256
+    /// Construye el JWKS con todas las claves publicadas del anillo (la activa
+    /// y las que están en retiro, para que sus tokens sigan validando).
+    fn build_jwks(keyring: &keys::Keyring) -> Jwks {
+        let keys = keyring
+            .published()
+            .iter()
+            .flat_map(|key| {
+                let (n, e, x, y) = match key.jwk.clone() {
+                    keys::PublicJwk::Rsa { n, e } => (Some(n), Some(e), None, None),
+                    keys::PublicJwk::Ec { x, y } => (None, None, Some(x), Some(y)),
+                    keys::PublicJwk::Okp { x } => (None, None, Some(x), None),
+                };
+                // La clave importada se publica también con su kid previo al
+                // anillo, para los tokens vivos que lo llevan.
+                std::iter::once(&key.kid)
+                    .chain(key.legacy_kid.as_ref())
+                    .map(move |kid| Jwk {
+                        kty: key.jwk.kty().to_string(),
+                        use_: "sig".to_string(),
+                        alg: key.alg.to_string(),
+                        kid: kid.clone(),
+                        n: n.clone(),
+                        e: e.clone(),
+                        crv: key.jwk.crv().map(str::to_string),
+                        x: x.clone(),
+                        y: y.clone(),
+                    })
+            })
+            .collect();
+        Jwks { keys }
+    }
+
+    /// Genera un JWT firmado por la clave activa del anillo (con su algoritmo), con
+    /// la vida (minutos) y la audiencia ya resueltas por la política del proyecto
     fn generate_jwt(
         &self,
         client_id: &str,
         permissions: Vec<MtxPermission>,
+        lifetime_minutes: i64,
+        aud: Vec<String>,
+        viewer: bool,
     ) -> Result<String, jsonwebtoken::errors::Error> {
         let now = OffsetDateTime::now_utc();
-        let exp = now + Duration::minutes(self.config.jwt_exp_minutes);
+        let exp = now + Duration::minutes(lifetime_minutes);
+
+        // La audiencia del despliegue va siempre primero, sin repetir.
+        let mut audiences: Vec<String> = self.config.jwt_audience.iter().cloned().collect();
+        for a in aud {
+            if !audiences.contains(&a) {
+                audiences.push(a);
+            }
+        }
 
         let claims = Claims {
             sub: client_id.to_string(),
+            iss: self.config.jwt_issuer.clone(),
             exp: exp.unix_timestamp(),
+            aud: audiences,
+            iat: now.unix_timestamp(),
+            nbf: now.unix_timestamp(),
+            jti: uuid::Uuid::new_v4().to_string(),
+            viewer,
             mediamtx_permissions: permissions,
         };
 
-        // Header con kid y algoritmo RS256
-        let mut header = Header::new(Algorithm::RS256);
-        header.kid = Some("key1".to_string());
+        // Header con el kid y el algoritmo de la clave activa
+        let keys = self.signing_keys();
+        let signing = keys.keyring.active();
+        let mut header = Header::new(signing.alg.algorithm());
+        header.kid = Some(signing.kid.clone());
 
-        encode(&header, &claims, &self.encoding_key)
+        encode(&header, &claims, &signing.encoding_key)
     }
 }
 
-/// Construye los permisos de MediaMTX (read + playback) según el acceso del
-/// proyecto (HU 4.4). `All` → path vacío (todas); `Only` → una entrada por cámara.
-/// No se incluye `publish`: los publishers están en `authJWTExclude`.
-fn build_permissions(access: &CameraAccess) -> Vec<MtxPermission> {
+/// Construye los permisos de MediaMTX según el acceso del proyecto (HU 4.4):
+/// sobre sus cámaras, read y/o playback según lo asignado (`All` → ambas con
+/// path vacío; `Only` → una regex `~^<prefijo>` por prefijo y una entrada por
+/// cámara y acción que ningún prefijo cubra) y `publish` por cada permiso de
+/// publicación (los de prefijo, también como regex).
+fn build_permissions(access: &CameraAccess, publish: &[PublishGrant]) -> Vec<MtxPermission> {
     let mut perms = Vec::new();
-    let mut grant = |path: String| {
-        perms.push(MtxPermission {
-            action: "read".to_string(),
-            path: path.clone(),
-        });
-        perms.push(MtxPermission {
-            action: "playback".to_string(),
-            path,
-        });
-    };
     match access {
-        CameraAccess::All => grant(String::new()),
-        CameraAccess::Only(paths) => {
-            for p in paths {
-                grant(p.clone());
+        CameraAccess::All => {
+            for action in CameraActions::Both.mtx_actions() {
+                perms.push(MtxPermission {
+                    action: action.to_string(),
+                    path: String::new(),
+                });
+            }
+        }
+        CameraAccess::Only(cameras) => {
+            // Primero los prefijos, para no repetir las cámaras que ya cubren:
+            // así el JWT no crece con la cantidad de cámaras bajo un prefijo.
+            for c in cameras.iter().filter(|c| c.prefix) {
+                for action in c.actions.mtx_actions() {
+                    perms.push(MtxPermission {
+                        action: action.to_string(),
+                        path: prefix_pattern(&c.path),
+                    });
+                }
+            }
+            for c in cameras.iter().filter(|c| !c.prefix) {
+                for action in c.actions.mtx_actions() {
+                    if !permits(&perms, action, &c.path) {
+                        perms.push(MtxPermission {
+                            action: action.to_string(),
+                            path: c.path.clone(),
+                        });
+                    }
+                }
             }
         }
     }
+    for g in publish {
+        perms.push(MtxPermission {
+            action: "publish".to_string(),
+            path: if g.prefix {
+                prefix_pattern(&g.path)
+            } else {
+                g.path.clone()
+            },
+        });
+    }
     perms
 }
 
+/// Acota la vida del token (minutos) al fin de ventana más próximo de sus
+/// cámaras, en minutos enteros hacia abajo: no sobrevive a una asignación
+/// vencida o fuera de horario. `access` sale de `CameraAccess::for_token` con
+/// el mismo `now`, así que queda al menos un minuto.
+fn cap_to_window(lifetime: i64, access: &CameraAccess, now: chrono::DateTime<chrono::Utc>) -> i64 {
+    match access.window_end() {
+        Some(end) => lifetime.min((end - now).num_minutes()),
+        None => lifetime,
+    }
+}
+
+/// Path de permiso de MediaMTX para todo lo que empiece por `prefix`.
+fn prefix_pattern(prefix: &str) -> String {
+    format!("~^{}", regex_escape(prefix))
+}
+
+/// Metacaracteres de las regex de MediaMTX (RE2) que se escapan en un prefijo.
+const REGEX_META: &str = r"\.+*?()|[]{}^$";
+
+fn regex_escape(literal: &str) -> String {
+    let mut out = String::with_capacity(literal.len());
+    for c in literal.chars() {
+        if REGEX_META.contains(c) {
+            out.push('\\');
+        }
+        out.push(c);
+    }
+    out
+}
+
+/// Prefijo literal de un path de permiso `~^<prefijo escapado>`. Cualquier
+/// otra regex da `None`: no se interpreta y no concede nada (fail-closed).
+fn regex_prefix(pattern: &str) -> Option<String> {
+    let mut chars = pattern.strip_prefix("~^")?.chars();
+    let mut prefix = String::new();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => prefix.push(chars.next().filter(|e| REGEX_META.contains(*e))?),
+            c if REGEX_META.contains(c) => return None,
+            c => prefix.push(c),
+        }
+    }
+    Some(prefix)
+}
+
+/// ¿Algún permiso concede `action` sobre `path`? Path vacío = todos;
+/// `~^<prefijo>` = los que empiezan por el prefijo.
+fn permits(permissions: &[MtxPermission], action: &str, path: &str) -> bool {
+    permissions.iter().any(|p| {
+        p.action == action
+            && if p.path.is_empty() {
+                true
+            } else if p.path.starts_with('~') {
+                regex_prefix(&p.path).is_some_and(|prefix| path.starts_with(&prefix))
+            } else {
+                p.path == path
+            }
+    })
+}
+
 // ============================================================================
 // Request/Response models
 // ============================================================================
@@ -378,6 +997,16 @@ struct LoginRequest {
     /// Project secret (transmitted securely over HTTPS in production)
     #[schema(example = "s3cret", min_length = 1)]
     client_secret: String,
+
+    /// Requested token lifetime in minutes. Clamped to the project's maximum;
+    /// omitted = the project's default lifetime.
+    #[schema(example = 720)]
+    expires_in_minutes: Option<i64>,
+
+    /// Requested `aud` claim; must be one of the project's allowed audiences.
+    /// Omitted = all allowed audiences (none if the project has none).
+    #[schema(example = "kiosk")]
+    audience: Option<String>,
 }
 
 /// Successful authentication response containing the JWT.
@@ -387,11 +1016,22 @@ struct LoginRequest {
 /// - As an Authorization header: `Authorization: Bearer <token>`
 #[derive(Debug, Serialize, ToSchema)]
 struct LoginResponse {
-    /// Signed JWT token (RS256 algorithm).
+    /// Signed JWT token (algorithm per `JWT_ALGORITHM`, RS256 by default).
     /// Contains user identity and MediaMTX permissions.
-    /// Default expiration: 60 minutes (configurable via JWT_EXP_MINUTES).
+    /// Default expiration: the project's token policy, or 60 minutes
+    /// (configurable via JWT_EXP_MINUTES) when it has none.
     #[schema(example = "eyJ0eXAiOiJKV1QiLCJhbGciOiJSUzI1NiIsImtpZCI6ImtleTEifQ.eyJzdWIiOiJhZG1pbiIsImV4cCI6MTczMzgxNzYwMCwibWVkaWFtdHhfcGVybWlzc2lvbnMiOlt7ImFjdGlvbiI6InJlYWQiLCJwYXRoIjoiIn1dfQ.signature")]
     token: String,
+
+    /// Opaque single-use refresh token. Exchange it at `POST /oauth/token`
+    /// (`grant_type=refresh_token`) for a new JWT without resending the
+    /// secret. Absent when refresh tokens are disabled.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    refresh_token: Option<String>,
+
+    /// Token lifetime in seconds (after clamping to the project policy).
+    #[schema(example = 3600)]
+    expires_in: i64,
 }
 
 /// Error response returned when a request fails.
@@ -427,57 +1067,29 @@ struct HealthResponse {
 // Handlers
 // ============================================================================
 
-/// Retrieve the JSON Web Key Set (JWKS) for token validation.
-///
-/// This endpoint exposes the public keys used to verify JWT signatures.
-/// MediaMTX fetches this endpoint (configured via `authJWTJWKS`) to validate
-/// incoming tokens. The keys are generated at server startup and remain
-/// constant for the lifetime of the service.
-///
-/// ## Usage
-/// Configure MediaMTX with:
-/// ```yaml
-/// authJWTJWKS: http://mediamtx-backend:8080/jwks
-/// ```
-#[utoipa::path(
-    get,
-    path = "/jwks",
-    tag = "JWT & Token Management",
-    operation_id = "getJwks",
-    responses(
-        (status = 200, description = "JWKS containing public keys for signature verification", body = Jwks,
-            example = json!({
-                "keys": [{
-                    "kty": "RSA",
-                    "use": "sig",
-                    "alg": "RS256",
-                    "kid": "key1",
-                    "n": "0vx7agoebGcQ...",
-                    "e": "AQAB"
-                }]
-            })
-        )
-    )
-)]
-async fn get_jwks(State(state): State<Arc<AppState>>) -> Json<Jwks> {
-    info!("Solicitud de JWKS recibida");
-    Json(state.jwks.clone())
-}
-
 /// Authenticate user and generate a JWT token.
 ///
 /// Validates the provided credentials and returns a signed JWT token
 /// that can be used to access MediaMTX streams. The token is signed
-/// using RS256 (RSA Signature with SHA-256) algorithm.
+/// with the active key of the signing keyring (RS256 by default).
 ///
 /// ## Token Contents
 /// The generated JWT includes:
 /// - `sub`: Subject identifier (project / client id)
-/// - `exp`: Expiration timestamp
+/// - `exp`: Expiration timestamp (lifetime per project token policy; a
+///   requested `expires_in_minutes` is clamped to the project maximum)
+/// - `aud`: Requested audience, or the project's allowed audiences
 /// - `mediamtx_permissions`: Array of granted permissions
 ///
-/// ## Default Permissions
-/// Tokens grant full access (read, publish, playback) to all paths.
+/// ## Permissions
+/// Derived from the project's grants at login time:
+/// - `read` / `playback`: every path if the project has `all_cameras`;
+///   otherwise the actions granted per camera, directly or through its camera
+///   groups, plus one `~^<prefix>` entry per path-prefix grant
+/// - `publish`: only the paths (or prefixes) the project may publish to;
+///   `all_cameras` does not imply it
+/// - Cameras whose time window closes within a minute are left out, and the
+///   token never outlives the nearest window end
 ///
 /// ## Token Usage
 /// Include the token in MediaMTX requests:
@@ -496,9 +1108,15 @@ async fn get_jwks(State(state): State<Arc<AppState>>) -> Json<Jwks> {
         (status = 200, description = "Authentication successful. Returns signed JWT.", body = LoginResponse,
             example = json!({"token": "eyJ0eXAiOiJKV1QiLCJhbGciOiJSUzI1NiIsImtpZCI6ImtleTEifQ..."})
         ),
+        (status = 400, description = "Requested audience not allowed for the project.", body = ErrorResponse,
+            example = json!({"error": "Audiencia no permitida"})
+        ),
         (status = 401, description = "Authentication failed. Invalid client_id or client_secret.", body = ErrorResponse,
             example = json!({"error": "Invalid credentials"})
         ),
+        (status = 429, description = "Too many failed attempts for this client_id or source IP. Retry after the `Retry-After` seconds.", body = ErrorResponse,
+            example = json!({"error": "Demasiados intentos fallidos; reintente más tarde"})
+        ),
         (status = 500, description = "Internal server error during token generation.", body = ErrorResponse,
             example = json!({"error": "Token generation failed"})
         )
@@ -506,62 +1124,123 @@ async fn get_jwks(State(state): State<Arc<AppState>>) -> Json<Jwks> {
 )]
 async fn login(
     State(state): State<Arc<AppState>>,
+    connect: Option<ConnectInfo<std::net::SocketAddr>>,
+    headers: HeaderMap,
     Json(payload): Json<LoginRequest>,
-) -> Result<Json<LoginResponse>, (StatusCode, Json<ErrorResponse>)> {
+) -> Result<Json<LoginResponse>, Response> {
     info!("Intento de login para proyecto: {}", payload.client_id);
+    let ip = http::client_ip::from_request(connect, &headers, &state.config.trusted_proxies);
 
-    // Validar credenciales contra la BD (fail-closed).
+    // Validar credenciales contra la BD (fail-closed), con bloqueo por fuerza bruta.
     let project = match state
-        .auth
-        .authenticate(&payload.client_id, &payload.client_secret)
+        .lockout
+        .authenticate(&state.auth, &payload.client_id, &payload.client_secret, ip.as_deref())
         .await
     {
-        Some(project) => project,
-        None => {
-            warn!("Credenciales inválidas para proyecto: {}", payload.client_id);
+        Ok(project) => project,
+        Err(LoginError::Locked { retry_after }) => {
+            warn!(
+                "Login bloqueado para proyecto {} (ip {:?})",
+                payload.client_id, ip
+            );
             return Err((
-                StatusCode::UNAUTHORIZED,
+                StatusCode::TOO_MANY_REQUESTS,
+                [(header::RETRY_AFTER, retry_after.to_string())],
                 Json(ErrorResponse {
-                    error: "Credenciales inválidas".to_string(),
+                    error: "Demasiados intentos fallidos; reintente más tarde".to_string(),
                 }),
-            ));
+            )
+                .into_response());
+        }
+        Err(LoginError::InvalidCredentials) => {
+            warn!("Credenciales inválidas para proyecto: {}", payload.client_id);
+            return Err(login_error(StatusCode::UNAUTHORIZED, "Credenciales inválidas"));
+        }
+        Err(LoginError::IpNotAllowed) => {
+            warn!("Login de {} desde IP no permitida: {:?}", payload.client_id, ip);
+            return Err(login_error(StatusCode::UNAUTHORIZED, "Credenciales inválidas"));
+        }
+        Err(LoginError::Repo(e)) => {
+            warn!("Error verificando el bloqueo de {}: {}", payload.client_id, e);
+            return Err(login_error(StatusCode::INTERNAL_SERVER_ERROR, "Error interno"));
         }
     };
 
     // Permisos granulares según el acceso del proyecto (HU 4.4).
+    let now = chrono::Utc::now();
     let access = match state.auth.camera_access(&project).await {
-        Ok(access) => access,
+        Ok(access) => access.for_token(now),
         Err(e) => {
             warn!(
                 "Error calculando el acceso del proyecto {}: {}",
                 project.client_id, e
             );
-            return Err((
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(ErrorResponse {
-                    error: "Error interno".to_string(),
-                }),
-            ));
+            return Err(login_error(StatusCode::INTERNAL_SERVER_ERROR, "Error interno"));
         }
     };
-    let permissions = build_permissions(&access);
-
-    // Generar JWT
-    match state.generate_jwt(&project.client_id, permissions) {
-        Ok(token) => {
-            info!("JWT generado exitosamente para proyecto: {}", project.client_id);
-            Ok(Json(LoginResponse { token }))
-        }
+    let publish = match state.auth.publish_grants(&project).await {
+        Ok(grants) => grants,
         Err(e) => {
+            warn!(
+                "Error leyendo los permisos de publicación de {}: {}",
+                project.client_id, e
+            );
+            return Err(login_error(StatusCode::INTERNAL_SERVER_ERROR, "Error interno"));
+        }
+    };
+    let permissions = build_permissions(&access, &publish);
+
+    // Política de tokens del proyecto: audiencia permitida y vida acotada.
+    let Some(aud) = project.token_audience(payload.audience.as_deref()) else {
+        warn!(
+            "Audiencia no permitida para proyecto {}: {:?}",
+            project.client_id, payload.audience
+        );
+        return Err(login_error(StatusCode::BAD_REQUEST, "Audiencia no permitida"));
+    };
+    let lifetime = cap_to_window(
+        project.token_lifetime(payload.expires_in_minutes, state.config.jwt_exp_minutes),
+        &access,
+        now,
+    );
+
+    // Firmar primero: si falla no queda en la BD un refresh token sin entregar.
+    let token = state
+        .generate_jwt(&project.client_id, permissions, lifetime, aud, false)
+        .map_err(|e| {
             warn!("Error generando JWT: {}", e);
-            Err((
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(ErrorResponse {
-                    error: "Error generando token".to_string(),
-                }),
-            ))
+            login_error(StatusCode::INTERNAL_SERVER_ERROR, "Error generando token")
+        })?;
+
+    // Refresh token para renovar sin reenviar el secreto.
+    let refresh_token = match state.refresh.issue(project.id, None).await {
+        Ok(token) => token,
+        Err(e) => {
+            warn!(
+                "Error emitiendo refresh token para {}: {}",
+                project.client_id, e
+            );
+            return Err(login_error(StatusCode::INTERNAL_SERVER_ERROR, "Error interno"));
         }
-    }
+    };
+
+    info!("JWT generado exitosamente para proyecto: {}", project.client_id);
+    Ok(Json(LoginResponse {
+        token,
+        refresh_token,
+        expires_in: lifetime * 60,
+    }))
+}
+
+/// Respuesta de error de `/auth/login`.
+fn login_error(status: StatusCode, error: &str) -> Response {
+    (
+        status,
+        Json(ErrorResponse {
+            error: error.to_string(),
+        }),
+    )
+        .into_response()
 }
 
 /// Service health check endpoint.
@@ -608,7 +1287,7 @@ async fn health() -> Json<HealthResponse> {
         description = r#"
 ## Overview
 
-This API provides JWT-based authentication for [MediaMTX](https://github.com/bluenviron/mediamtx) streaming server. It generates RS256-signed tokens that MediaMTX uses to authorize stream access.
+This API provides JWT-based authentication for [MediaMTX](https://github.com/bluenviron/mediamtx) streaming server. It generates signed tokens (RS256 by default; PS256, ES256 or EdDSA) that MediaMTX uses to authorize stream access.
 
 ## Authentication Flow
 
@@ -621,7 +1300,7 @@ This API provides JWT-based authentication for [MediaMTX](https://github.com/blu
      │  {client_id, client_secret}     │                                  │
      │────────────────────────────────>│                                  │
      │                                 │                                  │
-     │  2. JWT Token (RS256)           │                                  │
+     │  2. JWT Token (signed)          │                                  │
      │<────────────────────────────────│                                  │
      │                                 │                                  │
      │  3. Stream Request + JWT        │                                  │
@@ -646,6 +1325,12 @@ curl -X POST http://localhost:8080/auth/login \
   -d '{"client_id": "sigac", "client_secret": "s3cret"}'
 ```
 
+Or, with any OAuth2 client library (client credentials grant):
+```bash
+curl -X POST http://localhost:8080/oauth/token \
+  -u sigac:s3cret -d grant_type=client_credentials
+```
+
 ### 2. Access Streams
 ```bash
 # HLS
@@ -662,8 +1347,14 @@ The JWT contains the following claims:
 
 | Claim | Description |
 |-------|-------------|
+| `iss` | Issuer (`JWT_ISSUER`) |
 | `sub` | Subject (client id) |
+| `aud` | Deployment audience (`JWT_AUDIENCE`) plus the project's audiences |
 | `exp` | Expiration timestamp |
+| `iat` | Issued-at timestamp |
+| `nbf` | Not-before timestamp |
+| `jti` | Unique token id (used for revocation) |
+| `viewer` | Present (`true`) on down-scoped viewer tokens |
 | `mediamtx_permissions` | Array of permission objects |
 
 ### Permission Object
@@ -676,12 +1367,106 @@ The JWT contains the following claims:
 
 ## Security Considerations
 
-- Tokens are signed with RS256 (2048-bit RSA keys)
-- Keys are persisted across restarts (mounted volume)
-- Per-project credentials, secrets stored hashed (Argon2id); no shared user
-- Default token expiration: 60 minutes (configurable via JWT_EXP_MINUTES)
-- Token renewal: clients re-authenticate via `POST /auth/login` before expiry
-  to obtain a fresh token (no refresh tokens; stateless machine-to-machine)
+- Tokens are signed with the algorithm in `JWT_ALGORITHM`: RS256 (default,
+  2048-bit RSA), PS256, ES256 (P-256) or EdDSA (Ed25519). EC/OKP keys make
+  tokens several times smaller, which matters in `?jwt=` query strings
+- Keys are persisted across restarts (mounted volume) and rotated with the
+  `rotate-signing-key` subcommand without a restart: the new key is
+  published in the JWKS first and only signs once verifiers caching the JWKS
+  (`JWKS_CACHE_SECS`) have seen it; the previous one leaves the JWKS once the
+  tokens it signed expire
+- Discovery: `/.well-known/openid-configuration` lists the issuer, token and
+  introspection endpoints, signing algorithms and `jwks_uri`. The JWKS
+  (`/jwks`, `/.well-known/jwks.json`) carries an `ETag` tied to the keyring
+  and `Cache-Control: max-age` (`JWKS_CACHE_SECS`); `If-None-Match` yields 304
+- Per-project credentials, secrets stored hashed (Argon2id); no shared user.
+  A project may hold several active secrets (labels, optional expiry,
+  last-used time) so it can rotate without downtime via
+  `/admin/projects/{id}/secrets`. Omitting `secret` on project creation, or
+  calling `/admin/projects/{id}/secrets:rotate`, has the server generate a
+  256-bit secret that is returned exactly once; admin-chosen secrets must
+  pass a strength policy (length, character classes, no `client_id`).
+  Argon2id parameters are configurable (`ARGON2_*`); a successful login
+  against a hash with outdated parameters, or a legacy bcrypt hash imported
+  from `clients.json`, transparently rehashes it
+- Default token expiration: 60 minutes (configurable via JWT_EXP_MINUTES);
+  projects may define their own default/maximum lifetime (capped by
+  JWT_MAX_EXP_MINUTES) and allowed audiences
+- Token renewal: exchange the single-use `refresh_token` at `POST /oauth/token`
+  (`grant_type=refresh_token`); permissions are recomputed on every renewal.
+  Reusing an already exchanged refresh token revokes its whole family
+- Revocation: admins revoke by `jti`, by project or globally ("issued before")
+  via `/admin/revocations`; disabling or deleting a project revokes its tokens.
+  `/cameras` rejects revoked tokens and Caddy checks `GET /auth/verify`
+  (`forward_auth`) before proxying HLS to MediaMTX. RTSP, WebRTC/WHEP and
+  playback reach MediaMTX directly: with `MEDIAMTX_AUTH_MODE=jwt` they accept
+  a revoked token until its `exp`; `MEDIAMTX_AUTH_MODE=http` rejects it on
+  every new connection
+- Admin accounts: `/admin/*` takes the bearer of a session opened at
+  `POST /admin/login` (username and Argon2id-hashed password, same lockout as
+  project logins, `ADMIN_SESSION_MINUTES`). Roles are `viewer` (read-only),
+  `operator` (cameras, groups, failures, lockouts, revocations) and `admin`
+  (projects, secrets, IdP mappings, accounts); insufficient role → 403. Every
+  change is audited as `admin_action` with the account name. `ADMIN_API_TOKEN`
+  remains as a break-glass `admin`, audited as such
+- Service API keys: machine clients use `mk_...` keys from `/admin/api-keys`
+  (stored as SHA-256, shown once, optional expiry, last use recorded) that
+  only reach the routes in their scopes: `<resource>:read` for GET and
+  `<resource>:write` otherwise, e.g. `failures:write`; anything else → 403.
+  Keys never manage accounts or keys and cannot introspect tokens
+- Source IP allowlists: a project with `allowed_cidrs` only authenticates
+  (login, every `/oauth/token` grant, MediaMTX user/password) from those
+  networks, using the client IP from `X-Forwarded-For` only when the peer is
+  in `TRUSTED_PROXIES`. Denials answer like a bad secret, do not count towards
+  lockout and are audited as `login_ip_denied`
+- Token introspection: services that receive our JWTs call
+  `POST /oauth/introspect` (RFC 7662), authenticated as a project or as an
+  administrator, to learn whether a token is still active, whose it is and which
+  cameras it covers. Projects may only introspect their own tokens; tokens of
+  disabled projects are reported inactive
+- Token exchange: with `EXTERNAL_IDP_ISSUER`/`EXTERNAL_IDP_JWKS` set,
+  `POST /oauth/token` accepts `grant_type=urn:ietf:params:oauth:grant-type:token-exchange`
+  (RFC 8693). The corporate IdP's JWT is verified against its JWKS (asymmetric
+  algorithms only, issuer and optional audience enforced) and mapped to a
+  project through `/admin/idp-mappings`; the issued token carries that
+  project's permissions, has no refresh token and never outlives the external one
+- Per-camera actions: each camera granted to a project allows live video
+  (`read`), recordings (`playback`) or both; tokens, viewer tokens and the
+  MediaMTX callback only carry or allow what was granted
+- Camera groups: projects can be granted whole groups
+  (`/admin/camera-groups`); membership is resolved whenever access is
+  computed, so a camera added to a group reaches every project holding it
+- Time-bound grants: direct camera grants may carry `valid_from`/`valid_until`
+  and a weekly schedule (in `SCHEDULE_TIMEZONE`). Only grants active now are
+  put in tokens or allowed by the MediaMTX callback, and a token's `exp` is
+  capped at the earliest end of its cameras' current windows
+- Path-prefix grants: a project granted a prefix gets one `~^prefix` regex
+  per action instead of one entry per camera. Prefixes are literal (regex
+  metacharacters are escaped, raw regexes rejected); end them with `/` so
+  `site-a` does not also match `site-ab/...`
+- Publishing: tokens carry `publish` only for the paths granted to the
+  project (exact paths, or prefixes emitted as a `~^prefix` regex). Body cams
+  are registered as publisher cameras (no RTSP source) and push with their
+  project's token; only internal publishers are excluded from auth in MediaMTX
+- Viewer tokens: a backend holding a project token calls
+  `POST /auth/viewer-token` to mint a short-lived token limited to the cameras
+  (and optionally only `read` or `playback`) on one end user's screen, so the
+  project's own token never reaches the browser
+- Playback URLs: `GET /cameras/{id}/playback` returns ready-to-use HLS,
+  LL-HLS, WebRTC/WHEP, RTSP and recording URLs with a single-camera viewer
+  token embedded (protocols without a configured `PUBLIC_*_URL` are omitted)
+- MediaMTX modes: `jwt` (default) validates tokens offline against `/jwks`;
+  with `MEDIAMTX_AUTH_MODE=http` MediaMTX calls `POST /mediamtx/auth`
+  (`authMethod: http`) and every access is checked against the project's live
+  camera access, with decisions cached for `MEDIAMTX_AUTH_CACHE_SECS`
+- Strict validation: every endpoint that accepts a token requires a known
+  `kid`, the deployment `iss` and `aud`, valid `exp`/`nbf`/`iat` (with
+  `JWT_LEEWAY_SECS` of clock skew) and a `jti`; the 401 body states the reason
+- Brute-force protection: failed logins (`/auth/login`, `/oauth/token`,
+  MediaMTX `user`/`password`) are counted per `client_id` and per source IP
+  in Postgres; past the threshold the client or IP is locked out with
+  exponential backoff (429 + `Retry-After`). Admins list and clear lockouts
+  at `/admin/lockouts` and read the audit trail at `/admin/audit`
 - Use HTTPS in production environments
 "#,
         contact(
@@ -702,19 +1487,30 @@ The JWT contains the following claims:
         (name = "Authentication", description = "User authentication and token generation"),
         (name = "JWT & Token Management", description = "JSON Web Key Set and token validation endpoints"),
         (name = "System & Monitoring", description = "Health checks and service status"),
-        (name = "Administration", description = "CRUD de cámaras y proyectos (requiere ADMIN_API_TOKEN)"),
+        (name = "Administration", description = "CRUD de cámaras y proyectos (sesión de `/admin/login` con rol suficiente, clave de API con el alcance de la ruta, o ADMIN_API_TOKEN)"),
         (name = "Consumer", description = "Consulta de cámaras accesibles por proyecto (JWT)")
     ),
     modifiers(&SecurityAddon),
     paths(
-        get_jwks,
+        http::discovery::get_jwks,
+        http::discovery::well_known_jwks,
+        http::discovery::openid_configuration,
         login,
+        http::oauth::token,
+        http::oauth::introspect,
         health,
         http::admin::list_cameras,
         http::admin::create_camera,
         http::admin::get_camera,
         http::admin::update_camera,
         http::admin::delete_camera,
+        http::admin::list_camera_groups,
+        http::admin::create_camera_group,
+        http::admin::get_camera_group,
+        http::admin::update_camera_group,
+        http::admin::delete_camera_group,
+        http::admin::add_group_camera,
+        http::admin::remove_group_camera,
         http::admin::list_projects,
         http::admin::create_project,
         http::admin::get_project,
@@ -722,7 +1518,34 @@ The JWT contains the following claims:
         http::admin::delete_project,
         http::admin::record_failure,
         http::admin::list_failures,
-        http::consumer::list_my_cameras
+        http::admin::list_revocations,
+        http::admin::create_revocation,
+        http::admin::delete_revocation,
+        http::admin::list_project_secrets,
+        http::admin::add_project_secret,
+        http::admin::revoke_project_secret,
+        http::admin::rotate_project_secret,
+        http::admin::list_lockouts,
+        http::admin::clear_lockout,
+        http::admin::list_audit,
+        http::admin::list_idp_mappings,
+        http::admin::create_idp_mapping,
+        http::admin::delete_idp_mapping,
+        http::admin::admin_login,
+        http::admin::admin_logout,
+        http::admin::admin_me,
+        http::admin::list_admin_accounts,
+        http::admin::create_admin_account,
+        http::admin::update_admin_account,
+        http::admin::delete_admin_account,
+        http::admin::list_api_keys,
+        http::admin::create_api_key,
+        http::admin::delete_api_key,
+        http::consumer::list_my_cameras,
+        http::consumer::verify,
+        http::consumer::viewer_token,
+        http::consumer::playback,
+        http::mediamtx::authorize
     ),
     components(
         schemas(
@@ -734,21 +1557,64 @@ The JWT contains the following claims:
             Jwk,
             Claims,
             MtxPermission,
+            http::oauth::TokenRequest,
+            http::oauth::TokenResponse,
+            http::oauth::OAuthErrorBody,
+            http::oauth::IntrospectRequest,
+            http::oauth::IntrospectResponse,
+            http::oauth::IntrospectedCamera,
+            http::discovery::OpenIdConfiguration,
             http::admin::CameraResponse,
             http::admin::CreateCameraRequest,
             http::admin::UpdateCameraRequest,
             http::admin::ProjectResponse,
             http::admin::CreateProjectRequest,
             http::admin::UpdateProjectRequest,
+            http::admin::CameraGrantDto,
+            http::admin::ScheduleDto,
+            http::admin::GroupGrantDto,
+            http::admin::CameraGroupResponse,
+            http::admin::CreateCameraGroupRequest,
+            http::admin::UpdateCameraGroupRequest,
+            http::admin::PublishGrantDto,
+            http::admin::PrefixGrantDto,
             http::admin::RecordFailureRequest,
             http::admin::FailureResponse,
-            http::consumer::CameraRef
+            http::admin::RevokeRequest,
+            http::admin::RevocationResponse,
+            http::admin::AddSecretRequest,
+            http::admin::ProjectSecretResponse,
+            http::admin::CreatedProjectResponse,
+            http::admin::RotateSecretRequest,
+            http::admin::GeneratedSecretResponse,
+            http::admin::LockoutResponse,
+            http::admin::AuditResponse,
+            http::admin::CreateIdpMappingRequest,
+            http::admin::IdpMappingResponse,
+            http::admin::AdminLoginRequest,
+            http::admin::AdminLoginResponse,
+            http::admin::AdminMeResponse,
+            http::admin::AdminAccountResponse,
+            http::admin::CreateAdminAccountRequest,
+            http::admin::CreatedAdminAccountResponse,
+            http::admin::UpdateAdminAccountRequest,
+            http::admin::ApiKeyResponse,
+            http::admin::CreateApiKeyRequest,
+            http::admin::CreatedApiKeyResponse,
+            http::mediamtx::MtxAuthRequest,
+            http::consumer::CameraRef,
+            http::consumer::ViewerTokenRequest,
+            http::consumer::ViewerTokenResponse,
+            http::consumer::PlaybackResponse,
+            http::consumer::PlaybackUrls,
+            http::consumer::RecordingUrls
         )
     )
 )]
 struct ApiDoc;
 
-/// Agrega el esquema de seguridad bearer (ADMIN_API_TOKEN) usado por /admin/*.
+/// Agrega el esquema de seguridad bearer (sesión de `/admin/login`, clave de
+/// API o ADMIN_API_TOKEN) usado por /admin/*.
 struct SecurityAddon;
 
 impl Modify for SecurityAddon {
@@ -804,6 +1670,67 @@ fn spawn_reconciler(reconciler: Arc<ReconcilerService>, interval_secs: u64) {
     });
 }
 
+/// Recarga periódica de la denylist: aplica en esta instancia las revocaciones
+/// hechas en otras. Si la BD falla se conserva la última copia.
+fn spawn_revocation_refresh(revocations: Arc<RevocationService>, interval_secs: u64) {
+    tokio::spawn(async move {
+        let interval = std::time::Duration::from_secs(interval_secs);
+        loop {
+            tokio::time::sleep(interval).await;
+            if let Err(e) = revocations.reload().await {
+                warn!("Recarga de revocaciones falló: {}", e);
+            }
+        }
+    });
+}
+
+/// Cada cuánto se relee el anillo de claves del disco.
+const KEYRING_RELOAD_SECS: u64 = 60;
+
+/// Recarga periódica del anillo de claves: publica las rotaciones hechas con
+/// `rotate-signing-key` y retira las claves cuya gracia venció, sin reiniciar.
+fn spawn_keyring_reload(state: Arc<AppState>) {
+    tokio::spawn(async move {
+        let interval = std::time::Duration::from_secs(KEYRING_RELOAD_SECS);
+        loop {
+            tokio::time::sleep(interval).await;
+            if let Err(e) = state.reload_keyring() {
+                warn!("Recarga del anillo de claves falló: {}", e);
+            }
+        }
+    });
+}
+
+/// Limpieza horaria de los refresh tokens vencidos o revocados.
+fn spawn_refresh_purge(refresh: Arc<RefreshService>) {
+    tokio::spawn(async move {
+        let interval = std::time::Duration::from_secs(3600);
+        loop {
+            tokio::time::sleep(interval).await;
+            match refresh.purge().await {
+                Ok(0) => {}
+                Ok(n) => info!("Purgados {} refresh tokens vencidos o revocados", n),
+                Err(e) => warn!("Purga de refresh tokens falló: {}", e),
+            }
+        }
+    });
+}
+
+/// Limpieza horaria de los contadores de fallos de login ya inactivos.
+fn spawn_lockout_purge(lockout: Arc<LockoutService>) {
+    tokio::spawn(async move {
+        let interval = std::time::Duration::from_secs(3600);
+        loop {
+            tokio::time::sleep(interval).await;
+            match lockout.purge().await {
+                Ok(0) => {}
+                Ok(n) => info!("Purgados {} contadores de login inactivos", n),
+                Err(e) => warn!("Purga de contadores de login falló: {}", e),
+            }
+        }
+    });
+}
+
 /// Subcomando one-time: importa a la BD las cámaras configuradas en el MediaMTX
 /// vivo (source RTSP), cifrando la URL. Idempotente: omite las que ya existan.
 /// SEGURIDAD: solo registra el nombre de la ruta, nunca la URL con credenciales.
@@ -818,7 +1745,8 @@ async fn migrate_cameras(config: &Config) -> Result<(), Box<dyn std::error::Erro
     let (mut imported, mut skipped) = (0u32, 0u32);
 
     for p in paths {
-        // Solo cámaras de pull (source RTSP); saltamos regex y publishers.
+        // Solo cámaras de pull (source RTSP); saltamos regex y publishers
+        // (los de la BD se dan de alta por /admin/cameras).
         let source = match p.source {
             Some(s) if !p.name.starts_with('~') && s.starts_with("rtsp://") => s,
             _ => {
@@ -833,7 +1761,7 @@ async fn migrate_cameras(config: &Config) -> Result<(), Box<dyn std::error::Erro
         }
         repo.create(domain::models::NewCamera {
             path: p.name.clone(),
-            rtsp_url: source,
+            rtsp_url: Some(source),
             record: p.record,
             enabled: true,
             description: None,
@@ -852,7 +1780,8 @@ async fn migrate_cameras(config: &Config) -> Result<(), Box<dyn std::error::Erro
 
 /// Subcomando one-time: importa a la BD los proyectos de `clients.json`
 /// (client_id + secret_hash ya hasheado) con `all_cameras=true` para preservar
-/// el comportamiento actual. Idempotente: omite los que ya existan.
+/// el comportamiento actual. Idempotente: omite los que ya existan. Los hashes
+/// bcrypt se importan tal cual y pasan a Argon2id en el primer login.
 async fn migrate_clients(config: &Config) -> Result<(), Box<dyn std::error::Error>> {
     #[derive(serde::Deserialize)]
     struct ClientEntry {
@@ -898,22 +1827,54 @@ async fn migrate_clients(config: &Config) -> Result<(), Box<dyn std::error::Erro
     Ok(())
 }
 
+/// Subcomando: genera una clave de firma nueva y deja la anterior en retiro.
+/// La clave nueva se publica ya, pero firma recién cuando todo verificador que
+/// cacheó el JWKS (`JWKS_CACHE_SECS`) y toda instancia (recarga del anillo) la
+/// conocen. La gracia por defecto es `JWT_MAX_EXP_MINUTES` (lo que puede vivir
+/// el último token firmado con la anterior); durante ese tiempo, contado desde
+/// la activación, sigue en el JWKS y validando.
+fn rotate_signing_key(config: &Config, grace_arg: Option<&str>) -> Result<(), Box<dyn std::error::Error>> {
+    let grace_minutes = match grace_arg {
+        Some(v) => v
+            .parse::<i64>()
+            .map_err(|_| "uso: mediamtx-auth-backend rotate-signing-key [minutos_de_gracia]")?,
+        None => config.jwt_max_exp_minutes,
+    };
+    let reload = chrono::Duration::seconds(KEYRING_RELOAD_SECS as i64);
+    let activate_in = chrono::Duration::seconds(config.jwks_cache_secs as i64) + reload;
+    // Una instancia sigue firmando con la anterior hasta su primera recarga
+    // tras la activación: la gracia cubre también ese minuto.
+    let grace = chrono::Duration::minutes(grace_minutes) + reload;
+    let (kid, activate_after) = keys::rotate(
+        &config.jwt_private_key_path,
+        grace,
+        config.jwt_algorithm,
+        activate_in,
+    )?;
+    info!(
+        "Clave rotada: kid={} publicada en el JWKS; firma desde {} y la anterior valida {} min más desde entonces.",
+        kid, activate_after, grace_minutes
+    );
+    Ok(())
+}
+
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    // Subcomando: generar el hash Argon2 de un secreto (alta de proyectos).
+    // Cargar variables de entorno desde .env
+    dotenvy::dotenv().ok();
+
+    // Subcomando: generar el hash Argon2 de un secreto (alta de proyectos),
+    // con los parámetros de ARGON2_*.
     //   mediamtx-auth-backend hash <secreto>
     let args: Vec<String> = env::args().collect();
     if args.get(1).map(String::as_str) == Some("hash") {
         let secret = args
             .get(2)
             .ok_or("uso: mediamtx-auth-backend hash <secreto>")?;
-        println!("{}", secret::hash_secret(secret)?);
+        println!("{}", secret::hash_secret(secret, &hash_policy_from_env())?);
         return Ok(());
     }
 
-    // Cargar variables de entorno desde .env
-    dotenvy::dotenv().ok();
-
     // Inicializar tracing
     tracing_subscriber::fmt()
         .with_env_filter(
@@ -924,7 +1885,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         .init();
 
     // Cargar configuración
-    let config = Config::from_env();
+    let config = Config::from_env()?;
     info!("Configuración cargada: {:?}", config);
 
     // Subcomando one-time: importar a la BD las cámaras del MediaMTX vivo.
@@ -937,10 +1898,18 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         return migrate_clients(&config).await;
     }
 
+    // Subcomando: rotar la clave de firma (el backend en marcha la recarga solo).
+    //   mediamtx-auth-backend rotate-signing-key [minutos_de_gracia]
+    if args.get(1).map(String::as_str) == Some("rotate-signing-key") {
+        return rotate_signing_key(&config, args.get(2).map(String::as_str));
+    }
+
     // Conexión a Postgres (con reintento) y migraciones de esquema al arranque
     // (HU 4.1). Fail-closed: si la BD o las migraciones fallan, no arrancamos.
     let db_pool = infra::db::connect_with_retry(&config.database_url).await?;
     infra::db::run_migrations(&db_pool).await?;
+    // Una zona horaria mal escrita rompería cada login con horario: mejor no arrancar.
+    infra::db::check_timezone(&db_pool, &config.schedule_timezone).await?;
 
     // Crear estado de la aplicación
     let state = Arc::new(AppState::new(config.clone(), db_pool)?);
@@ -949,7 +1918,16 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     // (con reintentos) y luego periódicamente para sanar deriva.
     spawn_reconciler(state.reconciler.clone(), config.reconcile_interval_secs);
 
-    // Panel de administración (HU 4.5), protegido por ADMIN_API_TOKEN.
+    // Denylist de tokens revocados. Fail-closed: sin la carga inicial no
+    // arrancamos (aceptaríamos tokens revocados).
+    state.revocations.reload().await?;
+    spawn_revocation_refresh(state.revocations.clone(), config.revocation_refresh_secs);
+    spawn_keyring_reload(state.clone());
+    spawn_lockout_purge(state.lockout.clone());
+    spawn_refresh_purge(state.refresh.clone());
+
+    // Panel de administración (HU 4.5): sesiones de cuentas con rol o
+    // ADMIN_API_TOKEN (emergencia). El login queda fuera del middleware.
     let admin = http::admin::router().layer(axum::middleware::from_fn_with_state(
         state.clone(),
         http::admin::require_admin,
@@ -959,11 +1937,21 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let app = Router::new()
         // Endpoints de la API
         .route("/health", get(health))
-        .route("/jwks", get(get_jwks))
+        // Claves públicas y descubrimiento (JWKS con ETag/caché)
+        .merge(http::discovery::router())
         .route("/auth/login", post(login))
+        // OAuth2 estándar (client_credentials) para librerías genéricas
+        .merge(http::oauth::router())
         // Consumo por proyecto (SIGAC/Odin): lista de cámaras accesibles (JWT)
         .merge(http::consumer::router())
+        // Callback de MediaMTX (authMethod: http), solo si está seleccionado
+        .merge(if config.mediamtx_http_auth {
+            http::mediamtx::router()
+        } else {
+            Router::new()
+        })
         // Panel de administración
+        .merge(http::admin::login_router())
         .nest("/admin", admin)
         // Documentación OpenAPI (Scalar UI)
         .merge(Scalar::with_url("/docs", ApiDoc::openapi()))
@@ -976,13 +1964,30 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     info!("Servidor iniciando en http://{}", addr);
     info!("Endpoints disponibles:");
     info!("  GET  /health       - Health check");
-    info!("  GET  /jwks         - JSON Web Key Set");
+    info!("  GET  /jwks         - JSON Web Key Set (también /.well-known/jwks.json)");
+    info!("  GET  /.well-known/openid-configuration - Descubrimiento");
     info!("  POST /auth/login   - Login y obtención de JWT");
+    if config.external_idp.is_some() {
+        info!("  POST /oauth/token  - Token OAuth2 (client_credentials, refresh_token, token-exchange)");
+    } else {
+        info!("  POST /oauth/token  - Token OAuth2 (client_credentials, refresh_token)");
+    }
+    info!("  GET  /auth/verify  - Chequeo de token para forward_auth (Caddy)");
+    info!("  POST /auth/viewer-token - Token de visor (subconjunto de cámaras)");
+    info!("  GET  /cameras/{{id}}/playback - URLs de reproducción con token de visor");
+    if config.mediamtx_http_auth {
+        info!("  POST /mediamtx/auth - Callback de autenticación de MediaMTX");
+    }
     info!("  GET  /docs         - Documentación API (Scalar)");
     info!("  GET  /openapi.json - Especificación OpenAPI");
 
     let listener = tokio::net::TcpListener::bind(&addr).await?;
-    axum::serve(listener, app).await?;
+    // ConnectInfo: la IP del socket, base de la IP del cliente (fuerza bruta).
+    axum::serve(
+        listener,
+        app.into_make_service_with_connect_info::<std::net::SocketAddr>(),
+    )
+    .await?;
 
     Ok(())
 }
@@ -994,22 +1999,39 @@ async fn openapi_json() -> Json<utoipa::openapi::OpenApi> {
 
 #[cfg(test)]
 mod tests {
-    use super::{build_permissions, CameraAccess};
+    use super::{
+        build_permissions, cap_to_window, keys, parse_jwt_algorithm, permits, regex_prefix,
+        CameraAccess, MtxPermission, PublishGrant,
+    };
+    use crate::domain::models::{AllowedCamera, CameraActions};
+
+    fn allowed(path: &str, actions: CameraActions) -> AllowedCamera {
+        AllowedCamera {
+            path: path.into(),
+            actions,
+            prefix: false,
+            until: None,
+        }
+    }
 
     #[test]
     fn all_grants_read_and_playback_wildcard() {
-        let perms = build_permissions(&CameraAccess::All);
+        let perms = build_permissions(&CameraAccess::All, &[]);
         assert_eq!(perms.len(), 2);
         assert!(perms.iter().all(|p| p.path.is_empty()));
         let actions: Vec<&str> = perms.iter().map(|p| p.action.as_str()).collect();
         assert!(actions.contains(&"read"));
         assert!(actions.contains(&"playback"));
-        assert!(!actions.contains(&"publish"), "no debe conceder publish");
+        assert!(!actions.contains(&"publish"), "all_cameras no concede publish");
     }
 
     #[test]
     fn only_grants_read_and_playback_per_camera() {
-        let perms = build_permissions(&CameraAccess::Only(vec!["cam-a".into(), "cam-b".into()]));
+        let access = CameraAccess::Only(vec![
+            allowed("cam-a", CameraActions::Both),
+            allowed("cam-b", CameraActions::Both),
+        ]);
+        let perms = build_permissions(&access, &[]);
         assert_eq!(perms.len(), 4); // 2 cámaras × (read + playback)
         let read_paths: Vec<&str> = perms
             .iter()
@@ -1019,8 +2041,132 @@ mod tests {
         assert_eq!(read_paths, vec!["cam-a", "cam-b"]);
     }
 
+    #[test]
+    fn camera_actions_limit_live_or_recordings() {
+        let access = CameraAccess::Only(vec![
+            allowed("vivo", CameraActions::Read),
+            allowed("archivo", CameraActions::Playback),
+        ]);
+        let perms = build_permissions(&access, &[]);
+        assert_eq!(perms.len(), 2);
+        assert!(permits(&perms, "read", "vivo"));
+        assert!(!permits(&perms, "playback", "vivo"), "el contratista no ve grabaciones");
+        assert!(permits(&perms, "playback", "archivo"));
+        assert!(!permits(&perms, "read", "archivo"));
+    }
+
     #[test]
     fn empty_only_grants_nothing() {
-        assert!(build_permissions(&CameraAccess::Only(vec![])).is_empty());
+        assert!(build_permissions(&CameraAccess::Only(vec![]), &[]).is_empty());
+    }
+
+    #[test]
+    fn publish_grants_become_exact_or_prefix_permissions() {
+        let grants = vec![
+            PublishGrant {
+                path: "unidad-7".into(),
+                prefix: false,
+            },
+            PublishGrant {
+                path: "bodycam.v2/".into(),
+                prefix: true,
+            },
+        ];
+        let perms = build_permissions(&CameraAccess::Only(vec![]), &grants);
+        let publish: Vec<&str> = perms
+            .iter()
+            .filter(|p| p.action == "publish")
+            .map(|p| p.path.as_str())
+            .collect();
+        assert_eq!(publish, vec!["unidad-7", r"~^bodycam\.v2/"]);
+
+        assert!(permits(&perms, "publish", "unidad-7"));
+        assert!(permits(&perms, "publish", "bodycam.v2/ana"));
+        assert!(!permits(&perms, "publish", "bodycamXv2/ana"), "el punto es literal");
+        assert!(!permits(&perms, "read", "bodycam.v2/ana"));
+    }
+
+    #[test]
+    fn prefix_grants_become_one_regex_and_absorb_covered_cameras() {
+        let access = CameraAccess::Only(vec![
+            allowed("bodega-a/cam-1", CameraActions::Both),
+            allowed("patio", CameraActions::Read),
+            AllowedCamera {
+                path: "bodega-a/".into(),
+                actions: CameraActions::Read,
+                prefix: true,
+                until: None,
+            },
+        ]);
+        let perms = build_permissions(&access, &[]);
+        let paths: Vec<(&str, &str)> = perms
+            .iter()
+            .map(|p| (p.action.as_str(), p.path.as_str()))
+            .collect();
+        // bodega-a/cam-1 solo necesita su playback: el read lo cubre el prefijo.
+        assert_eq!(
+            paths,
+            vec![
+                ("read", "~^bodega-a/"),
+                ("playback", "bodega-a/cam-1"),
+                ("read", "patio"),
+            ]
+        );
+        assert!(permits(&perms, "read", "bodega-a/cam-99"));
+        assert!(!permits(&perms, "playback", "bodega-a/cam-99"));
+        assert!(!permits(&perms, "read", "bodega-ab/cam-1"));
+    }
+
+    #[test]
+    fn invalid_jwt_algorithm_is_an_error_not_rs256() {
+        assert_eq!(parse_jwt_algorithm(None), Ok(keys::KeyAlg::Rs256));
+        assert_eq!(parse_jwt_algorithm(Some(" ")), Ok(keys::KeyAlg::Rs256));
+        assert_eq!(parse_jwt_algorithm(Some("es256")), Ok(keys::KeyAlg::Es256));
+        assert!(parse_jwt_algorithm(Some("ES-256")).is_err());
+    }
+
+    #[test]
+    fn token_lifetime_is_capped_at_the_window_end() {
+        let now = chrono::Utc::now();
+        let ending = |seconds| {
+            CameraAccess::Only(vec![AllowedCamera {
+                until: Some(now + chrono::Duration::seconds(seconds)),
+                ..allowed("auditoria", CameraActions::Read)
+            }])
+            .for_token(now)
+        };
+        assert_eq!(cap_to_window(60, &ending(600), now), 10);
+        assert_eq!(cap_to_window(5, &ending(600), now), 5);
+        // 1 min 59 s: hacia abajo, nunca más allá de la ventana.
+        assert_eq!(cap_to_window(60, &ending(119), now), 1);
+        assert_eq!(cap_to_window(60, &CameraAccess::All, now), 60);
+        // Con menos de un minuto la cámara queda fuera del token.
+        for seconds in [59, 0] {
+            let access = ending(seconds);
+            assert!(matches!(&access, CameraAccess::Only(c) if c.is_empty()));
+            assert_eq!(cap_to_window(60, &access, now), 60);
+        }
+    }
+
+    #[test]
+    fn only_generated_prefix_regexes_are_understood() {
+        assert_eq!(regex_prefix(r"~^live/"), Some("live/".to_string()));
+        assert_eq!(regex_prefix(r"~^a\.b"), Some("a.b".to_string()));
+        assert_eq!(regex_prefix(r"~^live/.*$"), None);
+        assert_eq!(regex_prefix(r"~^\d+"), None);
+        assert_eq!(regex_prefix("~live/"), None, "sin ancla");
+    }
+
+    #[test]
+    fn permits_matches_action_and_path() {
+        let perm = |action: &str, path: &str| MtxPermission {
+            action: action.into(),
+            path: path.into(),
+        };
+        let perms = vec![perm("read", "cam-a"), perm("playback", "")];
+        assert!(permits(&perms, "read", "cam-a"));
+        assert!(!permits(&perms, "read", "cam-b"));
+        assert!(permits(&perms, "playback", "cam-b"), "path vacío = todas");
+        assert!(!permits(&perms, "publish", "cam-a"));
     }
 }
@@ -1,24 +1,143 @@
 //! Primitivos de secretos por proyecto (Argon2id).
 //!
-//! Responsabilidad única: hashear y verificar secretos. Los secretos se guardan
-//! HASHEADOS, nunca en claro. Fail-closed: cualquier fallo de verificación → false.
+//! Responsabilidad única: generar, validar, hashear y verificar secretos. Los
+//! secretos se guardan HASHEADOS, nunca en claro. Fail-closed: cualquier fallo
+//! de verificación → false.
+//!
+//! Los parámetros de Argon2id (`HashPolicy`) son configurables; un hash con
+//! parámetros viejos, o bcrypt heredado de `clients.json`, se sigue aceptando
+//! y `needs_rehash` avisa para regenerarlo en el próximo login correcto.
 
 use argon2::password_hash::SaltString;
-use argon2::{Argon2, PasswordHash, PasswordHasher, PasswordVerifier};
+use argon2::{Algorithm, Argon2, Params, PasswordHash, PasswordHasher, PasswordVerifier, Version};
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
 use rand::rngs::OsRng;
+use rand::RngCore;
+
+/// Bytes aleatorios de un secreto generado por el servidor (256 bits).
+const GENERATED_SECRET_BYTES: usize = 32;
+
+/// Largo mínimo de un secreto elegido por el admin.
+pub const MIN_SECRET_LEN: usize = 16;
+
+/// Desde este largo basta con dos clases de caracteres (frase de paso).
+const PASSPHRASE_LEN: usize = 24;
+
+/// Secreto de alta entropía: 256 bits de `OsRng` en Base64 URL-safe (43 caracteres).
+pub fn generate_secret() -> String {
+    let mut bytes = [0u8; GENERATED_SECRET_BYTES];
+    OsRng.fill_bytes(&mut bytes);
+    URL_SAFE_NO_PAD.encode(bytes)
+}
 
-/// Genera el hash Argon2id de un secreto (subcomando CLI para dar de alta proyectos).
-pub fn hash_secret(secret: &str) -> Result<String, Box<dyn std::error::Error>> {
+/// Política de fortaleza de los secretos que elige el admin: al menos
+/// `MIN_SECRET_LEN` caracteres, tres clases (minúsculas, mayúsculas, dígitos,
+/// símbolos) o dos si es una frase de `PASSPHRASE_LEN`+, sin espacios en los
+/// extremos, sin contener el `client_id` y sin un único carácter repetido.
+/// El `Err` explica qué falta, para devolverlo tal cual en un 400.
+pub fn check_strength(secret: &str, client_id: &str) -> Result<(), String> {
+    let len = secret.chars().count();
+    if len < MIN_SECRET_LEN {
+        return Err(format!("el secreto debe tener al menos {MIN_SECRET_LEN} caracteres"));
+    }
+    if secret.trim() != secret {
+        return Err("el secreto no puede empezar ni terminar con espacios".into());
+    }
+    let classes = [
+        secret.chars().any(|c| c.is_lowercase()),
+        secret.chars().any(|c| c.is_uppercase()),
+        secret.chars().any(|c| c.is_ascii_digit()),
+        secret.chars().any(|c| !c.is_alphanumeric()),
+    ]
+    .iter()
+    .filter(|&&present| present)
+    .count();
+    let required = if len >= PASSPHRASE_LEN { 2 } else { 3 };
+    if classes < required {
+        return Err(format!(
+            "el secreto debe combinar al menos {required} de: minúsculas, mayúsculas, dígitos, símbolos"
+        ));
+    }
+    let mut chars = secret.chars();
+    let first = chars.next();
+    if chars.all(|c| Some(c) == first) {
+        return Err("el secreto no puede ser un único carácter repetido".into());
+    }
+    if !client_id.is_empty() && secret.to_lowercase().contains(&client_id.to_lowercase()) {
+        return Err("el secreto no puede contener el client_id".into());
+    }
+    Ok(())
+}
+
+/// Parámetros de Argon2id con los que se hashean los secretos nuevos
+/// (`ARGON2_MEMORY_KIB`, `ARGON2_ITERATIONS`, `ARGON2_PARALLELISM`). Por
+/// defecto, los recomendados por OWASP (19 MiB, 2 pasadas, 1 hilo).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HashPolicy {
+    pub memory_kib: u32,
+    pub iterations: u32,
+    pub parallelism: u32,
+}
+
+impl Default for HashPolicy {
+    fn default() -> Self {
+        Self {
+            memory_kib: Params::DEFAULT_M_COST,
+            iterations: Params::DEFAULT_T_COST,
+            parallelism: Params::DEFAULT_P_COST,
+        }
+    }
+}
+
+impl HashPolicy {
+    /// Parámetros de Argon2; `Err` si la combinación no es válida (p.ej.
+    /// memoria menor a 8 KiB por hilo).
+    pub fn params(&self) -> Result<Params, String> {
+        Params::new(self.memory_kib, self.iterations, self.parallelism, None)
+            .map_err(|e| format!("parámetros Argon2 inválidos: {e}"))
+    }
+
+    /// ¿El hash debe regenerarse con esta política? Sí si es bcrypt, si no es
+    /// Argon2id v1.3 o si sus parámetros difieren de los configurados. Un hash
+    /// ilegible no se toca (tampoco verifica).
+    pub fn needs_rehash(&self, hash: &str) -> bool {
+        if is_bcrypt(hash) {
+            return true;
+        }
+        let Ok(parsed) = PasswordHash::new(hash) else {
+            return false;
+        };
+        if parsed.algorithm != argon2::ARGON2ID_IDENT || parsed.version != Some(Version::V0x13.into()) {
+            return true;
+        }
+        match Params::try_from(&parsed) {
+            Ok(p) => {
+                p.m_cost() != self.memory_kib
+                    || p.t_cost() != self.iterations
+                    || p.p_cost() != self.parallelism
+            }
+            Err(_) => true,
+        }
+    }
+}
+
+/// Genera el hash Argon2id de un secreto con los parámetros de `policy`.
+pub fn hash_secret(secret: &str, policy: &HashPolicy) -> Result<String, Box<dyn std::error::Error>> {
     let salt = SaltString::generate(&mut OsRng);
-    let hash = Argon2::default()
+    let hash = Argon2::new(Algorithm::Argon2id, Version::V0x13, policy.params()?)
         .hash_password(secret.as_bytes(), &salt)
         .map_err(|e| format!("error hasheando secreto: {e}"))?
         .to_string();
     Ok(hash)
 }
 
-/// Verifica un secreto contra su hash Argon2id. Cualquier fallo → false.
+/// Verifica un secreto contra su hash: Argon2 (con los parámetros que trae el
+/// propio hash) o bcrypt heredado. Cualquier fallo → false.
 pub fn verify_secret(hash: &str, secret: &str) -> bool {
+    if is_bcrypt(hash) {
+        return bcrypt::verify(secret, hash).unwrap_or(false);
+    }
     let Ok(parsed) = PasswordHash::new(hash) else {
         return false;
     };
@@ -27,17 +146,81 @@ pub fn verify_secret(hash: &str, secret: &str) -> bool {
         .is_ok()
 }
 
+/// Hash bcrypt (`$2a$`, `$2b$`, `$2x$`, `$2y$`), como los de `clients.json`.
+fn is_bcrypt(hash: &str) -> bool {
+    ["$2a$", "$2b$", "$2x$", "$2y$"]
+        .iter()
+        .any(|prefix| hash.starts_with(prefix))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
     #[test]
     fn hash_and_verify_roundtrip() {
-        let hash = hash_secret("s3cret").unwrap();
+        let hash = hash_secret("s3cret", &HashPolicy::default()).unwrap();
         assert!(verify_secret(&hash, "s3cret"));
         assert!(!verify_secret(&hash, "malo"));
     }
 
+    #[test]
+    fn hash_uses_configured_params_and_flags_outdated_ones() {
+        let old = HashPolicy::default();
+        let new = HashPolicy {
+            memory_kib: 8192,
+            iterations: 3,
+            parallelism: 1,
+        };
+        let hash = hash_secret("s3cret", &new).unwrap();
+        assert!(hash.starts_with("$argon2id$v=19$m=8192,t=3,p=1$"));
+        assert!(verify_secret(&hash, "s3cret"), "verifica con los parámetros del hash");
+        assert!(!new.needs_rehash(&hash));
+        assert!(old.needs_rehash(&hash));
+        assert!(!old.needs_rehash("no-es-un-hash-valido"));
+    }
+
+    #[test]
+    fn legacy_bcrypt_is_accepted_and_flagged() {
+        let legacy = bcrypt::hash("s3cret", 4).unwrap();
+        assert!(verify_secret(&legacy, "s3cret"));
+        assert!(!verify_secret(&legacy, "malo"));
+        assert!(HashPolicy::default().needs_rehash(&legacy));
+    }
+
+    #[test]
+    fn invalid_policy_is_rejected() {
+        let policy = HashPolicy {
+            memory_kib: 1,
+            iterations: 0,
+            parallelism: 1,
+        };
+        assert!(policy.params().is_err());
+        assert!(hash_secret("s3cret", &policy).is_err());
+    }
+
+    #[test]
+    fn generated_secrets_are_unique_and_pass_the_policy() {
+        let a = generate_secret();
+        assert_eq!(a.len(), 43);
+        assert_ne!(a, generate_secret());
+        // Base64 URL-safe puede salir sin mayúsculas o dígitos, pero con 43
+        // caracteres basta con dos clases.
+        assert!(check_strength(&a, "sigac").is_ok());
+    }
+
+    #[test]
+    fn strength_policy() {
+        assert!(check_strength("Corto1!", "sigac").is_err());
+        assert!(check_strength("solominusculaslarga", "sigac").is_err());
+        assert!(check_strength("Mezcla-de-Clases-99", "sigac").is_ok());
+        assert!(check_strength("unafrasedepasobastantelarga", "sigac").is_err());
+        assert!(check_strength("una frase de paso bastante larga", "sigac").is_ok());
+        assert!(check_strength(" Mezcla-de-Clases-99", "sigac").is_err());
+        assert!(check_strength("Secreto-de-SIGAC-2025", "sigac").is_err());
+        assert!(check_strength("aaaaaaaaaaaaaaaaaaaaaaaaaaaa", "").is_err());
+    }
+
     #[test]
     fn invalid_hash_is_rejected() {
         assert!(!verify_secret("no-es-un-hash-valido", "loquesea"));
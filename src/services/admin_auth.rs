@@ -0,0 +1,386 @@
+//! Cuentas de administración: login con usuario y contraseña, sesiones y
+//! auditoría de quién hizo qué.
+//!
+//! El login verifica la contraseña (Argon2id) con la misma protección de
+//! fuerza bruta que los proyectos (`LockoutService`, clave `admin:<usuario>`)
+//! y abre una sesión: un token opaco con vencimiento del que la BD solo guarda
+//! el SHA-256. Cada request se resuelve a la cuenta dueña de la sesión, así
+//! que deshabilitarla o cambiarle el rol aplica al instante. Depende de los
+//! puertos `AdminAccountRepo` y `AuditRepo`.
+
+use std::sync::{Arc, OnceLock};
+
+use chrono::{DateTime, Duration, Utc};
+use tracing::{info, warn};
+
+use crate::domain::models::{AdminAccount, NewAuditEntry};
+use crate::domain::ports::{AdminAccountRepo, AuditRepo, RepoResult};
+use crate::secret::HashPolicy;
+use crate::services::lockout::{LoginError, LockoutService};
+use crate::services::refresh::{generate_token, hash_token};
+
+/// Sesión recién abierta. `token` solo se entrega esta vez.
+pub struct AdminSession {
+    pub token: String,
+    pub expires_at: DateTime<Utc>,
+    pub account: AdminAccount,
+}
+
+pub struct AdminAuthService {
+    accounts: Arc<dyn AdminAccountRepo>,
+    audit: Arc<dyn AuditRepo>,
+    lockout: Arc<LockoutService>,
+    hash_policy: HashPolicy,
+    session_ttl: Duration,
+    /// Hash señuelo (política actual) para cuentas inexistentes o
+    /// deshabilitadas: el login tarda lo mismo y no revela cuáles existen.
+    dummy_hash: OnceLock<String>,
+}
+
+impl AdminAuthService {
+    pub fn new(
+        accounts: Arc<dyn AdminAccountRepo>,
+        audit: Arc<dyn AuditRepo>,
+        lockout: Arc<LockoutService>,
+        hash_policy: HashPolicy,
+        session_ttl: Duration,
+    ) -> Self {
+        Self {
+            accounts,
+            audit,
+            lockout,
+            hash_policy,
+            session_ttl,
+            dummy_hash: OnceLock::new(),
+        }
+    }
+
+    /// Valida usuario y contraseña y abre una sesión. Cuenta inexistente,
+    /// deshabilitada o contraseña incorrecta → `InvalidCredentials` (y cuenta
+    /// como fallo). Fail-closed ante error de BD.
+    pub async fn login(
+        &self,
+        username: &str,
+        password: &str,
+        ip: Option<&str>,
+    ) -> Result<AdminSession, LoginError> {
+        let key = format!("admin:{username}");
+        self.lockout.check(&key, ip).await?;
+        let account = match self.accounts.find_by_username(username).await? {
+            Some(a) if a.enabled => {
+                Some(a).filter(|a| crate::secret::verify_secret(&a.password_hash, password))
+            }
+            _ => {
+                crate::secret::verify_secret(self.dummy_hash(), password);
+                None
+            }
+        };
+        let Some(account) = account else {
+            if let Err(e) = self.lockout.record_failure(&key, ip).await {
+                warn!("no se pudo registrar el fallo de login de {}: {}", key, e);
+            }
+            return Err(LoginError::InvalidCredentials);
+        };
+        self.lockout.succeeded(&key).await;
+        let account = self.upgrade_hash(account, password).await;
+
+        let token = generate_token();
+        let expires_at = Utc::now() + self.session_ttl;
+        self.accounts
+            .create_session(account.id, &hash_token(&token), expires_at)
+            .await?;
+        info!("login de administración de {} ({})", account.username, account.role.as_str());
+        self.record(
+            "admin_login",
+            &account.username,
+            ip,
+            format!("rol {}", account.role.as_str()),
+        )
+        .await;
+        Ok(AdminSession {
+            token,
+            expires_at,
+            account,
+        })
+    }
+
+    /// Cuenta dueña de la sesión, si está vigente y la cuenta habilitada.
+    pub async fn session(&self, token: &str) -> RepoResult<Option<AdminAccount>> {
+        self.accounts.find_session(&hash_token(token)).await
+    }
+
+    /// Cierra la sesión del token (idempotente).
+    pub async fn logout(&self, token: &str) -> RepoResult<()> {
+        self.accounts.delete_session(&hash_token(token)).await
+    }
+
+    /// Deja en la auditoría una acción de `actor` (cuenta o token de emergencia).
+    /// La auditoría no debe tumbar la operación: un error solo se registra.
+    pub async fn record(&self, event: &str, actor: &str, ip: Option<&str>, detail: String) {
+        let entry = NewAuditEntry {
+            event: event.into(),
+            client_id: Some(actor.to_string()),
+            ip: ip.map(str::to_string),
+            detail: Some(detail),
+        };
+        if let Err(e) = self.audit.record(entry).await {
+            warn!("no se pudo registrar la auditoría: {}", e);
+        }
+    }
+
+    /// Hash de una contraseña aleatoria con la política actual, calculado una
+    /// sola vez. Si no se puede calcular, uno con los parámetros por defecto.
+    fn dummy_hash(&self) -> &str {
+        self.dummy_hash.get_or_init(|| {
+            let password = generate_token();
+            crate::secret::hash_secret(&password, &self.hash_policy)
+                .or_else(|_| crate::secret::hash_secret(&password, &HashPolicy::default()))
+                .unwrap_or_default()
+        })
+    }
+
+    /// Rehashea la contraseña con la política actual si su hash quedó viejo.
+    /// Un fallo no afecta al login: se reintenta en el próximo.
+    async fn upgrade_hash(&self, mut account: AdminAccount, password: &str) -> AdminAccount {
+        if !self.hash_policy.needs_rehash(&account.password_hash) {
+            return account;
+        }
+        match crate::secret::hash_secret(password, &self.hash_policy) {
+            Ok(hash) => account.password_hash = hash,
+            Err(e) => {
+                warn!("no se pudo rehashear la contraseña de {}: {}", account.username, e);
+                return account;
+            }
+        }
+        match self.accounts.update(&account).await {
+            Ok(updated) => updated,
+            Err(e) => {
+                warn!("no se pudo guardar el hash de {}: {}", account.username, e);
+                account
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::AdminAuthService;
+    use crate::domain::models::{
+        AdminAccount, AdminRole, AuditEntry, NewAdminAccount, NewAuditEntry, Throttle,
+        ThrottleScope,
+    };
+    use crate::domain::ports::{AdminAccountRepo, AuditRepo, LoginThrottleRepo, RepoResult};
+    use crate::secret::HashPolicy;
+    use crate::services::lockout::{LockoutPolicy, LockoutService, LoginError};
+    use async_trait::async_trait;
+    use chrono::{DateTime, Duration, Utc};
+    use std::sync::{Arc, Mutex};
+    use uuid::Uuid;
+
+    /// Repo en memoria de cuentas y sesiones (hash → cuenta, vencimiento).
+    #[derive(Default)]
+    struct MemAccountRepo {
+        accounts: Mutex<Vec<AdminAccount>>,
+        sessions: Mutex<Vec<(String, Uuid, DateTime<Utc>)>>,
+    }
+
+    #[async_trait]
+    impl AdminAccountRepo for MemAccountRepo {
+        async fn list_all(&self) -> RepoResult<Vec<AdminAccount>> {
+            unimplemented!()
+        }
+        async fn find_by_id(&self, _: Uuid) -> RepoResult<Option<AdminAccount>> {
+            unimplemented!()
+        }
+        async fn find_by_username(&self, username: &str) -> RepoResult<Option<AdminAccount>> {
+            let accounts = self.accounts.lock().unwrap();
+            Ok(accounts.iter().find(|a| a.username == username).cloned())
+        }
+        async fn create(&self, _: NewAdminAccount) -> RepoResult<AdminAccount> {
+            unimplemented!()
+        }
+        async fn update(&self, account: &AdminAccount) -> RepoResult<AdminAccount> {
+            let mut accounts = self.accounts.lock().unwrap();
+            let stored = accounts.iter_mut().find(|a| a.id == account.id).unwrap();
+            *stored = account.clone();
+            Ok(account.clone())
+        }
+        async fn delete(&self, _: Uuid) -> RepoResult<()> {
+            unimplemented!()
+        }
+        async fn create_session(
+            &self,
+            account_id: Uuid,
+            token_hash: &str,
+            expires_at: DateTime<Utc>,
+        ) -> RepoResult<()> {
+            let session = (token_hash.to_string(), account_id, expires_at);
+            self.sessions.lock().unwrap().push(session);
+            Ok(())
+        }
+        async fn find_session(&self, token_hash: &str) -> RepoResult<Option<AdminAccount>> {
+            let sessions = self.sessions.lock().unwrap();
+            let Some((_, id, expires_at)) = sessions.iter().find(|s| s.0 == token_hash) else {
+                return Ok(None);
+            };
+            let accounts = self.accounts.lock().unwrap();
+            Ok(accounts
+                .iter()
+                .find(|a| a.id == *id && a.enabled && *expires_at > Utc::now())
+                .cloned())
+        }
+        async fn delete_session(&self, token_hash: &str) -> RepoResult<()> {
+            self.sessions.lock().unwrap().retain(|s| s.0 != token_hash);
+            Ok(())
+        }
+        async fn delete_sessions(&self, _: Uuid) -> RepoResult<()> {
+            unimplemented!()
+        }
+    }
+
+    /// Repo falso de contadores: sin bloqueos; solo cuenta los fallos.
+    #[derive(Default)]
+    struct CountingThrottleRepo {
+        failures: Mutex<Vec<String>>,
+    }
+
+    #[async_trait]
+    impl LoginThrottleRepo for CountingThrottleRepo {
+        async fn find(&self, _: ThrottleScope, _: &str) -> RepoResult<Option<Throttle>> {
+            Ok(None)
+        }
+        async fn record_failure(
+            &self,
+            scope: ThrottleScope,
+            key: &str,
+            _: DateTime<Utc>,
+        ) -> RepoResult<Throttle> {
+            self.failures.lock().unwrap().push(key.to_string());
+            Ok(Throttle {
+                scope,
+                key: key.to_string(),
+                failures: 1,
+                last_failure_at: Utc::now(),
+                locked_until: None,
+            })
+        }
+        async fn lock(&self, _: ThrottleScope, _: &str, _: DateTime<Utc>) -> RepoResult<()> {
+            unimplemented!()
+        }
+        async fn clear(&self, _: ThrottleScope, _: &str) -> RepoResult<()> {
+            Ok(())
+        }
+        async fn list_active(&self, _: DateTime<Utc>) -> RepoResult<Vec<Throttle>> {
+            unimplemented!()
+        }
+        async fn purge(&self, _: DateTime<Utc>) -> RepoResult<u64> {
+            unimplemented!()
+        }
+    }
+
+    /// Repo en memoria de auditoría.
+    #[derive(Default)]
+    struct MemAuditRepo {
+        entries: Mutex<Vec<NewAuditEntry>>,
+    }
+
+    #[async_trait]
+    impl AuditRepo for MemAuditRepo {
+        async fn record(&self, new: NewAuditEntry) -> RepoResult<AuditEntry> {
+            self.entries.lock().unwrap().push(new.clone());
+            Ok(AuditEntry {
+                id: Uuid::new_v4(),
+                event: new.event,
+                client_id: new.client_id,
+                ip: new.ip,
+                detail: new.detail,
+                created_at: Utc::now(),
+            })
+        }
+        async fn list_recent(&self, _: i64) -> RepoResult<Vec<AuditEntry>> {
+            unimplemented!()
+        }
+    }
+
+    struct Fixture {
+        service: AdminAuthService,
+        accounts: Arc<MemAccountRepo>,
+        throttles: Arc<CountingThrottleRepo>,
+        audit: Arc<MemAuditRepo>,
+    }
+
+    fn fixture(enabled: bool) -> Fixture {
+        let account = AdminAccount {
+            id: Uuid::new_v4(),
+            username: "ana".into(),
+            password_hash: crate::secret::hash_secret("Cl4ve-de-Ana!", &HashPolicy::default())
+                .unwrap(),
+            role: AdminRole::Operator,
+            enabled,
+            last_login_at: None,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        };
+        let accounts = Arc::new(MemAccountRepo::default());
+        accounts.accounts.lock().unwrap().push(account);
+        let throttles = Arc::new(CountingThrottleRepo::default());
+        let audit = Arc::new(MemAuditRepo::default());
+        let lockout = Arc::new(LockoutService::new(
+            throttles.clone(),
+            audit.clone(),
+            LockoutPolicy::default(),
+        ));
+        Fixture {
+            service: AdminAuthService::new(
+                accounts.clone(),
+                audit.clone(),
+                lockout,
+                HashPolicy::default(),
+                Duration::hours(8),
+            ),
+            accounts,
+            throttles,
+            audit,
+        }
+    }
+
+    #[tokio::test]
+    async fn login_opens_a_session_that_logout_closes() {
+        let f = fixture(true);
+        let session = f.service.login("ana", "Cl4ve-de-Ana!", Some("10.0.0.7")).await.unwrap();
+        assert_eq!(session.account.role, AdminRole::Operator);
+        assert!(session.expires_at > Utc::now() + Duration::hours(7));
+        // En la BD solo queda el hash del token.
+        let stored = f.accounts.sessions.lock().unwrap()[0].0.clone();
+        assert_ne!(stored, session.token);
+
+        let found = f.service.session(&session.token).await.unwrap().unwrap();
+        assert_eq!(found.username, "ana");
+        assert!(f.service.session("otro").await.unwrap().is_none());
+
+        f.service.logout(&session.token).await.unwrap();
+        assert!(f.service.session(&session.token).await.unwrap().is_none());
+
+        let entries = f.audit.entries.lock().unwrap();
+        assert_eq!(entries[0].event, "admin_login");
+        assert_eq!(entries[0].client_id.as_deref(), Some("ana"));
+    }
+
+    #[tokio::test]
+    async fn bad_password_unknown_user_and_disabled_account_count_as_failures() {
+        let f = fixture(true);
+        for (user, password) in [("ana", "otra"), ("nadie", "Cl4ve-de-Ana!")] {
+            let r = f.service.login(user, password, None).await;
+            assert!(matches!(r, Err(LoginError::InvalidCredentials)));
+        }
+        assert_eq!(*f.throttles.failures.lock().unwrap(), vec!["admin:ana", "admin:nadie"]);
+        assert!(f.accounts.sessions.lock().unwrap().is_empty());
+        // La cuenta inexistente también pagó una verificación Argon2.
+        assert!(f.service.dummy_hash.get().is_some_and(|h| h.starts_with("$argon2id$")));
+
+        let f = fixture(false);
+        let r = f.service.login("ana", "Cl4ve-de-Ana!", None).await;
+        assert!(matches!(r, Err(LoginError::InvalidCredentials)));
+        assert!(f.service.dummy_hash.get().is_some(), "deshabilitada: contra el señuelo");
+    }
+}
@@ -1,31 +1,71 @@
 //! Autenticación de proyectos contra la BD (HU 4.3).
 //!
-//! Reemplaza el almacén basado en `clients.json`. Depende del puerto
-//! `ProjectRepo` (DIP). Fail-closed: proyecto inexistente, deshabilitado,
-//! secreto incorrecto o error de BD → autenticación denegada.
+//! Reemplaza el almacén basado en `clients.json`. Depende de los puertos
+//! `ProjectRepo` y `ProjectSecretRepo` (DIP). Fail-closed: proyecto
+//! inexistente, deshabilitado, secreto incorrecto (contra todos sus secretos
+//! vigentes) o error de BD → autenticación denegada.
+//!
+//! Un login correcto contra un hash con parámetros viejos (o bcrypt heredado)
+//! lo regenera con la `HashPolicy` actual, sin intervención del proyecto.
 
 use std::sync::Arc;
 
-use tracing::warn;
+use chrono::{DateTime, Duration, Utc};
+use tracing::{info, warn};
 
-use crate::domain::models::Project;
-use crate::domain::ports::{ProjectRepo, RepoResult};
+use crate::domain::models::{AllowedCamera, Project, ProjectSecret, PublishGrant};
+use crate::domain::ports::{ProjectRepo, ProjectSecretRepo, RepoResult};
+use crate::secret::HashPolicy;
 
 /// Acceso de un proyecto a las cámaras (autorización granular, HU 4.4).
 pub enum CameraAccess {
     /// Acceso a todas las cámaras (bandera all_cameras).
     All,
-    /// Acceso solo a estos paths de cámara (relación n-a-n).
-    Only(Vec<String>),
+    /// Acceso solo a estas cámaras (relación n-a-n), cada una con sus acciones.
+    Only(Vec<AllowedCamera>),
+}
+
+impl CameraAccess {
+    /// Primer fin de ventana entre las cámaras con asignación temporal: un
+    /// token no debe vivir más allá (`None` = ninguna termina).
+    pub fn window_end(&self) -> Option<DateTime<Utc>> {
+        match self {
+            CameraAccess::All => None,
+            CameraAccess::Only(cameras) => cameras.iter().filter_map(|c| c.until).min(),
+        }
+    }
+
+    /// Acceso para un token emitido en `now`: sin las cámaras cuya ventana
+    /// cierra en menos de un minuto. La vida de un token va en minutos
+    /// enteros; antes que dejar que le sobreviva, la cámara queda fuera.
+    pub fn for_token(self, now: DateTime<Utc>) -> Self {
+        match self {
+            CameraAccess::All => CameraAccess::All,
+            CameraAccess::Only(mut cameras) => {
+                cameras.retain(|c| c.until.is_none_or(|end| end - now >= Duration::minutes(1)));
+                CameraAccess::Only(cameras)
+            }
+        }
+    }
 }
 
 pub struct AuthService {
     projects: Arc<dyn ProjectRepo>,
+    secrets: Arc<dyn ProjectSecretRepo>,
+    hash_policy: HashPolicy,
 }
 
 impl AuthService {
-    pub fn new(projects: Arc<dyn ProjectRepo>) -> Self {
-        Self { projects }
+    pub fn new(
+        projects: Arc<dyn ProjectRepo>,
+        secrets: Arc<dyn ProjectSecretRepo>,
+        hash_policy: HashPolicy,
+    ) -> Self {
+        Self {
+            projects,
+            secrets,
+            hash_policy,
+        }
     }
 
     /// Devuelve el proyecto si las credenciales son válidas y está habilitado.
@@ -45,157 +85,395 @@ impl AuthService {
             return None;
         }
 
-        if crate::secret::verify_secret(&project.secret_hash, secret) {
-            Some(project)
-        } else {
-            None
+        // Durante una rotación conviven varios secretos: vale cualquiera vigente.
+        let secrets = match self.secrets.list_active(project.id).await {
+            Ok(secrets) => secrets,
+            Err(e) => {
+                warn!("error consultando los secretos de '{}': {}", client_id, e);
+                return None;
+            }
+        };
+        let matched = secrets
+            .iter()
+            .find(|s| crate::secret::verify_secret(&s.secret_hash, secret))?;
+
+        if let Err(e) = self.secrets.touch(matched.id).await {
+            warn!("no se pudo registrar el uso del secreto '{}': {}", matched.label, e);
+        }
+        self.upgrade_hash(&project, matched, secret).await;
+        Some(project)
+    }
+
+    /// Rehashea el secreto con la política actual si su hash quedó viejo. Un
+    /// fallo no afecta al login: se reintenta en el próximo.
+    async fn upgrade_hash(&self, project: &Project, matched: &ProjectSecret, secret: &str) {
+        if !self.hash_policy.needs_rehash(&matched.secret_hash) {
+            return;
+        }
+        let hash = match crate::secret::hash_secret(secret, &self.hash_policy) {
+            Ok(h) => h,
+            Err(e) => {
+                warn!("no se pudo rehashear el secreto '{}': {}", matched.label, e);
+                return;
+            }
+        };
+        match self.secrets.update_hash(matched.id, &hash).await {
+            Ok(()) => info!(
+                "hash del secreto '{}' de '{}' actualizado a los parámetros vigentes",
+                matched.label, project.client_id
+            ),
+            Err(e) => warn!("no se pudo guardar el nuevo hash de '{}': {}", matched.label, e),
         }
     }
 
     /// Determina el acceso a cámaras del proyecto, para construir los permisos
-    /// del JWT: todas (bandera all_cameras) o solo las asignadas (n-a-n).
+    /// del JWT: todas (bandera all_cameras) o las asignadas (n-a-n) más las de
+    /// sus grupos según la pertenencia actual, más sus prefijos de path. Una
+    /// cámara que llega por varias vías con el mismo fin de ventana suma sus
+    /// acciones; con fines distintos quedan entradas separadas, para que una
+    /// asignación temporal no recorte otra que no vence.
     pub async fn camera_access(&self, project: &Project) -> RepoResult<CameraAccess> {
         if project.all_cameras {
-            Ok(CameraAccess::All)
-        } else {
-            Ok(CameraAccess::Only(
-                self.projects.allowed_camera_paths(project.id).await?,
-            ))
+            return Ok(CameraAccess::All);
         }
+        let mut cameras = self.projects.allowed_camera_paths(project.id).await?;
+        let prefixes = self.projects.prefix_grants(project.id).await?;
+        let extra = self.projects.group_camera_paths(project.id).await?.into_iter().chain(
+            prefixes.into_iter().map(|g| AllowedCamera {
+                path: g.prefix,
+                actions: g.actions,
+                prefix: true,
+                until: None,
+            }),
+        );
+        for other in extra {
+            match cameras.iter_mut().find(|c| {
+                c.path == other.path && c.prefix == other.prefix && c.until == other.until
+            }) {
+                Some(c) => c.actions = c.actions.union(other.actions),
+                None => cameras.push(other),
+            }
+        }
+        cameras.sort_by(|a, b| (a.prefix, &a.path, a.until).cmp(&(b.prefix, &b.path, b.until)));
+        Ok(CameraAccess::Only(cameras))
+    }
+
+    /// Paths donde el proyecto puede publicar. Independiente de `all_cameras`:
+    /// ver todas las cámaras no habilita a publicar en ninguna.
+    pub async fn publish_grants(&self, project: &Project) -> RepoResult<Vec<PublishGrant>> {
+        self.projects.publish_grants(project.id).await
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::{AuthService, CameraAccess};
-    use crate::domain::models::{NewProject, Project};
-    use crate::domain::ports::{ProjectRepo, RepoError, RepoResult};
+    use crate::secret::HashPolicy;
+    use crate::domain::models::{
+        AllowedCamera, CameraActions, NewProjectSecret, PrefixGrant, Project, ProjectSecret,
+    };
+    use crate::domain::ports::{ProjectSecretRepo, RepoResult};
+    use crate::test_support::{project, MemProjectRepo};
     use async_trait::async_trait;
-    use chrono::Utc;
-    use std::sync::Arc;
+    use chrono::{DateTime, Duration, Utc};
+    use std::sync::{Arc, Mutex};
     use uuid::Uuid;
 
-    /// Repo falso: solo implementa `find_by_client_id`.
-    struct FakeProjectRepo {
-        project: Option<Project>,
-        allowed: Vec<String>,
-        fail: bool,
+    fn allowed(path: &str, actions: CameraActions) -> AllowedCamera {
+        AllowedCamera {
+            path: path.into(),
+            actions,
+            prefix: false,
+            until: None,
+        }
     }
 
-    #[async_trait]
-    impl ProjectRepo for FakeProjectRepo {
-        async fn find_by_client_id(&self, client_id: &str) -> RepoResult<Option<Project>> {
-            if self.fail {
-                return Err(RepoError::Backend("boom".into()));
-            }
-            Ok(self.project.clone().filter(|p| p.client_id == client_id))
+    /// Repo en memoria de secretos.
+    #[derive(Default)]
+    struct MemSecretRepo {
+        secrets: Mutex<Vec<ProjectSecret>>,
+    }
+
+    impl MemSecretRepo {
+        fn with(project: &Project, label: &str, secret: &str) -> Self {
+            let repo = MemSecretRepo::default();
+            repo.push(project, label, secret, None);
+            repo
         }
-        async fn find_by_id(&self, _: Uuid) -> RepoResult<Option<Project>> {
-            unimplemented!()
+
+        fn push(&self, project: &Project, label: &str, secret: &str, expires_in: Option<Duration>) {
+            self.secrets.lock().unwrap().push(ProjectSecret {
+                id: Uuid::new_v4(),
+                project_id: project.id,
+                label: label.into(),
+                secret_hash: crate::secret::hash_secret(secret, &HashPolicy::default()).unwrap(),
+                created_at: Utc::now(),
+                expires_at: expires_in.map(|d| Utc::now() + d),
+                last_used_at: None,
+                revoked_at: None,
+            });
         }
-        async fn list_all(&self) -> RepoResult<Vec<Project>> {
+    }
+
+    #[async_trait]
+    impl ProjectSecretRepo for MemSecretRepo {
+        async fn add(&self, _: NewProjectSecret) -> RepoResult<ProjectSecret> {
             unimplemented!()
         }
-        async fn create(&self, _: NewProject) -> RepoResult<Project> {
+        async fn list_by_project(&self, _: Uuid) -> RepoResult<Vec<ProjectSecret>> {
             unimplemented!()
         }
-        async fn update(&self, _: &Project) -> RepoResult<Project> {
-            unimplemented!()
+        async fn list_active(&self, project_id: Uuid) -> RepoResult<Vec<ProjectSecret>> {
+            let now = Utc::now();
+            let secrets = self.secrets.lock().unwrap();
+            Ok(secrets
+                .iter()
+                .filter(|s| s.project_id == project_id && s.is_active(now))
+                .cloned()
+                .collect())
+        }
+        async fn revoke(&self, _: Uuid, id: Uuid) -> RepoResult<()> {
+            let mut secrets = self.secrets.lock().unwrap();
+            secrets.iter_mut().find(|s| s.id == id).unwrap().revoked_at = Some(Utc::now());
+            Ok(())
         }
-        async fn delete(&self, _: Uuid) -> RepoResult<()> {
+        async fn revoke_others(&self, _: Uuid, _: Uuid) -> RepoResult<()> {
             unimplemented!()
         }
-        async fn set_cameras(&self, _: Uuid, _: &[Uuid]) -> RepoResult<()> {
+        async fn expire_others(&self, _: Uuid, _: Uuid, _: DateTime<Utc>) -> RepoResult<()> {
             unimplemented!()
         }
-        async fn allowed_camera_paths(&self, _: Uuid) -> RepoResult<Vec<String>> {
-            Ok(self.allowed.clone())
+        async fn touch(&self, id: Uuid) -> RepoResult<()> {
+            let mut secrets = self.secrets.lock().unwrap();
+            secrets.iter_mut().find(|s| s.id == id).unwrap().last_used_at = Some(Utc::now());
+            Ok(())
         }
-        async fn assigned_camera_ids(&self, _: Uuid) -> RepoResult<Vec<Uuid>> {
-            unimplemented!()
+        async fn update_hash(&self, id: Uuid, secret_hash: &str) -> RepoResult<()> {
+            let mut secrets = self.secrets.lock().unwrap();
+            secrets.iter_mut().find(|s| s.id == id).unwrap().secret_hash = secret_hash.into();
+            Ok(())
         }
     }
 
-    fn project(client_id: &str, secret: &str, enabled: bool) -> Project {
-        Project {
-            id: Uuid::new_v4(),
-            client_id: client_id.into(),
-            secret_hash: crate::secret::hash_secret(secret).unwrap(),
-            all_cameras: true,
-            enabled,
-            created_at: Utc::now(),
-            updated_at: Utc::now(),
-        }
+    fn service(project: Option<Project>, secrets: Arc<MemSecretRepo>) -> AuthService {
+        AuthService::new(
+            Arc::new(MemProjectRepo::with(project)),
+            secrets,
+            HashPolicy::default(),
+        )
     }
 
-    fn service(project: Option<Project>) -> AuthService {
-        AuthService::new(Arc::new(FakeProjectRepo {
-            project,
-            allowed: vec![],
-            fail: false,
-        }))
+    /// Proyecto con un único secreto `s3cret`.
+    fn simple(enabled: bool) -> AuthService {
+        let p = project("sigac", enabled);
+        let secrets = Arc::new(MemSecretRepo::with(&p, "inicial", "s3cret"));
+        service(Some(p), secrets)
     }
 
     #[tokio::test]
     async fn authenticates_valid_enabled_project() {
-        let p = service(Some(project("sigac", "s3cret", true)))
-            .authenticate("sigac", "s3cret")
-            .await;
+        let p = simple(true).authenticate("sigac", "s3cret").await;
         assert_eq!(p.map(|p| p.client_id), Some("sigac".to_string()));
     }
 
     #[tokio::test]
     async fn rejects_wrong_secret() {
-        let svc = service(Some(project("sigac", "s3cret", true)));
-        assert!(svc.authenticate("sigac", "malo").await.is_none());
+        assert!(simple(true).authenticate("sigac", "malo").await.is_none());
     }
 
     #[tokio::test]
     async fn rejects_disabled_project() {
-        let svc = service(Some(project("sigac", "s3cret", false)));
-        assert!(svc.authenticate("sigac", "s3cret").await.is_none());
+        assert!(simple(false).authenticate("sigac", "s3cret").await.is_none());
     }
 
     #[tokio::test]
     async fn rejects_unknown_project() {
-        assert!(service(None).authenticate("sigac", "s3cret").await.is_none());
+        let svc = service(None, Arc::new(MemSecretRepo::default()));
+        assert!(svc.authenticate("sigac", "s3cret").await.is_none());
     }
 
     #[tokio::test]
     async fn fails_closed_on_repo_error() {
-        let svc = AuthService::new(Arc::new(FakeProjectRepo {
-            project: None,
-            allowed: vec![],
-            fail: true,
-        }));
+        let svc = AuthService::new(
+            Arc::new(MemProjectRepo {
+                fail: true,
+                ..MemProjectRepo::default()
+            }),
+            Arc::new(MemSecretRepo::default()),
+            HashPolicy::default(),
+        );
         assert!(svc.authenticate("sigac", "s3cret").await.is_none());
     }
 
+    #[tokio::test]
+    async fn any_active_secret_works_during_rotation() {
+        let p = project("sigac", true);
+        let secrets = Arc::new(MemSecretRepo::with(&p, "viejo", "viejo-s3cret"));
+        secrets.push(&p, "nuevo", "nuevo-s3cret", None);
+        secrets.push(&p, "vencido", "vencido-s3cret", Some(Duration::minutes(-1)));
+        let svc = service(Some(p.clone()), secrets.clone());
+
+        assert!(svc.authenticate("sigac", "viejo-s3cret").await.is_some());
+        assert!(svc.authenticate("sigac", "nuevo-s3cret").await.is_some());
+        assert!(svc.authenticate("sigac", "vencido-s3cret").await.is_none());
+
+        // Revocado el viejo, solo vale el nuevo; el uso queda registrado.
+        let viejo = secrets.secrets.lock().unwrap()[0].id;
+        secrets.revoke(p.id, viejo).await.unwrap();
+        assert!(svc.authenticate("sigac", "viejo-s3cret").await.is_none());
+        assert!(secrets.secrets.lock().unwrap()[1].last_used_at.is_some());
+    }
+
+    #[tokio::test]
+    async fn outdated_and_legacy_hashes_are_upgraded_on_login() {
+        let p = project("sigac", true);
+        let secrets = Arc::new(MemSecretRepo::with(&p, "argon-viejo", "viejo-s3cret"));
+        secrets.push(&p, "bcrypt", "legado-s3cret", None);
+        secrets.secrets.lock().unwrap()[1].secret_hash = bcrypt::hash("legado-s3cret", 4).unwrap();
+        let policy = HashPolicy {
+            memory_kib: 8192,
+            iterations: 3,
+            parallelism: 1,
+        };
+        let svc = AuthService::new(
+            Arc::new(MemProjectRepo::with([p])),
+            secrets.clone(),
+            policy,
+        );
+
+        assert!(svc.authenticate("sigac", "viejo-s3cret").await.is_some());
+        assert!(svc.authenticate("sigac", "legado-s3cret").await.is_some());
+        for s in secrets.secrets.lock().unwrap().iter() {
+            assert!(!policy.needs_rehash(&s.secret_hash), "{} sin actualizar", s.label);
+        }
+        // El hash nuevo sigue verificando el mismo secreto.
+        assert!(svc.authenticate("sigac", "legado-s3cret").await.is_some());
+        assert!(svc.authenticate("sigac", "malo").await.is_none());
+    }
+
     #[tokio::test]
     async fn camera_access_all_when_flag_set() {
-        let p = project("sigac", "s3cret", true); // project() usa all_cameras=true
-        let svc = AuthService::new(Arc::new(FakeProjectRepo {
-            project: Some(p.clone()),
-            allowed: vec!["ignorado".into()],
-            fail: false,
-        }));
+        let p = project("sigac", true); // project() usa all_cameras=true
+        let svc = AuthService::new(
+            Arc::new(MemProjectRepo {
+                projects: vec![p.clone()],
+                allowed: vec![allowed("ignorado", CameraActions::Both)],
+                ..MemProjectRepo::default()
+            }),
+            Arc::new(MemSecretRepo::default()),
+            HashPolicy::default(),
+        );
         assert!(matches!(svc.camera_access(&p).await.unwrap(), CameraAccess::All));
     }
 
     #[tokio::test]
     async fn camera_access_only_assigned_when_not_all() {
-        let mut p = project("sigac", "s3cret", true);
+        let mut p = project("sigac", true);
         p.all_cameras = false;
-        let svc = AuthService::new(Arc::new(FakeProjectRepo {
-            project: Some(p.clone()),
-            allowed: vec!["cam-a".into(), "cam-b".into()],
-            fail: false,
-        }));
+        let svc = AuthService::new(
+            Arc::new(MemProjectRepo {
+                projects: vec![p.clone()],
+                allowed: vec![
+                    allowed("cam-a", CameraActions::Both),
+                    allowed("cam-b", CameraActions::Playback),
+                ],
+                ..MemProjectRepo::default()
+            }),
+            Arc::new(MemSecretRepo::default()),
+            HashPolicy::default(),
+        );
         match svc.camera_access(&p).await.unwrap() {
-            CameraAccess::Only(paths) => {
-                assert_eq!(paths, vec!["cam-a".to_string(), "cam-b".to_string()])
+            CameraAccess::Only(cameras) => {
+                let paths: Vec<_> = cameras.iter().map(|c| (c.path.as_str(), c.actions)).collect();
+                assert_eq!(
+                    paths,
+                    vec![("cam-a", CameraActions::Both), ("cam-b", CameraActions::Playback)]
+                )
             }
             CameraAccess::All => panic!("esperaba Only"),
         }
     }
+
+    #[tokio::test]
+    async fn camera_access_merges_groups_and_prefixes_into_direct_grants() {
+        let mut p = project("contratista", true);
+        p.all_cameras = false;
+        let soon = Utc::now() + Duration::hours(1);
+        let dated = AllowedCamera {
+            until: Some(soon),
+            ..allowed("norte-c", CameraActions::Read)
+        };
+        let svc = AuthService::new(
+            Arc::new(MemProjectRepo {
+                projects: vec![p.clone()],
+                allowed: vec![allowed("norte-b", CameraActions::Read), dated.clone()],
+                prefixes: vec![PrefixGrant {
+                    prefix: "norte-".into(),
+                    actions: CameraActions::Playback,
+                }],
+                grouped: vec![
+                    allowed("norte-a", CameraActions::Read),
+                    allowed("norte-b", CameraActions::Playback),
+                    allowed("norte-a", CameraActions::Read), // en dos grupos
+                    allowed("norte-c", CameraActions::Read),
+                ],
+                ..MemProjectRepo::default()
+            }),
+            Arc::new(MemSecretRepo::default()),
+            HashPolicy::default(),
+        );
+        match svc.camera_access(&p).await.unwrap() {
+            CameraAccess::Only(cameras) => assert_eq!(
+                cameras,
+                vec![
+                    allowed("norte-a", CameraActions::Read),
+                    allowed("norte-b", CameraActions::Both),
+                    // Con fines de ventana distintos no se funden: la vía del
+                    // grupo sigue sin vencer.
+                    allowed("norte-c", CameraActions::Read),
+                    dated,
+                    // El prefijo no se funde con las cámaras que cubre.
+                    AllowedCamera {
+                        path: "norte-".into(),
+                        actions: CameraActions::Playback,
+                        prefix: true,
+                        until: None,
+                    },
+                ]
+            ),
+            CameraAccess::All => panic!("esperaba Only"),
+        }
+    }
+
+    #[tokio::test]
+    async fn window_end_is_the_earliest_dated_grant() {
+        let mut p = project("auditoria", true);
+        p.all_cameras = false;
+        let soon = Utc::now() + Duration::hours(1);
+        let later = soon + Duration::hours(8);
+        let until = |path: &str, until| AllowedCamera {
+            until: Some(until),
+            ..allowed(path, CameraActions::Read)
+        };
+        let svc = AuthService::new(
+            Arc::new(MemProjectRepo {
+                projects: vec![p.clone()],
+                allowed: vec![until("norte-a", later), until("norte-b", soon)],
+                grouped: vec![allowed("norte-b", CameraActions::Playback)],
+                ..MemProjectRepo::default()
+            }),
+            Arc::new(MemSecretRepo::default()),
+            HashPolicy::default(),
+        );
+        let access = svc.camera_access(&p).await.unwrap();
+        match &access {
+            CameraAccess::Only(cameras) => assert_eq!(
+                cameras.iter().map(|c| c.until).collect::<Vec<_>>(),
+                vec![Some(later), None, Some(soon)]
+            ),
+            CameraAccess::All => panic!("esperaba Only"),
+        }
+        assert_eq!(access.window_end(), Some(soon));
+        assert_eq!(CameraAccess::All.window_end(), None);
+    }
 }
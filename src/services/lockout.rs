@@ -0,0 +1,553 @@
+//! Protección contra fuerza bruta en el login de proyectos.
+//!
+//! Cuenta los fallos recientes por `client_id` y por IP de origen (puerto
+//! `LoginThrottleRepo`, persistido para que valga entre instancias y
+//! reinicios). Al superar el umbral, el ámbito queda bloqueado un tiempo que
+//! se duplica con cada fallo adicional (backoff progresivo, con techo).
+//! Mientras dure el bloqueo no se evalúan credenciales: el cliente recibe
+//! 429 con `Retry-After`. Fallos, bloqueos y desbloqueos quedan en la
+//! auditoría (puerto `AuditRepo`), nunca con el secreto presentado.
+//!
+//! Un proyecto con lista de redes permitidas (`allowed_cidrs`) solo se
+//! autentica desde ellas: desde otra IP se rechaza aunque el secreto sea
+//! correcto, y queda en la auditoría (sin contar como fallo).
+
+use std::sync::Arc;
+
+use chrono::{DateTime, Duration, Utc};
+use tracing::warn;
+
+use crate::domain::models::{AuditEntry, NewAuditEntry, Project, Throttle, ThrottleScope};
+use crate::domain::ports::{AuditRepo, LoginThrottleRepo, RepoError, RepoResult};
+use crate::services::auth::AuthService;
+
+/// Umbrales y duraciones del bloqueo.
+#[derive(Debug, Clone)]
+pub struct LockoutPolicy {
+    /// Fallos seguidos de un `client_id` antes de bloquearlo (0 = sin límite).
+    pub max_failures_client: i32,
+    /// Fallos desde una IP (cualquier `client_id`) antes de bloquearla (0 = sin límite).
+    pub max_failures_ip: i32,
+    /// Un contador sin fallos ni bloqueo durante este tiempo vuelve a cero.
+    pub window: Duration,
+    /// Bloqueo al alcanzar el umbral; se duplica con cada fallo adicional.
+    pub base_lock: Duration,
+    /// Techo del bloqueo.
+    pub max_lock: Duration,
+}
+
+impl Default for LockoutPolicy {
+    fn default() -> Self {
+        Self {
+            max_failures_client: 5,
+            max_failures_ip: 20,
+            window: Duration::minutes(15),
+            base_lock: Duration::minutes(1),
+            max_lock: Duration::hours(1),
+        }
+    }
+}
+
+impl LockoutPolicy {
+    fn threshold(&self, scope: ThrottleScope) -> i32 {
+        match scope {
+            ThrottleScope::Client => self.max_failures_client,
+            ThrottleScope::Ip => self.max_failures_ip,
+        }
+    }
+
+    /// Bloqueo tras `failures` fallos, o `None` si aún no alcanza el umbral.
+    fn lock_for(&self, scope: ThrottleScope, failures: i32) -> Option<Duration> {
+        let threshold = self.threshold(scope);
+        if threshold <= 0 || failures < threshold {
+            return None;
+        }
+        let doublings = (failures - threshold).min(20) as u32;
+        let lock = self.base_lock * 2i32.pow(doublings);
+        Some(lock.min(self.max_lock))
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum LoginError {
+    /// `client_id` o IP bloqueados; reintentar en `retry_after` segundos.
+    #[error("demasiados intentos fallidos; reintente en {retry_after} s")]
+    Locked { retry_after: i64 },
+    /// Credenciales inválidas, proyecto inexistente o deshabilitado.
+    #[error("credenciales inválidas")]
+    InvalidCredentials,
+    /// Credenciales válidas desde una IP fuera de la lista del proyecto. Se
+    /// responde como `InvalidCredentials`: no confirma que el secreto sirva.
+    #[error("IP de origen no permitida")]
+    IpNotAllowed,
+    #[error(transparent)]
+    Repo(#[from] RepoError),
+}
+
+pub struct LockoutService {
+    throttles: Arc<dyn LoginThrottleRepo>,
+    audit: Arc<dyn AuditRepo>,
+    policy: LockoutPolicy,
+}
+
+impl LockoutService {
+    pub fn new(
+        throttles: Arc<dyn LoginThrottleRepo>,
+        audit: Arc<dyn AuditRepo>,
+        policy: LockoutPolicy,
+    ) -> Self {
+        Self {
+            throttles,
+            audit,
+            policy,
+        }
+    }
+
+    /// Autentica el proyecto con protección de fuerza bruta: rechaza sin
+    /// evaluar el secreto si el `client_id` o la IP están bloqueados, cuenta el
+    /// fallo si las credenciales no son válidas, rechaza una IP fuera de la
+    /// lista del proyecto y limpia el contador del `client_id` en un login
+    /// exitoso. Fail-closed ante error de BD.
+    pub async fn authenticate(
+        &self,
+        auth: &AuthService,
+        client_id: &str,
+        secret: &str,
+        ip: Option<&str>,
+    ) -> Result<Project, LoginError> {
+        self.check(client_id, ip).await?;
+        match auth.authenticate(client_id, secret).await {
+            Some(project) if self.check_source(&project, ip).await.is_err() => {
+                // No cuenta como fallo: bloquearía al proyecto legítimo.
+                Err(LoginError::IpNotAllowed)
+            }
+            Some(project) => {
+                self.succeeded(client_id).await;
+                Ok(project)
+            }
+            None => {
+                // El fallo ya es la respuesta; un error al contarlo solo se registra.
+                if let Err(e) = self.record_failure(client_id, ip).await {
+                    warn!("no se pudo registrar el fallo de login de {}: {}", client_id, e);
+                }
+                Err(LoginError::InvalidCredentials)
+            }
+        }
+    }
+
+    /// `IpNotAllowed` (auditado) si `ip` no está en las redes permitidas del
+    /// proyecto. También lo aplican los canjes que no pasan por el secreto
+    /// (refresh token, token externo).
+    pub async fn check_source(&self, project: &Project, ip: Option<&str>) -> Result<(), LoginError> {
+        if project.allows_ip(ip) {
+            return Ok(());
+        }
+        warn!("login de {} desde IP no permitida: {:?}", project.client_id, ip);
+        self.audit(NewAuditEntry {
+            event: "login_ip_denied".into(),
+            client_id: Some(project.client_id.clone()),
+            ip: ip.map(str::to_string),
+            detail: Some("IP fuera de las redes permitidas del proyecto".into()),
+        })
+        .await;
+        Err(LoginError::IpNotAllowed)
+    }
+
+    /// `Locked` con los segundos restantes si el `client_id` o la IP están bloqueados.
+    pub async fn check(&self, client_id: &str, ip: Option<&str>) -> Result<(), LoginError> {
+        let now = Utc::now();
+        let mut until: Option<DateTime<Utc>> = None;
+        for (scope, key) in scopes(client_id, ip) {
+            if let Some(t) = self.throttles.find(scope, key).await? {
+                until = until.max(t.locked_until.filter(|&u| u > now));
+            }
+        }
+        match until {
+            Some(until) => Err(LoginError::Locked {
+                retry_after: retry_after(until, now),
+            }),
+            None => Ok(()),
+        }
+    }
+
+    /// Login correcto de `client_id`: vuelve su contador a cero. Un error solo
+    /// se registra (el login ya se concedió).
+    pub async fn succeeded(&self, client_id: &str) {
+        match self.throttles.clear(ThrottleScope::Client, client_id).await {
+            Ok(()) | Err(RepoError::NotFound) => {}
+            Err(e) => warn!("no se pudo limpiar el contador de {}: {}", client_id, e),
+        }
+    }
+
+    /// Cuenta un fallo en cada ámbito y bloquea los que alcanzan su umbral.
+    /// Lo usan también los logins que no son de proyecto (cuentas de
+    /// administración), con su propia clave en el ámbito `client`.
+    pub async fn record_failure(&self, client_id: &str, ip: Option<&str>) -> RepoResult<()> {
+        let now = Utc::now();
+        self.audit(NewAuditEntry {
+            event: "login_failed".into(),
+            client_id: Some(client_id.to_string()),
+            ip: ip.map(str::to_string),
+            detail: None,
+        })
+        .await;
+
+        for (scope, key) in scopes(client_id, ip) {
+            let t = self
+                .throttles
+                .record_failure(scope, key, now - self.policy.window)
+                .await?;
+            let Some(lock) = self.policy.lock_for(scope, t.failures) else {
+                continue;
+            };
+            let until = now + lock;
+            self.throttles.lock(scope, key, until).await?;
+            warn!(
+                "login bloqueado para {} {} durante {} s ({} fallos)",
+                scope.as_str(),
+                key,
+                lock.num_seconds(),
+                t.failures
+            );
+            self.audit(NewAuditEntry {
+                event: "lockout".into(),
+                client_id: Some(client_id.to_string()),
+                ip: ip.map(str::to_string),
+                detail: Some(format!(
+                    "{} {} bloqueado hasta {} ({} fallos)",
+                    scope.as_str(),
+                    key,
+                    until.to_rfc3339(),
+                    t.failures
+                )),
+            })
+            .await;
+        }
+        Ok(())
+    }
+
+    /// Contadores activos (con fallos recientes o bloqueados).
+    pub async fn list(&self) -> RepoResult<Vec<Throttle>> {
+        self.throttles.list_active(Utc::now() - self.policy.window).await
+    }
+
+    /// Desbloqueo manual desde administración. `NotFound` si no había contador.
+    pub async fn clear(&self, scope: ThrottleScope, key: &str) -> RepoResult<()> {
+        self.throttles.clear(scope, key).await?;
+        let (client_id, ip) = match scope {
+            ThrottleScope::Client => (Some(key.to_string()), None),
+            ThrottleScope::Ip => (None, Some(key.to_string())),
+        };
+        self.audit(NewAuditEntry {
+            event: "lockout_cleared".into(),
+            client_id,
+            ip,
+            detail: Some("desbloqueo manual (admin)".into()),
+        })
+        .await;
+        Ok(())
+    }
+
+    /// Entradas de auditoría más recientes.
+    pub async fn audit_log(&self, limit: i64) -> RepoResult<Vec<AuditEntry>> {
+        self.audit.list_recent(limit).await
+    }
+
+    /// Borra los contadores que ya no están activos. Devuelve cuántos borró.
+    pub async fn purge(&self) -> RepoResult<u64> {
+        self.throttles.purge(Utc::now() - self.policy.window).await
+    }
+
+    /// La auditoría no debe tumbar el login: un error solo se registra.
+    async fn audit(&self, entry: NewAuditEntry) {
+        if let Err(e) = self.audit.record(entry).await {
+            warn!("no se pudo registrar la auditoría: {}", e);
+        }
+    }
+}
+
+fn scopes<'a>(client_id: &'a str, ip: Option<&'a str>) -> Vec<(ThrottleScope, &'a str)> {
+    let mut scopes = vec![(ThrottleScope::Client, client_id)];
+    if let Some(ip) = ip {
+        scopes.push((ThrottleScope::Ip, ip));
+    }
+    scopes
+}
+
+/// Segundos hasta `until`, redondeado hacia arriba (mínimo 1).
+fn retry_after(until: DateTime<Utc>, now: DateTime<Utc>) -> i64 {
+    let ms = (until - now).num_milliseconds();
+    ((ms + 999) / 1000).max(1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{LockoutPolicy, LockoutService, LoginError};
+    use crate::domain::models::{
+        AuditEntry, NewAuditEntry, NewProjectSecret, Project, ProjectSecret, Throttle,
+        ThrottleScope,
+    };
+    use crate::domain::ports::{
+        AuditRepo, LoginThrottleRepo, ProjectSecretRepo, RepoError, RepoResult,
+    };
+    use crate::test_support::{project, MemProjectRepo};
+    use crate::secret::HashPolicy;
+    use crate::services::auth::AuthService;
+    use async_trait::async_trait;
+    use chrono::{DateTime, Duration, Utc};
+    use std::sync::{Arc, Mutex};
+    use uuid::Uuid;
+
+    /// Repo en memoria de contadores.
+    #[derive(Default)]
+    struct MemThrottleRepo {
+        rows: Mutex<Vec<Throttle>>,
+    }
+
+    #[async_trait]
+    impl LoginThrottleRepo for MemThrottleRepo {
+        async fn find(&self, scope: ThrottleScope, key: &str) -> RepoResult<Option<Throttle>> {
+            let rows = self.rows.lock().unwrap();
+            Ok(rows.iter().find(|t| t.scope == scope && t.key == key).cloned())
+        }
+        async fn record_failure(
+            &self,
+            scope: ThrottleScope,
+            key: &str,
+            window_start: DateTime<Utc>,
+        ) -> RepoResult<Throttle> {
+            let mut rows = self.rows.lock().unwrap();
+            let now = Utc::now();
+            match rows.iter_mut().find(|t| t.scope == scope && t.key == key) {
+                Some(t) => {
+                    let last = t.locked_until.map_or(t.last_failure_at, |u| u.max(t.last_failure_at));
+                    t.failures = if last < window_start { 1 } else { t.failures + 1 };
+                    t.last_failure_at = now;
+                    Ok(t.clone())
+                }
+                None => {
+                    let t = Throttle {
+                        scope,
+                        key: key.into(),
+                        failures: 1,
+                        last_failure_at: now,
+                        locked_until: None,
+                    };
+                    rows.push(t.clone());
+                    Ok(t)
+                }
+            }
+        }
+        async fn lock(&self, scope: ThrottleScope, key: &str, until: DateTime<Utc>) -> RepoResult<()> {
+            let mut rows = self.rows.lock().unwrap();
+            if let Some(t) = rows.iter_mut().find(|t| t.scope == scope && t.key == key) {
+                t.locked_until = Some(until);
+            }
+            Ok(())
+        }
+        async fn clear(&self, scope: ThrottleScope, key: &str) -> RepoResult<()> {
+            let mut rows = self.rows.lock().unwrap();
+            let before = rows.len();
+            rows.retain(|t| !(t.scope == scope && t.key == key));
+            if rows.len() == before {
+                return Err(RepoError::NotFound);
+            }
+            Ok(())
+        }
+        async fn list_active(&self, _: DateTime<Utc>) -> RepoResult<Vec<Throttle>> {
+            Ok(self.rows.lock().unwrap().clone())
+        }
+        async fn purge(&self, _: DateTime<Utc>) -> RepoResult<u64> {
+            unimplemented!()
+        }
+    }
+
+    /// Repo en memoria de auditoría.
+    #[derive(Default)]
+    struct MemAuditRepo {
+        entries: Mutex<Vec<AuditEntry>>,
+    }
+
+    #[async_trait]
+    impl AuditRepo for MemAuditRepo {
+        async fn record(&self, new: NewAuditEntry) -> RepoResult<AuditEntry> {
+            let e = AuditEntry {
+                id: Uuid::new_v4(),
+                event: new.event,
+                client_id: new.client_id,
+                ip: new.ip,
+                detail: new.detail,
+                created_at: Utc::now(),
+            };
+            self.entries.lock().unwrap().push(e.clone());
+            Ok(e)
+        }
+        async fn list_recent(&self, _: i64) -> RepoResult<Vec<AuditEntry>> {
+            Ok(self.entries.lock().unwrap().iter().rev().cloned().collect())
+        }
+    }
+
+    /// Repo falso de secretos: un único secreto vigente.
+    struct FakeSecretRepo {
+        secret: ProjectSecret,
+    }
+
+    #[async_trait]
+    impl ProjectSecretRepo for FakeSecretRepo {
+        async fn add(&self, _: NewProjectSecret) -> RepoResult<ProjectSecret> {
+            unimplemented!()
+        }
+        async fn list_by_project(&self, _: Uuid) -> RepoResult<Vec<ProjectSecret>> {
+            unimplemented!()
+        }
+        async fn list_active(&self, project_id: Uuid) -> RepoResult<Vec<ProjectSecret>> {
+            Ok(Some(self.secret.clone()).filter(|s| s.project_id == project_id).into_iter().collect())
+        }
+        async fn revoke(&self, _: Uuid, _: Uuid) -> RepoResult<()> {
+            unimplemented!()
+        }
+        async fn revoke_others(&self, _: Uuid, _: Uuid) -> RepoResult<()> {
+            unimplemented!()
+        }
+        async fn expire_others(&self, _: Uuid, _: Uuid, _: DateTime<Utc>) -> RepoResult<()> {
+            unimplemented!()
+        }
+        async fn touch(&self, _: Uuid) -> RepoResult<()> {
+            Ok(())
+        }
+        async fn update_hash(&self, _: Uuid, _: &str) -> RepoResult<()> {
+            unimplemented!()
+        }
+    }
+
+    struct Fixture {
+        auth: AuthService,
+        lockout: LockoutService,
+        throttles: Arc<MemThrottleRepo>,
+        audit: Arc<MemAuditRepo>,
+    }
+
+    fn fixture(policy: LockoutPolicy) -> Fixture {
+        fixture_with_cidrs(policy, &[])
+    }
+
+    fn fixture_with_cidrs(policy: LockoutPolicy, cidrs: &[&str]) -> Fixture {
+        let project = Project {
+            allowed_cidrs: cidrs.iter().map(|c| c.to_string()).collect(),
+            ..project("sigac", true)
+        };
+        let secret = ProjectSecret {
+            id: Uuid::new_v4(),
+            project_id: project.id,
+            label: "inicial".into(),
+            secret_hash: crate::secret::hash_secret("s3cret", &HashPolicy::default()).unwrap(),
+            created_at: Utc::now(),
+            expires_at: None,
+            last_used_at: None,
+            revoked_at: None,
+        };
+        let throttles = Arc::new(MemThrottleRepo::default());
+        let audit = Arc::new(MemAuditRepo::default());
+        Fixture {
+            auth: AuthService::new(
+                Arc::new(MemProjectRepo::with([project])),
+                Arc::new(FakeSecretRepo { secret }),
+                HashPolicy::default(),
+            ),
+            lockout: LockoutService::new(throttles.clone(), audit.clone(), policy),
+            throttles,
+            audit,
+        }
+    }
+
+    fn policy(max_client: i32, max_ip: i32) -> LockoutPolicy {
+        LockoutPolicy {
+            max_failures_client: max_client,
+            max_failures_ip: max_ip,
+            ..LockoutPolicy::default()
+        }
+    }
+
+    #[test]
+    fn lock_doubles_per_extra_failure_up_to_cap() {
+        let p = LockoutPolicy::default();
+        assert_eq!(p.lock_for(ThrottleScope::Client, 4), None);
+        assert_eq!(p.lock_for(ThrottleScope::Client, 5), Some(Duration::minutes(1)));
+        assert_eq!(p.lock_for(ThrottleScope::Client, 6), Some(Duration::minutes(2)));
+        assert_eq!(p.lock_for(ThrottleScope::Client, 8), Some(Duration::minutes(8)));
+        assert_eq!(p.lock_for(ThrottleScope::Client, 500), Some(Duration::hours(1)));
+        assert_eq!(p.lock_for(ThrottleScope::Ip, 19), None);
+        assert_eq!(policy(0, 0).lock_for(ThrottleScope::Client, 500), None);
+    }
+
+    #[tokio::test]
+    async fn locks_client_after_threshold_even_with_right_secret() {
+        let f = fixture(policy(3, 0));
+        for _ in 0..3 {
+            let r = f.lockout.authenticate(&f.auth, "sigac", "malo", None).await;
+            assert!(matches!(r, Err(LoginError::InvalidCredentials)));
+        }
+        match f.lockout.authenticate(&f.auth, "sigac", "s3cret", None).await {
+            Err(LoginError::Locked { retry_after }) => assert!((1..=60).contains(&retry_after)),
+            other => panic!("esperaba Locked, obtuve {:?}", other.map(|p| p.client_id)),
+        }
+        let events: Vec<String> = f.audit.entries.lock().unwrap().iter().map(|e| e.event.clone()).collect();
+        assert_eq!(events.iter().filter(|e| *e == "login_failed").count(), 3);
+        assert!(events.contains(&"lockout".to_string()));
+    }
+
+    #[tokio::test]
+    async fn ip_lock_covers_every_client_id() {
+        let f = fixture(policy(0, 2));
+        f.lockout.authenticate(&f.auth, "a", "x", Some("10.0.0.9")).await.ok();
+        f.lockout.authenticate(&f.auth, "b", "x", Some("10.0.0.9")).await.ok();
+
+        let r = f.lockout.authenticate(&f.auth, "sigac", "s3cret", Some("10.0.0.9")).await;
+        assert!(matches!(r, Err(LoginError::Locked { .. })));
+        // Desde otra IP el proyecto entra.
+        let r = f.lockout.authenticate(&f.auth, "sigac", "s3cret", Some("10.0.0.1")).await;
+        assert!(r.is_ok());
+    }
+
+    #[tokio::test]
+    async fn success_resets_client_counter() {
+        let f = fixture(policy(3, 0));
+        f.lockout.authenticate(&f.auth, "sigac", "malo", None).await.ok();
+        f.lockout.authenticate(&f.auth, "sigac", "malo", None).await.ok();
+        assert!(f.lockout.authenticate(&f.auth, "sigac", "s3cret", None).await.is_ok());
+        assert!(f.throttles.rows.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn allowlist_denies_other_ips_without_counting_failures() {
+        let f = fixture_with_cidrs(policy(1, 1), &["10.20.0.0/16"]);
+        assert!(f.lockout.authenticate(&f.auth, "sigac", "s3cret", Some("10.20.3.4")).await.is_ok());
+        for ip in [Some("192.0.2.1"), None] {
+            let r = f.lockout.authenticate(&f.auth, "sigac", "s3cret", ip).await;
+            assert!(matches!(r, Err(LoginError::IpNotAllowed)));
+        }
+        // Los rechazos no bloquean al proyecto legítimo.
+        assert!(f.throttles.rows.lock().unwrap().is_empty());
+        assert!(f.lockout.authenticate(&f.auth, "sigac", "s3cret", Some("10.20.3.4")).await.is_ok());
+
+        let entries = f.audit.entries.lock().unwrap();
+        let denied: Vec<_> = entries.iter().filter(|e| e.event == "login_ip_denied").collect();
+        assert_eq!(denied.len(), 2);
+        assert_eq!(denied[0].ip.as_deref(), Some("192.0.2.1"));
+    }
+
+    #[tokio::test]
+    async fn admin_clear_unlocks_and_is_audited() {
+        let f = fixture(policy(1, 0));
+        f.lockout.authenticate(&f.auth, "sigac", "malo", None).await.ok();
+        assert!(f.lockout.check("sigac", None).await.is_err());
+
+        f.lockout.clear(ThrottleScope::Client, "sigac").await.unwrap();
+        assert!(f.lockout.check("sigac", None).await.is_ok());
+        assert_eq!(f.lockout.audit_log(10).await.unwrap()[0].event, "lockout_cleared");
+        assert!(matches!(
+            f.lockout.clear(ThrottleScope::Client, "sigac").await,
+            Err(RepoError::NotFound)
+        ));
+    }
+}
@@ -1,5 +1,10 @@
 //! Servicios de aplicación (HU 4.2+): orquestan el dominio y los adaptadores.
 //! Dependen de los puertos (traits), no de las implementaciones concretas.
 
+pub mod admin_auth;
 pub mod auth;
+pub mod lockout;
 pub mod reconciler;
+pub mod refresh;
+pub mod revocation;
+pub mod token_exchange;
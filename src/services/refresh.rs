@@ -0,0 +1,347 @@
+//! Refresh tokens opacos y rotatorios para proyectos.
+//!
+//! Permite renovar el JWT sin volver a enviar el `client_secret`. Cada canje
+//! rota el token (el anterior queda usado) dentro de su familia. Presentar un
+//! token ya usado o revocado indica robo: se revoca la familia completa y el
+//! legítimo dueño tendrá que volver a autenticarse. En la BD solo vive el
+//! SHA-256 del token. Depende de los puertos `RefreshTokenRepo` y `ProjectRepo`.
+
+use std::sync::Arc;
+
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use chrono::{Duration, Utc};
+use rand::rngs::OsRng;
+use rand::RngCore;
+use sha2::{Digest, Sha256};
+use tracing::warn;
+use uuid::Uuid;
+
+use crate::domain::models::{NewRefreshToken, Project, RefreshToken};
+use crate::domain::ports::{ProjectRepo, RefreshTokenRepo, RepoError, RepoResult};
+
+/// Bytes aleatorios de cada refresh token (256 bits).
+const TOKEN_BYTES: usize = 32;
+
+#[derive(Debug, thiserror::Error)]
+pub enum RefreshError {
+    /// Token desconocido, expirado o de un proyecto inexistente/deshabilitado.
+    #[error("refresh token inválido")]
+    Invalid,
+    /// Token ya canjeado o revocado: la familia quedó revocada.
+    #[error("refresh token reutilizado; familia revocada")]
+    Reused,
+    #[error(transparent)]
+    Repo(#[from] RepoError),
+}
+
+/// Refresh token presentado y válido, aún sin canjear: quien lo recibe puede
+/// rechazarlo (otro cliente, IP no permitida) sin consumirlo.
+pub struct Presented {
+    pub project: Project,
+    token: RefreshToken,
+}
+
+impl Presented {
+    /// Scope pedido al emitir la familia.
+    pub fn scope(&self) -> Option<&str> {
+        self.token.scope.as_deref()
+    }
+}
+
+/// Resultado de un canje: el proyecto (re-leído de la BD) y el nuevo refresh
+/// token.
+pub struct Rotated {
+    pub project: Project,
+    pub refresh_token: String,
+}
+
+pub struct RefreshService {
+    tokens: Arc<dyn RefreshTokenRepo>,
+    projects: Arc<dyn ProjectRepo>,
+    /// Vida de cada refresh token; `None` = refresh tokens deshabilitados.
+    ttl: Option<Duration>,
+}
+
+impl RefreshService {
+    pub fn new(
+        tokens: Arc<dyn RefreshTokenRepo>,
+        projects: Arc<dyn ProjectRepo>,
+        ttl: Option<Duration>,
+    ) -> Self {
+        Self {
+            tokens,
+            projects,
+            ttl,
+        }
+    }
+
+    /// Emite el primer token de una familia nueva. `None` si están deshabilitados.
+    pub async fn issue(&self, project_id: Uuid, scope: Option<&str>) -> RepoResult<Option<String>> {
+        let Some(ttl) = self.ttl else {
+            return Ok(None);
+        };
+        let token = self
+            .store(Uuid::new_v4(), project_id, scope.map(str::to_string), ttl)
+            .await?;
+        Ok(Some(token))
+    }
+
+    /// Valida un refresh token sin consumirlo y devuelve su proyecto, para que
+    /// el llamador haga sus comprobaciones antes de `rotate`. Un token ya usado
+    /// o revocado sí revoca la familia (reuso).
+    pub async fn present(&self, presented: &str) -> Result<Presented, RefreshError> {
+        if self.ttl.is_none() {
+            return Err(RefreshError::Invalid);
+        }
+        let token = self
+            .tokens
+            .find_by_hash(&hash_token(presented))
+            .await?
+            .ok_or(RefreshError::Invalid)?;
+
+        if token.used_at.is_some() || token.revoked_at.is_some() {
+            return Err(self.reused(token.family_id).await);
+        }
+        if token.expires_at <= Utc::now() {
+            return Err(RefreshError::Invalid);
+        }
+
+        // Se re-lee el proyecto: deshabilitarlo corta también las renovaciones.
+        let project = match self.projects.find_by_id(token.project_id).await? {
+            Some(p) if p.enabled => p,
+            _ => {
+                self.tokens.revoke_family(token.family_id).await?;
+                return Err(RefreshError::Invalid);
+            }
+        };
+        Ok(Presented { project, token })
+    }
+
+    /// Canjea un token ya validado por `present`: lo marca usado y emite el
+    /// siguiente de la familia.
+    pub async fn rotate(&self, presented: Presented) -> Result<Rotated, RefreshError> {
+        let ttl = self.ttl.ok_or(RefreshError::Invalid)?;
+        let Presented { project, token } = presented;
+        // Atómico: si otro canje concurrente ganó, también es reuso.
+        if !self.tokens.mark_used(token.id).await? {
+            return Err(self.reused(token.family_id).await);
+        }
+
+        let refresh_token = self
+            .store(token.family_id, token.project_id, token.scope.clone(), ttl)
+            .await?;
+        Ok(Rotated {
+            project,
+            refresh_token,
+        })
+    }
+
+    /// Revoca todos los refresh tokens vivos del proyecto.
+    pub async fn revoke_project(&self, project_id: Uuid) -> RepoResult<()> {
+        self.tokens.revoke_project(project_id).await
+    }
+
+    /// Borra los tokens vencidos o revocados. Devuelve cuántos borró.
+    pub async fn purge(&self) -> RepoResult<u64> {
+        self.tokens.purge(Utc::now()).await
+    }
+
+    async fn reused(&self, family_id: Uuid) -> RefreshError {
+        warn!("reuso de refresh token detectado; se revoca la familia {}", family_id);
+        match self.tokens.revoke_family(family_id).await {
+            Ok(()) => RefreshError::Reused,
+            Err(e) => RefreshError::Repo(e),
+        }
+    }
+
+    async fn store(
+        &self,
+        family_id: Uuid,
+        project_id: Uuid,
+        scope: Option<String>,
+        ttl: Duration,
+    ) -> RepoResult<String> {
+        let token = generate_token();
+        self.tokens
+            .create(NewRefreshToken {
+                family_id,
+                project_id,
+                token_hash: hash_token(&token),
+                scope,
+                expires_at: Utc::now() + ttl,
+            })
+            .await?;
+        Ok(token)
+    }
+}
+
+/// Token opaco: 256 bits aleatorios en Base64 URL-safe.
+pub(crate) fn generate_token() -> String {
+    let mut bytes = [0u8; TOKEN_BYTES];
+    OsRng.fill_bytes(&mut bytes);
+    URL_SAFE_NO_PAD.encode(bytes)
+}
+
+/// SHA-256 del token (hex). Basta un hash rápido: el token es de alta entropía.
+pub(crate) fn hash_token(token: &str) -> String {
+    Sha256::digest(token.as_bytes())
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{RefreshError, RefreshService, Rotated};
+    use crate::domain::models::{NewRefreshToken, Project, RefreshToken};
+    use crate::domain::ports::{RefreshTokenRepo, RepoResult};
+    use crate::test_support::{project, MemProjectRepo};
+    use async_trait::async_trait;
+    use chrono::{DateTime, Duration, Utc};
+    use std::sync::{Arc, Mutex};
+    use uuid::Uuid;
+
+    /// Repo en memoria de refresh tokens.
+    #[derive(Default)]
+    struct MemTokenRepo {
+        tokens: Mutex<Vec<RefreshToken>>,
+    }
+
+    #[async_trait]
+    impl RefreshTokenRepo for MemTokenRepo {
+        async fn create(&self, new: NewRefreshToken) -> RepoResult<RefreshToken> {
+            let t = RefreshToken {
+                id: Uuid::new_v4(),
+                family_id: new.family_id,
+                project_id: new.project_id,
+                token_hash: new.token_hash,
+                scope: new.scope,
+                created_at: Utc::now(),
+                expires_at: new.expires_at,
+                used_at: None,
+                revoked_at: None,
+            };
+            self.tokens.lock().unwrap().push(t.clone());
+            Ok(t)
+        }
+        async fn find_by_hash(&self, hash: &str) -> RepoResult<Option<RefreshToken>> {
+            let tokens = self.tokens.lock().unwrap();
+            Ok(tokens.iter().find(|t| t.token_hash == hash).cloned())
+        }
+        async fn mark_used(&self, id: Uuid) -> RepoResult<bool> {
+            let mut tokens = self.tokens.lock().unwrap();
+            let t = tokens.iter_mut().find(|t| t.id == id).unwrap();
+            if t.used_at.is_some() || t.revoked_at.is_some() {
+                return Ok(false);
+            }
+            t.used_at = Some(Utc::now());
+            Ok(true)
+        }
+        async fn revoke_family(&self, family_id: Uuid) -> RepoResult<()> {
+            for t in self.tokens.lock().unwrap().iter_mut() {
+                if t.family_id == family_id {
+                    t.revoked_at = Some(Utc::now());
+                }
+            }
+            Ok(())
+        }
+        async fn revoke_project(&self, project_id: Uuid) -> RepoResult<()> {
+            for t in self.tokens.lock().unwrap().iter_mut() {
+                if t.project_id == project_id {
+                    t.revoked_at = Some(Utc::now());
+                }
+            }
+            Ok(())
+        }
+        async fn purge(&self, _: DateTime<Utc>) -> RepoResult<u64> {
+            unimplemented!()
+        }
+    }
+
+    fn service(project: Project, tokens: Arc<MemTokenRepo>) -> RefreshService {
+        RefreshService::new(
+            tokens,
+            Arc::new(MemProjectRepo::with([project])),
+            Some(Duration::days(30)),
+        )
+    }
+
+    /// Canje completo, como lo hace el grant sin comprobaciones extra.
+    async fn redeem(svc: &RefreshService, token: &str) -> Result<Rotated, RefreshError> {
+        let presented = svc.present(token).await?;
+        svc.rotate(presented).await
+    }
+
+    #[tokio::test]
+    async fn rotation_issues_new_token_and_keeps_scope() {
+        let p = project("sigac", true);
+        let svc = service(p.clone(), Arc::new(MemTokenRepo::default()));
+        let first = svc.issue(p.id, Some("read")).await.unwrap().unwrap();
+
+        let rotated = redeem(&svc, &first).await.ok().unwrap();
+        assert_eq!(rotated.project.id, p.id);
+        assert_ne!(rotated.refresh_token, first);
+        let next = svc.present(&rotated.refresh_token).await.ok().unwrap();
+        assert_eq!(next.scope(), Some("read"));
+    }
+
+    #[tokio::test]
+    async fn reuse_revokes_whole_family() {
+        let p = project("sigac", true);
+        let svc = service(p.clone(), Arc::new(MemTokenRepo::default()));
+        let first = svc.issue(p.id, None).await.unwrap().unwrap();
+        let second = redeem(&svc, &first).await.ok().unwrap().refresh_token;
+
+        // Reusar el primero (robado) revoca también el vigente.
+        assert!(matches!(redeem(&svc, &first).await, Err(RefreshError::Reused)));
+        assert!(matches!(redeem(&svc, &second).await, Err(RefreshError::Reused)));
+    }
+
+    #[tokio::test]
+    async fn rejected_presentation_leaves_token_usable() {
+        let p = project("sigac", true);
+        let svc = service(p.clone(), Arc::new(MemTokenRepo::default()));
+        let t = svc.issue(p.id, None).await.unwrap().unwrap();
+
+        // El llamador lo rechaza (p.ej. IP fuera de la lista): no se consume.
+        let presented = svc.present(&t).await.ok().unwrap();
+        assert_eq!(presented.project.id, p.id);
+        drop(presented);
+
+        // El dueño lo canjea después sin disparar la detección de reuso.
+        let next = redeem(&svc, &t).await.ok().unwrap().refresh_token;
+        assert!(redeem(&svc, &next).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn unknown_and_expired_tokens_are_invalid() {
+        let p = project("sigac", true);
+        let tokens = Arc::new(MemTokenRepo::default());
+        let svc = service(p.clone(), tokens.clone());
+        assert!(matches!(redeem(&svc, "no-existe").await, Err(RefreshError::Invalid)));
+
+        let t = svc.issue(p.id, None).await.unwrap().unwrap();
+        tokens.tokens.lock().unwrap()[0].expires_at = Utc::now() - Duration::seconds(1);
+        assert!(matches!(redeem(&svc, &t).await, Err(RefreshError::Invalid)));
+    }
+
+    #[tokio::test]
+    async fn disabled_project_cannot_refresh() {
+        let p = project("sigac", false);
+        let svc = service(p.clone(), Arc::new(MemTokenRepo::default()));
+        let t = svc.issue(p.id, None).await.unwrap().unwrap();
+        assert!(matches!(redeem(&svc, &t).await, Err(RefreshError::Invalid)));
+    }
+
+    #[tokio::test]
+    async fn disabled_service_issues_nothing() {
+        let p = project("sigac", true);
+        let svc = RefreshService::new(
+            Arc::new(MemTokenRepo::default()),
+            Arc::new(MemProjectRepo::with([p.clone()])),
+            None,
+        );
+        assert!(svc.issue(p.id, None).await.unwrap().is_none());
+    }
+}
@@ -0,0 +1,264 @@
+//! Revocación de JWT emitidos (denylist).
+//!
+//! Los JWT son autocontenidos: sin esto, un token robado o de un proyecto
+//! deshabilitado sigue reproduciendo video hasta su `exp`. La fuente de verdad
+//! es la tabla `token_revocations` (puerto `RevocationRepo`); cada instancia
+//! mantiene una copia en memoria que recarga cada pocos segundos, así que la
+//! verificación por request no toca la BD y una revocación hecha en otra
+//! instancia se aplica en cuanto vence el intervalo de recarga.
+
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, RwLock};
+
+use chrono::{DateTime, Duration, Utc};
+use uuid::Uuid;
+
+use crate::domain::models::{NewRevocation, Revocation};
+use crate::domain::ports::{RepoResult, RevocationRepo};
+
+/// Qué tokens revocar.
+pub enum RevocationTarget {
+    /// Un token concreto, por su `jti`.
+    Token(String),
+    /// Los tokens del proyecto emitidos hasta `issued_before`.
+    Project {
+        client_id: String,
+        issued_before: DateTime<Utc>,
+    },
+    /// Todos los tokens emitidos hasta `issued_before`.
+    All { issued_before: DateTime<Utc> },
+}
+
+/// Copia en memoria de las revocaciones activas. Los cortes se guardan en
+/// segundos Unix (la resolución de `iat`).
+#[derive(Default)]
+struct Denylist {
+    jtis: HashSet<String>,
+    by_client: HashMap<String, i64>,
+    global: Option<i64>,
+}
+
+impl Denylist {
+    fn from_revocations(revocations: Vec<Revocation>) -> Self {
+        let mut list = Denylist::default();
+        for r in revocations {
+            match (r.jti, r.client_id, r.issued_before) {
+                (Some(jti), _, _) => {
+                    list.jtis.insert(jti);
+                }
+                (None, Some(client_id), Some(before)) => {
+                    let cut = list.by_client.entry(client_id).or_insert(i64::MIN);
+                    *cut = (*cut).max(before.timestamp());
+                }
+                (None, None, Some(before)) => {
+                    list.global = list.global.max(Some(before.timestamp()));
+                }
+                (None, _, None) => {}
+            }
+        }
+        list
+    }
+
+    /// `iat` igual al segundo del corte también cuenta como revocado: mejor
+    /// invalidar de más un token emitido en ese mismo segundo que dejar vivo
+    /// uno anterior a la revocación.
+    fn is_revoked(&self, jti: &str, sub: &str, iat: i64) -> bool {
+        (!jti.is_empty() && self.jtis.contains(jti))
+            || self.global.is_some_and(|cut| iat <= cut)
+            || self.by_client.get(sub).is_some_and(|&cut| iat <= cut)
+    }
+}
+
+pub struct RevocationService {
+    repo: Arc<dyn RevocationRepo>,
+    /// Vida máxima de un JWT: pasado ese tiempo la revocación ya no afecta a
+    /// ningún token vivo y deja de cargarse.
+    max_token_age: Duration,
+    denylist: RwLock<Denylist>,
+}
+
+impl RevocationService {
+    pub fn new(repo: Arc<dyn RevocationRepo>, max_token_age: Duration) -> Self {
+        Self {
+            repo,
+            max_token_age,
+            denylist: RwLock::new(Denylist::default()),
+        }
+    }
+
+    /// Recarga la copia en memoria desde la BD. Si falla, se conserva la
+    /// anterior (el llamador decide si es fatal).
+    pub async fn reload(&self) -> RepoResult<()> {
+        let active = self.repo.list_active(Utc::now()).await?;
+        let list = Denylist::from_revocations(active);
+        *self.denylist.write().unwrap_or_else(|e| e.into_inner()) = list;
+        Ok(())
+    }
+
+    /// ¿Está revocado el token con estos claims? Solo consulta memoria.
+    pub fn is_revoked(&self, jti: &str, sub: &str, iat: i64) -> bool {
+        self.denylist
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .is_revoked(jti, sub, iat)
+    }
+
+    /// Registra una revocación y la aplica de inmediato en esta instancia.
+    pub async fn revoke(
+        &self,
+        target: RevocationTarget,
+        reason: Option<String>,
+    ) -> RepoResult<Revocation> {
+        let now = Utc::now();
+        let new = match target {
+            RevocationTarget::Token(jti) => NewRevocation {
+                jti: Some(jti),
+                client_id: None,
+                issued_before: None,
+                reason,
+                expires_at: now + self.max_token_age,
+            },
+            RevocationTarget::Project {
+                client_id,
+                issued_before,
+            } => NewRevocation {
+                jti: None,
+                client_id: Some(client_id),
+                issued_before: Some(issued_before),
+                reason,
+                expires_at: issued_before + self.max_token_age,
+            },
+            RevocationTarget::All { issued_before } => NewRevocation {
+                jti: None,
+                client_id: None,
+                issued_before: Some(issued_before),
+                reason,
+                expires_at: issued_before + self.max_token_age,
+            },
+        };
+        let created = self.repo.create(new).await?;
+        self.reload().await?;
+        Ok(created)
+    }
+
+    /// Revocaciones que aún afectan a tokens vivos.
+    pub async fn list_active(&self) -> RepoResult<Vec<Revocation>> {
+        self.repo.list_active(Utc::now()).await
+    }
+
+    /// Levanta una revocación.
+    pub async fn lift(&self, id: Uuid) -> RepoResult<()> {
+        self.repo.delete(id).await?;
+        self.reload().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{RevocationService, RevocationTarget};
+    use crate::domain::models::{NewRevocation, Revocation};
+    use crate::domain::ports::{RepoError, RepoResult, RevocationRepo};
+    use async_trait::async_trait;
+    use chrono::{DateTime, Duration, Utc};
+    use std::sync::{Arc, Mutex};
+    use uuid::Uuid;
+
+    /// Repo en memoria de revocaciones.
+    #[derive(Default)]
+    struct MemRepo {
+        rows: Mutex<Vec<Revocation>>,
+    }
+
+    #[async_trait]
+    impl RevocationRepo for MemRepo {
+        async fn create(&self, new: NewRevocation) -> RepoResult<Revocation> {
+            let r = Revocation {
+                id: Uuid::new_v4(),
+                jti: new.jti,
+                client_id: new.client_id,
+                issued_before: new.issued_before,
+                reason: new.reason,
+                created_at: Utc::now(),
+                expires_at: new.expires_at,
+            };
+            self.rows.lock().unwrap().push(r.clone());
+            Ok(r)
+        }
+        async fn list_active(&self, now: DateTime<Utc>) -> RepoResult<Vec<Revocation>> {
+            let rows = self.rows.lock().unwrap();
+            Ok(rows.iter().filter(|r| r.expires_at > now).cloned().collect())
+        }
+        async fn delete(&self, id: Uuid) -> RepoResult<()> {
+            let mut rows = self.rows.lock().unwrap();
+            let before = rows.len();
+            rows.retain(|r| r.id != id);
+            if rows.len() == before {
+                return Err(RepoError::NotFound);
+            }
+            Ok(())
+        }
+    }
+
+    fn service() -> RevocationService {
+        RevocationService::new(Arc::new(MemRepo::default()), Duration::minutes(60))
+    }
+
+    #[tokio::test]
+    async fn revoked_jti_is_denied_and_can_be_lifted() {
+        let svc = service();
+        let now = Utc::now().timestamp();
+        let r = svc
+            .revoke(RevocationTarget::Token("abc".into()), None)
+            .await
+            .unwrap();
+        assert!(svc.is_revoked("abc", "sigac", now));
+        assert!(!svc.is_revoked("otro", "sigac", now));
+
+        svc.lift(r.id).await.unwrap();
+        assert!(!svc.is_revoked("abc", "sigac", now));
+    }
+
+    #[tokio::test]
+    async fn project_cutoff_only_hits_older_tokens_of_that_project() {
+        let svc = service();
+        let cut = Utc::now();
+        svc.revoke(
+            RevocationTarget::Project {
+                client_id: "sigac".into(),
+                issued_before: cut,
+            },
+            Some("proyecto comprometido".into()),
+        )
+        .await
+        .unwrap();
+
+        let before = (cut - Duration::minutes(5)).timestamp();
+        let after = (cut + Duration::minutes(5)).timestamp();
+        assert!(svc.is_revoked("j1", "sigac", before));
+        assert!(!svc.is_revoked("j2", "sigac", after), "tokens nuevos valen");
+        assert!(!svc.is_revoked("j3", "odin", before), "otro proyecto intacto");
+    }
+
+    #[tokio::test]
+    async fn global_cutoff_hits_every_project() {
+        let svc = service();
+        let cut = Utc::now();
+        svc.revoke(RevocationTarget::All { issued_before: cut }, None)
+            .await
+            .unwrap();
+        assert!(svc.is_revoked("", "sigac", cut.timestamp()));
+        assert!(svc.is_revoked("", "odin", 0), "token sin iat (legado) revocado");
+        assert!(!svc.is_revoked("", "odin", cut.timestamp() + 1));
+    }
+
+    #[tokio::test]
+    async fn expired_revocations_are_not_loaded() {
+        let svc = service();
+        let old = Utc::now() - Duration::hours(2);
+        svc.revoke(RevocationTarget::All { issued_before: old }, None)
+            .await
+            .unwrap();
+        // Ya no puede quedar vivo ningún token emitido antes del corte.
+        assert!(!svc.is_revoked("", "sigac", old.timestamp()));
+    }
+}
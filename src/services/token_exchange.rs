@@ -0,0 +1,467 @@
+//! Canje de tokens de un proveedor de identidad externo (RFC 8693).
+//!
+//! Los operadores ya se autentican contra el OIDC corporativo: en vez de
+//! repartir secretos de proyecto, un servicio presenta el JWT del IdP y recibe
+//! un token nuestro. El JWT externo se verifica contra el JWKS configurado
+//! (`ExternalKeySource`, cacheado y recargado ante un `kid` desconocido) y el
+//! emisor esperado; sus claims se cruzan con `idp_mappings` para elegir el
+//! proyecto, cuyos permisos son los que lleva el token emitido. Fail-closed:
+//! sin asociación, con varias sin desambiguar o con el proyecto deshabilitado,
+//! no hay canje.
+
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
+
+use jsonwebtoken::jwk::{Jwk, JwkSet};
+use jsonwebtoken::{Algorithm, DecodingKey, Validation};
+use serde_json::{Map, Value};
+use tracing::warn;
+use uuid::Uuid;
+
+use crate::domain::models::Project;
+use crate::domain::ports::{ExternalKeySource, IdpMappingRepo, ProjectRepo, RepoError};
+
+/// Algoritmos aceptados en tokens externos: solo asimétricos (un HS256 se
+/// podría firmar con la propia clave pública publicada).
+const ALLOWED_ALGS: [Algorithm; 9] = [
+    Algorithm::RS256,
+    Algorithm::RS384,
+    Algorithm::RS512,
+    Algorithm::PS256,
+    Algorithm::PS384,
+    Algorithm::PS512,
+    Algorithm::ES256,
+    Algorithm::ES384,
+    Algorithm::EdDSA,
+];
+
+/// Ante un `kid` desconocido se recarga el JWKS, pero no más de una vez en
+/// este lapso (un token basura no debe martillar al IdP).
+const MIN_REFETCH: Duration = Duration::from_secs(30);
+
+/// Qué tokens externos se aceptan (`EXTERNAL_IDP_*`).
+#[derive(Debug, Clone)]
+pub struct ExternalIdpPolicy {
+    /// `iss` exigido.
+    pub issuer: String,
+    /// `aud` exigido; `None` = no se valida.
+    pub audience: Option<String>,
+    /// Tolerancia de reloj para exp/nbf, en segundos.
+    pub leeway: u64,
+    /// Cada cuánto se recarga el JWKS aunque no falte ningún `kid`.
+    pub jwks_refresh: Duration,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum ExchangeError {
+    #[error("token externo inválido: {0}")]
+    InvalidToken(String),
+    #[error("no se pudieron obtener las claves del IdP: {0}")]
+    KeysUnavailable(String),
+    #[error("la identidad externa no está asociada a ningún proyecto habilitado")]
+    NoProject,
+    #[error("la identidad externa corresponde a varios proyectos ({0}); indique client_id")]
+    Ambiguous(String),
+    #[error("la identidad externa no está asociada al proyecto '{0}'")]
+    NotMapped(String),
+    #[error(transparent)]
+    Repo(#[from] RepoError),
+}
+
+/// Resultado de un canje: el proyecto elegido, el `sub` externo (para
+/// auditoría) y el `exp` del token externo (el nuestro no vive más).
+pub struct Exchanged {
+    pub project: Project,
+    pub subject: String,
+    pub expires_at: i64,
+}
+
+struct CachedKeys {
+    keys: JwkSet,
+    fetched_at: Instant,
+}
+
+pub struct TokenExchangeService {
+    source: Arc<dyn ExternalKeySource>,
+    mappings: Arc<dyn IdpMappingRepo>,
+    projects: Arc<dyn ProjectRepo>,
+    policy: ExternalIdpPolicy,
+    cache: RwLock<Option<CachedKeys>>,
+}
+
+impl TokenExchangeService {
+    pub fn new(
+        source: Arc<dyn ExternalKeySource>,
+        mappings: Arc<dyn IdpMappingRepo>,
+        projects: Arc<dyn ProjectRepo>,
+        policy: ExternalIdpPolicy,
+    ) -> Self {
+        Self {
+            source,
+            mappings,
+            projects,
+            policy,
+            cache: RwLock::new(None),
+        }
+    }
+
+    /// Verifica el JWT externo y resuelve su proyecto. `target` es el
+    /// `client_id` pedido, obligatorio solo si la
+    /// identidad está asociada a más de un proyecto.
+    pub async fn exchange(&self, token: &str, target: Option<&str>) -> Result<Exchanged, ExchangeError> {
+        let claims = self.verify(token).await?;
+        let project = self.resolve(&claims, target).await?;
+        Ok(Exchanged {
+            project,
+            subject: claims
+                .get("sub")
+                .and_then(Value::as_str)
+                .unwrap_or_default()
+                .to_string(),
+            expires_at: claims.get("exp").and_then(Value::as_i64).unwrap_or_default(),
+        })
+    }
+
+    /// Firma (clave del `kid` en el JWKS del IdP, algoritmo asimétrico),
+    /// `iss`, `aud` si se configuró, y `exp`/`nbf` con tolerancia.
+    async fn verify(&self, token: &str) -> Result<Map<String, Value>, ExchangeError> {
+        let invalid = |m: &str| ExchangeError::InvalidToken(m.to_string());
+        let header = jsonwebtoken::decode_header(token).map_err(|_| invalid("mal formado"))?;
+        if !ALLOWED_ALGS.contains(&header.alg) {
+            return Err(invalid("algoritmo no permitido"));
+        }
+        let kid = header.kid.ok_or_else(|| invalid("falta kid"))?;
+        let jwk = self.key(&kid).await?;
+        if jwk
+            .common
+            .key_algorithm
+            .is_some_and(|ka| ka.to_string() != format!("{:?}", header.alg))
+        {
+            return Err(invalid("el algoritmo no coincide con el de la clave"));
+        }
+        let key = DecodingKey::from_jwk(&jwk).map_err(|_| invalid("clave del IdP no soportada"))?;
+
+        let mut validation = Validation::new(header.alg);
+        validation.leeway = self.policy.leeway;
+        validation.validate_nbf = true;
+        validation.set_issuer(&[&self.policy.issuer]);
+        let mut required = vec!["exp", "iss", "sub"];
+        match &self.policy.audience {
+            Some(aud) => {
+                validation.set_audience(&[aud]);
+                required.push("aud");
+            }
+            None => validation.validate_aud = false,
+        }
+        validation.set_required_spec_claims(&required);
+
+        jsonwebtoken::decode::<Map<String, Value>>(token, &key, &validation)
+            .map(|data| data.claims)
+            .map_err(|e| ExchangeError::InvalidToken(e.to_string()))
+    }
+
+    /// Clave del `kid`: de la caché si está fresca; si no, recarga el JWKS.
+    /// Si el IdP no responde, vale la clave ya conocida.
+    async fn key(&self, kid: &str) -> Result<Jwk, ExchangeError> {
+        let unknown = || ExchangeError::InvalidToken("clave desconocida (kid)".into());
+        let cached = {
+            let cache = self.cache.read().unwrap_or_else(|e| e.into_inner());
+            match cache.as_ref() {
+                Some(c) => {
+                    let age = c.fetched_at.elapsed();
+                    let found = c.keys.find(kid).cloned();
+                    match found {
+                        Some(k) if age < self.policy.jwks_refresh => return Ok(k),
+                        None if age < MIN_REFETCH => return Err(unknown()),
+                        other => other,
+                    }
+                }
+                None => None,
+            }
+        };
+
+        match self.source.fetch().await {
+            Ok(keys) => {
+                let found = keys.find(kid).cloned();
+                *self.cache.write().unwrap_or_else(|e| e.into_inner()) = Some(CachedKeys {
+                    keys,
+                    fetched_at: Instant::now(),
+                });
+                found.ok_or_else(unknown)
+            }
+            Err(e) => match cached {
+                Some(k) => {
+                    warn!("No se pudo recargar el JWKS del IdP ({}); uso la clave en caché", e);
+                    Ok(k)
+                }
+                None => Err(ExchangeError::KeysUnavailable(e)),
+            },
+        }
+    }
+
+    /// Proyectos habilitados asociados a los claims; `target` elige entre ellos.
+    async fn resolve(&self, claims: &Map<String, Value>, target: Option<&str>) -> Result<Project, ExchangeError> {
+        let mut ids: Vec<Uuid> = Vec::new();
+        for m in self.mappings.list_all().await? {
+            if !ids.contains(&m.project_id) && claim_has(claims, &m.claim, &m.value) {
+                ids.push(m.project_id);
+            }
+        }
+        let mut candidates = Vec::new();
+        for id in ids {
+            if let Some(p) = self.projects.find_by_id(id).await? {
+                if p.enabled {
+                    candidates.push(p);
+                }
+            }
+        }
+
+        if let Some(target) = target {
+            return candidates
+                .into_iter()
+                .find(|p| p.client_id == target)
+                .ok_or_else(|| ExchangeError::NotMapped(target.to_string()));
+        }
+        match candidates.len() {
+            0 => Err(ExchangeError::NoProject),
+            1 => Ok(candidates.remove(0)),
+            _ => Err(ExchangeError::Ambiguous(
+                candidates
+                    .iter()
+                    .map(|p| p.client_id.as_str())
+                    .collect::<Vec<_>>()
+                    .join(", "),
+            )),
+        }
+    }
+}
+
+/// ¿El claim `path` (con `.` para anidar, p.ej. `realm_access.roles`) vale
+/// `value` o, si es una lista, lo contiene?
+fn claim_has(claims: &Map<String, Value>, path: &str, value: &str) -> bool {
+    let mut parts = path.split('.');
+    let Some(first) = parts.next() else {
+        return false;
+    };
+    let mut current = claims.get(first);
+    for part in parts {
+        current = current.and_then(|v| v.get(part));
+    }
+    let matches = |v: &Value| match v {
+        Value::String(s) => s == value,
+        Value::Number(n) => n.to_string() == value,
+        Value::Bool(b) => value.parse::<bool>() == Ok(*b),
+        _ => false,
+    };
+    match current {
+        Some(Value::Array(items)) => items.iter().any(matches),
+        Some(v) => matches(v),
+        None => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{claim_has, ExchangeError, ExternalIdpPolicy, TokenExchangeService};
+    use crate::domain::models::{IdpMapping, NewIdpMapping, Project};
+    use crate::domain::ports::{ExternalKeySource, IdpMappingRepo, RepoResult};
+    use crate::keys::{self, KeyAlg, Keyring, PublicJwk};
+    use crate::test_support::{project, MemProjectRepo};
+    use async_trait::async_trait;
+    use chrono::Utc;
+    use jsonwebtoken::jwk::JwkSet;
+    use jsonwebtoken::{Algorithm, Header};
+    use serde_json::{json, Value};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use std::time::Duration;
+    use uuid::Uuid;
+
+    const ISSUER: &str = "https://sso.example.com/realms/corp";
+
+    /// JWKS fijo; cuenta las descargas.
+    struct FakeKeys {
+        set: Value,
+        fetches: AtomicUsize,
+    }
+
+    #[async_trait]
+    impl ExternalKeySource for FakeKeys {
+        async fn fetch(&self) -> Result<JwkSet, String> {
+            self.fetches.fetch_add(1, Ordering::SeqCst);
+            serde_json::from_value(self.set.clone()).map_err(|e| e.to_string())
+        }
+    }
+
+    struct FakeMappings(Vec<IdpMapping>);
+
+    #[async_trait]
+    impl IdpMappingRepo for FakeMappings {
+        async fn list_all(&self) -> RepoResult<Vec<IdpMapping>> {
+            Ok(self.0.clone())
+        }
+        async fn create(&self, _: NewIdpMapping) -> RepoResult<IdpMapping> {
+            unimplemented!()
+        }
+        async fn delete(&self, _: Uuid) -> RepoResult<()> {
+            unimplemented!()
+        }
+    }
+
+    fn mapping(claim: &str, value: &str, project: &Project) -> IdpMapping {
+        IdpMapping {
+            id: Uuid::new_v4(),
+            claim: claim.into(),
+            value: value.into(),
+            project_id: project.id,
+            created_at: Utc::now(),
+        }
+    }
+
+    /// Clave del "IdP" (Ed25519) generada con nuestro propio anillo.
+    fn idp_key() -> Keyring {
+        let dir = std::env::temp_dir().join(format!("mtx-idp-{}", Uuid::new_v4()));
+        let path = dir.join("idp.pem");
+        let ring = keys::load_or_create(path.to_str().unwrap(), KeyAlg::EdDsa, chrono::Duration::zero()).unwrap();
+        std::fs::remove_dir_all(&dir).ok();
+        ring
+    }
+
+    fn jwks(ring: &Keyring) -> Value {
+        let key = ring.active();
+        let PublicJwk::Okp { x } = &key.jwk else {
+            panic!("esperaba OKP");
+        };
+        json!({ "keys": [{ "kty": "OKP", "crv": "Ed25519", "x": x, "kid": key.kid, "alg": "EdDSA" }] })
+    }
+
+    fn sign(ring: &Keyring, claims: Value) -> String {
+        let mut header = Header::new(Algorithm::EdDSA);
+        header.kid = Some(ring.active().kid.clone());
+        jsonwebtoken::encode(&header, &claims, &ring.active().encoding_key).unwrap()
+    }
+
+    fn claims(groups: &[&str]) -> Value {
+        json!({
+            "iss": ISSUER,
+            "sub": "ana",
+            "aud": "media-backend",
+            "exp": Utc::now().timestamp() + 600,
+            "groups": groups,
+        })
+    }
+
+    fn service(ring: &Keyring, mappings: Vec<IdpMapping>, projects: Vec<Project>) -> (TokenExchangeService, Arc<FakeKeys>) {
+        let keys = Arc::new(FakeKeys {
+            set: jwks(ring),
+            fetches: AtomicUsize::new(0),
+        });
+        let svc = TokenExchangeService::new(
+            keys.clone(),
+            Arc::new(FakeMappings(mappings)),
+            Arc::new(MemProjectRepo::with(projects)),
+            ExternalIdpPolicy {
+                issuer: ISSUER.into(),
+                audience: Some("media-backend".into()),
+                leeway: 30,
+                jwks_refresh: Duration::from_secs(3600),
+            },
+        );
+        (svc, keys)
+    }
+
+    #[tokio::test]
+    async fn mapped_group_yields_its_project() {
+        let ring = idp_key();
+        let sigac = project("sigac", true);
+        let (svc, _) = service(&ring, vec![mapping("groups", "video", &sigac)], vec![sigac]);
+
+        let got = svc.exchange(&sign(&ring, claims(&["otros", "video"])), None).await.unwrap();
+        assert_eq!(got.project.client_id, "sigac");
+        assert_eq!(got.subject, "ana");
+
+        let err = svc.exchange(&sign(&ring, claims(&["otros"])), None).await.err().unwrap();
+        assert!(matches!(err, ExchangeError::NoProject));
+    }
+
+    #[tokio::test]
+    async fn rejects_wrong_issuer_audience_and_foreign_keys() {
+        let ring = idp_key();
+        let sigac = project("sigac", true);
+        let (svc, _) = service(&ring, vec![mapping("groups", "video", &sigac)], vec![sigac]);
+
+        let mut other_iss = claims(&["video"]);
+        other_iss["iss"] = json!("https://otro.example.com");
+        let mut other_aud = claims(&["video"]);
+        other_aud["aud"] = json!("otra-app");
+        for c in [other_iss, other_aud] {
+            let err = svc.exchange(&sign(&ring, c), None).await.err().unwrap();
+            assert!(matches!(err, ExchangeError::InvalidToken(_)));
+        }
+
+        // Firmado por otra clave con el mismo kid: firma inválida.
+        let impostor = idp_key();
+        let mut header = Header::new(Algorithm::EdDSA);
+        header.kid = Some(ring.active().kid.clone());
+        let forged =
+            jsonwebtoken::encode(&header, &claims(&["video"]), &impostor.active().encoding_key).unwrap();
+        assert!(matches!(
+            svc.exchange(&forged, None).await.err().unwrap(),
+            ExchangeError::InvalidToken(_)
+        ));
+    }
+
+    #[tokio::test]
+    async fn several_projects_need_an_audience_and_skip_disabled() {
+        let ring = idp_key();
+        let (sigac, odin, off) = (project("sigac", true), project("odin", true), project("off", false));
+        let mappings = vec![
+            mapping("groups", "video", &sigac),
+            mapping("groups", "video", &odin),
+            mapping("groups", "video", &off),
+        ];
+        let (svc, _) = service(&ring, mappings, vec![sigac, odin, off]);
+        let token = sign(&ring, claims(&["video"]));
+
+        let err = svc.exchange(&token, None).await.err().unwrap();
+        assert!(matches!(err, ExchangeError::Ambiguous(ref list) if list == "sigac, odin"));
+        let got = svc.exchange(&token, Some("odin")).await.unwrap();
+        assert_eq!(got.project.client_id, "odin");
+        let err = svc.exchange(&token, Some("off")).await.err().unwrap();
+        assert!(matches!(err, ExchangeError::NotMapped(_)));
+    }
+
+    #[tokio::test]
+    async fn jwks_is_cached_and_unknown_kid_refetch_is_throttled() {
+        let ring = idp_key();
+        let sigac = project("sigac", true);
+        let (svc, keys) = service(&ring, vec![mapping("groups", "video", &sigac)], vec![sigac]);
+
+        let token = sign(&ring, claims(&["video"]));
+        svc.exchange(&token, None).await.unwrap();
+        svc.exchange(&token, None).await.unwrap();
+        assert_eq!(keys.fetches.load(Ordering::SeqCst), 1);
+
+        // Un kid desconocido no vuelve a descargar antes de MIN_REFETCH.
+        let stranger = sign(&idp_key(), claims(&["video"]));
+        assert!(svc.exchange(&stranger, None).await.is_err());
+        assert_eq!(keys.fetches.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn claim_matching_supports_lists_nesting_and_scalars() {
+        let claims = json!({
+            "groups": ["a", "video"],
+            "realm_access": { "roles": ["operador"] },
+            "email": "ana@example.com",
+            "level": 3
+        });
+        let claims = claims.as_object().unwrap();
+        assert!(claim_has(claims, "groups", "video"));
+        assert!(claim_has(claims, "realm_access.roles", "operador"));
+        assert!(claim_has(claims, "email", "ana@example.com"));
+        assert!(claim_has(claims, "level", "3"));
+        assert!(!claim_has(claims, "groups", "vid"));
+        assert!(!claim_has(claims, "realm_access.missing", "operador"));
+    }
+}
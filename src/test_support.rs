@@ -0,0 +1,110 @@
+//! Dobles de prueba compartidos por los tests de los servicios.
+//!
+//! `MemProjectRepo` implementa las lecturas de `ProjectRepo` sobre datos fijos;
+//! las escrituras no las usa ningún servicio probado y quedan sin implementar.
+
+use async_trait::async_trait;
+use chrono::Utc;
+use uuid::Uuid;
+
+use crate::domain::models::{
+    AllowedCamera, CameraGrant, GroupGrant, NewProject, PrefixGrant, Project, PublishGrant,
+};
+use crate::domain::ports::{ProjectRepo, RepoError, RepoResult};
+
+/// Repo de proyectos en memoria, de solo lectura. Con `fail`, las búsquedas
+/// de proyecto devuelven un error de backend.
+#[derive(Default)]
+pub struct MemProjectRepo {
+    pub projects: Vec<Project>,
+    pub allowed: Vec<AllowedCamera>,
+    pub grouped: Vec<AllowedCamera>,
+    pub prefixes: Vec<PrefixGrant>,
+    pub publish: Vec<PublishGrant>,
+    pub fail: bool,
+}
+
+impl MemProjectRepo {
+    /// Repo con estos proyectos y sin cámaras ni permisos asignados.
+    pub fn with(projects: impl IntoIterator<Item = Project>) -> Self {
+        Self {
+            projects: projects.into_iter().collect(),
+            ..Self::default()
+        }
+    }
+
+    fn find(&self, pred: impl Fn(&Project) -> bool) -> RepoResult<Option<Project>> {
+        if self.fail {
+            return Err(RepoError::Backend("boom".into()));
+        }
+        Ok(self.projects.iter().find(|p| pred(p)).cloned())
+    }
+}
+
+#[async_trait]
+impl ProjectRepo for MemProjectRepo {
+    async fn find_by_client_id(&self, client_id: &str) -> RepoResult<Option<Project>> {
+        self.find(|p| p.client_id == client_id)
+    }
+    async fn find_by_id(&self, id: Uuid) -> RepoResult<Option<Project>> {
+        self.find(|p| p.id == id)
+    }
+    async fn list_all(&self) -> RepoResult<Vec<Project>> {
+        Ok(self.projects.clone())
+    }
+    async fn create(&self, _: NewProject) -> RepoResult<Project> {
+        unimplemented!()
+    }
+    async fn update(&self, _: &Project) -> RepoResult<Project> {
+        unimplemented!()
+    }
+    async fn delete(&self, _: Uuid) -> RepoResult<()> {
+        unimplemented!()
+    }
+    async fn set_cameras(&self, _: Uuid, _: &[CameraGrant]) -> RepoResult<()> {
+        unimplemented!()
+    }
+    async fn allowed_camera_paths(&self, _: Uuid) -> RepoResult<Vec<AllowedCamera>> {
+        Ok(self.allowed.clone())
+    }
+    async fn assigned_cameras(&self, _: Uuid) -> RepoResult<Vec<CameraGrant>> {
+        unimplemented!()
+    }
+    async fn set_groups(&self, _: Uuid, _: &[GroupGrant]) -> RepoResult<()> {
+        unimplemented!()
+    }
+    async fn assigned_groups(&self, _: Uuid) -> RepoResult<Vec<GroupGrant>> {
+        unimplemented!()
+    }
+    async fn group_camera_paths(&self, _: Uuid) -> RepoResult<Vec<AllowedCamera>> {
+        Ok(self.grouped.clone())
+    }
+    async fn set_prefix_grants(&self, _: Uuid, _: &[PrefixGrant]) -> RepoResult<()> {
+        unimplemented!()
+    }
+    async fn prefix_grants(&self, _: Uuid) -> RepoResult<Vec<PrefixGrant>> {
+        Ok(self.prefixes.clone())
+    }
+    async fn set_publish_grants(&self, _: Uuid, _: &[PublishGrant]) -> RepoResult<()> {
+        unimplemented!()
+    }
+    async fn publish_grants(&self, _: Uuid) -> RepoResult<Vec<PublishGrant>> {
+        Ok(self.publish.clone())
+    }
+}
+
+/// Proyecto con acceso a todas las cámaras y sin política de tokens.
+pub fn project(client_id: &str, enabled: bool) -> Project {
+    Project {
+        id: Uuid::new_v4(),
+        client_id: client_id.into(),
+        all_cameras: true,
+        enabled,
+        token_max_minutes: None,
+        token_default_minutes: None,
+        allowed_audiences: vec![],
+        allowed_cidrs: vec![],
+        created_at: Utc::now(),
+        updated_at: Utc::now(),
+    }
+}